@@ -0,0 +1,69 @@
+// String packages borrowed from a sibling image for cost-reduced builds that ship form packages without
+// their string packages. Packages are matched by the GUID of their package lists, which stays the same
+// across variants of the same driver
+use super::{combine_indexed, FormPackage, StringPackage};
+use package_lists::{uefi_package_lists, HiiPackageList};
+use uefi_parser::{Guid, HiiPackageType};
+
+// Package list with a header that contains a package at a given offset
+fn list_with_guid(lists: &[HiiPackageList], offset: usize) -> Option<(&HiiPackageList, Guid)> {
+    lists
+        .iter()
+        .find(|list| list.range().contains(&offset))
+        .and_then(|list| list.guid.map(|guid| (list, guid)))
+}
+
+// Indices of UEFI form packages in package lists without any string package, with GUIDs of their lists.
+// Lists without a header are skipped, the GUID is needed to find their strings elsewhere
+pub fn stripped_uefi_forms(data: &[u8], forms: &[FormPackage]) -> Vec<(usize, Guid)> {
+    let lists = uefi_package_lists(data);
+    forms
+        .iter()
+        .enumerate()
+        .filter_map(|(form_num, form)| {
+            let (list, guid) = list_with_guid(&lists, form.offset)?;
+            let has_strings = list
+                .packages
+                .iter()
+                .any(|package| package.package_type == HiiPackageType::Strings);
+            if has_strings {
+                None
+            } else {
+                Some((form_num, guid))
+            }
+        })
+        .collect()
+}
+
+// String packages of a sibling image in package lists with a given GUID, with their indices
+pub fn borrowed_string_packages<'a>(
+    sibling_data: &[u8],
+    sibling_strings: &'a [StringPackage],
+    package_list_guid: &Guid,
+) -> Vec<(usize, &'a StringPackage)> {
+    let lists = uefi_package_lists(sibling_data);
+    sibling_strings
+        .iter()
+        .enumerate()
+        .filter(|(_, string)| {
+            list_with_guid(&lists, string.offset).is_some_and(|(_, guid)| guid == *package_list_guid)
+        })
+        .collect()
+}
+
+// Borrowed string packages in a given language combined into one to extract a stripped form package.
+// Indices into the string packages of the sibling image are returned with it. Extract with resources
+// without fallback string packages, fallbacks of the input image don't belong to the borrowed ones
+pub fn borrowed_string_package(
+    sibling_data: &[u8],
+    sibling_strings: &[StringPackage],
+    package_list_guid: &Guid,
+    language: &str,
+) -> Option<(Vec<usize>, StringPackage)> {
+    combine_indexed(
+        borrowed_string_packages(sibling_data, sibling_strings, package_list_guid)
+            .into_iter()
+            .filter(|(_, string)| string.language.eq_ignore_ascii_case(language))
+            .collect(),
+    )
+}
@@ -0,0 +1,787 @@
+// Decompression of FFS sections: EFI 1.1 and Tiano compression used by compression sections,
+// LZMA used by GUID-defined sections. Both decoders work on whole buffers and fail on corrupted input
+// instead of panicking, they are used on untrusted data
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Efi,     // EFI 1.1 compression
+    Tiano,   // Tiano compression, same format with a wider position table
+    Lzma,    // LZMA with a 13-byte header
+    LzmaF86, // LZMA with x86 branch conversion applied before compression
+}
+
+// Output buffers are never larger than this, declared sizes above it are treated as corrupted
+pub const MAX_DECOMPRESSED_SIZE: usize = 0x1000_0000;
+
+pub fn decompress(compression: Compression, input: &[u8]) -> Option<Vec<u8>> {
+    match compression {
+        Compression::Efi => efi_decompress(input, 4),
+        Compression::Tiano => efi_decompress(input, 5),
+        Compression::Lzma => lzma_decompress(input),
+        Compression::LzmaF86 => lzma_decompress(input).map(|mut output| {
+            x86_convert(&mut output);
+            output
+        }),
+    }
+}
+
+//
+// EFI 1.1 and Tiano decompression
+//
+const BITBUFSIZ: u32 = 32;
+const MAXMATCH: usize = 256;
+const THRESHOLD: usize = 3;
+const NC: usize = 0xFF + MAXMATCH + 2 - THRESHOLD;
+const CBIT: u32 = 9;
+const MAXPBIT: usize = 5;
+const TBIT: u32 = 5;
+const MAXNP: usize = (1 << MAXPBIT) - 1;
+const NT: usize = 16 + 3;
+const NPT: usize = MAXNP;
+
+// Node of a table built by make_table, links beyond the table are kept in left and right arrays
+#[derive(Clone, Copy)]
+enum Slot {
+    Table(usize),
+    Left(usize),
+    Right(usize),
+}
+
+struct EfiDecoder<'a> {
+    source: &'a [u8],
+    position: usize,
+    compressed_size: usize,
+    bit_buffer: u32,
+    sub_bit_buffer: u32,
+    bit_count: u32,
+    block_size: u16,
+    p_bit: u32,
+    left: Vec<u16>,
+    right: Vec<u16>,
+    c_len: Vec<u8>,
+    pt_len: Vec<u8>,
+    c_table: Vec<u16>,
+    pt_table: Vec<u16>,
+}
+
+impl<'a> EfiDecoder<'a> {
+    fn fill_buffer(&mut self, count: u32) {
+        let mut count = count;
+        self.bit_buffer = self.bit_buffer.checked_shl(count).unwrap_or(0);
+        while count > self.bit_count {
+            count -= self.bit_count;
+            self.bit_buffer |= self.sub_bit_buffer.checked_shl(count).unwrap_or(0);
+            if self.compressed_size > 0 && self.position < self.source.len() {
+                self.compressed_size -= 1;
+                self.sub_bit_buffer = self.source[self.position] as u32;
+                self.position += 1;
+            } else {
+                self.sub_bit_buffer = 0;
+            }
+            self.bit_count = 8;
+        }
+        self.bit_count -= count;
+        self.bit_buffer |= self.sub_bit_buffer >> self.bit_count;
+    }
+
+    fn get_bits(&mut self, count: u32) -> u32 {
+        let result = self.bit_buffer.checked_shr(BITBUFSIZ - count).unwrap_or(0);
+        self.fill_buffer(count);
+        result
+    }
+
+    fn read_slot(&self, pt: bool, slot: Slot) -> u16 {
+        match slot {
+            Slot::Table(index) if pt => self.pt_table[index],
+            Slot::Table(index) => self.c_table[index],
+            Slot::Left(index) => self.left[index],
+            Slot::Right(index) => self.right[index],
+        }
+    }
+
+    fn write_slot(&mut self, pt: bool, slot: Slot, value: u16) {
+        match slot {
+            Slot::Table(index) if pt => self.pt_table[index] = value,
+            Slot::Table(index) => self.c_table[index] = value,
+            Slot::Left(index) => self.left[index] = value,
+            Slot::Right(index) => self.right[index] = value,
+        }
+    }
+
+    // Build a decoding table from code lengths, codes longer than table_bits continue in the left and right arrays.
+    // Returns false if the lengths don't describe a complete code
+    fn make_table(&mut self, lengths: &[u8], table_bits: usize, pt: bool) -> bool {
+        let mut count = [0u16; 17];
+        let mut weight = [0u16; 17];
+        let mut start = [0u16; 18];
+        for &length in lengths {
+            if length > 16 {
+                return false;
+            }
+            count[length as usize] += 1;
+        }
+        for index in 1..=16 {
+            start[index + 1] =
+                start[index].wrapping_add(count[index].wrapping_shl(16 - index as u32));
+        }
+        if start[17] != 0 {
+            return false;
+        }
+
+        let skipped_bits = 16 - table_bits as u32;
+        for index in 1..=table_bits {
+            start[index] >>= skipped_bits;
+            weight[index] = 1 << (table_bits - index);
+        }
+        for (index, weight) in weight.iter_mut().enumerate().skip(table_bits + 1) {
+            *weight = 1 << (16 - index);
+        }
+
+        let table_size = 1 << table_bits;
+        let table_len = |decoder: &EfiDecoder| {
+            if pt {
+                decoder.pt_table.len()
+            } else {
+                decoder.c_table.len()
+            }
+        };
+        let first_unused = (start[table_bits + 1] >> skipped_bits) as usize;
+        if first_unused != 0 {
+            for index in first_unused..table_size.min(table_len(self)) {
+                self.write_slot(pt, Slot::Table(index), 0);
+            }
+        }
+
+        let mut available = lengths.len();
+        let mask = 1u16 << (15 - table_bits);
+        for (character, &length) in lengths.iter().enumerate() {
+            let length = length as usize;
+            if length == 0 {
+                continue;
+            }
+            let next_code = start[length].wrapping_add(weight[length]);
+            if length <= table_bits {
+                for index in start[length] as usize..next_code as usize {
+                    if index >= table_len(self) {
+                        return false;
+                    }
+                    self.write_slot(pt, Slot::Table(index), character as u16);
+                }
+            } else {
+                let mut code = start[length];
+                let mut slot = Slot::Table((code >> skipped_bits) as usize);
+                if (code >> skipped_bits) as usize >= table_len(self) {
+                    return false;
+                }
+                for _ in 0..length - table_bits {
+                    let mut value = self.read_slot(pt, slot);
+                    if value == 0 {
+                        if available >= self.left.len() {
+                            return false;
+                        }
+                        self.left[available] = 0;
+                        self.right[available] = 0;
+                        value = available as u16;
+                        self.write_slot(pt, slot, value);
+                        available += 1;
+                    }
+                    if value as usize >= self.left.len() {
+                        return false;
+                    }
+                    slot = if code & mask != 0 {
+                        Slot::Right(value as usize)
+                    } else {
+                        Slot::Left(value as usize)
+                    };
+                    code = code.wrapping_shl(1);
+                }
+                self.write_slot(pt, slot, character as u16);
+            }
+            start[length] = next_code;
+        }
+        true
+    }
+
+    // Follow a code longer than the table through the left and right arrays
+    fn walk_tree(&self, mut value: u16, limit: usize, table_bits: u32) -> Option<u16> {
+        let mut mask = 1u32 << (BITBUFSIZ - 1 - table_bits);
+        while value as usize >= limit {
+            if value as usize >= self.left.len() || mask == 0 {
+                return None;
+            }
+            value = if self.bit_buffer & mask != 0 {
+                self.right[value as usize]
+            } else {
+                self.left[value as usize]
+            };
+            mask >>= 1;
+        }
+        Some(value)
+    }
+
+    fn read_pt_len(&mut self, count: usize, bits: u32, special: Option<usize>) -> bool {
+        let number = self.get_bits(bits) as usize;
+        if number == 0 {
+            let character = self.get_bits(bits) as u16;
+            self.pt_table
+                .iter_mut()
+                .for_each(|value| *value = character);
+            self.pt_len[..count]
+                .iter_mut()
+                .for_each(|length| *length = 0);
+            return true;
+        }
+        if number > count {
+            return false;
+        }
+
+        let mut index = 0;
+        while index < number && index < NPT {
+            let mut character = self.bit_buffer >> (BITBUFSIZ - 3);
+            if character == 7 {
+                let mut mask = 1u32 << (BITBUFSIZ - 1 - 3);
+                while mask & self.bit_buffer != 0 {
+                    mask >>= 1;
+                    character += 1;
+                }
+            }
+            if character > 16 {
+                return false;
+            }
+            self.fill_buffer(if character < 7 { 3 } else { character - 3 });
+            self.pt_len[index] = character as u8;
+            index += 1;
+            if Some(index) == special {
+                let zeros = self.get_bits(2);
+                for _ in 0..zeros {
+                    if index >= NPT {
+                        break;
+                    }
+                    self.pt_len[index] = 0;
+                    index += 1;
+                }
+            }
+        }
+        while index < count && index < NPT {
+            self.pt_len[index] = 0;
+            index += 1;
+        }
+        let lengths = self.pt_len[..count].to_vec();
+        self.make_table(&lengths, 8, true)
+    }
+
+    fn read_c_len(&mut self) -> bool {
+        let number = self.get_bits(CBIT) as usize;
+        if number == 0 {
+            let character = self.get_bits(CBIT) as u16;
+            self.c_len.iter_mut().for_each(|length| *length = 0);
+            self.c_table.iter_mut().for_each(|value| *value = character);
+            return true;
+        }
+
+        let mut index = 0;
+        while index < number && index < NC {
+            let entry = self.pt_table[(self.bit_buffer >> (BITBUFSIZ - 8)) as usize];
+            let character = match self.walk_tree(entry, NT, 8) {
+                Some(character) => character as usize,
+                None => return false,
+            };
+            self.fill_buffer(self.pt_len[character] as u32);
+            if character <= 2 {
+                let zeros = match character {
+                    0 => 1,
+                    1 => self.get_bits(4) as usize + 3,
+                    _ => self.get_bits(CBIT) as usize + 20,
+                };
+                for _ in 0..zeros {
+                    if index >= NC {
+                        break;
+                    }
+                    self.c_len[index] = 0;
+                    index += 1;
+                }
+            } else {
+                self.c_len[index] = (character - 2) as u8;
+                index += 1;
+            }
+        }
+        self.c_len[index..]
+            .iter_mut()
+            .for_each(|length| *length = 0);
+        let lengths = self.c_len.clone();
+        self.make_table(&lengths, 12, false)
+    }
+
+    fn decode_c(&mut self) -> Option<usize> {
+        if self.block_size == 0 {
+            self.block_size = self.get_bits(16) as u16;
+            let p_bit = self.p_bit;
+            if !self.read_pt_len(NT, TBIT, Some(3))
+                || !self.read_c_len()
+                || !self.read_pt_len(MAXNP, p_bit, None)
+            {
+                return None;
+            }
+        }
+        self.block_size = self.block_size.wrapping_sub(1);
+        let entry = self.c_table[(self.bit_buffer >> (BITBUFSIZ - 12)) as usize];
+        let character = self.walk_tree(entry, NC, 12)? as usize;
+        self.fill_buffer(self.c_len[character] as u32);
+        Some(character)
+    }
+
+    fn decode_p(&mut self) -> Option<usize> {
+        let entry = self.pt_table[(self.bit_buffer >> (BITBUFSIZ - 8)) as usize];
+        let value = self.walk_tree(entry, MAXNP, 8)? as u32;
+        self.fill_buffer(self.pt_len[value as usize] as u32);
+        if value > 1 {
+            Some(((1u32 << (value - 1)) + self.get_bits(value - 1)) as usize)
+        } else {
+            Some(value as usize)
+        }
+    }
+}
+
+// Header is a 32-bit compressed size followed by a 32-bit original size, p_bit is 4 for EFI 1.1 and 5 for Tiano
+fn efi_decompress(input: &[u8], p_bit: u32) -> Option<Vec<u8>> {
+    if input.len() < 8 {
+        return None;
+    }
+    let compressed_size = u32::from_le_bytes([input[0], input[1], input[2], input[3]]) as usize;
+    let original_size = u32::from_le_bytes([input[4], input[5], input[6], input[7]]) as usize;
+    if compressed_size > input.len() - 8 || original_size > MAX_DECOMPRESSED_SIZE {
+        return None;
+    }
+
+    let mut decoder = EfiDecoder {
+        source: &input[8..],
+        position: 0,
+        compressed_size,
+        bit_buffer: 0,
+        sub_bit_buffer: 0,
+        bit_count: 0,
+        block_size: 0,
+        p_bit,
+        left: vec![0; 2 * NC - 1],
+        right: vec![0; 2 * NC - 1],
+        c_len: vec![0; NC],
+        pt_len: vec![0; NPT],
+        c_table: vec![0; 4096],
+        pt_table: vec![0; 256],
+    };
+    decoder.fill_buffer(BITBUFSIZ);
+
+    let mut output = Vec::with_capacity(original_size);
+    while output.len() < original_size {
+        let character = decoder.decode_c()?;
+        if character < 256 {
+            output.push(character as u8);
+        } else {
+            let length = character - (0xFF + 1 - THRESHOLD);
+            let distance = decoder.decode_p()? + 1;
+            if distance > output.len() {
+                return None;
+            }
+            let start = output.len() - distance;
+            for index in start..start + length {
+                if output.len() >= original_size {
+                    break;
+                }
+                output.push(output[index]);
+            }
+        }
+    }
+    Some(output)
+}
+
+//
+// LZMA decompression
+//
+const PROBABILITY_BITS: u32 = 11;
+const PROBABILITY_INIT: u16 = 1 << (PROBABILITY_BITS - 1);
+const TOP_VALUE: u32 = 1 << 24;
+const NUM_STATES: usize = 12;
+const END_POS_MODEL_INDEX: usize = 14;
+const NUM_FULL_DISTANCES: usize = 1 << (END_POS_MODEL_INDEX >> 1);
+const NUM_ALIGN_BITS: usize = 4;
+const MATCH_MIN_LENGTH: usize = 2;
+
+struct RangeDecoder<'a> {
+    input: &'a [u8],
+    position: usize,
+    range: u32,
+    code: u32,
+    corrupted: bool,
+}
+
+impl<'a> RangeDecoder<'a> {
+    fn next_byte(&mut self) -> u8 {
+        match self.input.get(self.position) {
+            Some(&byte) => {
+                self.position += 1;
+                byte
+            }
+            None => {
+                self.corrupted = true;
+                0
+            }
+        }
+    }
+
+    fn new(input: &'a [u8]) -> Option<RangeDecoder<'a>> {
+        let mut decoder = RangeDecoder {
+            input,
+            position: 0,
+            range: 0xFFFF_FFFF,
+            code: 0,
+            corrupted: false,
+        };
+        if decoder.next_byte() != 0 {
+            return None;
+        }
+        for _ in 0..4 {
+            decoder.code = (decoder.code << 8) | decoder.next_byte() as u32;
+        }
+        if decoder.code == decoder.range || decoder.corrupted {
+            return None;
+        }
+        Some(decoder)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.code == 0
+    }
+
+    fn normalize(&mut self) {
+        if self.range < TOP_VALUE {
+            self.range <<= 8;
+            self.code = (self.code << 8) | self.next_byte() as u32;
+        }
+    }
+
+    fn direct_bits(&mut self, count: usize) -> u32 {
+        let mut result = 0u32;
+        for _ in 0..count {
+            self.range >>= 1;
+            self.code = self.code.wrapping_sub(self.range);
+            let t = 0u32.wrapping_sub(self.code >> 31);
+            self.code = self.code.wrapping_add(self.range & t);
+            if self.code == self.range {
+                self.corrupted = true;
+            }
+            self.normalize();
+            result = (result << 1).wrapping_add(t.wrapping_add(1));
+        }
+        result
+    }
+
+    fn bit(&mut self, probability: &mut u16) -> usize {
+        let bound = (self.range >> PROBABILITY_BITS) * *probability as u32;
+        let symbol = if self.code < bound {
+            *probability += ((1 << PROBABILITY_BITS) - *probability) >> 5;
+            self.range = bound;
+            0
+        } else {
+            *probability -= *probability >> 5;
+            self.code -= bound;
+            self.range -= bound;
+            1
+        };
+        self.normalize();
+        symbol
+    }
+
+    fn bit_tree(&mut self, probabilities: &mut [u16], bits: usize) -> usize {
+        let mut m = 1;
+        for _ in 0..bits {
+            m = (m << 1) + self.bit(&mut probabilities[m]);
+        }
+        m - (1 << bits)
+    }
+
+    fn reverse_bit_tree(&mut self, probabilities: &mut [u16], bits: usize) -> usize {
+        let mut m = 1;
+        let mut symbol = 0;
+        for index in 0..bits {
+            let bit = self.bit(&mut probabilities[m]);
+            m = (m << 1) + bit;
+            symbol |= bit << index;
+        }
+        symbol
+    }
+}
+
+struct LengthDecoder {
+    choice: u16,
+    choice2: u16,
+    low: Vec<u16>,  // 16 trees of 3 bits
+    mid: Vec<u16>,  // 16 trees of 3 bits
+    high: Vec<u16>, // 8 bits
+}
+
+impl LengthDecoder {
+    fn new() -> LengthDecoder {
+        LengthDecoder {
+            choice: PROBABILITY_INIT,
+            choice2: PROBABILITY_INIT,
+            low: vec![PROBABILITY_INIT; 16 << 3],
+            mid: vec![PROBABILITY_INIT; 16 << 3],
+            high: vec![PROBABILITY_INIT; 1 << 8],
+        }
+    }
+
+    fn decode(&mut self, decoder: &mut RangeDecoder, pos_state: usize) -> usize {
+        if decoder.bit(&mut self.choice) == 0 {
+            return decoder.bit_tree(&mut self.low[pos_state << 3..(pos_state + 1) << 3], 3);
+        }
+        if decoder.bit(&mut self.choice2) == 0 {
+            return 8 + decoder.bit_tree(&mut self.mid[pos_state << 3..(pos_state + 1) << 3], 3);
+        }
+        16 + decoder.bit_tree(&mut self.high, 8)
+    }
+}
+
+// Header is a properties byte, a 32-bit dictionary size and a 64-bit uncompressed size, all ones if unknown
+fn lzma_decompress(input: &[u8]) -> Option<Vec<u8>> {
+    if input.len() < 13 {
+        return None;
+    }
+    let mut properties = input[0] as usize;
+    if properties >= 9 * 5 * 5 {
+        return None;
+    }
+    let lc = properties % 9;
+    properties /= 9;
+    let lp = properties % 5;
+    let pb = properties / 5;
+    let dictionary_size =
+        u32::from_le_bytes([input[1], input[2], input[3], input[4]]).max(1 << 12) as usize;
+    let mut size_bytes = [0u8; 8];
+    size_bytes.copy_from_slice(&input[5..13]);
+    let declared_size = u64::from_le_bytes(size_bytes);
+    let size_known = declared_size != u64::MAX;
+    if size_known && declared_size > MAX_DECOMPRESSED_SIZE as u64 {
+        return None;
+    }
+    let mut remaining = declared_size as usize;
+
+    let mut decoder = RangeDecoder::new(&input[13..])?;
+    let mut literals = vec![PROBABILITY_INIT; 0x300 << (lc + lp)];
+    let mut pos_slots = vec![PROBABILITY_INIT; 4 << 6];
+    let mut pos_decoders = vec![PROBABILITY_INIT; 1 + NUM_FULL_DISTANCES - END_POS_MODEL_INDEX];
+    let mut align = vec![PROBABILITY_INIT; 1 << NUM_ALIGN_BITS];
+    let mut is_match = vec![PROBABILITY_INIT; NUM_STATES << 4];
+    let mut is_rep = [PROBABILITY_INIT; NUM_STATES];
+    let mut is_rep_g0 = [PROBABILITY_INIT; NUM_STATES];
+    let mut is_rep_g1 = [PROBABILITY_INIT; NUM_STATES];
+    let mut is_rep_g2 = [PROBABILITY_INIT; NUM_STATES];
+    let mut is_rep0_long = vec![PROBABILITY_INIT; NUM_STATES << 4];
+    let mut lengths = LengthDecoder::new();
+    let mut rep_lengths = LengthDecoder::new();
+
+    let mut output: Vec<u8> = Vec::with_capacity(if size_known { remaining } else { 0 });
+    let mut state = 0usize;
+    let mut reps = [0usize; 4];
+    loop {
+        if decoder.corrupted || output.len() > MAX_DECOMPRESSED_SIZE {
+            return None;
+        }
+        if size_known && remaining == 0 && decoder.is_finished() {
+            break;
+        }
+
+        let pos_state = output.len() & ((1 << pb) - 1);
+        if decoder.bit(&mut is_match[(state << 4) + pos_state]) == 0 {
+            if size_known && remaining == 0 {
+                return None;
+            }
+            let previous = output.last().copied().unwrap_or(0) as usize;
+            let literal_state = ((output.len() & ((1 << lp) - 1)) << lc) + (previous >> (8 - lc));
+            let probabilities = &mut literals[0x300 * literal_state..0x300 * (literal_state + 1)];
+            let mut symbol = 1usize;
+            if state >= 7 {
+                let mut match_byte = *output.get(output.len().wrapping_sub(reps[0] + 1))? as usize;
+                while symbol < 0x100 {
+                    let match_bit = (match_byte >> 7) & 1;
+                    match_byte <<= 1;
+                    let bit = decoder.bit(&mut probabilities[((1 + match_bit) << 8) + symbol]);
+                    symbol = (symbol << 1) | bit;
+                    if match_bit != bit {
+                        break;
+                    }
+                }
+            }
+            while symbol < 0x100 {
+                symbol = (symbol << 1) | decoder.bit(&mut probabilities[symbol]);
+            }
+            output.push((symbol - 0x100) as u8);
+            state = match state {
+                0..=3 => 0,
+                4..=9 => state - 3,
+                _ => state - 6,
+            };
+            remaining = remaining.wrapping_sub(1);
+            continue;
+        }
+
+        let mut length;
+        if decoder.bit(&mut is_rep[state]) != 0 {
+            if (size_known && remaining == 0) || output.is_empty() {
+                return None;
+            }
+            if decoder.bit(&mut is_rep_g0[state]) == 0 {
+                if decoder.bit(&mut is_rep0_long[(state << 4) + pos_state]) == 0 {
+                    state = if state < 7 { 9 } else { 11 };
+                    let byte = *output.get(output.len().wrapping_sub(reps[0] + 1))?;
+                    output.push(byte);
+                    remaining = remaining.wrapping_sub(1);
+                    continue;
+                }
+            } else {
+                let distance;
+                if decoder.bit(&mut is_rep_g1[state]) == 0 {
+                    distance = reps[1];
+                } else {
+                    if decoder.bit(&mut is_rep_g2[state]) == 0 {
+                        distance = reps[2];
+                    } else {
+                        distance = reps[3];
+                        reps[3] = reps[2];
+                    }
+                    reps[2] = reps[1];
+                }
+                reps[1] = reps[0];
+                reps[0] = distance;
+            }
+            length = rep_lengths.decode(&mut decoder, pos_state);
+            state = if state < 7 { 8 } else { 11 };
+        } else {
+            reps[3] = reps[2];
+            reps[2] = reps[1];
+            reps[1] = reps[0];
+            length = lengths.decode(&mut decoder, pos_state);
+            state = if state < 7 { 7 } else { 10 };
+
+            // Distance slot depends on the length, short distances are coded with their own trees
+            let length_state = length.min(3);
+            let pos_slot = decoder.bit_tree(
+                &mut pos_slots[length_state << 6..(length_state + 1) << 6],
+                6,
+            );
+            let distance = if pos_slot < 4 {
+                pos_slot as u32
+            } else {
+                let direct_bits = (pos_slot >> 1) - 1;
+                let base = (2 | (pos_slot & 1)) << direct_bits;
+                if pos_slot < END_POS_MODEL_INDEX {
+                    let probabilities = &mut pos_decoders[base - pos_slot..];
+                    (base + decoder.reverse_bit_tree(probabilities, direct_bits)) as u32
+                } else {
+                    let high = decoder.direct_bits(direct_bits - NUM_ALIGN_BITS) << NUM_ALIGN_BITS;
+                    (base as u32)
+                        .wrapping_add(high)
+                        .wrapping_add(decoder.reverse_bit_tree(&mut align, NUM_ALIGN_BITS) as u32)
+                }
+            };
+            // End marker
+            if distance == u32::MAX {
+                if decoder.is_finished() && (!size_known || remaining == 0) {
+                    break;
+                }
+                return None;
+            }
+            reps[0] = distance as usize;
+            if (size_known && remaining == 0)
+                || reps[0] >= dictionary_size
+                || reps[0] >= output.len()
+            {
+                return None;
+            }
+        }
+
+        length += MATCH_MIN_LENGTH;
+        let truncated = size_known && remaining < length;
+        if truncated {
+            length = remaining;
+        }
+        let start = output.len().checked_sub(reps[0] + 1)?;
+        for index in start..start + length {
+            output.push(output[index]);
+        }
+        remaining = remaining.wrapping_sub(length);
+        if truncated {
+            return None;
+        }
+    }
+    Some(output)
+}
+
+// Undo the x86 branch conversion, relative CALL and JMP targets were made absolute before compression
+fn x86_convert(data: &mut [u8]) {
+    const ALLOWED: [bool; 8] = [true, true, true, false, true, false, false, false];
+    const BIT_NUMBER: [u32; 8] = [0, 1, 2, 2, 3, 3, 3, 3];
+    let is_ms_byte = |byte: u8| byte == 0 || byte == 0xFF;
+    if data.len() < 5 {
+        return;
+    }
+
+    let ip: u32 = 5;
+    let mut previous_mask: u32 = 0;
+    let mut previous_position = usize::MAX;
+    let mut position = 0;
+    let limit = data.len() - 4;
+    loop {
+        while position < limit && data[position] & 0xFE != 0xE8 {
+            position += 1;
+        }
+        if position >= limit {
+            break;
+        }
+        let distance = position.wrapping_sub(previous_position);
+        if distance > 3 {
+            previous_mask = 0;
+        } else {
+            previous_mask = (previous_mask << (distance - 1)) & 0x7;
+            if previous_mask != 0 {
+                let byte = data[position + 4 - BIT_NUMBER[previous_mask as usize] as usize];
+                if !ALLOWED[previous_mask as usize] || is_ms_byte(byte) {
+                    previous_position = position;
+                    previous_mask = ((previous_mask << 1) & 0x7) | 1;
+                    position += 1;
+                    continue;
+                }
+            }
+        }
+        previous_position = position;
+
+        if is_ms_byte(data[position + 4]) {
+            let mut source = u32::from_le_bytes([
+                data[position + 1],
+                data[position + 2],
+                data[position + 3],
+                data[position + 4],
+            ]);
+            let mut destination;
+            loop {
+                destination = source.wrapping_sub(ip.wrapping_add(position as u32));
+                if previous_mask == 0 {
+                    break;
+                }
+                let index = BIT_NUMBER[previous_mask as usize] * 8;
+                let byte = (destination >> (24 - index)) as u8;
+                if !is_ms_byte(byte) {
+                    break;
+                }
+                source = destination ^ ((1u32 << (32 - index)) - 1);
+            }
+            data[position + 4] = !(((destination >> 24) & 1).wrapping_sub(1)) as u8;
+            data[position + 3] = (destination >> 16) as u8;
+            data[position + 2] = (destination >> 8) as u8;
+            data[position + 1] = destination as u8;
+            position += 5;
+        } else {
+            previous_mask = ((previous_mask << 1) & 0x7) | 1;
+            position += 1;
+        }
+    }
+}
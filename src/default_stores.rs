@@ -0,0 +1,87 @@
+// DefaultStores declared by UEFI formsets, so callers can pick a default store by its name or class
+// instead of assuming DefaultIds 0 and 1 are the standard and manufacturing ones
+use super::{uefi_string_map, ExtractOptions, FormPackage, HiiResources, StringPackage};
+use ifr_tree::parse_uefi_ifr;
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultStoreDeclaration {
+    pub offset: usize, // Offset of the DefaultStore opcode in the input data
+    pub formset_guid: uefi_parser::Guid,
+    pub default_id: u16,
+    pub name_string_id: u16,
+    pub name: String,
+}
+
+impl DefaultStoreDeclaration {
+    pub fn default_class(&self) -> &'static str {
+        default_class(self.default_id)
+    }
+}
+
+// Default class of a DefaultId as defined by the UEFI specification
+pub fn default_class(default_id: u16) -> &'static str {
+    match default_id {
+        0x0000 => "standard",
+        0x0001 => "manufacturing",
+        0x0002 => "safe",
+        0x4000..=0x7FFF => "platform",
+        0x8000..=0xBFFF => "hardware",
+        0xC000..=0xFFFF => "firmware",
+        _ => "reserved",
+    }
+}
+
+// DefaultStores of all formsets of a UEFI form package in the order they are declared,
+// strings are resolved the same way extract_uefi_ifr does
+pub fn default_stores(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+) -> Vec<DefaultStoreDeclaration> {
+    let options = ExtractOptions::default();
+    let strings_map = uefi_string_map(data, form_package, string_package, resources, &options);
+    let tree = parse_uefi_ifr(data, form_package, string_package, resources);
+
+    let mut result = Vec::new();
+    for root in &tree.roots {
+        if root.opcode != uefi_parser::IfrOpcode::FormSet {
+            continue;
+        }
+        let formset_guid = match uefi_parser::ifr_form_set(&root.data) {
+            Ok((_, form_set)) => form_set.Guid,
+            Err(_) => continue,
+        };
+        // DefaultStores are declared in the scope of their formset
+        for child in &root.children {
+            if child.opcode != uefi_parser::IfrOpcode::DefaultStore {
+                continue;
+            }
+            if let Ok((_, default_store)) = uefi_parser::ifr_default_store(&child.data) {
+                result.push(DefaultStoreDeclaration {
+                    offset: child.offset,
+                    formset_guid,
+                    default_id: default_store.DefaultId,
+                    name_string_id: default_store.NameStringId,
+                    name: strings_map.resolve(default_store.NameStringId).into_owned(),
+                });
+            }
+        }
+    }
+    result
+}
+
+// DefaultId of a DefaultStore given by its name, compared case-insensitively, or by its class name,
+// i.e. "manufacturing". Declared names take precedence over classes
+pub fn find_default_id(stores: &[DefaultStoreDeclaration], query: &str) -> Option<u16> {
+    stores
+        .iter()
+        .find(|store| store.name.eq_ignore_ascii_case(query))
+        .or_else(|| {
+            stores
+                .iter()
+                .find(|store| store.default_class().eq_ignore_ascii_case(query))
+        })
+        .map(|store| store.default_id)
+}
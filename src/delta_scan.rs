@@ -0,0 +1,121 @@
+// Rescan of a modified image that reuses packages found in its previous version, for patched dumps and appended images.
+// Both images are compared from the start and from the end, packages in unchanged bytes are kept
+// and only the region between them is scanned again
+use std::ops::Range;
+
+use super::{scan_framework_packages, scan_uefi_packages, FormPackage, ScanOptions, StringPackage};
+use scan_metrics::ScanMetrics;
+
+type Packages = (Vec<StringPackage>, Vec<FormPackage>);
+
+// Rescan UEFI packages, old_packages are the result of find_uefi_packages on old_data
+pub fn rescan_uefi_packages(old_data: &[u8], old_packages: Packages, new_data: &[u8]) -> Packages {
+    rescan(old_data, old_packages, new_data, scan_uefi_packages)
+}
+
+// Rescan Framework packages, old_packages are the result of find_framework_packages on old_data
+pub fn rescan_framework_packages(
+    old_data: &[u8],
+    old_packages: Packages,
+    new_data: &[u8],
+) -> Packages {
+    rescan(old_data, old_packages, new_data, scan_framework_packages)
+}
+
+// Range of the new image that has to be scanned again. Packages don't overlap,
+// so the range starts after the last reused package before the change and ends at the first one after it
+pub fn changed_region(old_data: &[u8], old_packages: &Packages, new_data: &[u8]) -> Range<usize> {
+    let (prefix, suffix) = common_lengths(old_data, new_data);
+    let mut region = 0..new_data.len();
+    for (offset, length) in package_extents(old_packages) {
+        if offset + length <= prefix {
+            region.start = region.start.max(offset + length);
+        } else if offset >= old_data.len() - suffix {
+            region.end = region.end.min(offset + new_data.len() - old_data.len());
+        }
+    }
+    region
+}
+
+// Lengths of the common prefix and the common suffix, they never overlap
+fn common_lengths(old_data: &[u8], new_data: &[u8]) -> (usize, usize) {
+    let prefix = old_data
+        .iter()
+        .zip(new_data)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let limit = old_data.len().min(new_data.len()) - prefix;
+    let suffix = old_data
+        .iter()
+        .rev()
+        .zip(new_data.iter().rev())
+        .take(limit)
+        .take_while(|(old, new)| old == new)
+        .count();
+    (prefix, suffix)
+}
+
+fn package_extents(packages: &Packages) -> Vec<(usize, usize)> {
+    let strings = packages
+        .0
+        .iter()
+        .map(|string| (string.offset, string.length));
+    let forms = packages.1.iter().map(|form| (form.offset, form.length));
+    strings.chain(forms).collect()
+}
+
+fn rescan(
+    old_data: &[u8],
+    old_packages: Packages,
+    new_data: &[u8],
+    scan: fn(&[u8], &ScanOptions, &mut ScanMetrics, bool) -> Packages,
+) -> Packages {
+    let region = changed_region(old_data, &old_packages, new_data);
+    let (prefix, suffix) = common_lengths(old_data, new_data);
+    let (old_strings, old_forms) = old_packages;
+    let (mut region_strings, mut region_forms) = scan(
+        &new_data[region.clone()],
+        &ScanOptions::default(),
+        &mut ScanMetrics::new(),
+        true,
+    );
+    for string in &mut region_strings {
+        string.offset += region.start;
+    }
+    for form in &mut region_forms {
+        form.offset += region.start;
+    }
+
+    // Reused packages before the region, rescanned ones, then reused packages after the region moved to their new offsets
+    let mut strings = Vec::new();
+    let mut strings_after = Vec::new();
+    for mut string in old_strings {
+        if string.offset + string.length <= prefix {
+            strings.push(string);
+        } else if string.offset >= old_data.len() - suffix {
+            string.offset = string.offset + new_data.len() - old_data.len();
+            strings_after.push(string);
+        }
+    }
+    strings.append(&mut region_strings);
+    strings.append(&mut strings_after);
+
+    let mut forms = Vec::new();
+    let mut forms_after = Vec::new();
+    for mut form in old_forms {
+        if form.offset + form.length <= prefix {
+            forms.push(form);
+        } else if form.offset >= old_data.len() - suffix {
+            form.offset = form.offset + new_data.len() - old_data.len();
+            forms_after.push(form);
+        }
+    }
+    forms.append(&mut region_forms);
+    forms.append(&mut forms_after);
+
+    // Same as a full scan, nothing is returned unless both kinds of packages are found
+    if strings.is_empty() || forms.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    (strings, forms)
+}
@@ -0,0 +1,364 @@
+// UEFI HII device path packages and conversion of device paths into their text representation,
+// so forms can report which controller their package list was installed for
+use pyo3::prelude::*;
+
+use super::{prefilter, sha256, uefi_same_package_list, FormPackage, ScanOptions};
+use uefi_parser::{self, DevicePathNode, Guid};
+
+pub struct DevicePathPackage {
+    pub offset: usize,
+    pub length: usize,
+    pub text: String, // i.e. PciRoot(0x0)/Pci(0x1f,0x2)/Sata(0x0,0xffff,0x0)
+    pub sha256: [u8; 32],
+}
+
+// Search for all device path packages in the input file
+pub fn find_uefi_device_path_packages(data: &[u8]) -> Vec<DevicePathPackage> {
+    find_uefi_device_path_packages_with_options(data, &ScanOptions::default())
+}
+
+// Same as find_uefi_device_path_packages, only searching a window of the input
+pub fn find_uefi_device_path_packages_with_options(data: &[u8], options: &ScanOptions) -> Vec<DevicePathPackage> {
+    let mut result = Vec::new();
+
+    let window = options.window(data.len());
+    let mut i = window.start;
+    while i < window.end {
+        i = options.next_offset(prefilter::UEFI_DEVICE_PATH_PACKAGE.next_candidate(data, i));
+        if i >= window.end {
+            break;
+        }
+        if let Ok((_, candidate)) = uefi_parser::hii_device_path_package_candidate(&data[i..]) {
+            // Device path fills the whole package
+            if let Ok((rest, nodes)) = uefi_parser::device_path_nodes(&candidate[4..]) {
+                if rest.is_empty() {
+                    result.push(DevicePathPackage {
+                        offset: i,
+                        length: candidate.len(),
+                        text: nodes_to_text(&nodes),
+                        sha256: sha256(candidate),
+                    });
+                    i += candidate.len();
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    result
+}
+
+// Device path package in the same package list as a form package
+pub fn uefi_device_path_package<'a>(
+    data: &[u8],
+    device_paths: &'a [DevicePathPackage],
+    form_package: &FormPackage,
+) -> Option<&'a DevicePathPackage> {
+    device_paths
+        .iter()
+        .find(|device_path| uefi_same_package_list(data, device_path.offset, form_package.offset))
+}
+
+// Text representation of a binary device path. Nodes are separated by slashes and instances by commas,
+// nodes without a text form of their own use the generic forms, i.e. HardwarePath(6,0102).
+// None if the device path is malformed or not terminated
+pub fn device_path_to_text(device_path: &[u8]) -> Option<String> {
+    uefi_parser::device_path_nodes(device_path)
+        .ok()
+        .map(|(_, nodes)| nodes_to_text(&nodes))
+}
+
+#[pyfunction]
+#[pyo3(name = "device_path_to_text")]
+pub fn py_device_path_to_text(device_path: Vec<u8>) -> Option<String> {
+    device_path_to_text(&device_path)
+}
+
+fn nodes_to_text(nodes: &[DevicePathNode]) -> String {
+    let mut text = String::new();
+    let mut separator = "";
+    for node in nodes {
+        match (node.Type, node.SubType) {
+            (0x7F, 0xFF) => break, // End Entire Device Path
+            (0x7F, 0x01) => separator = ",", // End This Instance of a Device Path
+            _ => {
+                text.push_str(separator);
+                text.push_str(&node_to_text(node));
+                separator = "/";
+            }
+        }
+    }
+    text
+}
+
+// Little-endian readers for node data, missing bytes are read as zeroes
+fn read_u8(data: &[u8], offset: usize) -> u8 {
+    data.get(offset).copied().unwrap_or(0)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([read_u8(data, offset), read_u8(data, offset + 1)])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    read_u16(data, offset) as u32 | (read_u16(data, offset + 2) as u32) << 16
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    read_u32(data, offset) as u64 | (read_u32(data, offset + 4) as u64) << 32
+}
+
+fn read_guid(data: &[u8], offset: usize) -> Guid {
+    let mut bytes = [0u8; 16];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = read_u8(data, offset + index);
+    }
+    Guid::from_bytes_le(&bytes)
+}
+
+fn hex_bytes(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn ipv4_text(data: &[u8], offset: usize) -> String {
+    let address: Vec<String> = (0..4).map(|index| read_u8(data, offset + index).to_string()).collect();
+    address.join(".")
+}
+
+// Compressed EISA ID of ACPI nodes, i.e. PNP0A03
+fn eisa_id_text(id: u32) -> String {
+    let letter = |shift: u32| (b'@' + ((id >> shift) & 0x1F) as u8) as char;
+    format!("{}{}{}{:04X}", letter(10), letter(5), letter(0), id >> 16)
+}
+
+// Vendor-defined node with its GUID and data, i.e. VenHw(GUID) or VenHw(GUID,0102)
+fn vendor_text(prefix: &str, data: &[u8]) -> String {
+    let guid = read_guid(data, 0);
+    match data.get(16..) {
+        Some(extra) if !extra.is_empty() => format!("{}({},{})", prefix, guid, hex_bytes(extra)),
+        _ => format!("{prefix}({guid})"),
+    }
+}
+
+// Well-known terminal types of VenMsg nodes
+const TERMINAL_TYPES: &[(&str, &str)] = &[
+    ("E0C14753-F9BE-11D2-9A0C-0090273FC14D", "VenPcAnsi"),
+    ("DFA66065-B419-11D3-9A2D-0090273FC14D", "VenVt100"),
+    ("7BAEC70B-57E0-4C76-8E87-2F9E28088343", "VenVt100Plus"),
+    ("AD15A0D6-8BEC-4ACF-A073-D01DE77E2D88", "VenUtf8"),
+];
+
+fn node_to_text(node: &DevicePathNode) -> String {
+    let data = node.Data;
+    match (node.Type, node.SubType) {
+        // Hardware
+        (0x01, 0x01) => format!("Pci(0x{:x},0x{:x})", read_u8(data, 1), read_u8(data, 0)),
+        (0x01, 0x02) => format!("PcCard(0x{:x})", read_u8(data, 0)),
+        (0x01, 0x03) => format!(
+            "MemoryMapped(0x{:x},0x{:x},0x{:x})",
+            read_u32(data, 0),
+            read_u64(data, 4),
+            read_u64(data, 12)
+        ),
+        (0x01, 0x04) => vendor_text("VenHw", data),
+        (0x01, 0x05) => format!("Ctrl(0x{:x})", read_u32(data, 0)),
+        (0x01, 0x06) => format!("BMC(0x{:x},0x{:x})", read_u8(data, 0), read_u64(data, 1)),
+
+        // ACPI
+        (0x02, 0x01) => {
+            let (hid, uid) = (read_u32(data, 0), read_u32(data, 4));
+            if hid & 0xFFFF == 0x41D0 {
+                match hid >> 16 {
+                    0x0A03 => format!("PciRoot(0x{uid:x})"),
+                    0x0A08 => format!("PcieRoot(0x{uid:x})"),
+                    0x0604 => format!("Floppy(0x{uid:x})"),
+                    0x0301 => format!("Keyboard(0x{uid:x})"),
+                    0x0501 => format!("Serial(0x{uid:x})"),
+                    0x0401 => format!("ParallelPort(0x{uid:x})"),
+                    _ => format!("Acpi(PNP{:04X},0x{:x})", hid >> 16, uid),
+                }
+            } else {
+                format!("Acpi(0x{hid:08x},0x{uid:x})")
+            }
+        }
+        (0x02, 0x02) => {
+            // HID, UID and CID are followed by HIDSTR, UIDSTR and CIDSTR
+            let (hid, uid, cid) = (read_u32(data, 0), read_u32(data, 4), read_u32(data, 8));
+            let strings: Vec<String> = data
+                .get(12..)
+                .unwrap_or(&[])
+                .split(|byte| *byte == 0)
+                .take(3)
+                .map(|string| String::from_utf8_lossy(string).into_owned())
+                .collect();
+            let string = |index: usize| strings.get(index).cloned().unwrap_or_default();
+            format!(
+                "AcpiEx({},{},0x{:x},{},{},{})",
+                eisa_id_text(hid),
+                eisa_id_text(cid),
+                uid,
+                string(0),
+                string(2),
+                string(1)
+            )
+        }
+        (0x02, 0x03) => {
+            let addresses: Vec<String> = data
+                .chunks_exact(4)
+                .map(|address| format!("0x{:x}", read_u32(address, 0)))
+                .collect();
+            format!("AcpiAdr({})", addresses.join(","))
+        }
+
+        // Messaging
+        (0x03, 0x01) => format!(
+            "Ata({},{},0x{:x})",
+            if read_u8(data, 0) == 0 { "Primary" } else { "Secondary" },
+            if read_u8(data, 1) == 0 { "Master" } else { "Slave" },
+            read_u16(data, 2)
+        ),
+        (0x03, 0x02) => format!("Scsi(0x{:x},0x{:x})", read_u16(data, 0), read_u16(data, 2)),
+        (0x03, 0x03) => format!("Fibre(0x{:x},0x{:x})", read_u64(data, 4), read_u64(data, 12)),
+        (0x03, 0x04) => format!("I1394({:016X})", read_u64(data, 4)),
+        (0x03, 0x05) => format!("USB(0x{:x},0x{:x})", read_u8(data, 0), read_u8(data, 1)),
+        (0x03, 0x0A) => {
+            let guid = read_guid(data, 0).to_string();
+            match TERMINAL_TYPES.iter().find(|(terminal, _)| *terminal == guid) {
+                Some((_, name)) => format!("{name}()"),
+                None => vendor_text("VenMsg", data),
+            }
+        }
+        (0x03, 0x0B) => {
+            // 32 bytes of address, only 6 of them are used by Ethernet
+            let if_type = read_u8(data, 32);
+            let length = if if_type == 0x00 || if_type == 0x01 { 6 } else { 32 };
+            format!("MAC({},0x{:x})", hex_bytes(data.get(..length).unwrap_or(data)), if_type)
+        }
+        (0x03, 0x0C) => format!(
+            "IPv4({},{},{},{},{},{})",
+            ipv4_text(data, 4),
+            match read_u16(data, 12) {
+                6 => String::from("TCP"),
+                17 => String::from("UDP"),
+                protocol => format!("0x{protocol:x}"),
+            },
+            if read_u8(data, 14) == 0 { "DHCP" } else { "Static" },
+            ipv4_text(data, 0),
+            ipv4_text(data, 15),
+            ipv4_text(data, 19)
+        ),
+        (0x03, 0x0E) => format!(
+            "Uart({},{},{},{})",
+            read_u64(data, 4),
+            read_u8(data, 12),
+            match read_u8(data, 13) {
+                0 => "D",
+                1 => "N",
+                2 => "E",
+                3 => "O",
+                4 => "M",
+                5 => "S",
+                _ => "x",
+            },
+            match read_u8(data, 14) {
+                0 => "D",
+                1 => "1",
+                2 => "1.5",
+                3 => "2",
+                _ => "x",
+            }
+        ),
+        (0x03, 0x0F) => format!(
+            "UsbClass(0x{:x},0x{:x},0x{:x},0x{:x},0x{:x})",
+            read_u16(data, 0),
+            read_u16(data, 2),
+            read_u8(data, 4),
+            read_u8(data, 5),
+            read_u8(data, 6)
+        ),
+        (0x03, 0x11) => format!("Unit(0x{:x})", read_u8(data, 0)),
+        (0x03, 0x12) => format!(
+            "Sata(0x{:x},0x{:x},0x{:x})",
+            read_u16(data, 0),
+            read_u16(data, 2),
+            read_u16(data, 4)
+        ),
+        (0x03, 0x17) => {
+            // IEEE EUI-64 is printed from its most significant byte
+            let eui: Vec<String> = (0..8).rev().map(|index| format!("{:02X}", read_u8(data, 4 + index))).collect();
+            format!("NVMe(0x{:x},{})", read_u32(data, 0), eui.join("-"))
+        }
+        (0x03, 0x18) => format!("Uri({})", String::from_utf8_lossy(data)),
+        (0x03, 0x1A) => format!("SD(0x{:x})", read_u8(data, 0)),
+        (0x03, 0x1D) => format!("eMMC(0x{:x})", read_u8(data, 0)),
+
+        // Media
+        (0x04, 0x01) => {
+            let signature = match read_u8(data, 37) {
+                0x01 => format!("MBR,0x{:08x}", read_u32(data, 20)),
+                0x02 => format!("GPT,{}", read_guid(data, 20)),
+                signature_type => format!("{signature_type},0"),
+            };
+            format!(
+                "HD({},{},0x{:x},0x{:x})",
+                read_u32(data, 0),
+                signature,
+                read_u64(data, 4),
+                read_u64(data, 12)
+            )
+        }
+        (0x04, 0x02) => format!(
+            "CDROM(0x{:x},0x{:x},0x{:x})",
+            read_u32(data, 0),
+            read_u64(data, 4),
+            read_u64(data, 12)
+        ),
+        (0x04, 0x03) => vendor_text("VenMedia", data),
+        (0x04, 0x04) => {
+            // Path name is a null-terminated UCS-2 string
+            let name: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|pair| read_u16(pair, 0))
+                .take_while(|unit| *unit != 0)
+                .collect();
+            String::from_utf16_lossy(&name)
+        }
+        (0x04, 0x05) => format!("Media({})", read_guid(data, 0)),
+        (0x04, 0x06) => format!("FvFile({})", read_guid(data, 0)),
+        (0x04, 0x07) => format!("Fv({})", read_guid(data, 0)),
+        (0x04, 0x08) => format!("Offset(0x{:x},0x{:x})", read_u64(data, 4), read_u64(data, 12)),
+
+        // BIOS Boot Specification
+        (0x05, 0x01) => {
+            let device_type = match read_u16(data, 0) {
+                0x01 => String::from("Floppy"),
+                0x02 => String::from("HD"),
+                0x03 => String::from("CDROM"),
+                0x04 => String::from("PCMCIA"),
+                0x05 => String::from("USB"),
+                0x06 => String::from("Network"),
+                device_type => format!("0x{device_type:x}"),
+            };
+            let description = data.get(4..).unwrap_or(&[]).split(|byte| *byte == 0).next().unwrap_or(&[]);
+            format!(
+                "BBS({},{},0x{:x})",
+                device_type,
+                String::from_utf8_lossy(description),
+                read_u16(data, 2)
+            )
+        }
+
+        // Generic forms of the remaining nodes
+        (node_type, sub_type) => {
+            let name = match node_type {
+                0x01 => "HardwarePath",
+                0x02 => "AcpiPath",
+                0x03 => "Msg",
+                0x04 => "MediaPath",
+                0x05 => "BbsPath",
+                _ => return format!("Path({},{},{})", node_type, sub_type, hex_bytes(data)),
+            };
+            format!("{}({},{})", name, sub_type, hex_bytes(data))
+        }
+    }
+}
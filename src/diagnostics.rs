@@ -0,0 +1,178 @@
+// Diagnostics collected during extraction. Only the first occurrence of every kind is kept,
+// the following ones are counted, so a broken package doesn't flood the output with thousands of lines.
+// The library never prints them, callers decide where they go
+use std::collections::HashMap;
+use std::fmt;
+
+// Bytes taken before and after the offset of a location as its context
+pub const CONTEXT_BEFORE: usize = 8;
+pub const CONTEXT_AFTER: usize = 8;
+
+// Absolute offset in the input with a few bytes around it, so malformed input can be reported
+// without sharing the whole of it
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    pub offset: usize,
+    pub context_offset: usize, // Offset of the first context byte, lower than offset near the start of the input
+    pub context: Vec<u8>,      // Empty until resolved against the input
+}
+
+impl ErrorLocation {
+    // Location without context, see resolve
+    pub fn at(offset: usize) -> ErrorLocation {
+        ErrorLocation {
+            offset,
+            context_offset: offset,
+            context: Vec::new(),
+        }
+    }
+
+    pub fn new(data: &[u8], offset: usize) -> ErrorLocation {
+        let mut location = ErrorLocation::at(offset);
+        location.resolve(data);
+        location
+    }
+
+    // Take context bytes from the input, clamped to its bounds
+    pub fn resolve(&mut self, data: &[u8]) {
+        let start = self.offset.saturating_sub(CONTEXT_BEFORE).min(data.len());
+        let end = self.offset.saturating_add(CONTEXT_AFTER).min(data.len());
+        self.context_offset = start;
+        self.context = data[start..end.max(start)].to_vec();
+    }
+
+    // Context as hex bytes, the byte at the offset is put in brackets
+    pub fn context_hex(&self) -> String {
+        self.context
+            .iter()
+            .enumerate()
+            .map(|(index, byte)| {
+                if self.context_offset + index == self.offset {
+                    format!("[{byte:02X}]")
+                } else {
+                    format!("{byte:02X}")
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+// i.e. "offset 0x1234, context at 0x122C: 00 01 02 03 04 05 06 07 [08] 09 0A 0B 0C 0D 0E 0F"
+impl fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "offset 0x{:X}", self.offset)?;
+        if !self.context.is_empty() {
+            write!(f, ", context at 0x{:X}: {}", self.context_offset, self.context_hex())?;
+        }
+        Ok(())
+    }
+}
+
+// Single kind of diagnostic with details of its first occurrence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: String, // i.e. "OneOf parse error"
+    pub details: String,
+    pub count: usize,                      // Number of occurrences, the first one included
+    pub location: Option<ErrorLocation>,   // Location of the first occurrence, if it refers to the input
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostics {
+    warnings: Vec<Warning>,          // In order of their first occurrence
+    indices: HashMap<String, usize>, // Indices of warnings by kind
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics::default()
+    }
+
+    // Record a diagnostic, details are kept if this kind was not seen before
+    pub fn warn(&mut self, kind: &str, details: fmt::Arguments) {
+        self.record(kind, details, None);
+    }
+
+    // Same as warn for a diagnostic at an absolute offset in the input, context is taken by resolve_context
+    pub fn warn_at(&mut self, kind: &str, offset: usize, details: fmt::Arguments) {
+        self.record(kind, details, Some(ErrorLocation::at(offset)));
+    }
+
+    fn record(&mut self, kind: &str, details: fmt::Arguments, location: Option<ErrorLocation>) {
+        match self.indices.get(kind) {
+            Some(&index) => self.warnings[index].count += 1,
+            None => {
+                self.indices.insert(String::from(kind), self.warnings.len());
+                self.warnings.push(Warning {
+                    kind: String::from(kind),
+                    details: details.to_string(),
+                    count: 1,
+                    location,
+                });
+            }
+        }
+    }
+
+    // Take context bytes of all locations that don't have them yet from the input they refer to
+    pub fn resolve_context(&mut self, data: &[u8]) {
+        for location in self.warnings.iter_mut().filter_map(|warning| warning.location.as_mut()) {
+            if location.context.is_empty() {
+                location.resolve(data);
+            }
+        }
+    }
+
+    // Add diagnostics collected elsewhere, i.e. while extracting another package
+    pub fn merge(&mut self, other: Diagnostics) {
+        for warning in other.warnings {
+            match self.indices.get(&warning.kind) {
+                Some(&index) => self.warnings[index].count += warning.count,
+                None => {
+                    self.indices
+                        .insert(warning.kind.clone(), self.warnings.len());
+                    self.warnings.push(warning);
+                }
+            }
+        }
+    }
+
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    // Number of occurrences per kind, in order of their first occurrence
+    pub fn counts(&self) -> Vec<(&str, usize)> {
+        self.warnings
+            .iter()
+            .map(|warning| (warning.kind.as_str(), warning.count))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+// First occurrences as "kind: details", then how many occurrences of every kind were suppressed
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for warning in &self.warnings {
+            writeln!(f, "{}: {}", warning.kind, warning.details)?;
+            if let Some(location) = warning.location.as_ref().filter(|location| !location.context.is_empty()) {
+                writeln!(f, "    Context at 0x{:X}: {}", location.context_offset, location.context_hex())?;
+            }
+        }
+        for warning in &self.warnings {
+            if warning.count > 1 {
+                writeln!(
+                    f,
+                    "{}: {} more occurrence(s) suppressed",
+                    warning.kind,
+                    warning.count - 1
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// On-disk cache of extraction results, so re-analyzing the same firmware family is near-instant.
+// Every result is stored in its own file named by a SHA-256 of everything that affects the output:
+// program version, options, checksums and offsets of all packages involved
+use std::fs;
+use std::path::Path;
+
+use super::{hex_string, sha256, ExtractOptions, VERSION};
+
+// Builder of a cache key, offsets are included because they are printed in verbose mode
+pub struct CacheKey {
+    text: String,
+}
+
+impl CacheKey {
+    pub fn new(kind: &str, options: &ExtractOptions) -> CacheKey {
+        // The cache directory itself doesn't affect the output
+        let options = ExtractOptions {
+            cache: None,
+            ..options.clone()
+        };
+        CacheKey {
+            text: format!("{}\n{}\n{:?}\n", VERSION.unwrap_or("0.0.0"), kind, options),
+        }
+    }
+
+    pub fn package(mut self, kind: &str, offset: usize, checksum: &[u8; 32]) -> CacheKey {
+        self.text.push_str(&format!(
+            "{} 0x{:X} {}\n",
+            kind,
+            offset,
+            hex_string(checksum)
+        ));
+        self
+    }
+
+    pub fn file_name(&self) -> String {
+        hex_string(&sha256(self.text.as_bytes())) + ".txt"
+    }
+}
+
+// Cached text for a key, rendered and stored on a miss.
+// Cache errors are ignored, the result is rendered again instead
+pub fn cached(dir: &Path, key: &CacheKey, render: impl FnOnce() -> String) -> String {
+    let path = dir.join(key.file_name());
+    if let Ok(text) = fs::read_to_string(&path) {
+        return text;
+    }
+
+    let text = render();
+    // Write to a temporary file first, so concurrent runs never see a partial result
+    let temporary = dir.join(format!("{}.{}.tmp", key.file_name(), std::process::id()));
+    if fs::create_dir_all(dir).is_ok()
+        && fs::write(&temporary, &text).is_ok()
+        && fs::rename(&temporary, &path).is_err()
+    {
+        let _ = fs::remove_file(&temporary);
+    }
+    text
+}
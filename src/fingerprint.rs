@@ -0,0 +1,122 @@
+// Fingerprint of a UEFI form package computed over its opcode structure, so the same formset
+// can be recognized in images of different vendors that rebrand identical reference code
+use super::{sha256, FormPackage};
+use opcode_records::uefi_opcode_records;
+use uefi_parser;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FingerprintOptions {
+    pub ignore_string_ids: bool, // Strings are usually what gets rebranded
+    pub ignore_guids: bool,      // FormSet, VarStore and Guid opcode GUIDs
+}
+
+impl Default for FingerprintOptions {
+    fn default() -> Self {
+        FingerprintOptions {
+            ignore_string_ids: true,
+            ignore_guids: false,
+        }
+    }
+}
+
+// Offsets of StringId fields in opcode data
+fn string_id_offsets(opcode: uefi_parser::IfrOpcode, data: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    if opcode.is_question() {
+        // PromptStringId and HelpStringId of the question header
+        offsets.extend([0, 2]);
+    }
+    match opcode {
+        uefi_parser::IfrOpcode::Ref => offsets.push(31), // DevicePath of Ref4 and Ref5
+        uefi_parser::IfrOpcode::Action => offsets.push(11), // QuestionConfig
+        uefi_parser::IfrOpcode::FormSet => offsets.extend([16, 18]),
+        uefi_parser::IfrOpcode::Form => offsets.push(2),
+        uefi_parser::IfrOpcode::FormMap => offsets.extend((2..data.len()).step_by(18)),
+        uefi_parser::IfrOpcode::Subtitle | uefi_parser::IfrOpcode::ResetButton => {
+            offsets.extend([0, 2])
+        }
+        uefi_parser::IfrOpcode::Text => offsets.extend([0, 2, 4]),
+        uefi_parser::IfrOpcode::OneOfOption
+        | uefi_parser::IfrOpcode::DefaultStore
+        | uefi_parser::IfrOpcode::VarStoreDevice
+        | uefi_parser::IfrOpcode::InconsistentIf
+        | uefi_parser::IfrOpcode::NoSubmitIf
+        | uefi_parser::IfrOpcode::WarningIf
+        | uefi_parser::IfrOpcode::StringRef1
+        | uefi_parser::IfrOpcode::QuestionRef3 => offsets.push(0),
+        uefi_parser::IfrOpcode::Guid => {
+            // Title of the Tiano Banner extension
+            if let Ok((_, guid)) = uefi_parser::guid(data) {
+                if guid == uefi_parser::IFR_TIANO_GUID && data.get(16) == Some(&0x01) {
+                    offsets.push(17);
+                }
+            }
+        }
+        _ => {}
+    }
+    offsets
+}
+
+// Offsets of GUID fields in opcode data
+fn guid_offsets(opcode: uefi_parser::IfrOpcode, data: &[u8]) -> Vec<usize> {
+    match opcode {
+        // FormSet GUID followed by class GUIDs after Title, Help and Flags
+        uefi_parser::IfrOpcode::FormSet => {
+            let mut offsets = vec![0];
+            offsets.extend((21..data.len()).step_by(16));
+            offsets
+        }
+        uefi_parser::IfrOpcode::FormMap => (4..data.len()).step_by(18).collect(),
+        uefi_parser::IfrOpcode::Ref => vec![15],
+        uefi_parser::IfrOpcode::VarStoreNameValue
+        | uefi_parser::IfrOpcode::VarStoreEfi
+        | uefi_parser::IfrOpcode::QuestionRef3 => vec![2],
+        uefi_parser::IfrOpcode::VarStore
+        | uefi_parser::IfrOpcode::Guid
+        | uefi_parser::IfrOpcode::Security
+        | uefi_parser::IfrOpcode::RefreshId
+        | uefi_parser::IfrOpcode::Match2 => vec![0],
+        _ => Vec::new(),
+    }
+}
+
+// Zero a field of opcode data if the opcode is long enough to contain it
+fn clear_field(data: &mut [u8], offset: usize, size: usize) {
+    if offset + size <= data.len() {
+        data[offset..offset + size]
+            .iter_mut()
+            .for_each(|byte| *byte = 0);
+    }
+}
+
+// SHA-256 over opcodes, lengths, scope bits and data of all opcodes with ignored fields zeroed,
+// None if the form package can't be parsed
+pub fn uefi_form_fingerprint(
+    data: &[u8],
+    form_package: &FormPackage,
+    options: &FingerprintOptions,
+) -> Option<[u8; 32]> {
+    let records = uefi_opcode_records(data, form_package);
+    if records.is_empty() {
+        return None;
+    }
+
+    let mut structure = Vec::new();
+    for record in &records {
+        let mut opcode_data = record.data.to_vec();
+        if options.ignore_string_ids {
+            for offset in string_id_offsets(record.opcode, record.data) {
+                clear_field(&mut opcode_data, offset, 2);
+            }
+        }
+        if options.ignore_guids {
+            for offset in guid_offsets(record.opcode, record.data) {
+                clear_field(&mut opcode_data, offset, 16);
+            }
+        }
+        structure.push(u8::from(record.opcode));
+        structure.push(record.length | if record.scope_start { 0x80 } else { 0 });
+        structure.extend(opcode_data);
+    }
+    Some(sha256(&structure))
+}
@@ -0,0 +1,165 @@
+// Symbolic names of flag fields of UEFI opcodes, so structured output carries them along with raw values
+// and consumers don't need their own bitmask tables
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlagField {
+    pub label: &'static str, // Label of the field in the text output, i.e. QuestionFlags
+    pub value: u32,
+    pub names: Vec<&'static str>,
+    pub unknown: u32, // Set bits without a name
+}
+
+// EFI_IFR_FLAG_* of the question header
+const QUESTION_FLAGS: &[(u32, &str)] = &[
+    (0x01, "ReadOnly"),
+    (0x04, "Callback"),
+    (0x10, "ResetRequired"),
+    (0x20, "RestStyle"),
+    (0x40, "ReconnectRequired"),
+    (0x80, "OptionsOnly"),
+];
+
+const CHECKBOX_FLAGS: &[(u32, &str)] = &[(0x01, "Default"), (0x02, "DefaultMfg")];
+
+const ONE_OF_OPTION_FLAGS: &[(u32, &str)] = &[(0x10, "Default"), (0x20, "DefaultMfg")];
+
+const ORDERED_LIST_FLAGS: &[(u32, &str)] = &[(0x01, "UniqueSet"), (0x02, "NoEmptySet")];
+
+const STRING_FLAGS: &[(u32, &str)] = &[(0x01, "MultiLine")];
+
+const DATE_FLAGS: &[(u32, &str)] = &[
+    (0x01, "YearSuppress"),
+    (0x02, "MonthSuppress"),
+    (0x04, "DaySuppress"),
+];
+
+const TIME_FLAGS: &[(u32, &str)] = &[
+    (0x01, "HourSuppress"),
+    (0x02, "MinuteSuppress"),
+    (0x04, "SecondSuppress"),
+];
+
+// EFI_VARIABLE_* attributes of VarStoreEfi
+const VARIABLE_ATTRIBUTES: &[(u32, &str)] = &[
+    (0x01, "NonVolatile"),
+    (0x02, "BootserviceAccess"),
+    (0x04, "RuntimeAccess"),
+    (0x08, "HardwareErrorRecord"),
+    (0x10, "AuthenticatedWriteAccess"),
+    (0x20, "TimeBasedAuthenticatedWriteAccess"),
+    (0x40, "AppendWrite"),
+];
+
+// Names of single bits, other names that are encoded in the same field are given by the caller
+fn flag_field(
+    label: &'static str,
+    value: u32,
+    table: &[(u32, &'static str)],
+    mut names: Vec<&'static str>,
+    encoded: u32,
+) -> FlagField {
+    let mut unknown = value & !encoded;
+    for &(bit, name) in table {
+        if value & bit != 0 {
+            names.push(name);
+            unknown &= !bit;
+        }
+    }
+    FlagField {
+        label,
+        value,
+        names,
+        unknown,
+    }
+}
+
+// Size and display format of OneOf and Numeric values
+fn numeric_flag_names(flags: u8) -> Vec<&'static str> {
+    let size = match flags & 0x03 {
+        0x00 => "NumSize8",
+        0x01 => "NumSize16",
+        0x02 => "NumSize32",
+        _ => "NumSize64",
+    };
+    let display = match uefi_parser::IfrNumericDisplay::from_flags(flags) {
+        uefi_parser::IfrNumericDisplay::IntDec => "DisplayIntDec",
+        uefi_parser::IfrNumericDisplay::UintDec => "DisplayUintDec",
+        uefi_parser::IfrNumericDisplay::UintHex => "DisplayUintHex",
+        uefi_parser::IfrNumericDisplay::Unknown => "DisplayUnknown",
+    };
+    vec![size, display]
+}
+
+// Storage of Date and Time values
+fn storage_flag_names(flags: u8) -> Vec<&'static str> {
+    match uefi_parser::IfrDateTimeStorage::from(flags) {
+        uefi_parser::IfrDateTimeStorage::Normal => vec!["StorageNormal"],
+        uefi_parser::IfrDateTimeStorage::Time => vec!["StorageTime"],
+        uefi_parser::IfrDateTimeStorage::Wait => vec!["StorageWait"],
+        uefi_parser::IfrDateTimeStorage::Unknown(_) => Vec::new(),
+    }
+}
+
+// All flag fields of an opcode that has any, fields missing from truncated data are left out
+pub fn uefi_flag_fields(opcode: uefi_parser::IfrOpcode, data: &[u8]) -> Vec<FlagField> {
+    let mut result = Vec::new();
+    // Question header is 11 bytes long, QuestionFlags are its last byte
+    if opcode.is_question() {
+        if let Some(&flags) = data.get(10) {
+            result.push(flag_field(
+                "QuestionFlags",
+                flags as u32,
+                QUESTION_FLAGS,
+                Vec::new(),
+                0,
+            ));
+        }
+    }
+    let field = match opcode {
+        uefi_parser::IfrOpcode::CheckBox => data
+            .get(11)
+            .map(|&flags| flag_field("Flags", flags as u32, CHECKBOX_FLAGS, Vec::new(), 0)),
+        uefi_parser::IfrOpcode::OneOf | uefi_parser::IfrOpcode::Numeric => data
+            .get(11)
+            .map(|&flags| flag_field("Flags", flags as u32, &[], numeric_flag_names(flags), 0x33)),
+        uefi_parser::IfrOpcode::Date => data.get(11).map(|&flags| {
+            flag_field(
+                "Flags",
+                flags as u32,
+                DATE_FLAGS,
+                storage_flag_names(flags),
+                0x30,
+            )
+        }),
+        uefi_parser::IfrOpcode::Time => data.get(11).map(|&flags| {
+            flag_field(
+                "Flags",
+                flags as u32,
+                TIME_FLAGS,
+                storage_flag_names(flags),
+                0x30,
+            )
+        }),
+        // After MaxContainers
+        uefi_parser::IfrOpcode::OrderedList => data
+            .get(12)
+            .map(|&flags| flag_field("Flags", flags as u32, ORDERED_LIST_FLAGS, Vec::new(), 0)),
+        // After MinSize and MaxSize
+        uefi_parser::IfrOpcode::String => data
+            .get(13)
+            .map(|&flags| flag_field("Flags", flags as u32, STRING_FLAGS, Vec::new(), 0)),
+        // After OptionStringId
+        uefi_parser::IfrOpcode::OneOfOption => data
+            .get(2)
+            .map(|&flags| flag_field("Flags", flags as u32, ONE_OF_OPTION_FLAGS, Vec::new(), 0)),
+        // After VarStoreId and Guid
+        uefi_parser::IfrOpcode::VarStoreEfi => data.get(18..22).map(|bytes| {
+            let attributes = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            flag_field("Attributes", attributes, VARIABLE_ATTRIBUTES, Vec::new(), 0)
+        }),
+        _ => None,
+    };
+    result.extend(field);
+    result
+}
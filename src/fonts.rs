@@ -0,0 +1,275 @@
+// UEFI HII font and simple font packages with their glyph metadata, and fonts referenced by strings,
+// so fonts can be inventoried and strings with a font can report which font package draws them
+use std::collections::BTreeMap;
+
+use super::{prefilter, sha256, StringPackage};
+use uefi_parser::{self, HiiGibtType, HiiGlyphInfo, HiiSibtType};
+
+// EFI_HII_FONT_STYLE_* bits
+const FONT_STYLES: &[(u32, &str)] = &[
+    (0x0000_0001, "Bold"),
+    (0x0000_0002, "Italic"),
+    (0x0001_0000, "Emboss"),
+    (0x0002_0000, "Outline"),
+    (0x0004_0000, "Shadow"),
+    (0x0008_0000, "Underline"),
+    (0x0010_0000, "DoubleUnderline"),
+];
+
+// Names of set style bits, Normal if there are none
+pub fn font_style_names(style: u32) -> Vec<&'static str> {
+    let names: Vec<&'static str> = FONT_STYLES
+        .iter()
+        .filter(|(bit, _)| style & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if names.is_empty() {
+        vec!["Normal"]
+    } else {
+        names
+    }
+}
+
+pub struct FontPackage {
+    pub offset: usize,
+    pub length: usize,
+    pub family: String,
+    pub style: u32,
+    pub cell: HiiGlyphInfo,                   // Default cell of the package
+    pub glyphs: BTreeMap<u16, HiiGlyphInfo>, // Map of characters to cells of their glyphs
+    pub sha256: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimpleGlyph {
+    pub character: u16,
+    pub attributes: u8, // EFI_GLYPH_NON_SPACING and EFI_GLYPH_WIDE
+    pub wide: bool,     // 16x19 instead of 8x19
+}
+
+pub struct SimpleFontPackage {
+    pub offset: usize,
+    pub length: usize,
+    pub glyphs: Vec<SimpleGlyph>, // Narrow glyphs first
+    pub sha256: [u8; 32],
+}
+
+// Glyph cells of all characters, characters start from 1.
+// Parsing stops at the first block that can't be parsed
+fn font_glyphs(blocks: &[u8], mut default_cell: HiiGlyphInfo) -> BTreeMap<u16, HiiGlyphInfo> {
+    let mut glyphs = BTreeMap::new();
+    let mut current_char: u16 = 1;
+    let mut input = blocks;
+    while let Ok((rest, block)) = uefi_parser::hii_glyph_block(input, default_cell) {
+        let data = block.Data.unwrap_or(&[]);
+        match block.Type {
+            HiiGibtType::End => break,
+            HiiGibtType::Glyph => {
+                if let Ok((_, cell)) = uefi_parser::hii_glyph_info(data) {
+                    glyphs.insert(current_char, cell);
+                }
+                current_char = current_char.wrapping_add(1);
+            }
+            HiiGibtType::Glyphs => {
+                if let Ok((_, cell)) = uefi_parser::hii_glyph_info(data) {
+                    let count = data[10] as u16 + 0x100 * data[11] as u16;
+                    for _ in 0..count {
+                        glyphs.insert(current_char, cell);
+                        current_char = current_char.wrapping_add(1);
+                    }
+                }
+            }
+            HiiGibtType::GlyphDefault => {
+                glyphs.insert(current_char, default_cell);
+                current_char = current_char.wrapping_add(1);
+            }
+            HiiGibtType::GlyphsDefault => {
+                let count = data[0] as u16 + 0x100 * data[1] as u16;
+                for _ in 0..count {
+                    glyphs.insert(current_char, default_cell);
+                    current_char = current_char.wrapping_add(1);
+                }
+            }
+            HiiGibtType::Duplicate => {
+                let duplicated = data[0] as u16 + 0x100 * data[1] as u16;
+                if let Some(cell) = glyphs.get(&duplicated).copied() {
+                    glyphs.insert(current_char, cell);
+                }
+                current_char = current_char.wrapping_add(1);
+            }
+            HiiGibtType::Skip2 => {
+                current_char = current_char.wrapping_add(data[0] as u16 + 0x100 * data[1] as u16);
+            }
+            HiiGibtType::Skip1 => {
+                current_char = current_char.wrapping_add(data[0] as u16);
+            }
+            HiiGibtType::Defaults => {
+                if let Ok((_, cell)) = uefi_parser::hii_glyph_info(data) {
+                    default_cell = cell;
+                }
+            }
+            // Extended blocks don't contain glyphs
+            HiiGibtType::Ext1 | HiiGibtType::Ext2 | HiiGibtType::Ext4 | HiiGibtType::Unknown(_) => {}
+        }
+        input = rest;
+    }
+    glyphs
+}
+
+// Search for all font packages in the input file
+pub fn find_uefi_font_packages(data: &[u8]) -> Vec<FontPackage> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        i = prefilter::UEFI_FONT_PACKAGE.next_candidate(data, i);
+        if i >= data.len() {
+            break;
+        }
+        if let Ok((_, candidate)) = uefi_parser::hii_font_package_candidate(&data[i..]) {
+            if let Ok((_, header)) = uefi_parser::hii_font_package(&candidate[4..]) {
+                let glyphs = font_glyphs(&candidate[header.GlyphBlockOffset as usize..], header.Cell);
+                result.push(FontPackage {
+                    offset: i,
+                    length: candidate.len(),
+                    family: header.FontFamily,
+                    style: header.FontStyle,
+                    cell: header.Cell,
+                    glyphs,
+                    sha256: sha256(candidate),
+                });
+                i += candidate.len();
+                continue;
+            }
+        }
+        i += 1;
+    }
+    result
+}
+
+// Search for all simple font packages in the input file
+pub fn find_uefi_simple_font_packages(data: &[u8]) -> Vec<SimpleFontPackage> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        i = prefilter::UEFI_SIMPLE_FONT_PACKAGE.next_candidate(data, i);
+        if i >= data.len() {
+            break;
+        }
+        if let Ok((_, candidate)) = uefi_parser::hii_simple_font_package_candidate(&data[i..]) {
+            if let Ok((_, package)) = uefi_parser::hii_simple_font_package(&candidate[4..]) {
+                let narrow = package.NarrowGlyphs.iter().map(|glyph| (glyph, false));
+                let wide = package.WideGlyphs.iter().map(|glyph| (glyph, true));
+                result.push(SimpleFontPackage {
+                    offset: i,
+                    length: candidate.len(),
+                    glyphs: narrow
+                        .chain(wide)
+                        .map(|(glyph, wide)| SimpleGlyph {
+                            character: glyph.UnicodeWeight,
+                            attributes: glyph.Attributes,
+                            wide,
+                        })
+                        .collect(),
+                    sha256: sha256(candidate),
+                });
+                i += candidate.len();
+                continue;
+            }
+        }
+        i += 1;
+    }
+    result
+}
+
+// Font declared by a SIBT_FONT block of a string package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontDeclaration {
+    pub font_id: u8,
+    pub size: u16,
+    pub style: u32,
+    pub name: String,
+}
+
+impl FontDeclaration {
+    // Font package drawing this font, matched by family name and style.
+    // Packages with a cell as high as the font size are preferred
+    pub fn font_package<'a>(&self, fonts: &'a [FontPackage]) -> Option<&'a FontPackage> {
+        let mut candidates = fonts
+            .iter()
+            .filter(|font| font.family.eq_ignore_ascii_case(&self.name) && font.style == self.style);
+        let first = candidates.next()?;
+        Some(
+            std::iter::once(first)
+                .chain(candidates)
+                .find(|font| font.cell.Height == self.size)
+                .unwrap_or(first),
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StringFonts {
+    pub declarations: Vec<FontDeclaration>,
+    pub string_fonts: BTreeMap<u16, u8>, // Map of StringIds to FontIds of strings with a font
+}
+
+impl StringFonts {
+    // Font declared for a string, None for strings without a font and undeclared FontIds
+    pub fn font(&self, string_id: u16) -> Option<&FontDeclaration> {
+        let font_id = self.string_fonts.get(&string_id)?;
+        self.declarations.iter().find(|declaration| declaration.font_id == *font_id)
+    }
+}
+
+// Fonts declared in a UEFI string package and FontIds of its strings, StringIds are counted the same way
+// string packages are parsed
+pub fn uefi_string_fonts(data: &[u8], string_package: &StringPackage) -> StringFonts {
+    let mut result = StringFonts::default();
+    let package_data = &data[string_package.offset..string_package.offset + string_package.length];
+    let blocks = match uefi_parser::hii_package(package_data)
+        .ok()
+        .and_then(|(_, package)| package.Data)
+        .and_then(|payload| uefi_parser::hii_string_package(payload).ok())
+        .and_then(|(_, string_package)| uefi_parser::hii_sibt_blocks(string_package.Data).ok())
+    {
+        Some((_, blocks)) => blocks,
+        None => return result,
+    };
+
+    let mut current_string_id: u16 = 1;
+    for block in &blocks {
+        let data = block.Data.unwrap_or(&[]);
+        let count = match block.Type {
+            HiiSibtType::StringScsu
+            | HiiSibtType::StringScsuFont
+            | HiiSibtType::StringUcs2
+            | HiiSibtType::StringUcs2Font
+            | HiiSibtType::Duplicate => 1,
+            HiiSibtType::StringsScsu => uefi_parser::sibt_strings_scsu(data).map_or(0, |(_, strings)| strings.len()),
+            HiiSibtType::StringsScsuFont => uefi_parser::sibt_strings_scsu_font(data).map_or(0, |(_, strings)| strings.len()),
+            HiiSibtType::StringsUcs2 => uefi_parser::sibt_strings_ucs2(data).map_or(0, |(_, strings)| strings.len()),
+            HiiSibtType::StringsUcs2Font => uefi_parser::sibt_strings_ucs2_font(data).map_or(0, |(_, strings)| strings.len()),
+            HiiSibtType::Skip2 => data[0] as usize + 0x100 * data[1] as usize,
+            HiiSibtType::Skip1 => data[0] as usize,
+            HiiSibtType::Ext2 => {
+                if let Ok((_, font)) = uefi_parser::sibt_font(data) {
+                    result.declarations.push(FontDeclaration {
+                        font_id: font.FontId,
+                        size: font.FontSize,
+                        style: font.FontStyle,
+                        name: font.FontName,
+                    });
+                }
+                0
+            }
+            _ => 0,
+        };
+        if let Some(font_id) = uefi_parser::sibt_font_id(block.Type, data) {
+            for offset in 0..count {
+                result.string_fonts.insert(current_string_id.wrapping_add(offset as u16), font_id);
+            }
+        }
+        current_string_id = current_string_id.wrapping_add(count as u16);
+    }
+    result
+}
@@ -0,0 +1,185 @@
+// Navigation graph of forms: formsets contain forms, forms lead to other forms through Refs.
+// Emitted in GraphViz DOT format to visualize menu structure
+use std::fmt::Write;
+
+use super::{
+    find_uefi_packages, uefi_preferred_string_package, uefi_resources, uefi_string_map,
+    ExtractOptions, FormPackage, HiiResources, StringEscaping, StringPackage,
+};
+use ifr_tree::parse_uefi_ifr;
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphNode {
+    pub id: String, // FormSet GUID for formsets, GUID and FormId for forms
+    pub label: String,
+    pub formset: bool,
+    pub defined: bool, // False for forms that are only known as Ref targets
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub label: Option<String>, // Prompt of the Ref, None for FormSet to Form edges
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FormGraph {
+    pub nodes: Vec<GraphNode>, // In order of appearance
+    pub edges: Vec<GraphEdge>,
+}
+
+fn form_node_id(formset: &uefi_parser::Guid, form_id: u16) -> String {
+    format!("{formset}:0x{form_id:X}")
+}
+
+impl FormGraph {
+    pub fn new() -> FormGraph {
+        FormGraph::default()
+    }
+
+    // Add a node, a placeholder of the same node is replaced by its definition
+    fn add_node(&mut self, node: GraphNode) {
+        match self.nodes.iter_mut().find(|other| other.id == node.id) {
+            Some(other) => {
+                if node.defined && !other.defined {
+                    *other = node;
+                }
+            }
+            None => self.nodes.push(node),
+        }
+    }
+
+    fn add_edge(&mut self, edge: GraphEdge) {
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    // Add formsets, forms and Refs of a UEFI form package, titles and prompts are resolved
+    // the same way extract_uefi_ifr does
+    pub fn add_package(
+        &mut self,
+        data: &[u8],
+        form_package: &FormPackage,
+        string_package: &StringPackage,
+        resources: &HiiResources,
+    ) {
+        let options = ExtractOptions::default();
+        let strings_map = uefi_string_map(data, form_package, string_package, resources, &options);
+        let tree = parse_uefi_ifr(data, form_package, string_package, resources);
+
+        for root in &tree.roots {
+            if root.opcode != uefi_parser::IfrOpcode::FormSet {
+                continue;
+            }
+            let form_set = match uefi_parser::ifr_form_set(&root.data) {
+                Ok((_, form_set)) => form_set,
+                Err(_) => continue,
+            };
+            let formset_id = form_set.Guid.to_string();
+            self.add_node(GraphNode {
+                id: formset_id.clone(),
+                label: strings_map.resolve(form_set.TitleStringId).into_owned(),
+                formset: true,
+                defined: true,
+            });
+
+            // Forms are direct children of their formset
+            for child in &root.children {
+                let form = match tree.forms.iter().find(|form| form.offset == child.offset) {
+                    Some(form) => form,
+                    None => continue,
+                };
+                let form_id = form_node_id(&form_set.Guid, form.form_id);
+                self.add_node(GraphNode {
+                    id: form_id.clone(),
+                    label: form
+                        .title
+                        .text
+                        .clone()
+                        .unwrap_or_else(|| format!("FormId 0x{:X}", form.form_id)),
+                    formset: false,
+                    defined: true,
+                });
+                self.add_edge(GraphEdge {
+                    from: formset_id.clone(),
+                    to: form_id.clone(),
+                    label: None,
+                });
+
+                for reference in &form.refs {
+                    // Ref5 targets are given by question values
+                    let target = match reference.form_id {
+                        Some(target) => target,
+                        None => continue,
+                    };
+                    // Zero FormSetGuid refers to the current formset
+                    let target_formset = reference
+                        .formset_guid
+                        .filter(|guid| *guid != uefi_parser::Guid::default())
+                        .unwrap_or(form_set.Guid);
+                    let target_id = form_node_id(&target_formset, target);
+                    self.add_node(GraphNode {
+                        id: target_id.clone(),
+                        label: format!("FormId 0x{target:X}"),
+                        formset: false,
+                        defined: false,
+                    });
+                    self.add_edge(GraphEdge {
+                        from: form_id.clone(),
+                        to: target_id,
+                        label: reference.prompt.text.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    // GraphViz DOT representation, formsets are drawn as folders and forms not found in the input are dashed
+    pub fn to_dot(&self) -> String {
+        let quote = |string: &str| format!("\"{}\"", StringEscaping::C.escape(string));
+        let mut dot = String::from("digraph Forms {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let style = if node.formset {
+                ", shape=folder"
+            } else if !node.defined {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            writeln!(
+                &mut dot,
+                "    {} [label={}{}];",
+                quote(&node.id),
+                quote(&node.label),
+                style
+            )
+            .unwrap();
+        }
+        for edge in &self.edges {
+            write!(&mut dot, "    {} -> {}", quote(&edge.from), quote(&edge.to)).unwrap();
+            match &edge.label {
+                Some(label) => writeln!(&mut dot, " [label={}];", quote(label)).unwrap(),
+                None => writeln!(&mut dot, ";").unwrap(),
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Navigation graph of all UEFI form packages of an image in DOT format,
+// strings are resolved using preferred string packages
+pub fn export_form_graph_dot(data: &[u8]) -> String {
+    let (strings, forms) = find_uefi_packages(data);
+    let mut graph = FormGraph::new();
+    for form in &forms {
+        if let Some(string_package) = uefi_preferred_string_package(data, &strings, form) {
+            let resources = uefi_resources(data, &[], &[], &[], &strings, form);
+            graph.add_package(data, form, string_package, &resources);
+        }
+    }
+    graph.to_dot()
+}
@@ -0,0 +1,77 @@
+// Quick inventory of all formsets in the input file, for triage of images with hundreds of form packages.
+// Only FormSet opcodes are parsed, all other opcodes are skipped using their headers
+use framework_parser;
+use uefi_parser;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormSetLocation {
+    pub offset: usize,         // Offset of the FormSet opcode in the input data
+    pub package_offset: usize, // Offset of the form package containing it
+    pub package_length: usize,
+    pub guid: uefi_parser::Guid,
+    pub title_string_id: u16,
+}
+
+// Locate all FormSet opcodes in UEFI HII form packages
+pub fn uefi_formset_inventory(data: &[u8]) -> Vec<FormSetLocation> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        if let Ok((_, candidate)) = uefi_parser::hii_form_package_candidate(&data[i..]) {
+            // Form package header is 4 bytes long
+            let mut offset = 4;
+            while offset < candidate.len() {
+                let operation = match uefi_parser::ifr_operation(&candidate[offset..]) {
+                    Ok((_, operation)) => operation,
+                    Err(_) => break,
+                };
+                if operation.OpCode == uefi_parser::IfrOpcode::FormSet {
+                    if let Ok((_, form_set)) =
+                        uefi_parser::ifr_form_set(operation.Data.unwrap_or(&[]))
+                    {
+                        result.push(FormSetLocation {
+                            offset: i + offset,
+                            package_offset: i,
+                            package_length: candidate.len(),
+                            guid: form_set.Guid,
+                            title_string_id: form_set.TitleStringId,
+                        });
+                    }
+                }
+                offset += operation.Length as usize;
+            }
+            i += candidate.len();
+        } else {
+            i += 1;
+        }
+    }
+    result
+}
+
+// Locate FormSet opcodes in Framework HII form packages, there is only one at the start of every package
+pub fn framework_formset_inventory(data: &[u8]) -> Vec<FormSetLocation> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        if let Ok((_, candidate)) = framework_parser::hii_form_package_candidate(&data[i..]) {
+            // Form package header is 6 bytes long
+            if let Ok((_, operation)) = framework_parser::ifr_operation(&candidate[6..]) {
+                if let Ok((_, form_set)) =
+                    framework_parser::ifr_form_set(operation.Data.unwrap_or(&[]))
+                {
+                    result.push(FormSetLocation {
+                        offset: i + 6,
+                        package_offset: i,
+                        package_length: candidate.len(),
+                        guid: form_set.Guid,
+                        title_string_id: form_set.TitleStringId,
+                    });
+                }
+            }
+            i += candidate.len();
+        } else {
+            i += 1;
+        }
+    }
+    result
+}
@@ -6,16 +6,12 @@ extern crate nom;
 use nom::{le_u16, le_u32, le_u64, le_u8, IResult};
 use std::fmt;
 
+use OpcodeCategory;
+
 //
 // Common data types
 //
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct Guid {
-    pub data1: u32,
-    pub data2: u16,
-    pub data3: u16,
-    pub data4: [u8; 8],
-}
+pub use guid::Guid;
 
 pub fn guid(input: &[u8]) -> IResult<&[u8], Guid> {
     do_parse!(
@@ -33,26 +29,6 @@ pub fn guid(input: &[u8]) -> IResult<&[u8], Guid> {
     )
 }
 
-impl fmt::Display for Guid {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
-            self.data1,
-            self.data2,
-            self.data3,
-            self.data4[0],
-            self.data4[1],
-            self.data4[2],
-            self.data4[3],
-            self.data4[4],
-            self.data4[5],
-            self.data4[6],
-            self.data4[7]
-        )
-    }
-}
-
 //
 // HII package header
 //
@@ -90,7 +66,7 @@ impl From<u16> for HiiPackageType {
     }
 }
 
-pub fn hii_package(input: &[u8]) -> IResult<&[u8], HiiPackage> {
+pub fn hii_package(input: &[u8]) -> IResult<&[u8], HiiPackage<'_>> {
     do_parse!(
         input,
         len: le_u32
@@ -207,7 +183,7 @@ pub struct IfrOperation<'a> {
     pub Data: Option<&'a [u8]>,
 }
 
-pub fn ifr_operation(input: &[u8]) -> IResult<&[u8], IfrOperation> {
+pub fn ifr_operation(input: &[u8]) -> IResult<&[u8], IfrOperation<'_>> {
     do_parse!(
         input,
         opcode: le_u8
@@ -222,7 +198,7 @@ pub fn ifr_operation(input: &[u8]) -> IResult<&[u8], IfrOperation> {
     )
 }
 
-pub fn ifr_operations(input: &[u8]) -> IResult<&[u8], Vec<IfrOperation>> {
+pub fn ifr_operations(input: &[u8]) -> IResult<&[u8], Vec<IfrOperation<'_>>> {
     do_parse!(input, v: many1!(complete!(ifr_operation)) >> (v))
 }
 
@@ -276,6 +252,85 @@ pub enum IfrOpcode {
     Unknown(u8),
 }
 
+impl IfrOpcode {
+    // Length of the shortest valid opcode of this type, header included
+    pub fn minimum_length(self) -> u8 {
+        match self {
+            IfrOpcode::SuppressIf | IfrOpcode::GrayOutIf => 3,
+            IfrOpcode::Subtitle | IfrOpcode::Label | IfrOpcode::VarStoreSelect => 4,
+            IfrOpcode::InconsistentIf => 5,
+            IfrOpcode::Form
+            | IfrOpcode::Hidden
+            | IfrOpcode::EqIdVal
+            | IfrOpcode::EqIdId
+            | IfrOpcode::EqVarVal
+            | IfrOpcode::VarStoreSelectPair => 6,
+            IfrOpcode::EqIdList | IfrOpcode::Banner => 7,
+            IfrOpcode::Inventory => 8,
+            IfrOpcode::OneOf | IfrOpcode::OneOfOption | IfrOpcode::OrderedList => 9,
+            IfrOpcode::Text | IfrOpcode::Ref | IfrOpcode::SaveDefaults | IfrOpcode::RestoreDefaults => 11,
+            IfrOpcode::CheckBox => 12,
+            IfrOpcode::String => 14,
+            IfrOpcode::Password => 16,
+            IfrOpcode::Numeric | IfrOpcode::Date | IfrOpcode::Time => 20,
+            IfrOpcode::VarStore => 23,
+            IfrOpcode::FormSet => 36,
+            _ => 2,
+        }
+    }
+
+    // Category used to filter opcodes during extraction
+    pub fn category(self) -> OpcodeCategory {
+        match self {
+            IfrOpcode::FormSet | IfrOpcode::EndFormSet | IfrOpcode::Form | IfrOpcode::EndForm => {
+                OpcodeCategory::Form
+            }
+            // End closes option lists of OneOf and OrderedList
+            IfrOpcode::OneOf
+            | IfrOpcode::CheckBox
+            | IfrOpcode::Numeric
+            | IfrOpcode::Password
+            | IfrOpcode::Ref
+            | IfrOpcode::Date
+            | IfrOpcode::Time
+            | IfrOpcode::String
+            | IfrOpcode::OrderedList
+            | IfrOpcode::SaveDefaults
+            | IfrOpcode::RestoreDefaults
+            | IfrOpcode::End => OpcodeCategory::Question,
+            IfrOpcode::OneOfOption => OpcodeCategory::Option,
+            IfrOpcode::Subtitle
+            | IfrOpcode::Text
+            | IfrOpcode::Graphic
+            | IfrOpcode::Label
+            | IfrOpcode::Banner
+            | IfrOpcode::Inventory => OpcodeCategory::Statement,
+            IfrOpcode::SuppressIf | IfrOpcode::GrayOutIf | IfrOpcode::InconsistentIf | IfrOpcode::EndIf => {
+                OpcodeCategory::Condition
+            }
+            IfrOpcode::EqIdVal
+            | IfrOpcode::EqIdId
+            | IfrOpcode::EqIdList
+            | IfrOpcode::EqVarVal
+            | IfrOpcode::And
+            | IfrOpcode::Or
+            | IfrOpcode::Not
+            | IfrOpcode::True
+            | IfrOpcode::False
+            | IfrOpcode::Greater
+            | IfrOpcode::GreaterEqual => OpcodeCategory::Expression,
+            IfrOpcode::VarStore | IfrOpcode::VarStoreSelect | IfrOpcode::VarStoreSelectPair => {
+                OpcodeCategory::Storage
+            }
+            IfrOpcode::Hidden
+            | IfrOpcode::OemDefined
+            | IfrOpcode::Oem
+            | IfrOpcode::NvAccessCommand
+            | IfrOpcode::Unknown(_) => OpcodeCategory::Other,
+        }
+    }
+}
+
 impl From<u8> for IfrOpcode {
     fn from(n: u8) -> IfrOpcode {
         match n {
@@ -1068,7 +1123,26 @@ pub fn ifr_restore_defaults(input: &[u8]) -> IResult<&[u8], IfrRestoreDefaults>
 pub struct IfrBanner {
     pub TitleStringId: u16,
     pub LineNumber: u16,
-    pub Alignment: u8,
+    pub Alignment: IfrBannerAlignment,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum IfrBannerAlignment {
+    Left,
+    Center,
+    Right,
+    Unknown(u8),
+}
+
+impl From<u8> for IfrBannerAlignment {
+    fn from(n: u8) -> IfrBannerAlignment {
+        match n {
+            0x00 => IfrBannerAlignment::Left,
+            0x01 => IfrBannerAlignment::Center,
+            0x02 => IfrBannerAlignment::Right,
+            _ => IfrBannerAlignment::Unknown(n),
+        }
+    }
 }
 
 pub fn ifr_banner(input: &[u8]) -> IResult<&[u8], IfrBanner> {
@@ -1080,7 +1154,7 @@ pub fn ifr_banner(input: &[u8]) -> IResult<&[u8], IfrBanner> {
             >> (IfrBanner {
                 TitleStringId: tsid,
                 LineNumber: ln,
-                Alignment: a,
+                Alignment: IfrBannerAlignment::from(a),
             })
     )
 }
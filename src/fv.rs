@@ -0,0 +1,509 @@
+// Traversal of EFI Firmware Volumes and FFS files in a full SPI dump, so HII packages found in it
+// can be attributed to the FFS files they came from. Compressed sections are recorded while walking
+// and can be decompressed on request, packages inside them are then found in the decompressed data
+use std::convert::TryInto;
+use std::ops::Range;
+
+use super::find_uefi_packages;
+use decompression::{decompress, Compression};
+use uefi_parser::Guid;
+
+// FFS file types without sections
+const FFS_TYPE_RAW: u8 = 0x01;
+const FFS_TYPE_PAD: u8 = 0xF0;
+
+// Section types
+const SECTION_COMPRESSION: u8 = 0x01;
+const SECTION_GUID_DEFINED: u8 = 0x02;
+const SECTION_DISPOSABLE: u8 = 0x03;
+const SECTION_USER_INTERFACE: u8 = 0x15;
+const SECTION_FIRMWARE_VOLUME_IMAGE: u8 = 0x17;
+
+// Definition GUIDs of compressed GUID-defined sections
+const LZMA_GUID: Guid = Guid {
+    data1: 0xEE4E5898,
+    data2: 0x3914,
+    data3: 0x4259,
+    data4: [0x9D, 0x6E, 0xDC, 0x7B, 0xD7, 0x94, 0x03, 0xCF],
+};
+const LZMA_F86_GUID: Guid = Guid {
+    data1: 0xD42AE6BD,
+    data2: 0x1352,
+    data3: 0x4BFB,
+    data4: [0x90, 0x9A, 0xCA, 0x72, 0xA6, 0xEA, 0xE8, 0x89],
+};
+const TIANO_GUID: Guid = Guid {
+    data1: 0xA31280AD,
+    data2: 0x481E,
+    data3: 0x41B6,
+    data4: [0x95, 0xE8, 0x12, 0x7F, 0x4C, 0x98, 0x47, 0x79],
+};
+
+// Compressed sections nested deeper than this are not decompressed
+const MAX_DECOMPRESSION_DEPTH: usize = 8;
+
+// Sections and volumes nested deeper than this are not walked, so crafted input can't exhaust the stack
+const MAX_NESTING_DEPTH: usize = 32;
+
+// Section that couldn't be walked without processing its data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedSection {
+    pub offset: usize,                    // Offset of the section header
+    pub data: Range<usize>,               // Compressed data
+    pub compression: Option<Compression>, // None for unknown GUID-defined sections. Compression sections say Efi, but Tiano is tried too
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfsFile {
+    pub offset: usize, // Offset of the file header in the input data
+    pub length: usize, // Header included
+    pub guid: Guid,
+    pub file_type: u8,
+    pub name: Option<String>, // From the user interface section, if there is one
+    pub compressed: bool, // Some sections are compressed or need processing and were not traversed
+    pub compressed_sections: Vec<CompressedSection>,
+}
+
+impl FfsFile {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.length
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirmwareVolume {
+    pub offset: usize,
+    pub length: usize,
+    pub file_system: Guid,  // FileSystemGuid of the volume header
+    pub name: Option<Guid>, // FvName of the extended header, if there is one
+    pub depth: usize, // 0 for volumes in the input data, 1 for volumes in their files and so on
+    pub files: Vec<FfsFile>,
+}
+
+// Decompressed data of a compressed section, offsets of its files and volumes are relative to this data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecompressedSection {
+    pub offset: usize, // Offset of the compressed section in the data of its parent
+    pub parent: Option<usize>, // Index of the parent decompressed section, None for the input data
+    pub compression: Compression,
+    pub file: FfsFile, // File the compressed section is in, its name is taken from the decompressed data if needed
+    pub volumes: Vec<FirmwareVolume>, // Volumes nested in the decompressed data
+    pub data: Vec<u8>,
+}
+
+// HII package with the FFS file it was found in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageOrigin {
+    pub kind: &'static str,     // "UEFI string" or "UEFI form"
+    pub offset: usize,          // Relative to the data of the decompressed section, if there is one
+    pub section: Option<usize>, // Index of the decompressed section, None for packages in the input data
+    pub file: Option<FfsFile>,  // None for packages outside of any FFS file
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u24(data: &[u8], offset: usize) -> usize {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], 0]) as usize
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn read_guid(data: &[u8], offset: usize) -> Guid {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&data[offset..offset + 16]);
+    Guid::from_bytes_le(&bytes)
+}
+
+fn align(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+// Length of a valid volume header at a given offset, header and its checksum are checked
+fn volume_at(data: &[u8], offset: usize) -> Option<usize> {
+    // ZeroVector, FileSystemGuid, FvLength, Signature, Attributes, HeaderLength, Checksum, ExtHeaderOffset, Reserved, Revision
+    if data.len() < offset + 56 || &data[offset + 40..offset + 44] != b"_FVH" {
+        return None;
+    }
+    let length = u64::from_le_bytes(data[offset + 32..offset + 40].try_into().unwrap()) as usize;
+    let header_length = read_u16(data, offset + 48) as usize;
+    if header_length < 56
+        || !header_length.is_multiple_of(2)
+        || length < header_length
+        || length > data.len() - offset
+    {
+        return None;
+    }
+    let checksum = (0..header_length).step_by(2).fold(0u16, |sum, index| {
+        sum.wrapping_add(read_u16(data, offset + index))
+    });
+    if checksum != 0 {
+        return None;
+    }
+    Some(length)
+}
+
+// Find all firmware volumes, nested ones in uncompressed FFS files included
+pub fn find_firmware_volumes(data: &[u8]) -> Vec<FirmwareVolume> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+    while offset + 56 <= data.len() {
+        match volume_at(data, offset) {
+            Some(length) => {
+                parse_volume(data, offset, length, 0, &mut result);
+                offset += length;
+            }
+            None => offset += 1,
+        }
+    }
+    result
+}
+
+// Parse a volume with a valid header and add it to the result before its nested volumes
+fn parse_volume(
+    data: &[u8],
+    offset: usize,
+    length: usize,
+    depth: usize,
+    result: &mut Vec<FirmwareVolume>,
+) {
+    if depth > MAX_NESTING_DEPTH {
+        return;
+    }
+    let end = offset + length;
+    let attributes = read_u32(data, offset + 44);
+    let erased = if attributes & 0x800 != 0 { 0xFF } else { 0x00 }; // EFI_FVB2_ERASE_POLARITY
+    let header_length = read_u16(data, offset + 48) as usize;
+    let ext_header_offset = read_u16(data, offset + 52) as usize;
+
+    // Extended header starts with FvName and its own size
+    let mut name = None;
+    let mut files_start = offset + header_length;
+    if ext_header_offset != 0 && offset + ext_header_offset + 20 <= end {
+        name = Some(read_guid(data, offset + ext_header_offset));
+        let ext_header_size = read_u32(data, offset + ext_header_offset + 16) as usize;
+        files_start = files_start.max(offset + ext_header_offset + ext_header_size);
+    }
+
+    let index = result.len();
+    result.push(FirmwareVolume {
+        offset,
+        length,
+        file_system: read_guid(data, offset + 16),
+        name,
+        depth,
+        files: Vec::new(),
+    });
+
+    // Files are 8-byte aligned relative to the start of the volume
+    let mut files = Vec::new();
+    let mut file_offset = offset + align(files_start - offset, 8);
+    while file_offset + 24 <= end {
+        let header = &data[file_offset..file_offset + 24];
+        if header.iter().all(|&byte| byte == erased) {
+            break; // Free space
+        }
+        let file_type = header[18];
+        // FFS_ATTRIB_LARGE_FILE adds a 64-bit ExtendedSize after the common header
+        let (file_length, header_size) = if header[19] & 0x01 != 0 && file_offset + 32 <= end {
+            let extended = data[file_offset + 24..file_offset + 32].try_into().unwrap();
+            (u64::from_le_bytes(extended) as usize, 32)
+        } else {
+            (read_u24(header, 20), 24)
+        };
+        if file_length < header_size || file_length > end - file_offset {
+            break;
+        }
+
+        let mut file = FfsFile {
+            offset: file_offset,
+            length: file_length,
+            guid: read_guid(header, 0),
+            file_type,
+            name: None,
+            compressed: false,
+            compressed_sections: Vec::new(),
+        };
+        if file_type != FFS_TYPE_RAW && file_type != FFS_TYPE_PAD {
+            parse_sections(
+                data,
+                file_offset + header_size..file_offset + file_length,
+                depth,
+                0,
+                &mut file,
+                result,
+            );
+        }
+        files.push(file);
+        file_offset = offset + align(file_offset + file_length - offset, 8);
+    }
+    result[index].files = files;
+}
+
+// Walk sections of a file, the name is taken from its user interface section
+// and volume image sections are parsed as nested volumes. Nesting is the number of encapsulation
+// sections the range is in
+fn parse_sections(
+    data: &[u8],
+    range: Range<usize>,
+    depth: usize,
+    nesting: usize,
+    file: &mut FfsFile,
+    result: &mut Vec<FirmwareVolume>,
+) {
+    if nesting > MAX_NESTING_DEPTH {
+        return;
+    }
+    // Sections are 4-byte aligned relative to the start of the file data
+    let mut offset = range.start;
+    while offset + 4 <= range.end {
+        let mut section_length = read_u24(data, offset);
+        let section_type = data[offset + 3];
+        let mut header_size = 4;
+        // Size of 0xFFFFFF means a 32-bit ExtendedSize follows the common header
+        if section_length == 0xFFFFFF && offset + 8 <= range.end {
+            section_length = read_u32(data, offset + 4) as usize;
+            header_size = 8;
+        }
+        if section_length < header_size || section_length > range.end - offset {
+            break;
+        }
+        let body = offset + header_size..offset + section_length;
+
+        match section_type {
+            // CompressionType follows UncompressedLength, only uncompressed sections can be walked
+            SECTION_COMPRESSION if body.len() >= 5 => {
+                if data[body.start + 4] == 0 {
+                    parse_sections(data, body.start + 5..body.end, depth, nesting + 1, file, result);
+                } else {
+                    file.compressed = true;
+                    file.compressed_sections.push(CompressedSection {
+                        offset,
+                        data: body.start + 5..body.end,
+                        compression: Some(Compression::Efi),
+                    });
+                }
+            }
+            // SectionDefinitionGuid, DataOffset and Attributes, data needs processing if bit 0 is set
+            SECTION_GUID_DEFINED if body.len() >= 20 => {
+                let data_offset = read_u16(data, body.start + 16) as usize;
+                let attributes = read_u16(data, body.start + 18);
+                // Data can't start inside the header, that would walk the same section again
+                if data_offset < header_size + 20 || data_offset > section_length {
+                    file.compressed = true;
+                } else if attributes & 0x01 != 0 {
+                    let compression = match read_guid(data, body.start) {
+                        LZMA_GUID => Some(Compression::Lzma),
+                        LZMA_F86_GUID => Some(Compression::LzmaF86),
+                        TIANO_GUID => Some(Compression::Tiano),
+                        _ => None,
+                    };
+                    file.compressed = true;
+                    file.compressed_sections.push(CompressedSection {
+                        offset,
+                        data: offset + data_offset..body.end,
+                        compression,
+                    });
+                } else {
+                    parse_sections(
+                        data,
+                        offset + data_offset..body.end,
+                        depth,
+                        nesting + 1,
+                        file,
+                        result,
+                    );
+                }
+            }
+            SECTION_DISPOSABLE => parse_sections(data, body, depth, nesting + 1, file, result),
+            SECTION_USER_INTERFACE => {
+                let name: Vec<u16> = data[body]
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .take_while(|&character| character != 0)
+                    .collect();
+                file.name = Some(String::from_utf16_lossy(&name));
+            }
+            SECTION_FIRMWARE_VOLUME_IMAGE => {
+                if let Some(length) = volume_at(&data[..body.end], body.start) {
+                    parse_volume(data, body.start, length, depth + 1, result);
+                }
+            }
+            _ => {}
+        }
+        offset = range.start + align(offset + section_length - range.start, 4);
+    }
+}
+
+// Check that data starts with a plausible section, used to choose between EFI 1.1 and Tiano decompression
+fn starts_with_section(data: &[u8]) -> bool {
+    data.len() >= 4 && (4..=data.len()).contains(&read_u24(data, 0))
+        || data.len() >= 8 && read_u24(data, 0) == 0xFFFFFF
+}
+
+// Decompress a section. Compression sections don't say which of the standard algorithms was used,
+// the one producing a plausible section stream is preferred
+fn decompress_section(data: &[u8], section: &CompressedSection) -> Option<(Compression, Vec<u8>)> {
+    let input = &data[section.data.clone()];
+    match section.compression? {
+        Compression::Efi => {
+            let efi = decompress(Compression::Efi, input);
+            if efi.as_deref().is_some_and(starts_with_section) {
+                return efi.map(|output| (Compression::Efi, output));
+            }
+            match decompress(Compression::Tiano, input) {
+                Some(output) if starts_with_section(&output) || efi.is_none() => {
+                    Some((Compression::Tiano, output))
+                }
+                _ => efi.map(|output| (Compression::Efi, output)),
+            }
+        }
+        compression => decompress(compression, input).map(|output| (compression, output)),
+    }
+}
+
+// Decompress all compressed sections of all files, including the ones found in decompressed data.
+// Parents always come before their children
+pub fn decompress_sections(data: &[u8]) -> Vec<DecompressedSection> {
+    let mut result = Vec::new();
+    for file in find_firmware_volumes(data)
+        .iter()
+        .flat_map(|volume| volume.files.iter())
+    {
+        add_decompressed(data, file, None, &mut result);
+    }
+
+    // Decompressed data is a section stream, it may contain more compressed sections and volumes
+    let mut index = 0;
+    while index < result.len() {
+        let depth = section_path(&result, index).len();
+        let section_data = std::mem::take(&mut result[index].data);
+        let mut inner = FfsFile {
+            compressed: false,
+            compressed_sections: Vec::new(),
+            name: None,
+            ..result[index].file.clone()
+        };
+        let mut volumes = Vec::new();
+        parse_sections(
+            &section_data,
+            0..section_data.len(),
+            0,
+            0,
+            &mut inner,
+            &mut volumes,
+        );
+        if result[index].file.name.is_none() {
+            result[index].file.name = inner.name.clone();
+        }
+        if depth < MAX_DECOMPRESSION_DEPTH {
+            inner.name = result[index].file.name.clone();
+            add_decompressed(&section_data, &inner, Some(index), &mut result);
+            for file in volumes.iter().flat_map(|volume| volume.files.iter()) {
+                add_decompressed(&section_data, file, Some(index), &mut result);
+            }
+        }
+        result[index].volumes = volumes;
+        result[index].data = section_data;
+        index += 1;
+    }
+    result
+}
+
+fn add_decompressed(
+    data: &[u8],
+    file: &FfsFile,
+    parent: Option<usize>,
+    result: &mut Vec<DecompressedSection>,
+) {
+    for section in &file.compressed_sections {
+        if let Some((compression, output)) = decompress_section(data, section) {
+            result.push(DecompressedSection {
+                offset: section.offset,
+                parent,
+                compression,
+                file: file.clone(),
+                volumes: Vec::new(),
+                data: output,
+            });
+        }
+    }
+}
+
+// Offsets of compressed sections containing a decompressed section, outermost first.
+// Every offset is relative to the data of the previous section, the first one to the input data
+pub fn section_path(sections: &[DecompressedSection], index: usize) -> Vec<usize> {
+    let mut path = vec![sections[index].offset];
+    let mut current = sections[index].parent;
+    while let Some(parent) = current {
+        path.insert(0, sections[parent].offset);
+        current = sections[parent].parent;
+    }
+    path
+}
+
+// Innermost FFS file containing a given offset
+pub fn ffs_file_at(volumes: &[FirmwareVolume], offset: usize) -> Option<&FfsFile> {
+    volumes
+        .iter()
+        .flat_map(|volume| volume.files.iter())
+        .filter(|file| file.range().contains(&offset))
+        .min_by_key(|file| file.length)
+}
+
+// Find UEFI string and form packages in a full firmware image with the FFS files they came from
+pub fn uefi_package_origins(data: &[u8]) -> Vec<PackageOrigin> {
+    let volumes = find_firmware_volumes(data);
+    let (strings, forms) = find_uefi_packages(data);
+    let mut result: Vec<PackageOrigin> = strings
+        .iter()
+        .map(|string| ("UEFI string", string.offset))
+        .chain(forms.iter().map(|form| ("UEFI form", form.offset)))
+        .map(|(kind, offset)| PackageOrigin {
+            kind,
+            offset,
+            section: None,
+            file: ffs_file_at(&volumes, offset).cloned(),
+        })
+        .collect();
+    result.sort_by_key(|origin| origin.offset);
+    result
+}
+
+// Same as uefi_package_origins, packages in compressed sections are found too.
+// Their files are the innermost ones in the decompressed data, or the files of the compressed sections
+pub fn uefi_package_origins_with_decompression(
+    data: &[u8],
+) -> (Vec<DecompressedSection>, Vec<PackageOrigin>) {
+    let sections = decompress_sections(data);
+    let mut result = uefi_package_origins(data);
+    for (index, section) in sections.iter().enumerate() {
+        let (strings, forms) = find_uefi_packages(&section.data);
+        let mut origins: Vec<PackageOrigin> = strings
+            .iter()
+            .map(|string| ("UEFI string", string.offset))
+            .chain(forms.iter().map(|form| ("UEFI form", form.offset)))
+            .map(|(kind, offset)| PackageOrigin {
+                kind,
+                offset,
+                section: Some(index),
+                file: Some(
+                    ffs_file_at(&section.volumes, offset)
+                        .unwrap_or(&section.file)
+                        .clone(),
+                ),
+            })
+            .collect();
+        origins.sort_by_key(|origin| origin.offset);
+        result.append(&mut origins);
+    }
+    (sections, result)
+}
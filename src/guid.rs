@@ -0,0 +1,135 @@
+// GUID shared by UEFI and Framework parsers.
+// Firmware stores GUIDs in mixed-endian order: the first three fields are little-endian, the last 8 bytes are kept as is
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Default)]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuidParseError {
+    InvalidLength,    // Not 36 characters without braces
+    InvalidSeparator, // Dashes are not at their places
+    InvalidDigit,     // Not a hexadecimal digit
+}
+
+impl Guid {
+    // GUID as stored in firmware images and HII packages
+    pub fn from_bytes_le(bytes: &[u8; 16]) -> Guid {
+        let mut data4 = [0u8; 8];
+        data4.copy_from_slice(&bytes[8..16]);
+        Guid {
+            data1: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            data2: u16::from_le_bytes([bytes[4], bytes[5]]),
+            data3: u16::from_le_bytes([bytes[6], bytes[7]]),
+            data4,
+        }
+    }
+
+    pub fn to_bytes_le(&self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[0..4].copy_from_slice(&self.data1.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.data2.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.data3.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.data4);
+        bytes
+    }
+
+    // GUID in RFC 4122 byte order, as used by most non-UEFI tools
+    pub fn from_bytes_be(bytes: &[u8; 16]) -> Guid {
+        let mut data4 = [0u8; 8];
+        data4.copy_from_slice(&bytes[8..16]);
+        Guid {
+            data1: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            data2: u16::from_be_bytes([bytes[4], bytes[5]]),
+            data3: u16::from_be_bytes([bytes[6], bytes[7]]),
+            data4,
+        }
+    }
+
+    pub fn to_bytes_be(&self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[0..4].copy_from_slice(&self.data1.to_be_bytes());
+        bytes[4..6].copy_from_slice(&self.data2.to_be_bytes());
+        bytes[6..8].copy_from_slice(&self.data3.to_be_bytes());
+        bytes[8..16].copy_from_slice(&self.data4);
+        bytes
+    }
+
+    // C struct initializer as used in EDK2 sources, i.e. { 0x7B59104A, 0xC00D, 0x4158, { 0x87, 0xFF, ... } }
+    pub fn c_struct(&self) -> String {
+        let data4: Vec<String> = self.data4.iter().map(|byte| format!("0x{byte:02X}")).collect();
+        format!(
+            "{{ 0x{:08X}, 0x{:04X}, 0x{:04X}, {{ {} }} }}",
+            self.data1,
+            self.data2,
+            self.data3,
+            data4.join(", ")
+        )
+    }
+}
+
+// Registry format, i.e. 7B59104A-C00D-4158-87FF-F04D6396A915
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+            self.data1,
+            self.data2,
+            self.data3,
+            self.data4[0],
+            self.data4[1],
+            self.data4[2],
+            self.data4[3],
+            self.data4[4],
+            self.data4[5],
+            self.data4[6],
+            self.data4[7]
+        )
+    }
+}
+
+// Debug output uses the registry format too, so GUIDs look the same in all outputs
+impl fmt::Debug for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Guid({self})")
+    }
+}
+
+// Registry format in any case, optionally enclosed in braces
+impl FromStr for Guid {
+    type Err = GuidParseError;
+
+    fn from_str(s: &str) -> Result<Guid, GuidParseError> {
+        let s = s
+            .strip_prefix('{')
+            .and_then(|inner| inner.strip_suffix('}'))
+            .unwrap_or(s);
+        if s.len() != 36 {
+            return Err(GuidParseError::InvalidLength);
+        }
+        let mut digits = [0u8; 32];
+        let mut count = 0;
+        for (i, c) in s.chars().enumerate() {
+            if i == 8 || i == 13 || i == 18 || i == 23 {
+                if c != '-' {
+                    return Err(GuidParseError::InvalidSeparator);
+                }
+            } else {
+                digits[count] = c.to_digit(16).ok_or(GuidParseError::InvalidDigit)? as u8;
+                count += 1;
+            }
+        }
+        let mut bytes = [0u8; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = digits[2 * i] << 4 | digits[2 * i + 1];
+        }
+        Ok(Guid::from_bytes_be(&bytes))
+    }
+}
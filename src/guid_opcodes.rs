@@ -0,0 +1,92 @@
+// Decoders of Guid opcodes with vendor GUIDs, i.e. AMI, Insyde or Phoenix extensions.
+// Registered decoders are used while StringIds of form packages are collected and while they are rendered,
+// and take precedence over the built-in EDK and EDK2 decoders for the same GUID
+use std::fmt;
+use std::sync::Arc;
+
+use super::StringMap;
+use uefi_parser::Guid;
+
+pub trait GuidOpcodeDecoder: Send + Sync {
+    // GUID of the opcodes handled by this decoder
+    fn guid(&self) -> Guid;
+
+    // Name identifying the decoder in cache keys
+    fn name(&self) -> String;
+
+    // StringIds referenced by the payload after the GUID, so string packages with them can be paired
+    fn string_ids(&self, _payload: &[u8]) -> Vec<u16> {
+        Vec::new()
+    }
+
+    // Text printed after the GUID, i.e. "Knob: \"Setup\", Offset: 0x10".
+    // None if the payload can't be decoded, it's printed the way unknown Guid opcodes are then
+    fn render(&self, payload: &[u8], strings: &StringMap) -> Option<String>;
+}
+
+// Registry of decoders, the one registered last wins if several handle the same GUID
+#[derive(Default, Clone)]
+pub struct GuidOpcodeDecoders {
+    decoders: Vec<Arc<dyn GuidOpcodeDecoder>>,
+}
+
+impl GuidOpcodeDecoders {
+    pub fn new() -> GuidOpcodeDecoders {
+        GuidOpcodeDecoders::default()
+    }
+
+    pub fn register<D: GuidOpcodeDecoder + 'static>(&mut self, decoder: D) {
+        self.decoders.push(Arc::new(decoder));
+    }
+
+    pub fn decoder(&self, guid: &Guid) -> Option<&dyn GuidOpcodeDecoder> {
+        self.decoders
+            .iter()
+            .rev()
+            .find(|decoder| decoder.guid() == *guid)
+            .map(|decoder| decoder.as_ref())
+    }
+
+    pub fn contains(&self, guid: &Guid) -> bool {
+        self.decoder(guid).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+
+    // StringIds of a Guid opcode payload, empty if no decoder handles its GUID
+    pub fn string_ids(&self, guid: &Guid, payload: &[u8]) -> Vec<u16> {
+        self.decoder(guid)
+            .map_or_else(Vec::new, |decoder| decoder.string_ids(payload))
+    }
+
+    // Text of a Guid opcode payload, None if no decoder handles its GUID or decoding fails
+    pub fn render(&self, guid: &Guid, payload: &[u8], strings: &StringMap) -> Option<String> {
+        self.decoder(guid)
+            .and_then(|decoder| decoder.render(payload, strings))
+    }
+
+    // (GUID, name) pairs of all decoders in registration order
+    fn entries(&self) -> Vec<(Guid, String)> {
+        self.decoders
+            .iter()
+            .map(|decoder| (decoder.guid(), decoder.name()))
+            .collect()
+    }
+}
+
+impl fmt::Debug for GuidOpcodeDecoders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.entries()).finish()
+    }
+}
+
+// Registries are equal if they have decoders with the same GUIDs and names in the same order
+impl PartialEq for GuidOpcodeDecoders {
+    fn eq(&self, other: &GuidOpcodeDecoders) -> bool {
+        self.entries() == other.entries()
+    }
+}
+
+impl Eq for GuidOpcodeDecoders {}
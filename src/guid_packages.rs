@@ -0,0 +1,88 @@
+// UEFI HII GUID packages, vendor-defined packages identified by the GUID after their header.
+// Payloads are exposed as they are, decoders for vendor formats can be registered by GUID to describe them
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::sha256;
+use package_lists::uefi_package_lists;
+use uefi_parser::{Guid, HiiPackageType};
+
+// Package header and GUID come before the payload
+const PAYLOAD_OFFSET: usize = 4 + 16;
+
+pub struct GuidPackage {
+    pub offset: usize,
+    pub length: usize,
+    pub guid: Guid,
+    pub package_list_guid: Option<Guid>, // None if the package list has no header
+    pub sha256: [u8; 32],
+}
+
+impl GuidPackage {
+    // Vendor-defined data after the GUID
+    pub fn payload<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        &data[self.offset + PAYLOAD_OFFSET..self.offset + self.length]
+    }
+}
+
+// Find all GUID packages in UEFI HII package lists, in order of their offsets.
+// The package type alone matches too much unrelated data, so only packages of known package lists are reported
+pub fn find_uefi_guid_packages(data: &[u8]) -> Vec<GuidPackage> {
+    uefi_package_lists(data)
+        .iter()
+        .flat_map(|list| {
+            list.packages
+                .iter()
+                .filter(|package| package.package_type == HiiPackageType::Guid)
+                .filter_map(move |package| {
+                    Some(GuidPackage {
+                        offset: package.offset,
+                        length: package.length,
+                        guid: package.guid?,
+                        package_list_guid: list.guid,
+                        sha256: sha256(package.data(data)),
+                    })
+                })
+        })
+        .collect()
+}
+
+// Decoder of GUID package payloads, returns a description of the payload or None if it can't be decoded
+pub type GuidPackageDecoder = Arc<dyn Fn(&[u8]) -> Option<String> + Send + Sync>;
+
+// Registry of decoders keyed by package GUID
+#[derive(Default, Clone)]
+pub struct GuidPackageDecoders {
+    decoders: HashMap<Guid, GuidPackageDecoder>,
+}
+
+impl GuidPackageDecoders {
+    pub fn new() -> GuidPackageDecoders {
+        GuidPackageDecoders::default()
+    }
+
+    // Register a decoder for packages with a given GUID, replacing the previous one
+    pub fn register<F: Fn(&[u8]) -> Option<String> + Send + Sync + 'static>(&mut self, guid: Guid, decoder: F) {
+        self.decoders.insert(guid, Arc::new(decoder));
+    }
+
+    pub fn contains(&self, guid: &Guid) -> bool {
+        self.decoders.contains_key(guid)
+    }
+
+    // Description of a package payload, None if no decoder is registered for its GUID or decoding fails
+    pub fn decode(&self, data: &[u8], package: &GuidPackage) -> Option<String> {
+        self.decoders
+            .get(&package.guid)
+            .and_then(|decoder| decoder(package.payload(data)))
+    }
+}
+
+impl fmt::Debug for GuidPackageDecoders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut guids: Vec<&Guid> = self.decoders.keys().collect();
+        guids.sort();
+        f.debug_set().entries(guids).finish()
+    }
+}
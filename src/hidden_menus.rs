@@ -0,0 +1,333 @@
+// Report of forms and questions a user can't normally reach or change: ones in SuppressIf, GrayOutIf
+// and DisableIf scopes with their conditions, and forms no Ref leads to. Conditions are not evaluated,
+// so the report lists everything that may be hidden
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use super::{
+    find_uefi_packages, uefi_expression, uefi_preferred_string_package, FormPackage,
+    OpcodeCategory, StringEscaping, StringMap, StringPackage,
+};
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HiddenReason {
+    SuppressIf { expression: String },
+    GrayOutIf { expression: String },
+    DisableIf { expression: String },
+    UnreferencedForm { form_id: u16 }, // Form itself or the form a question is in
+}
+
+impl HiddenReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HiddenReason::SuppressIf { .. } => "SuppressIf",
+            HiddenReason::GrayOutIf { .. } => "GrayOutIf",
+            HiddenReason::DisableIf { .. } => "DisableIf",
+            HiddenReason::UnreferencedForm { .. } => "UnreferencedForm",
+        }
+    }
+
+    // Condition responsible for hiding, FormId for unreferenced forms
+    pub fn condition(&self) -> String {
+        match self {
+            HiddenReason::SuppressIf { expression }
+            | HiddenReason::GrayOutIf { expression }
+            | HiddenReason::DisableIf { expression } => expression.clone(),
+            HiddenReason::UnreferencedForm { form_id } => format!("FormId 0x{form_id:X}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HiddenItem {
+    pub offset: usize, // Offset of the Form, FormMap or question opcode in the input data
+    pub opcode: uefi_parser::IfrOpcode,
+    pub form_id: Option<u16>, // FormId of a form or of the form a question is in
+    pub question_id: Option<u16>, // None for forms
+    pub text: String,         // Title of a form or prompt of a question
+    pub reasons: Vec<HiddenReason>, // Outermost condition first
+}
+
+// Form found while walking, it is reported if nothing refers to it
+struct FormInfo {
+    item: HiddenItem,
+    formset: Option<uefi_parser::Guid>,
+    first: bool, // First form of a formset is shown by the browser without a Ref
+}
+
+// Number of operations of an expression at the start of a conditional scope, statements it controls follow it
+fn expression_length(operations: &[uefi_parser::IfrOperation]) -> usize {
+    let mut nested_depth: usize = 0;
+    for (index, operation) in operations.iter().enumerate() {
+        if nested_depth > 0 {
+            // Contents of nested scopes, i.e. Map pairs, belong to the expression
+            if operation.OpCode == uefi_parser::IfrOpcode::End {
+                nested_depth -= 1;
+            } else if operation.ScopeStart {
+                nested_depth += 1;
+            }
+            continue;
+        }
+        if operation.OpCode.category() != OpcodeCategory::Expression {
+            return index;
+        }
+        if operation.ScopeStart {
+            nested_depth += 1;
+        }
+    }
+    operations.len()
+}
+
+// Collect hidden forms and questions of a UEFI form package, strings are resolved using a given string package
+pub fn uefi_hidden_items(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: Option<&StringPackage>,
+) -> Vec<HiddenItem> {
+    let no_strings = HashMap::new();
+    let strings_map = &StringMap::plain(
+        string_package.map_or(&no_strings, |strings| &strings.string_id_map),
+        "InvalidId",
+    );
+
+    let operations = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate).ok())
+        .and_then(|(_, package)| uefi_parser::ifr_operations(package.Data.unwrap_or(&[])).ok())
+    {
+        Some((_, operations)) => operations,
+        None => return Vec::new(),
+    };
+
+    let mut questions: Vec<(HiddenItem, usize)> = Vec::new(); // With the index of their form
+    let mut forms: Vec<FormInfo> = Vec::new();
+    let mut references: HashSet<(Option<uefi_parser::Guid>, u16)> = HashSet::new();
+
+    // Every open scope remembers the condition it adds and whether it is a form
+    let mut scopes: Vec<(Option<HiddenReason>, bool)> = Vec::new();
+    let mut current_formset = None;
+    let mut formset_has_forms = false;
+    let mut current_form: Option<usize> = None;
+    // Form package header is 4 bytes long
+    let mut offset = form_package.offset + 4;
+    for (index, operation) in operations.iter().enumerate() {
+        let op_data = operation.Data.unwrap_or(&[]);
+        let expression = || {
+            let expression_end = index + 1 + expression_length(&operations[index + 1..]);
+            uefi_expression(&operations[index + 1..expression_end], strings_map)
+        };
+        let conditions = || -> Vec<HiddenReason> {
+            scopes
+                .iter()
+                .filter_map(|(condition, _)| condition.clone())
+                .collect()
+        };
+
+        let mut condition = None;
+        let mut is_form = false;
+        match operation.OpCode {
+            uefi_parser::IfrOpcode::FormSet => {
+                current_formset = uefi_parser::ifr_form_set(op_data)
+                    .ok()
+                    .map(|(_, form_set)| form_set.Guid);
+                formset_has_forms = false;
+            }
+            uefi_parser::IfrOpcode::Form | uefi_parser::IfrOpcode::FormMap => {
+                let form = if operation.OpCode == uefi_parser::IfrOpcode::Form {
+                    uefi_parser::ifr_form(op_data)
+                        .ok()
+                        .map(|(_, form)| (form.FormId, form.TitleStringId))
+                } else {
+                    uefi_parser::ifr_form_map(op_data)
+                        .ok()
+                        .map(|(_, form_map)| (form_map.FormId, form_map.Methods[0].MethodTitleId))
+                };
+                if let Some((form_id, title)) = form {
+                    is_form = true;
+                    current_form = Some(forms.len());
+                    forms.push(FormInfo {
+                        item: HiddenItem {
+                            offset,
+                            opcode: operation.OpCode,
+                            form_id: Some(form_id),
+                            question_id: None,
+                            text: strings_map.resolve(title).into_owned(),
+                            reasons: conditions(),
+                        },
+                        formset: current_formset,
+                        first: !formset_has_forms,
+                    });
+                    formset_has_forms = true;
+                }
+            }
+            uefi_parser::IfrOpcode::SuppressIf => {
+                condition = Some(HiddenReason::SuppressIf {
+                    expression: expression(),
+                })
+            }
+            uefi_parser::IfrOpcode::GrayOutIf => {
+                condition = Some(HiddenReason::GrayOutIf {
+                    expression: expression(),
+                })
+            }
+            uefi_parser::IfrOpcode::DisableIf => {
+                condition = Some(HiddenReason::DisableIf {
+                    expression: expression(),
+                })
+            }
+            _ => {}
+        }
+
+        if operation.OpCode == uefi_parser::IfrOpcode::Ref {
+            if let Ok((_, reference)) = uefi_parser::ifr_ref(op_data) {
+                if let Some(form_id) = reference.FormId {
+                    // Zero FormSetGuid refers to the current formset
+                    let formset = reference
+                        .FormSetGuid
+                        .filter(|guid| *guid != uefi_parser::Guid::default())
+                        .or(current_formset);
+                    references.insert((formset, form_id));
+                }
+            }
+        }
+        if operation.OpCode.is_question() && op_data.len() >= 6 {
+            if let Some(form) = current_form {
+                questions.push((
+                    HiddenItem {
+                        offset,
+                        opcode: operation.OpCode,
+                        form_id: forms[form].item.form_id,
+                        question_id: Some(u16::from_le_bytes([op_data[4], op_data[5]])),
+                        text: strings_map
+                            .resolve(u16::from_le_bytes([op_data[0], op_data[1]]))
+                            .into_owned(),
+                        reasons: conditions(),
+                    },
+                    form,
+                ));
+            }
+        }
+
+        if operation.OpCode == uefi_parser::IfrOpcode::End {
+            if let Some((_, true)) = scopes.pop() {
+                current_form = None;
+            }
+        } else if operation.ScopeStart {
+            scopes.push((condition, is_form));
+        }
+        offset += operation.Length as usize;
+    }
+
+    // Forms nothing refers to hide all their questions
+    let unreferenced: Vec<bool> = forms
+        .iter()
+        .map(|form| {
+            let form_id = form.item.form_id.unwrap_or(0);
+            !form.first && !references.contains(&(form.formset, form_id))
+        })
+        .collect();
+    let mut result = Vec::new();
+    for (index, form) in forms.into_iter().enumerate() {
+        let mut item = form.item;
+        if unreferenced[index] {
+            item.reasons.push(HiddenReason::UnreferencedForm {
+                form_id: item.form_id.unwrap_or(0),
+            });
+        }
+        if !item.reasons.is_empty() {
+            result.push(item);
+        }
+    }
+    for (mut item, form) in questions {
+        if unreferenced[form] {
+            item.reasons.insert(
+                0,
+                HiddenReason::UnreferencedForm {
+                    form_id: item.form_id.unwrap_or(0),
+                },
+            );
+        }
+        if !item.reasons.is_empty() {
+            result.push(item);
+        }
+    }
+    result.sort_by_key(|item| item.offset);
+    result
+}
+
+// Hidden items of all UEFI form packages of an image, prompts are resolved using preferred string packages
+pub fn uefi_hidden_report(data: &[u8]) -> Vec<HiddenItem> {
+    let (strings, forms) = find_uefi_packages(data);
+    forms
+        .iter()
+        .flat_map(|form| {
+            uefi_hidden_items(
+                data,
+                form,
+                uefi_preferred_string_package(data, &strings, form),
+            )
+        })
+        .collect()
+}
+
+// One line per item followed by its conditions, outermost first
+pub fn hidden_items_text(items: &[HiddenItem]) -> String {
+    let mut text = String::new();
+    for item in items {
+        write!(
+            &mut text,
+            "Offset: 0x{:X}, {:?} \"{}\"",
+            item.offset, item.opcode, item.text
+        )
+        .unwrap();
+        if let Some(form_id) = item.form_id {
+            write!(&mut text, ", FormId: 0x{form_id:X}").unwrap();
+        }
+        if let Some(question_id) = item.question_id {
+            write!(&mut text, ", QuestionId: 0x{question_id:X}").unwrap();
+        }
+        text.push('\n');
+        for reason in &item.reasons {
+            writeln!(&mut text, "\t{}: {}", reason.name(), reason.condition()).unwrap();
+        }
+    }
+    text
+}
+
+// JSON array of items, every one with a list of its reasons
+pub fn hidden_items_json(items: &[HiddenItem]) -> String {
+    let quote = |string: &str| format!("\"{}\"", StringEscaping::Json.escape(string));
+    let number =
+        |value: Option<u16>| value.map_or_else(|| String::from("null"), |value| value.to_string());
+    let mut json = String::from("[");
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        let reasons: Vec<String> = item
+            .reasons
+            .iter()
+            .map(|reason| {
+                format!(
+                    "{{\"type\":{},\"condition\":{}}}",
+                    quote(reason.name()),
+                    quote(&reason.condition())
+                )
+            })
+            .collect();
+        write!(
+            &mut json,
+            "\n{{\"offset\":{},\"opcode\":{},\"form_id\":{},\"question_id\":{},\"text\":{},\"reasons\":[{}]}}",
+            item.offset,
+            quote(&format!("{:?}", item.opcode)),
+            number(item.form_id),
+            number(item.question_id),
+            quote(&item.text),
+            reasons.join(",")
+        )
+        .unwrap();
+    }
+    json.push_str("\n]\n");
+    json
+}
@@ -0,0 +1,1686 @@
+// Input file with all its HII packages, their pairings and extraction results,
+// so callers don't have to pair string and form packages themselves
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use pyo3::prelude::*;
+
+use super::{
+    extract_framework_ifr_to_writer, extract_framework_ifr_with_diagnostics,
+    extract_uefi_ifr_to_writer, extract_uefi_ifr_with_diagnostics,
+    find_packages_auto_with_options, find_packages_auto_with_progress,
+    find_packages_auto_with_summary, find_uefi_animation_packages_with_options,
+    find_uefi_image_packages_with_options, framework_string_packages, hex_string,
+    try_extract_framework_ifr, try_extract_uefi_ifr, uefi_resources, AnimationPackage,
+    DetectedPackages, ExtractOptions, HiiFlavor, IfrError, ImagePackage, LanguageExtraction,
+    Preview, ScanOptions, StringMap, StringProcessor,
+};
+use super::{
+    borrowed_strings, default_stores, fingerprint, form_graph, fv, knob_map, nvram_comparison,
+    question_csv, question_defaults, setup_var, uefi_preferred_string_package, var_stores,
+};
+use device_paths::{find_uefi_device_path_packages_with_options, DevicePathPackage};
+use diagnostics::Diagnostics;
+use fonts::{find_uefi_font_packages, find_uefi_simple_font_packages, font_style_names};
+use guid_opcodes::{GuidOpcodeDecoder, GuidOpcodeDecoders};
+use guid_packages::{find_uefi_guid_packages, GuidPackageDecoders};
+use ifr_tree::{parse_uefi_ifr, IfrTree};
+use mapped_file::MappedInput;
+use package_lists::{uefi_package_lists, HiiPackageList};
+use scan_metrics::{ScanMetrics, ScanProgress, ScanSummary};
+use uefi_parser::Guid;
+
+// Form package and string package used to extract it, indices are into the lists of the same flavor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pairing {
+    pub flavor: HiiFlavor,
+    pub form_index: usize,
+    pub string_index: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Extraction {
+    pub pairing: Pairing,
+    pub language: String,
+    pub text: String,
+    pub diagnostics: Diagnostics,
+}
+
+// Knob as returned to Python
+type PyKnob = (
+    usize,
+    u16,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<u16>,
+    usize,
+    &'static str,
+    Option<(usize, usize)>,
+);
+
+// Diagnostic as returned to Python
+type PyWarning = (usize, usize, String, String, usize);
+
+// Location of a diagnostic as returned to Python
+type PyWarningLocation = (usize, usize, String, usize, usize, Vec<u8>);
+
+// Raised by try_extract, with the message, offset, context offset and context bytes as arguments
+pyo3::create_exception!(ifrextractor, IfrParseError, pyo3::exceptions::PyValueError);
+
+// Default value of a question as returned to Python
+type PyDefault = (
+    usize,
+    u16,
+    Option<String>,
+    &'static str,
+    u16,
+    String,
+    Option<String>,
+);
+
+// Comparison of a default value with NVRAM as returned to Python
+type PyNvramComparison = (
+    usize,
+    u16,
+    Option<String>,
+    &'static str,
+    String,
+    u16,
+    String,
+    String,
+    Option<String>,
+    bool,
+    Option<String>,
+);
+
+// Scan summary as returned to Python
+type PySummary = (usize, Vec<(String, usize)>, Vec<String>, f64, usize);
+
+// setup_var commands of a question as returned to Python
+type PySetupVarCommands = (usize, u16, String, String, String, Vec<String>, Option<u64>);
+
+// Node of a scope tree as returned to Python
+type PyScopeNode = (
+    usize,
+    usize,
+    Option<usize>,
+    usize,
+    usize,
+    String,
+    String,
+    Option<u16>,
+    Option<String>,
+    Vec<(&'static str, u32, Vec<&'static str>)>,
+    String,
+);
+
+// Package list with (type, offset, length, GUID) tuples of its packages as returned to Python
+type PyPackageList = (
+    usize,
+    usize,
+    Option<String>,
+    Vec<(String, usize, usize, Option<String>)>,
+);
+
+// Font or simple font package as returned to Python
+type PyFont = (&'static str, usize, usize, String, Vec<&'static str>, usize);
+
+// GUID package with its payload and decoded description as returned to Python
+type PyGuidPackage<'a> = (usize, usize, String, Option<String>, Cow<'a, [u8]>, Option<String>);
+
+// DefaultStore as returned to Python
+type PyDefaultStore = (usize, String, u16, String, &'static str);
+
+// Varstore as returned to Python
+type PyVarStore = (
+    usize,
+    u16,
+    String,
+    String,
+    Option<String>,
+    Option<u16>,
+    Option<u32>,
+);
+
+// Documents are immutable apart from caches, so a single loaded image can serve concurrent requests
+#[pyclass(frozen)]
+pub struct IfrDocument {
+    pub path: Option<PathBuf>, // None if the document was created from bytes
+    data: MappedInput,
+    scan_options: ScanOptions,
+    packages: Vec<DetectedPackages>,
+    images: Vec<ImagePackage>,              // UEFI only
+    animations: Vec<AnimationPackage>,      // UEFI only
+    device_paths: Vec<DevicePathPackage>,   // UEFI only
+    extractions: OnceLock<Vec<Extraction>>, // Cached result of extract_all
+    string_processors: Vec<StringProcessor>, // Applied to strings of all extractions
+    summary: ScanSummary,
+}
+
+// Fails to compile if a field breaks sharing between threads
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<IfrDocument>();
+};
+
+impl IfrDocument {
+    pub fn new(data: impl Into<MappedInput>) -> IfrDocument {
+        let data = data.into();
+        let (packages, summary) = find_packages_auto_with_summary(&data);
+        IfrDocument::from_scan(data, ScanOptions::default(), packages, summary)
+    }
+
+    // Same as new, calling a progress callback periodically while the input is scanned for packages
+    pub fn new_with_progress(
+        data: impl Into<MappedInput>,
+        progress: impl Fn(ScanProgress) + Send + Sync + 'static,
+    ) -> IfrDocument {
+        let data = data.into();
+        let (packages, summary) = find_packages_auto_with_progress(&data, progress);
+        IfrDocument::from_scan(data, ScanOptions::default(), packages, summary)
+    }
+
+    // Same as new, using given metrics with their progress callback and cancellation token.
+    // None if the scan is cancelled
+    pub fn new_with_metrics(
+        data: impl Into<MappedInput>,
+        metrics: ScanMetrics,
+    ) -> Option<IfrDocument> {
+        IfrDocument::new_with_options(data, ScanOptions::default(), metrics)
+    }
+
+    // Same as new_with_metrics, only searching a window of the input for packages.
+    // The window is kept for documents patched from this one
+    pub fn new_with_options(
+        data: impl Into<MappedInput>,
+        options: ScanOptions,
+        metrics: ScanMetrics,
+    ) -> Option<IfrDocument> {
+        let data = data.into();
+        let (packages, summary) = find_packages_auto_with_options(&data, &options, metrics);
+        if summary.cancelled {
+            return None;
+        }
+        Some(IfrDocument::from_scan(data, options, packages, summary))
+    }
+
+    fn from_scan(
+        data: MappedInput,
+        scan_options: ScanOptions,
+        packages: Vec<DetectedPackages>,
+        mut summary: ScanSummary,
+    ) -> IfrDocument {
+        let (images, animations, device_paths) = if packages.iter().any(|p| p.flavor == HiiFlavor::Uefi) {
+            (
+                find_uefi_image_packages_with_options(&data, &scan_options),
+                find_uefi_animation_packages_with_options(&data, &scan_options),
+                find_uefi_device_path_packages_with_options(&data, &scan_options),
+            )
+        } else {
+            (Vec::new(), Vec::new(), Vec::new())
+        };
+        summary.add_packages("UEFI image", images.len());
+        summary.add_packages("UEFI animation", animations.len());
+        summary.add_packages("UEFI device path", device_paths.len());
+        IfrDocument {
+            path: None,
+            data,
+            scan_options,
+            packages,
+            images,
+            animations,
+            device_paths,
+            extractions: OnceLock::new(),
+            string_processors: Vec::new(),
+            summary,
+        }
+    }
+
+    // Set string processors used by extract_all, they can't change once extractions are cached
+    pub fn with_string_processors(mut self, processors: Vec<StringProcessor>) -> IfrDocument {
+        self.string_processors = processors;
+        self.extractions = OnceLock::new();
+        self
+    }
+
+    // Input file is read into memory, IfrDocument::new with MappedInput::map maps it instead
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<IfrDocument> {
+        let mut document = IfrDocument::new(MappedInput::open(path.as_ref())?);
+        document.path = Some(path.as_ref().to_path_buf());
+        Ok(document)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    // UEFI packages come first if both formats are found
+    pub fn packages(&self) -> &[DetectedPackages] {
+        &self.packages
+    }
+
+    // All UEFI package lists with every package in them, not only the ones that are parsed
+    pub fn package_lists(&self) -> Vec<HiiPackageList> {
+        uefi_package_lists(&self.data)
+    }
+
+    // Summary of the package scan done when the document was created
+    pub fn summary(&self) -> &ScanSummary {
+        &self.summary
+    }
+
+    // Same pairings as the default extraction mode: UEFI form packages with all en-US string packages,
+    // or Framework form packages with eng string packages from their pack lists. UEFI wins if both are found
+    pub fn pairings(&self) -> Vec<Pairing> {
+        let mut result = Vec::new();
+        let packages = match self.packages.first() {
+            Some(packages) => packages,
+            None => return result,
+        };
+        for (form_index, form) in packages.forms.iter().enumerate() {
+            match packages.flavor {
+                HiiFlavor::Uefi => {
+                    for (string_index, string) in packages.strings.iter().enumerate() {
+                        if string.language == "en-US" {
+                            result.push(Pairing {
+                                flavor: packages.flavor,
+                                form_index,
+                                string_index,
+                            });
+                        }
+                    }
+                }
+                HiiFlavor::Framework => {
+                    for (string_index, _) in
+                        framework_string_packages(&self.data, &packages.strings, form, "eng")
+                    {
+                        result.push(Pairing {
+                            flavor: packages.flavor,
+                            form_index,
+                            string_index,
+                        });
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    // Extract all pairings, the result is cached.
+    // Concurrent callers wait for the first one to finish instead of extracting again
+    pub fn extract_all(&self) -> &[Extraction] {
+        self.extractions.get_or_init(|| {
+            self.pairings()
+                .into_iter()
+                .map(|pairing| self.extract(pairing))
+                .collect()
+        })
+    }
+
+    // Options of all extractions of this document
+    fn options(&self) -> ExtractOptions {
+        ExtractOptions {
+            string_processors: self.string_processors.clone(),
+            guid_opcode_decoders: self.scan_options.guid_opcode_decoders.clone(),
+            ..Default::default()
+        }
+    }
+
+    // Extract a single pairing, uncached
+    fn extract(&self, pairing: Pairing) -> Extraction {
+        self.extract_with_options(pairing, &self.options())
+    }
+
+    // Extract only a part of a single pairing, uncached
+    pub fn preview(&self, pairing: Pairing, preview: Preview) -> Extraction {
+        let options = ExtractOptions {
+            preview,
+            ..self.options()
+        };
+        self.extract_with_options(pairing, &options)
+    }
+
+    fn extract_with_options(&self, pairing: Pairing, options: &ExtractOptions) -> Extraction {
+        let packages = &self.packages[0];
+        let form = &packages.forms[pairing.form_index];
+        let string = &packages.strings[pairing.string_index];
+        let mut diagnostics = Diagnostics::new();
+        let text = match pairing.flavor {
+            HiiFlavor::Uefi => extract_uefi_ifr_with_diagnostics(
+                &self.data,
+                form,
+                string,
+                &uefi_resources(
+                    &self.data,
+                    &self.images,
+                    &self.animations,
+                    &self.device_paths,
+                    &packages.strings,
+                    form,
+                ),
+                options,
+                &mut diagnostics,
+            ),
+            HiiFlavor::Framework => extract_framework_ifr_with_diagnostics(
+                &self.data,
+                form,
+                string,
+                options,
+                &mut diagnostics,
+            ),
+        };
+        Extraction {
+            pairing,
+            language: string.language.clone(),
+            text,
+            diagnostics,
+        }
+    }
+
+    // Write the text of a single pairing operation by operation, without keeping it whole in memory
+    // or caching it
+    pub fn write_extraction(
+        &self,
+        pairing: Pairing,
+        writer: &mut impl io::Write,
+    ) -> io::Result<Diagnostics> {
+        let options = self.options();
+        let packages = &self.packages[0];
+        let form = &packages.forms[pairing.form_index];
+        let string = &packages.strings[pairing.string_index];
+        match pairing.flavor {
+            HiiFlavor::Uefi => extract_uefi_ifr_to_writer(
+                &self.data,
+                form,
+                string,
+                &uefi_resources(
+                    &self.data,
+                    &self.images,
+                    &self.animations,
+                    &self.device_paths,
+                    &packages.strings,
+                    form,
+                ),
+                &options,
+                writer,
+            ),
+            HiiFlavor::Framework => {
+                extract_framework_ifr_to_writer(&self.data, form, string, &options, writer)
+            }
+        }
+    }
+
+    // Text of a single pairing, failing with the location of the problem if the form package can't be parsed
+    // completely or its strings were converted with replacement characters
+    pub fn try_extract(&self, pairing: Pairing) -> Result<String, IfrError> {
+        let options = self.options();
+        let packages = &self.packages[0];
+        let form = &packages.forms[pairing.form_index];
+        let string = &packages.strings[pairing.string_index];
+        match pairing.flavor {
+            HiiFlavor::Uefi => try_extract_uefi_ifr(
+                &self.data,
+                form,
+                string,
+                &uefi_resources(
+                    &self.data,
+                    &self.images,
+                    &self.animations,
+                    &self.device_paths,
+                    &packages.strings,
+                    form,
+                ),
+                &options,
+            ),
+            HiiFlavor::Framework => try_extract_framework_ifr(&self.data, form, string, &options),
+        }
+    }
+
+    // Copy of the document with bytes at a given offset replaced, None if they don't fit the input data.
+    // Packages are found again. If extractions are cached, the ones of packages the patch doesn't touch
+    // are kept and only the affected ones are extracted again
+    pub fn patched(&self, offset: usize, bytes: &[u8]) -> Option<IfrDocument> {
+        let end = offset.checked_add(bytes.len())?;
+        if end > self.data.len() {
+            return None;
+        }
+        let mut data = self.data.to_vec();
+        data[offset..end].copy_from_slice(bytes);
+        // Scans without a cancellation token always finish
+        let mut document =
+            IfrDocument::new_with_options(data, self.scan_options.clone(), ScanMetrics::new())?
+                .with_string_processors(self.string_processors.clone());
+        document.path = self.path.clone();
+
+        let old_extractions = match self.extractions.get() {
+            Some(extractions) => extractions,
+            None => return Some(document),
+        };
+        let touches = |package_offset: usize, length: usize| {
+            offset < package_offset + length && package_offset < end
+        };
+        // Extractions also depend on all string packages of their flavor and on UEFI images, animations and device paths
+        let resources_touched = document.packages.first().is_some_and(|packages| {
+            packages
+                .strings
+                .iter()
+                .map(|string| (string.offset, string.length))
+                .chain(document.images.iter().map(|image| (image.offset, image.length)))
+                .chain(
+                    document
+                        .animations
+                        .iter()
+                        .map(|animation| (animation.offset, animation.length)),
+                )
+                .chain(
+                    document
+                        .device_paths
+                        .iter()
+                        .map(|device_path| (device_path.offset, device_path.length)),
+                )
+                .any(|(package_offset, length)| touches(package_offset, length))
+        });
+        let extractions = document
+            .pairings()
+            .into_iter()
+            .map(|pairing| {
+                let packages = &document.packages[0];
+                let form = &packages.forms[pairing.form_index];
+                let string = &packages.strings[pairing.string_index];
+                let unchanged = old_extractions.iter().find(|extraction| {
+                    let old_packages = &self.packages[0];
+                    let old_form = &old_packages.forms[extraction.pairing.form_index];
+                    let old_string = &old_packages.strings[extraction.pairing.string_index];
+                    extraction.pairing.flavor == pairing.flavor
+                        && old_form.offset == form.offset
+                        && old_form.length == form.length
+                        && old_string.offset == string.offset
+                        && old_string.length == string.length
+                });
+                let touched = resources_touched || touches(form.offset, form.length);
+                match unchanged {
+                    Some(extraction) if !touched => Extraction {
+                        pairing,
+                        ..extraction.clone()
+                    },
+                    _ => document.extract(pairing),
+                }
+            })
+            .collect();
+        let _ = document.extractions.set(extractions);
+        Some(document)
+    }
+
+    // Knobs of all UEFI form packages with indices of their form packages
+    pub fn knob_map(&self) -> Vec<(usize, knob_map::Knob)> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                let string_package =
+                    uefi_preferred_string_package(&self.data, &packages.strings, form);
+                for knob in knob_map::knob_map(&self.data, form, string_package) {
+                    result.push((form_index, knob));
+                }
+            }
+        }
+        result
+    }
+
+    // setup_var commands for questions with a given prompt or QuestionId in all UEFI form packages,
+    // with indices of their form packages. Value is None for commands that print the current value
+    pub fn setup_var_commands(
+        &self,
+        query: &str,
+        value: Option<u64>,
+    ) -> Vec<(usize, setup_var::SetupVarCommands)> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                let string_package =
+                    uefi_preferred_string_package(&self.data, &packages.strings, form);
+                let knobs = knob_map::knob_map(&self.data, form, string_package);
+                for commands in setup_var::setup_var_commands(&knobs, query, value) {
+                    result.push((form_index, commands));
+                }
+            }
+        }
+        result
+    }
+
+    // Structural fingerprints of all UEFI form packages with their indices,
+    // packages that can't be parsed are skipped
+    pub fn fingerprints(
+        &self,
+        options: &fingerprint::FingerprintOptions,
+    ) -> Vec<(usize, [u8; 32])> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                if let Some(hash) = fingerprint::uefi_form_fingerprint(&self.data, form, options) {
+                    result.push((form_index, hash));
+                }
+            }
+        }
+        result
+    }
+
+    // Defaults of all questions of UEFI form packages with indices of their form packages,
+    // strings are resolved using preferred string packages
+    pub fn defaults(&self) -> Vec<(usize, question_defaults::QuestionDefaults)> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                let string_package =
+                    match uefi_preferred_string_package(&self.data, &packages.strings, form) {
+                        Some(string_package) => string_package,
+                        None => continue,
+                    };
+                let resources = uefi_resources(
+                    &self.data,
+                    &self.images,
+                    &self.animations,
+                    &self.device_paths,
+                    &packages.strings,
+                    form,
+                );
+                for question in
+                    question_defaults::question_defaults(&self.data, form, string_package, &resources)
+                {
+                    result.push((form_index, question));
+                }
+            }
+        }
+        result
+    }
+
+    // Opcode trees of all UEFI form packages with indices of their form packages,
+    // strings are resolved using preferred string packages
+    pub fn trees(&self) -> Vec<(usize, IfrTree)> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                let string_package =
+                    match uefi_preferred_string_package(&self.data, &packages.strings, form) {
+                        Some(string_package) => string_package,
+                        None => continue,
+                    };
+                let resources = uefi_resources(
+                    &self.data,
+                    &self.images,
+                    &self.animations,
+                    &self.device_paths,
+                    &packages.strings,
+                    form,
+                );
+                result.push((
+                    form_index,
+                    parse_uefi_ifr(&self.data, form, string_package, &resources),
+                ));
+            }
+        }
+        result
+    }
+
+    // DefaultStores of all formsets of UEFI form packages with indices of their form packages,
+    // names are resolved using preferred string packages
+    pub fn default_stores(&self) -> Vec<(usize, default_stores::DefaultStoreDeclaration)> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                let string_package =
+                    match uefi_preferred_string_package(&self.data, &packages.strings, form) {
+                        Some(string_package) => string_package,
+                        None => continue,
+                    };
+                let resources = uefi_resources(
+                    &self.data,
+                    &self.images,
+                    &self.animations,
+                    &self.device_paths,
+                    &packages.strings,
+                    form,
+                );
+                for store in
+                    default_stores::default_stores(&self.data, form, string_package, &resources)
+                {
+                    result.push((form_index, store));
+                }
+            }
+        }
+        result
+    }
+
+    // Defaults of all questions of UEFI form packages compared with current values from an NVRAM dump,
+    // with indices of their form packages and diagnostics of all packages
+    pub fn compare_nvram(
+        &self,
+        nvram: &[u8],
+    ) -> (Vec<(usize, nvram_comparison::NvramComparison)>, Diagnostics) {
+        let variables = nvram_comparison::nvram_variables(nvram);
+        let mut diagnostics = Diagnostics::new();
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                let string_package =
+                    match uefi_preferred_string_package(&self.data, &packages.strings, form) {
+                        Some(string_package) => string_package,
+                        None => continue,
+                    };
+                let resources = uefi_resources(
+                    &self.data,
+                    &self.images,
+                    &self.animations,
+                    &self.device_paths,
+                    &packages.strings,
+                    form,
+                );
+                for comparison in nvram_comparison::compare_nvram_defaults(
+                    &self.data,
+                    form,
+                    string_package,
+                    &resources,
+                    &variables,
+                    &mut diagnostics,
+                ) {
+                    result.push((form_index, comparison));
+                }
+            }
+        }
+        (result, diagnostics)
+    }
+
+    // UEFI form packages shipped without string packages extracted with strings in a given language borrowed
+    // from package lists with the same GUID in a sibling document, with indices of their form packages.
+    // String package indices are into the UEFI string packages of the sibling
+    pub fn extract_with_borrowed_strings(
+        &self,
+        sibling: &IfrDocument,
+        language: &str,
+    ) -> Vec<(usize, LanguageExtraction)> {
+        fn uefi(document: &IfrDocument) -> Option<&DetectedPackages> {
+            document
+                .packages
+                .iter()
+                .find(|packages| packages.flavor == HiiFlavor::Uefi)
+        }
+        let (forms, sibling_strings) = match (uefi(self), uefi(sibling)) {
+            (Some(packages), Some(sibling_packages)) => (&packages.forms, &sibling_packages.strings),
+            _ => return Vec::new(),
+        };
+        let mut result = Vec::new();
+        for (form_index, guid) in borrowed_strings::stripped_uefi_forms(&self.data, forms) {
+            let form = &forms[form_index];
+            let (indices, string) = match borrowed_strings::borrowed_string_package(
+                &sibling.data,
+                sibling_strings,
+                &guid,
+                language,
+            ) {
+                Some(borrowed) => borrowed,
+                None => continue,
+            };
+            let resources = uefi_resources(&self.data, &self.images, &self.animations, &self.device_paths, &[], form);
+            let mut diagnostics = Diagnostics::new();
+            let text = extract_uefi_ifr_with_diagnostics(
+                &self.data,
+                form,
+                &string,
+                &resources,
+                &self.options(),
+                &mut diagnostics,
+            );
+            result.push((
+                form_index,
+                LanguageExtraction {
+                    language: string.language,
+                    string_packages: indices,
+                    text,
+                    diagnostics,
+                },
+            ));
+        }
+        result
+    }
+
+    // Varstores of all UEFI form packages with indices of their form packages,
+    // questions refer to them by VarStoreId within the same form package
+    pub fn var_stores(&self) -> Vec<(usize, var_stores::VarStoreDeclaration)> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for (form_index, form) in packages.forms.iter().enumerate() {
+                for var_store in var_stores::uefi_var_stores(&self.data, form) {
+                    result.push((form_index, var_store));
+                }
+            }
+        }
+        result
+    }
+
+    // Navigation graph of all UEFI form packages in GraphViz DOT format
+    pub fn export_form_graph_dot(&self) -> String {
+        let mut graph = form_graph::FormGraph::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for form in &packages.forms {
+                if let Some(string_package) =
+                    uefi_preferred_string_package(&self.data, &packages.strings, form)
+                {
+                    let resources = uefi_resources(
+                        &self.data,
+                        &self.images,
+                        &self.animations,
+                        &self.device_paths,
+                        &packages.strings,
+                        form,
+                    );
+                    graph.add_package(&self.data, form, string_package, &resources);
+                }
+            }
+        }
+        graph.to_dot()
+    }
+
+    // All questions of all UEFI form packages as CSV, one row per question,
+    // attributed to FFS files of firmware volumes the packages are in
+    pub fn export_questions_csv(&self) -> String {
+        let volumes = fv::find_firmware_volumes(&self.data);
+        let mut rows = Vec::new();
+        for packages in &self.packages {
+            if packages.flavor != HiiFlavor::Uefi {
+                continue;
+            }
+            for form in &packages.forms {
+                if let Some(string_package) =
+                    uefi_preferred_string_package(&self.data, &packages.strings, form)
+                {
+                    let resources = uefi_resources(
+                        &self.data,
+                        &self.images,
+                        &self.animations,
+                        &self.device_paths,
+                        &packages.strings,
+                        form,
+                    );
+                    let mut form_rows = question_csv::question_rows(
+                        &self.data,
+                        form,
+                        string_package,
+                        &resources,
+                    );
+                    question_csv::set_question_module(
+                        &mut form_rows,
+                        fv::ffs_file_at(&volumes, form.offset),
+                    );
+                    rows.append(&mut form_rows);
+                }
+            }
+        }
+        question_csv::questions_csv(&rows)
+    }
+
+    // Write every extraction to its own file named the same way as the command line tool does,
+    // i.e. path.0.0.en-US.ifr.txt
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        for extraction in self.extract_all() {
+            let mut file_path = path.as_ref().as_os_str().to_os_string();
+            file_path.push(format!(
+                ".{}.{}.{}.ifr.txt",
+                extraction.pairing.form_index, extraction.pairing.string_index, extraction.language
+            ));
+            fs::write(file_path, &extraction.text)?;
+        }
+        Ok(())
+    }
+
+    // Pairing of packages given by their indices, ValueError if they are not paired
+    fn py_pairing(&self, form_index: usize, string_index: usize) -> PyResult<Pairing> {
+        self.pairings()
+            .into_iter()
+            .find(|pairing| {
+                pairing.form_index == form_index && pairing.string_index == string_index
+            })
+            .ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!(
+                    "No pairing of form package {form_index} with string package {string_index}"
+                ))
+            })
+    }
+}
+
+// Guid opcode decoder implemented in Python, either a callable rendering a payload or an object with
+// a render method and an optional string_ids method. Payloads are left undecoded if a call fails
+struct PyGuidOpcodeDecoder {
+    guid: Guid,
+    name: String,
+    render: Py<PyAny>,
+    string_ids: Option<Py<PyAny>>,
+}
+
+impl GuidOpcodeDecoder for PyGuidOpcodeDecoder {
+    fn guid(&self) -> Guid {
+        self.guid
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn string_ids(&self, payload: &[u8]) -> Vec<u16> {
+        match &self.string_ids {
+            Some(callable) => Python::with_gil(|py| {
+                callable
+                    .call1(py, (payload,))
+                    .and_then(|string_ids| string_ids.extract::<Vec<u16>>(py))
+                    .unwrap_or_default()
+            }),
+            None => Vec::new(),
+        }
+    }
+
+    // Render is called with the payload and a dict of its StringIds with their quoted strings
+    fn render(&self, payload: &[u8], strings: &StringMap) -> Option<String> {
+        let resolved: HashMap<u16, String> = self
+            .string_ids(payload)
+            .into_iter()
+            .map(|string_id| (string_id, strings.quoted(string_id)))
+            .collect();
+        Python::with_gil(|py| {
+            self.render
+                .call1(py, (payload, resolved))
+                .and_then(|decoded| decoded.extract::<Option<String>>(py))
+                .unwrap_or(None)
+        })
+    }
+}
+
+// Guid opcode decoders given as a dict of GUIDs and Python decoders, named by their qualified names
+fn py_guid_opcode_decoders(decoders: Option<HashMap<String, Bound<'_, PyAny>>>) -> PyResult<GuidOpcodeDecoders> {
+    let mut result = GuidOpcodeDecoders::new();
+    for (guid, decoder) in decoders.unwrap_or_default() {
+        let guid = Guid::from_str(&guid)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Invalid GUID {guid}: {e:?}")))?;
+        let name = decoder
+            .getattr("__qualname__")
+            .or_else(|_| decoder.get_type().getattr("__qualname__"))
+            .and_then(|name| name.extract::<String>())
+            .unwrap_or_else(|_| decoder.to_string());
+        let (render, string_ids) = if decoder.hasattr("render")? {
+            let string_ids = match decoder.hasattr("string_ids")? {
+                true => Some(decoder.getattr("string_ids")?.unbind()),
+                false => None,
+            };
+            (decoder.getattr("render")?.unbind(), string_ids)
+        } else if decoder.is_callable() {
+            (decoder.unbind(), None)
+        } else {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "Guid opcode decoders must be callables or objects with a render method",
+            ));
+        };
+        result.register(PyGuidOpcodeDecoder {
+            guid,
+            name,
+            render,
+            string_ids,
+        });
+    }
+    Ok(result)
+}
+
+// GUID package decoders given as a dict of GUIDs and callables taking a payload and returning a string or None.
+// A payload is left undecoded if its callable fails
+fn py_guid_package_decoders(decoders: Option<HashMap<String, Bound<'_, PyAny>>>) -> PyResult<GuidPackageDecoders> {
+    let mut result = GuidPackageDecoders::new();
+    for (guid, decoder) in decoders.unwrap_or_default() {
+        let guid = Guid::from_str(&guid)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Invalid GUID {guid}: {e:?}")))?;
+        if !decoder.is_callable() {
+            return Err(pyo3::exceptions::PyTypeError::new_err("GUID package decoders must be callables"));
+        }
+        let callable: Py<PyAny> = decoder.unbind();
+        result.register(guid, move |payload| {
+            Python::with_gil(|py| {
+                callable
+                    .call1(py, (payload,))
+                    .and_then(|decoded| decoded.extract::<Option<String>>(py))
+                    .unwrap_or(None)
+            })
+        });
+    }
+    Ok(result)
+}
+
+// String processors given by their names or as callables taking and returning a string.
+// Callables are named by their qualified names, a string is kept as is if a callable fails
+fn py_string_processors(items: Option<Vec<Bound<'_, PyAny>>>) -> PyResult<Vec<StringProcessor>> {
+    let mut result = Vec::new();
+    for item in items.unwrap_or_default() {
+        if let Ok(name) = item.extract::<String>() {
+            match StringProcessor::from_name(&name) {
+                Some(processor) => result.push(processor),
+                None => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "Unknown string processor {name}, expected strip-placeholders or collapse-whitespace"
+                    )))
+                }
+            }
+        } else if item.is_callable() {
+            let name = item
+                .getattr("__qualname__")
+                .and_then(|name| name.extract::<String>())
+                .unwrap_or_else(|_| item.to_string());
+            let callable: Py<PyAny> = item.unbind();
+            result.push(StringProcessor::custom(&name, move |string| {
+                Python::with_gil(|py| {
+                    callable
+                        .call1(py, (string,))
+                        .and_then(|processed| processed.extract::<String>(py))
+                        .unwrap_or_else(|_| String::from(string))
+                })
+            }));
+        } else {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "String processors must be names or callables",
+            ));
+        }
+    }
+    Ok(result)
+}
+
+// Python file-like object opened in text mode, the first exception raised by its write method is kept
+// so it can be raised again instead of a generic OSError
+struct PyTextWriter<'py> {
+    file: Bound<'py, PyAny>,
+    error: Option<PyErr>,
+}
+
+impl io::Write for PyTextWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self
+            .file
+            .call_method1("write", (String::from_utf8_lossy(buf),))
+        {
+            Ok(_) => Ok(buf.len()),
+            Err(e) => {
+                let message = e.to_string();
+                self.error.get_or_insert(e);
+                Err(io::Error::other(message))
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Scan input data, calling a Python callable with bytes processed and packages found if one is given.
+// The scan is cancelled if the callable returns False, exceptions raised by it are ignored
+fn py_scan(
+    data: MappedInput,
+    options: ScanOptions,
+    progress: Option<Py<PyAny>>,
+) -> PyResult<IfrDocument> {
+    let callable = match progress {
+        Some(callable) => callable,
+        None => {
+            return IfrDocument::new_with_options(data, options, ScanMetrics::new())
+                .ok_or_else(|| pyo3::exceptions::PyInterruptedError::new_err("Scan cancelled"))
+        }
+    };
+    let token = Arc::new(AtomicBool::new(false));
+    let cancellation = token.clone();
+    let metrics = ScanMetrics::with_progress(move |progress| {
+        Python::with_gil(|py| {
+            let result = callable.call1(py, (progress.bytes_processed, progress.packages_found));
+            if let Ok(false) = result.and_then(|result| result.extract::<bool>(py)) {
+                cancellation.store(true, Ordering::Relaxed);
+            }
+        })
+    })
+    .with_cancellation(token);
+    IfrDocument::new_with_options(data, options, metrics)
+        .ok_or_else(|| pyo3::exceptions::PyInterruptedError::new_err("Scan cancelled"))
+}
+
+#[pymethods]
+impl IfrDocument {
+    #[new]
+    #[pyo3(signature = (
+        path,
+        string_processors=None,
+        guid_opcode_decoders=None,
+        progress=None,
+        start_offset=0,
+        end_offset=None,
+        alignment=1
+    ))]
+    #[allow(clippy::too_many_arguments)] // Keyword arguments of the Python constructor
+    fn py_open(
+        py: Python<'_>,
+        path: PathBuf,
+        string_processors: Option<Vec<Bound<'_, PyAny>>>,
+        guid_opcode_decoders: Option<HashMap<String, Bound<'_, PyAny>>>,
+        progress: Option<Py<PyAny>>,
+        start_offset: usize,
+        end_offset: Option<usize>,
+        alignment: usize,
+    ) -> PyResult<IfrDocument> {
+        let processors = py_string_processors(string_processors)?;
+        let options = ScanOptions {
+            start_offset,
+            end_offset,
+            alignment,
+            guid_opcode_decoders: py_guid_opcode_decoders(guid_opcode_decoders)?,
+        };
+        // Scanning a whole firmware image takes a while, other Python threads can run meanwhile
+        let mut document = py.allow_threads(|| -> PyResult<IfrDocument> {
+            py_scan(MappedInput::open(&path)?, options, progress)
+        })?;
+        document.path = Some(path);
+        Ok(document.with_string_processors(processors))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_bytes", signature = (
+        data,
+        string_processors=None,
+        guid_opcode_decoders=None,
+        progress=None,
+        start_offset=0,
+        end_offset=None,
+        alignment=1
+    ))]
+    #[allow(clippy::too_many_arguments)] // Keyword arguments of the Python constructor
+    fn py_from_bytes(
+        py: Python<'_>,
+        data: Vec<u8>,
+        string_processors: Option<Vec<Bound<'_, PyAny>>>,
+        guid_opcode_decoders: Option<HashMap<String, Bound<'_, PyAny>>>,
+        progress: Option<Py<PyAny>>,
+        start_offset: usize,
+        end_offset: Option<usize>,
+        alignment: usize,
+    ) -> PyResult<IfrDocument> {
+        let processors = py_string_processors(string_processors)?;
+        let options = ScanOptions {
+            start_offset,
+            end_offset,
+            alignment,
+            guid_opcode_decoders: py_guid_opcode_decoders(guid_opcode_decoders)?,
+        };
+        let document = py.allow_threads(|| py_scan(data.into(), options, progress))?;
+        Ok(document.with_string_processors(processors))
+    }
+
+    // New document with bytes at a given offset replaced, cached extractions of untouched packages are kept
+    #[pyo3(name = "patched")]
+    fn py_patched(&self, py: Python<'_>, offset: usize, data: Vec<u8>) -> PyResult<IfrDocument> {
+        py.allow_threads(|| self.patched(offset, &data)).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Patch of 0x{:X} bytes at offset 0x{:X} doesn't fit the input data",
+                data.len(),
+                offset
+            ))
+        })
+    }
+
+    #[getter(path)]
+    fn py_path(&self) -> Option<PathBuf> {
+        self.path.clone()
+    }
+
+    // (bytes scanned, (package kind, count) tuples, languages, elapsed seconds, rejected package candidates)
+    #[pyo3(name = "summary")]
+    fn py_summary(&self) -> PySummary {
+        (
+            self.summary.bytes_scanned,
+            self.summary
+                .package_counts
+                .iter()
+                .map(|(kind, count)| (String::from(*kind), *count))
+                .collect(),
+            self.summary.languages.clone(),
+            self.summary.elapsed.as_secs_f64(),
+            self.summary.diagnostics,
+        )
+    }
+
+    // (offset, length, GUID, packages) tuples of all UEFI package lists, packages are (type, offset, length, GUID)
+    // tuples. GUIDs are None for lists without a header and for packages other than GUID packages
+    #[pyo3(name = "package_lists")]
+    fn py_package_lists(&self, py: Python<'_>) -> Vec<PyPackageList> {
+        py.allow_threads(|| self.package_lists())
+            .into_iter()
+            .map(|list| {
+                (
+                    list.offset,
+                    list.length,
+                    list.guid.map(|guid| guid.to_string()),
+                    list.packages
+                        .iter()
+                        .map(|package| {
+                            (
+                                format!("{:?}", package.package_type),
+                                package.offset,
+                                package.length,
+                                package.guid.map(|guid| guid.to_string()),
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    // (offset, length, GUID, package list GUID, payload, decoded) tuples of all UEFI GUID packages.
+    // Payloads are decoded by callables given in a dict keyed by GUID, decoded is None for other packages
+    #[pyo3(name = "guid_packages", signature = (decoders=None))]
+    fn py_guid_packages(
+        &self,
+        py: Python<'_>,
+        decoders: Option<HashMap<String, Bound<'_, PyAny>>>,
+    ) -> PyResult<Vec<PyGuidPackage<'_>>> {
+        let decoders = py_guid_package_decoders(decoders)?;
+        let packages = py.allow_threads(|| find_uefi_guid_packages(&self.data));
+        Ok(packages
+            .iter()
+            .map(|package| {
+                (
+                    package.offset,
+                    package.length,
+                    package.guid.to_string(),
+                    package.package_list_guid.map(|guid| guid.to_string()),
+                    Cow::Borrowed(package.payload(&self.data)),
+                    decoders.decode(&self.data, package),
+                )
+            })
+            .collect())
+    }
+
+    // (offset, length, text) tuples of all UEFI device path packages
+    #[pyo3(name = "device_paths")]
+    fn py_device_paths(&self) -> Vec<(usize, usize, String)> {
+        self.device_paths
+            .iter()
+            .map(|device_path| (device_path.offset, device_path.length, device_path.text.clone()))
+            .collect()
+    }
+
+    // (kind, offset, length, family, style names, glyph count) tuples of all UEFI font and simple font packages,
+    // family and style names are empty for simple fonts
+    #[pyo3(name = "fonts")]
+    fn py_fonts(&self, py: Python<'_>) -> Vec<PyFont> {
+        py.allow_threads(|| {
+            let fonts = find_uefi_font_packages(&self.data).into_iter().map(|font| {
+                (
+                    "font",
+                    font.offset,
+                    font.length,
+                    font.family,
+                    font_style_names(font.style),
+                    font.glyphs.len(),
+                )
+            });
+            let simple_fonts = find_uefi_simple_font_packages(&self.data)
+                .into_iter()
+                .map(|font| ("simple font", font.offset, font.length, String::new(), Vec::new(), font.glyphs.len()));
+            fonts.chain(simple_fonts).collect()
+        })
+    }
+
+    // Raw bytes of the input, i.e. of a package found by package_lists
+    #[pyo3(name = "read")]
+    fn py_read(&self, offset: usize, length: usize) -> PyResult<Cow<'_, [u8]>> {
+        match offset.checked_add(length) {
+            Some(end) if end <= self.data.len() => Ok(Cow::Borrowed(&self.data[offset..end])),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "0x{:X} bytes at offset 0x{:X} are outside of the input data",
+                length, offset
+            ))),
+        }
+    }
+
+    // (flavor, kind, offset, length, language) tuples, language is empty for form packages
+    #[pyo3(name = "packages")]
+    fn py_packages(&self) -> Vec<(String, String, usize, usize, String)> {
+        let mut result = Vec::new();
+        for packages in &self.packages {
+            let flavor = format!("{:?}", packages.flavor);
+            for string in &packages.strings {
+                result.push((
+                    flavor.clone(),
+                    String::from("string"),
+                    string.offset,
+                    string.length,
+                    string.language.clone(),
+                ));
+            }
+            for form in &packages.forms {
+                result.push((
+                    flavor.clone(),
+                    String::from("form"),
+                    form.offset,
+                    form.length,
+                    String::new(),
+                ));
+            }
+        }
+        result
+    }
+
+    // (form index, string index) tuples
+    // (flavor, form index, question count, opcode count, estimated text size) tuples of all form packages,
+    // known without extracting them
+    #[pyo3(name = "form_estimates")]
+    fn py_form_estimates(&self) -> Vec<(String, usize, usize, usize, usize)> {
+        self.packages
+            .iter()
+            .flat_map(|packages| {
+                packages.forms.iter().enumerate().map(move |(form_index, form)| {
+                    (
+                        format!("{:?}", packages.flavor),
+                        form_index,
+                        form.question_count,
+                        form.opcode_count,
+                        form.estimated_text_size,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[pyo3(name = "pairings")]
+    fn py_pairings(&self) -> Vec<(usize, usize)> {
+        self.pairings()
+            .iter()
+            .map(|pairing| (pairing.form_index, pairing.string_index))
+            .collect()
+    }
+
+    // (form index, string index, language, text) tuples
+    #[pyo3(name = "extract_all")]
+    fn py_extract_all(&self, py: Python<'_>) -> Vec<(usize, usize, String, String)> {
+        // Other Python threads can use the document while extraction is running
+        py.allow_threads(|| self.extract_all())
+            .iter()
+            .map(|extraction| {
+                (
+                    extraction.pairing.form_index,
+                    extraction.pairing.string_index,
+                    extraction.language.clone(),
+                    extraction.text.clone(),
+                )
+            })
+            .collect()
+    }
+
+    // Write the text of a pairing to a file given by its path or to a file-like object opened in text mode,
+    // without keeping it whole in memory. Returns (kind, details of the first occurrence, count) of all warnings
+    #[pyo3(name = "write_extraction")]
+    fn py_write_extraction(
+        &self,
+        py: Python<'_>,
+        form_index: usize,
+        string_index: usize,
+        file: Bound<'_, PyAny>,
+    ) -> PyResult<Vec<(String, String, usize)>> {
+        let pairing = self.py_pairing(form_index, string_index)?;
+        let diagnostics = match file.extract::<PathBuf>() {
+            Ok(path) => py.allow_threads(|| -> io::Result<Diagnostics> {
+                let mut writer = io::BufWriter::new(fs::File::create(path)?);
+                let diagnostics = self.write_extraction(pairing, &mut writer)?;
+                io::Write::flush(&mut writer)?;
+                Ok(diagnostics)
+            })?,
+            Err(_) => {
+                let mut writer = PyTextWriter { file, error: None };
+                match self.write_extraction(pairing, &mut writer) {
+                    Ok(diagnostics) => diagnostics,
+                    Err(e) => return Err(writer.error.take().unwrap_or_else(|| e.into())),
+                }
+            }
+        };
+        Ok(diagnostics
+            .warnings()
+            .iter()
+            .map(|warning| (warning.kind.clone(), warning.details.clone(), warning.count))
+            .collect())
+    }
+
+    // Language and text of a pairing limited to FormSet and Form opcodes, or to a given number of opcodes
+    #[pyo3(name = "preview", signature = (form_index, string_index, opcodes=None))]
+    fn py_preview(
+        &self,
+        py: Python<'_>,
+        form_index: usize,
+        string_index: usize,
+        opcodes: Option<usize>,
+    ) -> PyResult<(String, String)> {
+        let pairing = self.py_pairing(form_index, string_index)?;
+        let preview = match opcodes {
+            Some(count) => Preview::Opcodes(count),
+            None => Preview::Headers,
+        };
+        let extraction = py.allow_threads(|| self.preview(pairing, preview));
+        Ok((extraction.language, extraction.text))
+    }
+
+    // Text of a pairing, raises IfrParseError with (message, offset, context offset, context bytes) arguments
+    // if the form package can't be parsed completely. Offsets are None for invalid strings
+    #[pyo3(name = "try_extract")]
+    fn py_try_extract(&self, py: Python<'_>, form_index: usize, string_index: usize) -> PyResult<String> {
+        let pairing = self.py_pairing(form_index, string_index)?;
+        py.allow_threads(|| self.try_extract(pairing)).map_err(|e| {
+            let location = e.location();
+            IfrParseError::new_err((
+                e.to_string(),
+                location.map(|location| location.offset),
+                location.map(|location| location.context_offset),
+                location.map(|location| location.context.clone()),
+            ))
+        })
+    }
+
+    // Locations of diagnostics of all extractions: form index, string index, kind, offset, context offset,
+    // context bytes. Diagnostics that don't refer to the input are left out
+    #[pyo3(name = "warning_locations")]
+    fn py_warning_locations(&self, py: Python<'_>) -> Vec<PyWarningLocation> {
+        let mut result = Vec::new();
+        for extraction in py.allow_threads(|| self.extract_all()) {
+            for warning in extraction.diagnostics.warnings() {
+                if let Some(location) = &warning.location {
+                    result.push((
+                        extraction.pairing.form_index,
+                        extraction.pairing.string_index,
+                        warning.kind.clone(),
+                        location.offset,
+                        location.context_offset,
+                        location.context.clone(),
+                    ));
+                }
+            }
+        }
+        result
+    }
+
+    // Diagnostics of all extractions: form index, string index, kind, details of the first occurrence, count
+    #[pyo3(name = "warnings")]
+    fn py_warnings(&self, py: Python<'_>) -> Vec<PyWarning> {
+        let mut result = Vec::new();
+        for extraction in py.allow_threads(|| self.extract_all()) {
+            for warning in extraction.diagnostics.warnings() {
+                result.push((
+                    extraction.pairing.form_index,
+                    extraction.pairing.string_index,
+                    warning.kind.clone(),
+                    warning.details.clone(),
+                    warning.count,
+                ));
+            }
+        }
+        result
+    }
+
+    // (form index, question id, prompt, varstore name, varstore guid, varstore offset, size, question type, bit field) tuples,
+    // name and guid are None for undeclared varstores, offset is None for name/value varstores.
+    // Bit field is (bit offset, bit width) for questions in bit varstores, offset and size then cover the bytes it touches
+    #[pyo3(name = "knob_map")]
+    fn py_knob_map(&self) -> Vec<PyKnob> {
+        self.knob_map()
+            .into_iter()
+            .map(|(form_index, knob)| {
+                (
+                    form_index,
+                    knob.question_id,
+                    knob.prompt,
+                    knob.var_store_name,
+                    knob.var_store_guid.map(|guid| guid.to_string()),
+                    knob.var_offset,
+                    knob.size,
+                    knob.question_type.name(),
+                    knob.bit_field.map(|bits| (bits.bit_offset, bits.bit_width)),
+                )
+            })
+            .collect()
+    }
+
+    // (form index, fingerprint as a hex string) tuples
+    #[pyo3(name = "fingerprints", signature = (ignore_string_ids=true, ignore_guids=false))]
+    fn py_fingerprints(&self, ignore_string_ids: bool, ignore_guids: bool) -> Vec<(usize, String)> {
+        let options = fingerprint::FingerprintOptions {
+            ignore_string_ids,
+            ignore_guids,
+        };
+        self.fingerprints(&options)
+            .into_iter()
+            .map(|(form_index, hash)| (form_index, hex_string(&hash)))
+            .collect()
+    }
+
+    // (form index, question id, prompt, varstore name, varstore guid, commands, mask) tuples,
+    // commands are setup_var ones for the Setup variable followed by a setup_var_cv one.
+    // Mask is only set for questions in bit varstores, it selects their bits of the value the commands print
+    #[pyo3(name = "setup_var_commands", signature = (query, value=None))]
+    fn py_setup_var_commands(&self, query: &str, value: Option<u64>) -> Vec<PySetupVarCommands> {
+        self.setup_var_commands(query, value)
+            .into_iter()
+            .map(|(form_index, commands)| {
+                let mut lines = commands.setup_var();
+                lines.push(commands.setup_var_cv());
+                (
+                    form_index,
+                    commands.question_id,
+                    commands.prompt,
+                    commands.var_store_name,
+                    commands.var_store_guid.to_string(),
+                    lines,
+                    commands.mask,
+                )
+            })
+            .collect()
+    }
+
+    // (form index, question id, prompt, question type, default id, default store name, value) tuples, one per default,
+    // value is None if it is given by an expression
+    #[pyo3(name = "defaults")]
+    fn py_defaults(&self) -> Vec<PyDefault> {
+        let mut result = Vec::new();
+        for (form_index, question) in self.defaults() {
+            for default in question.defaults {
+                result.push((
+                    form_index,
+                    question.question_id,
+                    question.prompt.text.clone(),
+                    question.question_type.name(),
+                    default.default_id,
+                    default.store_name,
+                    default.value.map(|value| value.to_string()),
+                ));
+            }
+        }
+        result
+    }
+
+    // (form index, question id, prompt, question type, location, default id, default store name, default,
+    // current value, matches, current option text) tuples and (kind, details, count) tuples of diagnostics,
+    // current value is None if it can't be read with the declared storage of the question
+    // and current option text is None for questions other than OneOf
+    #[pyo3(name = "compare_nvram")]
+    fn py_compare_nvram(
+        &self,
+        py: Python<'_>,
+        nvram: Vec<u8>,
+    ) -> (Vec<PyNvramComparison>, Vec<(String, String, usize)>) {
+        let (comparisons, diagnostics) = py.allow_threads(|| self.compare_nvram(&nvram));
+        (
+            comparisons
+                .into_iter()
+                .map(|(form_index, comparison)| {
+                    (
+                        form_index,
+                        comparison.question_id,
+                        comparison.prompt.text.clone(),
+                        comparison.question_type.name(),
+                        comparison.location(),
+                        comparison.default_id,
+                        comparison.store_name.clone(),
+                        comparison.default.to_string(),
+                        comparison.current.as_ref().map(|value| value.to_string()),
+                        comparison.matches(),
+                        comparison.current_text.clone(),
+                    )
+                })
+                .collect(),
+            diagnostics
+                .warnings()
+                .iter()
+                .map(|warning| (warning.kind.clone(), warning.details.clone(), warning.count))
+                .collect(),
+        )
+    }
+
+    // (form index, sibling string indices, language, text) tuples of UEFI form packages shipped without
+    // string packages, extracted with strings borrowed from package lists with the same GUID in a sibling document
+    #[pyo3(name = "extract_with_borrowed_strings", signature = (sibling, language="en-US"))]
+    fn py_extract_with_borrowed_strings(
+        &self,
+        py: Python<'_>,
+        sibling: PyRef<'_, IfrDocument>,
+        language: &str,
+    ) -> Vec<(usize, Vec<usize>, String, String)> {
+        let sibling = &*sibling;
+        py.allow_threads(|| self.extract_with_borrowed_strings(sibling, language))
+            .into_iter()
+            .map(|(form_index, extraction)| {
+                (
+                    form_index,
+                    extraction.string_packages,
+                    extraction.language,
+                    extraction.text,
+                )
+            })
+            .collect()
+    }
+
+    // (form index, varstore id, opcode, guid, name, size, attributes) tuples,
+    // name and size are None for name/value varstores, attributes are only set for VarStoreEfi
+    #[pyo3(name = "varstores")]
+    fn py_var_stores(&self) -> Vec<PyVarStore> {
+        self.var_stores()
+            .into_iter()
+            .map(|(form_index, var_store)| {
+                (
+                    form_index,
+                    var_store.var_store_id,
+                    format!("{:?}", var_store.opcode),
+                    var_store.guid.to_string(),
+                    var_store.name,
+                    var_store.size,
+                    var_store.attributes,
+                )
+            })
+            .collect()
+    }
+
+    // (form index, node index, parent index, depth, offset, opcode, description, question id, prompt,
+    // flags, opcode id) tuples in order of appearance. Node indices are per form package, parent index is None
+    // for roots. Flags are (label, raw value, symbolic names) tuples, i.e. ("QuestionFlags", 0x10, ["ResetRequired"]).
+    // Opcode ids stay the same across runs as long as the form package doesn't change
+    #[pyo3(name = "scope_tree")]
+    fn py_scope_tree(&self, py: Python<'_>) -> Vec<PyScopeNode> {
+        py.allow_threads(|| {
+            let mut result = Vec::new();
+            for (form_index, tree) in self.trees() {
+                for (index, entry) in tree.scopes().into_iter().enumerate() {
+                    let question = entry.node.question.as_ref();
+                    result.push((
+                        form_index,
+                        index,
+                        entry.parent,
+                        entry.depth,
+                        entry.node.offset,
+                        format!("{:?}", entry.node.opcode),
+                        entry.node.description.clone(),
+                        question.map(|header| header.question_id),
+                        question.and_then(|header| header.prompt.text.clone()),
+                        entry
+                            .node
+                            .flag_fields()
+                            .into_iter()
+                            .map(|field| (field.label, field.value, field.names))
+                            .collect(),
+                        entry.node.id.to_string(),
+                    ));
+                }
+            }
+            result
+        })
+    }
+
+    // (form index, formset guid, default id, name, default class) tuples,
+    // class is standard, manufacturing, safe, platform, hardware, firmware or reserved
+    #[pyo3(name = "default_stores")]
+    fn py_default_stores(&self) -> Vec<PyDefaultStore> {
+        self.default_stores()
+            .into_iter()
+            .map(|(form_index, store)| {
+                (
+                    form_index,
+                    store.formset_guid.to_string(),
+                    store.default_id,
+                    store.name.clone(),
+                    store.default_class(),
+                )
+            })
+            .collect()
+    }
+
+    #[pyo3(name = "export_form_graph_dot")]
+    fn py_export_form_graph_dot(&self, py: Python<'_>) -> String {
+        py.allow_threads(|| self.export_form_graph_dot())
+    }
+
+    #[pyo3(name = "export_questions_csv")]
+    fn py_export_questions_csv(&self, py: Python<'_>) -> String {
+        py.allow_threads(|| self.export_questions_csv())
+    }
+
+    #[pyo3(name = "save")]
+    fn py_save(&self, py: Python<'_>, path: PathBuf) -> PyResult<()> {
+        Ok(py.allow_threads(|| self.save(path))?)
+    }
+}
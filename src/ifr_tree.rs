@@ -0,0 +1,472 @@
+// Typed tree of UEFI IFR opcodes with resolved strings, for tools that would otherwise parse the text output.
+// Scopes are represented by children, so End opcodes are not a part of the tree
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use super::{
+    hex_string, render_uefi_ifr, uefi_string_map, ExtractOptions, FormPackage, HiiResources,
+    QuestionType, RenderOutput, StringPackage,
+};
+use flag_names::{uefi_flag_fields, FlagField};
+use uefi_parser;
+
+// StringId with its string, None if it can't be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedString {
+    pub string_id: u16,
+    pub text: Option<String>,
+}
+
+// Header shared by all questions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionHeader {
+    pub question_type: QuestionType,
+    pub prompt: ResolvedString,
+    pub help: ResolvedString,
+    pub question_id: u16,
+    pub var_store_id: u16,
+    pub var_store_info: u16, // VarOffset or VarName StringId, depending on the varstore
+    pub flags: u8,
+}
+
+// Identifier of an opcode that stays the same across runs and tool versions as long as its form package
+// doesn't change, so annotations kept elsewhere can refer to it. Written as "<package SHA-256>:<index>"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpcodeId {
+    pub package_sha256: [u8; 32], // SHA-256 of the whole form package, header included
+    pub index: usize,             // Index of the opcode in the form package, End opcodes included
+}
+
+impl OpcodeId {
+    pub fn parse(text: &str) -> Option<OpcodeId> {
+        let (hash, index) = text.split_once(':')?;
+        if hash.len() != 64 || !hash.is_ascii() {
+            return None;
+        }
+        let mut package_sha256 = [0; 32];
+        for (byte, pair) in package_sha256.iter_mut().zip(hash.as_bytes().chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+        Some(OpcodeId {
+            package_sha256,
+            index: index.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for OpcodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", hex_string(&self.package_sha256), self.index)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IfrNode {
+    pub offset: usize, // Offset of the opcode in the input data
+    pub id: OpcodeId,
+    pub opcode: uefi_parser::IfrOpcode,
+    pub data: Vec<u8>,                    // Opcode data without the header
+    pub question: Option<QuestionHeader>, // Only for questions
+    pub description: String,              // Opcode data as printed by extract_uefi_ifr
+    pub children: Vec<IfrNode>,           // Opcodes in the scope of this one
+}
+
+// Location of a default value of a question in the input data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultLocation {
+    pub offset: usize, // Offset of the Default or OneOfOption opcode
+    pub opcode: uefi_parser::IfrOpcode,
+    pub default_id: Option<u16>, // None for OneOfOption, its Flags tell which defaults it is
+    pub flags: Option<usize>,    // Offset of the OneOfOption Flags byte
+    pub value: Range<usize>,     // Bytes of the value, empty if it is given by an expression
+}
+
+// Offsets of the patchable parts of a question in the input data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionPatchLocations {
+    pub header: usize,         // Offset of the opcode header
+    pub question_flags: usize, // Offset of the QuestionFlags byte of the question header
+    pub flags: Option<usize>, // Offset of the opcode-specific Flags byte, i.e. CheckBox defaults or OneOf size
+    pub defaults: Vec<DefaultLocation>,
+}
+
+impl IfrNode {
+    // Raw values and symbolic names of all flag fields of this opcode
+    pub fn flag_fields(&self) -> Vec<FlagField> {
+        uefi_flag_fields(self.opcode, &self.data)
+    }
+
+    // Guid opcode of the EDKII bit varstore extension, questions in its scope are stored in bit fields
+    pub fn is_bit_var_store(&self) -> bool {
+        uefi_parser::ifr_is_bit_var_store(self.opcode, &self.data)
+    }
+
+    // Locate fields of a question that patch tools usually change, None if this node is not a question
+    pub fn patch_locations(&self) -> Option<QuestionPatchLocations> {
+        self.question.as_ref()?;
+        // Opcode header is 2 bytes long, Flags follow the 11 bytes long question header
+        let payload = self.offset + 2;
+        let flags = match self.opcode {
+            uefi_parser::IfrOpcode::OneOf
+            | uefi_parser::IfrOpcode::CheckBox
+            | uefi_parser::IfrOpcode::Numeric
+            | uefi_parser::IfrOpcode::Date
+            | uefi_parser::IfrOpcode::Time => Some(11),
+            uefi_parser::IfrOpcode::OrderedList => Some(12), // After MaxContainers
+            uefi_parser::IfrOpcode::String => Some(13),      // After MinSize and MaxSize
+            _ => None,
+        }
+        .filter(|&index| index < self.data.len())
+        .map(|index| payload + index);
+
+        let mut defaults = Vec::new();
+        for child in &self.children {
+            let child_payload = child.offset + 2;
+            let child_end = child_payload + child.data.len();
+            match child.opcode {
+                // DefaultId, Type, Value
+                uefi_parser::IfrOpcode::Default if child.data.len() >= 3 => {
+                    defaults.push(DefaultLocation {
+                        offset: child.offset,
+                        opcode: child.opcode,
+                        default_id: Some(u16::from_le_bytes([child.data[0], child.data[1]])),
+                        flags: None,
+                        value: child_payload + 3..child_end,
+                    })
+                }
+                // OptionStringId, Flags, Type, Value
+                uefi_parser::IfrOpcode::OneOfOption if child.data.len() >= 4 => {
+                    defaults.push(DefaultLocation {
+                        offset: child.offset,
+                        opcode: child.opcode,
+                        default_id: None,
+                        flags: Some(child_payload + 2),
+                        value: child_payload + 4..child_end,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        Some(QuestionPatchLocations {
+            header: self.offset,
+            question_flags: payload + 10,
+            flags,
+            defaults,
+        })
+    }
+}
+
+// Ref question leading to another form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormRef {
+    pub offset: usize, // Offset of the Ref opcode in the input data
+    pub prompt: ResolvedString,
+    pub form_id: Option<u16>, // None for Ref5, its target is given by a question value
+    pub question_id: Option<u16>,
+    pub formset_guid: Option<uefi_parser::Guid>, // Only for refs to other formsets
+}
+
+// Questions shown under a Subtitle, setup browsers display a subtitle as the heading of all questions
+// that follow it, whether they are in its scope or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleGroup {
+    pub offset: Option<usize>, // Offset of the Subtitle opcode, None for questions before the first subtitle
+    pub prompt: Option<ResolvedString>,
+    pub horizontal: bool,      // Questions are displayed in a single row
+    pub questions: Vec<usize>, // Indices into the questions of the form
+}
+
+// Form with all its questions, including the ones in conditional scopes
+#[derive(Debug, Clone)]
+pub struct Form {
+    pub offset: usize, // Offset of the Form or FormMap opcode in the input data
+    pub form_id: u16,
+    pub title: ResolvedString,   // Title of the first method for FormMap
+    pub questions: Vec<IfrNode>, // In order of appearance, Refs included
+    pub refs: Vec<FormRef>,
+    pub groups: Vec<SubtitleGroup>, // In order of appearance, every question is in exactly one
+}
+
+impl Form {
+    // Group a question is displayed in, by the offset of the question
+    pub fn group_of(&self, offset: usize) -> Option<&SubtitleGroup> {
+        let index = self.questions.iter().position(|node| node.offset == offset)?;
+        self.groups.iter().find(|group| group.questions.contains(&index))
+    }
+}
+
+// Node of a flattened tree with its parent, for callers that can't follow references, i.e. Python
+#[derive(Debug, Clone, Copy)]
+pub struct ScopeEntry<'a> {
+    pub parent: Option<usize>, // Index of the node whose scope this one is in, None for roots
+    pub depth: usize,          // 0 for roots
+    pub node: &'a IfrNode,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IfrTree {
+    pub roots: Vec<IfrNode>, // Usually a single FormSet
+    pub forms: Vec<Form>,    // All forms of all formsets
+}
+
+impl IfrTree {
+    // Form with a given FormId
+    pub fn form(&self, form_id: u16) -> Option<&Form> {
+        self.forms.iter().find(|form| form.form_id == form_id)
+    }
+
+    // First form with a given title
+    pub fn form_by_title(&self, title: &str) -> Option<&Form> {
+        self.forms
+            .iter()
+            .find(|form| form.title.text.as_deref() == Some(title))
+    }
+
+    // All nodes in order of their appearance in the form package
+    pub fn walk(&self) -> Vec<&IfrNode> {
+        let mut result = Vec::new();
+        let mut stack: Vec<&IfrNode> = self.roots.iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            result.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+        result
+    }
+
+    // All questions in order of their appearance, with a flag telling if they are in the scope
+    // of a bit varstore Guid opcode
+    pub fn questions(&self) -> Vec<(&IfrNode, bool)> {
+        let mut result = Vec::new();
+        collect_questions(&self.roots, false, &mut result);
+        result
+    }
+
+    // Node with a given identifier
+    pub fn node(&self, id: &OpcodeId) -> Option<&IfrNode> {
+        self.walk().into_iter().find(|node| node.id == *id)
+    }
+
+    // All nodes in the same order as walk returns them, with indices of their parents
+    pub fn scopes(&self) -> Vec<ScopeEntry<'_>> {
+        let mut result = Vec::new();
+        let mut stack: Vec<(Option<usize>, usize, &IfrNode)> =
+            self.roots.iter().rev().map(|node| (None, 0, node)).collect();
+        while let Some((parent, depth, node)) = stack.pop() {
+            let index = result.len();
+            result.push(ScopeEntry {
+                parent,
+                depth,
+                node,
+            });
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|child| (Some(index), depth + 1, child)),
+            );
+        }
+        result
+    }
+}
+
+fn collect_questions<'a>(nodes: &'a [IfrNode], bit_field: bool, result: &mut Vec<(&'a IfrNode, bool)>) {
+    for node in nodes {
+        if node.question.is_some() {
+            result.push((node, bit_field));
+        }
+        collect_questions(&node.children, bit_field || node.is_bit_var_store(), result);
+    }
+}
+
+// Parse a UEFI form package into a tree, strings are resolved the same way extract_uefi_ifr does
+pub fn parse_uefi_ifr(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+) -> IfrTree {
+    let options = ExtractOptions::default();
+    let strings_map = uefi_string_map(data, form_package, string_package, resources, &options);
+    let resolve = |string_id: u16| ResolvedString {
+        string_id,
+        text: strings_map.get(string_id).cloned(),
+    };
+
+    // Descriptions are taken from the text renderer, so both outputs always agree
+    let mut spans = Vec::new();
+    let (text, _) = render_uefi_ifr(
+        data,
+        form_package,
+        None,
+        string_package,
+        resources,
+        &options,
+        &mut RenderOutput::Keep(&mut spans),
+    );
+    let descriptions: HashMap<usize, &str> = spans
+        .into_iter()
+        .map(|(offset, range)| (offset, text[range].trim_end()))
+        .collect();
+
+    let mut tree = IfrTree::default();
+    let operations = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate).ok())
+        .and_then(|(_, package)| uefi_parser::ifr_operations(package.Data.unwrap_or(&[])).ok())
+    {
+        Some((_, operations)) => operations,
+        None => return tree,
+    };
+
+    // Nodes with open scopes, a node is moved to its parent when its scope is closed
+    let mut open: Vec<IfrNode> = Vec::new();
+    // Form package header is 4 bytes long
+    let mut offset = form_package.offset + 4;
+    for (index, operation) in operations.iter().enumerate() {
+        let op_data = operation.Data.unwrap_or(&[]);
+        if operation.OpCode == uefi_parser::IfrOpcode::End {
+            if let Some(node) = open.pop() {
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => tree.roots.push(node),
+                }
+            }
+        } else {
+            // All questions start with a question header:
+            // PromptStringId, HelpStringId, QuestionId, VarStoreId, VarStoreInfo, Flags
+            let question = match operation.OpCode.question_type() {
+                Some(question_type) if op_data.len() >= 11 => {
+                    let word =
+                        |index: usize| u16::from_le_bytes([op_data[index], op_data[index + 1]]);
+                    Some(QuestionHeader {
+                        question_type,
+                        prompt: resolve(word(0)),
+                        help: resolve(word(2)),
+                        question_id: word(4),
+                        var_store_id: word(6),
+                        var_store_info: word(8),
+                        flags: op_data[10],
+                    })
+                }
+                _ => None,
+            };
+            let node = IfrNode {
+                offset,
+                id: OpcodeId {
+                    package_sha256: form_package.sha256,
+                    index,
+                },
+                opcode: operation.OpCode,
+                data: op_data.to_vec(),
+                question,
+                description: descriptions
+                    .get(&offset)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string(),
+                children: Vec::new(),
+            };
+            if operation.ScopeStart {
+                open.push(node);
+            } else {
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => tree.roots.push(node),
+                }
+            }
+        }
+        offset += operation.Length as usize;
+    }
+
+    // Scopes left open at the end of a broken package
+    while let Some(node) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => tree.roots.push(node),
+        }
+    }
+
+    let mut forms = Vec::new();
+    for node in tree.walk() {
+        let (form_id, title_string_id) = match node.opcode {
+            // FormId, TitleStringId
+            uefi_parser::IfrOpcode::Form => match uefi_parser::ifr_form(&node.data) {
+                Ok((_, form)) => (form.FormId, form.TitleStringId),
+                Err(_) => continue,
+            },
+            uefi_parser::IfrOpcode::FormMap => match uefi_parser::ifr_form_map(&node.data) {
+                Ok((_, form_map)) => (form_map.FormId, form_map.Methods[0].MethodTitleId),
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        forms.push(assemble_form(
+            node,
+            form_id,
+            resolve(title_string_id),
+            &resolve,
+        ));
+    }
+    tree.forms = forms;
+    tree
+}
+
+// Collect questions, refs and subtitle groups in the scope of a Form or FormMap node
+fn assemble_form(
+    node: &IfrNode,
+    form_id: u16,
+    title: ResolvedString,
+    resolve: &dyn Fn(u16) -> ResolvedString,
+) -> Form {
+    let mut form = Form {
+        offset: node.offset,
+        form_id,
+        title,
+        questions: Vec::new(),
+        refs: Vec::new(),
+        groups: Vec::new(),
+    };
+    let mut stack: Vec<&IfrNode> = node.children.iter().rev().collect();
+    while let Some(child) = stack.pop() {
+        if child.opcode == uefi_parser::IfrOpcode::Subtitle {
+            if let Ok((_, subtitle)) = uefi_parser::ifr_subtitle(&child.data) {
+                let horizontal = uefi_parser::IfrSubtitleFlags::Horizontal as u8;
+                form.groups.push(SubtitleGroup {
+                    offset: Some(child.offset),
+                    prompt: Some(resolve(subtitle.PromptStringId)),
+                    horizontal: subtitle.Flags & horizontal != 0,
+                    questions: Vec::new(),
+                });
+            }
+        }
+        if child.question.is_none() {
+            stack.extend(child.children.iter().rev());
+            continue;
+        }
+        if child.opcode == uefi_parser::IfrOpcode::Ref {
+            if let Ok((_, reference)) = uefi_parser::ifr_ref(&child.data) {
+                form.refs.push(FormRef {
+                    offset: child.offset,
+                    prompt: resolve(reference.PromptStringId),
+                    form_id: reference.FormId,
+                    question_id: reference.RefQuestionId,
+                    formset_guid: reference.FormSetGuid,
+                });
+            }
+        }
+        if form.groups.is_empty() {
+            form.groups.push(SubtitleGroup {
+                offset: None,
+                prompt: None,
+                horizontal: false,
+                questions: Vec::new(),
+            });
+        }
+        let index = form.questions.len();
+        form.groups.last_mut().unwrap().questions.push(index);
+        form.questions.push(child.clone());
+    }
+    form
+}
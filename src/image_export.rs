@@ -0,0 +1,301 @@
+// Export of images in UEFI HII image packages, i.e. BIOS logos and icons referenced from Image opcodes.
+// 1, 4, 8 and 24-bit bitmap blocks are converted into PNG or BMP files, JPEG and PNG blocks are written as they are
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pyo3::prelude::*;
+
+use super::{find_uefi_image_packages, ImageInfo, ImagePackage};
+use uefi_parser::{self, HiiIibtType};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Bmp,
+}
+
+impl ImageFormat {
+    pub fn from_name(name: &str) -> Option<ImageFormat> {
+        match name {
+            "png" => Some(ImageFormat::Png),
+            "bmp" => Some(ImageFormat::Bmp),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Bmp => "bmp",
+        }
+    }
+}
+
+// Decoded bitmap, pixels are in RGBA order row by row from the top
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Bitmap {
+    // PNG file with an 8-bit RGBA image, image data is stored without compression
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(self.pixels.len() * 4 + self.height as usize);
+        for row in self.pixels.chunks(self.width.max(1) as usize) {
+            raw.push(0); // No filter
+            for pixel in row {
+                raw.extend_from_slice(pixel);
+            }
+        }
+
+        // zlib stream with stored deflate blocks of at most 0xFFFF bytes
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(0xFFFF).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+        }
+        while let Some(block) = blocks.next() {
+            zlib.push(u8::from(blocks.peek().is_none()));
+            zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+            zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bits per channel, RGBA, deflate, no filters, no interlace
+
+        let mut result = b"\x89PNG\r\n\x1A\n".to_vec();
+        png_chunk(&mut result, b"IHDR", &header);
+        png_chunk(&mut result, b"IDAT", &zlib);
+        png_chunk(&mut result, b"IEND", &[]);
+        result
+    }
+
+    // BMP file with a 32-bit BGRA image stored bottom-up, viewers may ignore the alpha channel
+    pub fn to_bmp(&self) -> Vec<u8> {
+        let image_size = self.pixels.len() as u32 * 4;
+        let mut result = Vec::with_capacity(54 + image_size as usize);
+        result.extend_from_slice(b"BM");
+        result.extend_from_slice(&(54 + image_size).to_le_bytes());
+        result.extend_from_slice(&0u32.to_le_bytes());
+        result.extend_from_slice(&54u32.to_le_bytes()); // Offset of the image data
+        result.extend_from_slice(&40u32.to_le_bytes()); // BITMAPINFOHEADER
+        result.extend_from_slice(&(self.width as i32).to_le_bytes());
+        result.extend_from_slice(&(self.height as i32).to_le_bytes());
+        result.extend_from_slice(&1u16.to_le_bytes()); // Planes
+        result.extend_from_slice(&32u16.to_le_bytes()); // Bits per pixel
+        result.extend_from_slice(&0u32.to_le_bytes()); // BI_RGB
+        result.extend_from_slice(&image_size.to_le_bytes());
+        result.extend_from_slice(&[0; 16]); // Resolution and palette sizes
+        for row in self.pixels.chunks(self.width.max(1) as usize).rev() {
+            for &[red, green, blue, alpha] in row {
+                result.extend_from_slice(&[blue, green, red, alpha]);
+            }
+        }
+        result
+    }
+}
+
+fn png_chunk(output: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = output.len();
+    output.extend_from_slice(kind);
+    output.extend_from_slice(data);
+    let crc = crc32(&output[start..]);
+    output.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// Palettes of an image package as RGB colors, palette indices in image blocks are 1-based
+pub fn image_palettes(data: &[u8], package: &ImagePackage) -> Vec<Vec<[u8; 3]>> {
+    let package_data = &data[package.offset..package.offset + package.length];
+    let palette_offset = match uefi_parser::hii_image_package(&package_data[4..]) {
+        Ok((_, header)) => header.PaletteInfoOffset as usize,
+        Err(_) => return Vec::new(),
+    };
+    if palette_offset == 0 || palette_offset >= package_data.len() {
+        return Vec::new();
+    }
+    match uefi_parser::hii_image_palettes(&package_data[palette_offset..]) {
+        Ok((_, palettes)) => palettes
+            .iter()
+            .map(|palette| {
+                palette
+                    .chunks_exact(3)
+                    .map(|pixel| [pixel[2], pixel[1], pixel[0]])
+                    .collect()
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Decode a 1, 4, 8 or 24-bit bitmap block. Pixels of transparent images that are black after palette lookup
+// are made transparent, the way firmware skips them when drawing. None for other blocks, missing palettes
+// and truncated bitmaps
+pub fn decode_image(data: &[u8], package: &ImagePackage, image: &ImageInfo) -> Option<Bitmap> {
+    let block = data.get(image.offset + 1..image.offset + image.length)?;
+    let (bits, transparent) = match image.format {
+        HiiIibtType::Image1Bit => (1, false),
+        HiiIibtType::Image1BitTrans => (1, true),
+        HiiIibtType::Image4Bit => (4, false),
+        HiiIibtType::Image4BitTrans => (4, true),
+        HiiIibtType::Image8Bit => (8, false),
+        HiiIibtType::Image8BitTrans => (8, true),
+        HiiIibtType::Image24Bit => (24, false),
+        HiiIibtType::Image24BitTrans => (24, true),
+        _ => return None,
+    };
+    let (width, height) = uefi_parser::hii_image_dimensions(image.format, block)?;
+    let (w, h) = (width as usize, height as usize);
+
+    let colors: Vec<[u8; 3]> = if bits == 24 {
+        block
+            .get(4..4 + 3 * w * h)?
+            .chunks_exact(3)
+            .map(|pixel| [pixel[2], pixel[1], pixel[0]])
+            .collect()
+    } else {
+        let palette_index = *block.first()? as usize;
+        let palettes = image_palettes(data, package);
+        let palette = palettes.get(palette_index.checked_sub(1)?)?;
+        let stride = (w * bits).div_ceil(8); // Rows are padded to whole bytes
+        let bitmap = block.get(5..5 + stride * h)?;
+        let mut colors = Vec::with_capacity(w * h);
+        for row in bitmap.chunks_exact(stride.max(1)).take(h) {
+            for x in 0..w {
+                let bit = x * bits;
+                let index = (row[bit / 8] >> (8 - bits - bit % 8)) & ((1u16 << bits) - 1) as u8; // Leftmost pixel in high bits
+                colors.push(*palette.get(index as usize).unwrap_or(&[0, 0, 0]));
+            }
+        }
+        colors
+    };
+
+    Some(Bitmap {
+        width,
+        height,
+        pixels: colors
+            .into_iter()
+            .map(|[red, green, blue]| {
+                let alpha = if transparent && red == 0 && green == 0 && blue == 0 { 0 } else { 0xFF };
+                [red, green, blue, alpha]
+            })
+            .collect(),
+    })
+}
+
+// Image file contents with its file extension, bitmaps are converted into a given format
+pub fn export_image(
+    data: &[u8],
+    package: &ImagePackage,
+    image: &ImageInfo,
+    format: ImageFormat,
+) -> Option<(Vec<u8>, &'static str)> {
+    match image.format {
+        // Size is followed by the file as it is
+        HiiIibtType::ImageJpeg => Some((data.get(image.offset + 5..image.offset + image.length)?.to_vec(), "jpg")),
+        HiiIibtType::ImagePng => Some((data.get(image.offset + 5..image.offset + image.length)?.to_vec(), "png")),
+        _ => {
+            let bitmap = decode_image(data, package, image)?;
+            let file = match format {
+                ImageFormat::Png => bitmap.to_png(),
+                ImageFormat::Bmp => bitmap.to_bmp(),
+            };
+            Some((file, format.extension()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedImage {
+    pub package_offset: usize,
+    pub image_id: u16,
+    pub width: u32,
+    pub height: u32,
+    pub file_name: String, // Relative to the output directory
+}
+
+// Write every image of all UEFI image packages to its own file in a given directory,
+// images are returned in order of their packages and ImageIds. Blocks that can't be decoded are skipped
+pub fn export_images(data: &[u8], dir: &Path, format: ImageFormat) -> io::Result<Vec<ExportedImage>> {
+    let mut result = Vec::new();
+    for package in &find_uefi_image_packages(data) {
+        let mut images: Vec<(&u16, &ImageInfo)> = package.images.iter().collect();
+        images.sort_by_key(|(image_id, _)| **image_id);
+        for (image_id, image) in images {
+            let (file, extension) = match export_image(data, package, image, format) {
+                Some(exported) => exported,
+                None => continue,
+            };
+            if result.is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+            // i.e. 00000010_0001.png
+            let file_name = format!("{:08X}_{:04X}.{}", package.offset, image_id, extension);
+            fs::write(dir.join(&file_name), file)?;
+            result.push(ExportedImage {
+                package_offset: package.offset,
+                image_id: *image_id,
+                width: image.width,
+                height: image.height,
+                file_name,
+            });
+        }
+    }
+    Ok(result)
+}
+
+// Exported image as returned to Python: package offset, image id, width, height, file name
+type PyExportedImage = (usize, u16, u32, u32, String);
+
+#[pyfunction]
+#[pyo3(name = "export_images", signature = (data, dir, format="png"))]
+pub fn py_export_images(
+    py: Python<'_>,
+    data: Vec<u8>,
+    dir: PathBuf,
+    format: &str,
+) -> PyResult<Vec<PyExportedImage>> {
+    let format = ImageFormat::from_name(format).ok_or_else(|| {
+        pyo3::exceptions::PyValueError::new_err(format!("Unknown image format {format}, png or bmp expected"))
+    })?;
+    Ok(py.allow_threads(|| export_images(&data, &dir, format))?
+        .into_iter()
+        .map(|image| {
+            (
+                image.package_offset,
+                image.image_id,
+                image.width,
+                image.height,
+                image.file_name,
+            )
+        })
+        .collect())
+}
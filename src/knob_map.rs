@@ -0,0 +1,190 @@
+// Map of questions to the EFI variables they are stored in, as needed by setup_var-like tools
+use std::collections::HashMap;
+
+use super::{FormPackage, QuestionType, StringMap, StringPackage};
+use question_value::QuestionStorage;
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Knob {
+    pub offset: usize, // Offset of the question opcode in the input data
+    pub opcode: uefi_parser::IfrOpcode,
+    pub question_type: QuestionType,
+    pub question_id: u16,
+    pub prompt: String,
+    pub var_store_id: u16,
+    pub var_store_name: Option<String>, // Variable name, or name of the pair for name/value varstores. None if not declared
+    pub var_store_guid: Option<uefi_parser::Guid>, // None if the varstore is not declared in this form package
+    pub var_offset: Option<u16>,                   // None for name/value varstores
+    pub size: usize,                               // Size of the value in bytes
+    pub bit_field: Option<QuestionStorage>, // Only for questions in bit varstores, var_offset and size then cover the bytes it touches
+}
+
+// Declared varstore
+struct VarStore {
+    guid: uefi_parser::Guid,
+    name: Option<String>,
+    name_value: bool,
+}
+
+// Size of an option value of a given type, 0 for non-numeric types
+pub fn value_type_size(value_type: u8) -> usize {
+    match value_type {
+        0 => 1, // NumSize8
+        1 => 2, // NumSize16
+        2 => 4, // NumSize32
+        3 => 8, // NumSize64
+        4 => 1, // Boolean
+        _ => 0,
+    }
+}
+
+// Find all questions of a UEFI form package that keep their values in varstores.
+// Prompts and names of name/value pairs are resolved using a given string package
+pub fn knob_map(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: Option<&StringPackage>,
+) -> Vec<Knob> {
+    let mut result = Vec::new();
+    let no_strings = HashMap::new();
+    let strings_map = &StringMap::plain(
+        string_package.map_or(&no_strings, |strings| &strings.string_id_map),
+        "StringId {id}",
+    );
+
+    let operations = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate).ok())
+        .and_then(|(_, package)| uefi_parser::ifr_operations(package.Data.unwrap_or(&[])).ok())
+    {
+        Some((_, operations)) => operations,
+        None => return result,
+    };
+
+    let mut var_stores: HashMap<u16, VarStore> = HashMap::new();
+    for operation in &operations {
+        let op_data = operation.Data.unwrap_or(&[]);
+        match operation.OpCode {
+            uefi_parser::IfrOpcode::VarStore => {
+                if let Ok((_, var_store)) = uefi_parser::ifr_var_store(op_data) {
+                    var_stores.insert(
+                        var_store.VarStoreId,
+                        VarStore {
+                            guid: var_store.Guid,
+                            name: Some(var_store.Name),
+                            name_value: false,
+                        },
+                    );
+                }
+            }
+            uefi_parser::IfrOpcode::VarStoreNameValue => {
+                if let Ok((_, var_store)) = uefi_parser::ifr_var_store_name_value(op_data) {
+                    var_stores.insert(
+                        var_store.VarStoreId,
+                        VarStore {
+                            guid: var_store.Guid,
+                            name: None,
+                            name_value: true,
+                        },
+                    );
+                }
+            }
+            uefi_parser::IfrOpcode::VarStoreEfi => {
+                if let Ok((_, var_store)) = uefi_parser::ifr_var_store_efi(op_data) {
+                    var_stores.insert(
+                        var_store.VarStoreId,
+                        VarStore {
+                            guid: var_store.Guid,
+                            name: var_store.Name,
+                            name_value: false,
+                        },
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let bit_fields = uefi_parser::ifr_bit_var_store_scopes(&operations);
+
+    // Form package header is 4 bytes long
+    let mut offset = form_package.offset + 4;
+    for (index, operation) in operations.iter().enumerate() {
+        let op_data = operation.Data.unwrap_or(&[]);
+        // Question header is followed by opcode-specific fields:
+        // PromptStringId, HelpStringId, QuestionId, VarStoreId, VarStoreInfo, Flags
+        if let Some(question_type) = operation
+            .OpCode
+            .question_type()
+            .filter(|_| op_data.len() >= 12)
+        {
+            let word = |index: usize| u16::from_le_bytes([op_data[index], op_data[index + 1]]);
+            let var_store_id = word(6);
+            let var_store_info = word(8);
+            // VarStoreInfo is an offset in bits in bit varstores, OneOf and Numeric keep their width in bits in Flags
+            let bit_field = match operation.OpCode {
+                uefi_parser::IfrOpcode::OneOf | uefi_parser::IfrOpcode::Numeric if bit_fields[index] => {
+                    Some(QuestionStorage::bits(var_store_info as usize, (op_data[11] & 0x3F) as usize))
+                }
+                uefi_parser::IfrOpcode::CheckBox if bit_fields[index] => {
+                    Some(QuestionStorage::bits(var_store_info as usize, 1))
+                }
+                _ => None,
+            };
+            let size = match operation.OpCode {
+                uefi_parser::IfrOpcode::OneOf | uefi_parser::IfrOpcode::Numeric => {
+                    1 << (op_data[11] & 0x03)
+                }
+                uefi_parser::IfrOpcode::CheckBox => 1,
+                // Only dates and times with normal storage are kept in varstores
+                uefi_parser::IfrOpcode::Date if op_data[11] & 0x30 == 0 => 4,
+                uefi_parser::IfrOpcode::Time if op_data[11] & 0x30 == 0 => 3,
+                // MinSize, MaxSize in CHAR16 characters
+                uefi_parser::IfrOpcode::String if op_data.len() >= 13 => op_data[12] as usize * 2,
+                uefi_parser::IfrOpcode::Password if op_data.len() >= 15 => word(13) as usize * 2,
+                // MaxContainers values of the type of the first option
+                uefi_parser::IfrOpcode::OrderedList => {
+                    let value_type = operations[index + 1..]
+                        .iter()
+                        .find(|option| option.OpCode == uefi_parser::IfrOpcode::OneOfOption)
+                        .and_then(|option| option.Data)
+                        .filter(|option_data| option_data.len() >= 4)
+                        .map(|option_data| option_data[3])
+                        .unwrap_or(0);
+                    op_data[11] as usize * value_type_size(value_type)
+                }
+                _ => 0,
+            };
+            // Questions without storage use VarStoreId 0
+            if var_store_id != 0 && size != 0 {
+                let var_store = var_stores.get(&var_store_id);
+                let name_value = var_store.is_some_and(|var_store| var_store.name_value);
+                result.push(Knob {
+                    offset,
+                    opcode: operation.OpCode,
+                    question_type,
+                    question_id: word(4),
+                    prompt: strings_map.resolve(word(0)).into_owned(),
+                    var_store_id,
+                    var_store_name: if name_value {
+                        Some(strings_map.resolve(var_store_info).into_owned())
+                    } else {
+                        var_store.and_then(|var_store| var_store.name.clone())
+                    },
+                    var_store_guid: var_store.map(|var_store| var_store.guid),
+                    var_offset: match bit_field {
+                        _ if name_value => None,
+                        Some(bits) => Some(bits.byte_range().start as u16),
+                        None => Some(var_store_info),
+                    },
+                    size: bit_field.map_or(size, |bits| bits.byte_range().len()),
+                    bit_field,
+                });
+            }
+        }
+        offset += operation.Length as usize;
+    }
+
+    result
+}
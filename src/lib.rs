@@ -0,0 +1,4821 @@
+// Parser
+#[macro_use]
+extern crate nom;
+extern crate pyo3;
+extern crate sha2;
+extern crate memchr;
+#[cfg(unix)]
+extern crate libc;
+pub mod borrowed_strings;
+pub mod decompression;
+pub mod default_stores;
+pub mod delta_scan;
+pub mod device_paths;
+pub mod diagnostics;
+pub mod extraction_cache;
+pub mod fingerprint;
+pub mod fonts;
+pub mod flag_names;
+pub mod form_graph;
+pub mod formset_inventory;
+pub mod framework_parser;
+pub mod fv;
+pub mod guid;
+pub mod guid_opcodes;
+pub mod guid_packages;
+pub mod hidden_menus;
+pub mod ifr_document;
+pub mod ifr_tree;
+pub mod image_export;
+pub mod knob_map;
+pub mod mapped_file;
+pub mod normalization;
+pub mod nvram_comparison;
+pub mod opcode_records;
+pub mod option_diff;
+pub mod prefilter;
+pub mod opcode_table;
+pub mod package_dump;
+pub mod package_lists;
+pub mod provenance;
+pub mod question_csv;
+pub mod question_defaults;
+pub mod question_ids;
+pub mod question_modifiers;
+pub mod question_value;
+pub mod scan_metrics;
+pub mod setup_var;
+pub mod string_diff;
+pub mod uefi_parser;
+pub mod validation;
+pub mod var_stores;
+pub mod variable_dependencies;
+pub mod version_diff;
+
+// Library
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use pyo3::prelude::*;
+use sha2::{Digest, Sha256};
+
+use device_paths::{uefi_device_path_package, DevicePathPackage};
+use diagnostics::{Diagnostics, ErrorLocation};
+use guid_opcodes::GuidOpcodeDecoders;
+use mapped_file::MappedInput;
+use scan_metrics::{ScanMetrics, ScanProgress, ScanSummary};
+
+pub struct StringPackage {
+    pub offset: usize,
+    pub length: usize,
+    pub language: String,
+    pub string_id_map: HashMap<u16, String>,
+    pub sha256: [u8; 32], // SHA-256 of the whole package, header included
+}
+
+impl StringPackage {
+    // Copy of the package with StringIds in an inclusive range only, i.e. the range used by a form package.
+    // Offset, length and checksum still describe the whole package
+    pub fn subset(&self, min_id: u16, max_id: u16) -> StringPackage {
+        StringPackage {
+            offset: self.offset,
+            length: self.length,
+            language: self.language.clone(),
+            string_id_map: self
+                .string_id_map
+                .iter()
+                .filter(|(string_id, _)| (min_id..=max_id).contains(*string_id))
+                .map(|(string_id, string)| (*string_id, string.clone()))
+                .collect(),
+            sha256: self.sha256,
+        }
+    }
+
+    // Single package with StringIds of all given packages, for vendors that split strings of a language
+    // across several packages. Earlier packages win for duplicate StringIds, offset, length and language are taken
+    // from the first package and the checksum covers checksums of all of them. None if no packages are given
+    pub fn combine(packages: &[&StringPackage]) -> Option<StringPackage> {
+        let first = packages.first()?;
+        let mut string_id_map = HashMap::new();
+        let mut checksums = Vec::new();
+        for package in packages {
+            for (string_id, string) in &package.string_id_map {
+                string_id_map.entry(*string_id).or_insert_with(|| string.clone());
+            }
+            checksums.extend_from_slice(&package.sha256);
+        }
+        Some(StringPackage {
+            offset: first.offset,
+            length: first.length,
+            language: first.language.clone(),
+            string_id_map,
+            sha256: if packages.len() == 1 { first.sha256 } else { sha256(&checksums) },
+        })
+    }
+}
+
+pub struct FormPackage {
+    pub offset: usize,
+    pub length: usize,
+    pub used_strings: usize,
+    pub min_string_id: u16,
+    pub max_string_id: u16,
+    pub question_count: usize, // Opcodes with a question header, known at discovery so packages can be sorted before extraction
+    pub opcode_count: usize,
+    pub estimated_text_size: usize, // Rough size of the extracted text, see estimate_text_size
+    pub sha256: [u8; 32], // SHA-256 of the whole package, header included
+    pub framework_var_stores: Vec<FrameworkVarStore>, // Always empty for UEFI form packages
+}
+
+// VarStore declared in a Framework form package
+#[derive(Debug, Clone)]
+pub struct FrameworkVarStore {
+    pub var_store_id: u16,
+    pub guid: framework_parser::Guid,
+    pub name: String,
+    pub size: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageInfo {
+    pub offset: usize, // Offset of the image block in the input file
+    pub length: usize,
+    pub format: uefi_parser::HiiIibtType,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct ImagePackage {
+    pub offset: usize,
+    pub length: usize,
+    pub images: HashMap<u16, ImageInfo>, // Map of ImageIds to images
+    pub sha256: [u8; 32],
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationInfo {
+    pub offset: usize, // Offset of the animation block in the input file
+    pub length: usize,
+    pub kind: uefi_parser::HiiAibtType,
+    pub animation: uefi_parser::HiiAnimation,
+}
+
+pub struct AnimationPackage {
+    pub offset: usize,
+    pub length: usize,
+    pub animations: HashMap<u16, AnimationInfo>, // Map of AnimationIds to animations
+    pub sha256: [u8; 32],
+}
+
+// Packages that can be referenced from a form package
+#[derive(Default, Clone, Copy)]
+pub struct HiiResources<'a> {
+    pub image_package: Option<&'a ImagePackage>,
+    pub animation_package: Option<&'a AnimationPackage>,
+    pub device_path_package: Option<&'a DevicePathPackage>,
+    pub string_packages: &'a [StringPackage], // Other languages of the same package list are used for missing StringIds
+}
+
+// Origin of the offsets printed in verbose mode
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetBase {
+    #[default]
+    Absolute, // Offset in the input file
+    Package,  // Offset from the start of the form package header
+    Payload,  // Offset from the first opcode of the form package
+}
+
+impl OffsetBase {
+    // Parse the name used on the command line
+    pub fn from_name(name: &str) -> Option<OffsetBase> {
+        match name {
+            "absolute" => Some(OffsetBase::Absolute),
+            "package" => Some(OffsetBase::Package),
+            "payload" => Some(OffsetBase::Payload),
+            _ => None,
+        }
+    }
+
+    // Absolute offset that is printed as zero
+    fn origin(self, package_offset: usize, header_size: usize) -> usize {
+        match self {
+            OffsetBase::Absolute => 0,
+            OffsetBase::Package => package_offset,
+            OffsetBase::Payload => package_offset + header_size,
+        }
+    }
+}
+
+// Groups of opcodes that can be included in or excluded from extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpcodeCategory {
+    Form,       // FormSet, Form and their ends
+    Question,   // OneOf, CheckBox, Numeric and other questions
+    Option,     // OneOfOption
+    Statement,  // Subtitle, Text, Image and other non-interactive statements
+    Condition,  // SuppressIf, GrayOutIf, InconsistentIf and other conditionals
+    Expression, // Opcodes of expressions used by conditionals
+    Storage,    // VarStores and DefaultStores
+    Default,    // Default values and question value accessors
+    Modifier,   // Locked, Refresh and other question modifiers
+    Extension,  // GUIDed opcodes
+    Other,      // Everything else, including unknown opcodes
+}
+
+impl OpcodeCategory {
+    // Parse the name used on the command line
+    pub fn from_name(name: &str) -> Option<OpcodeCategory> {
+        match name {
+            "form" => Some(OpcodeCategory::Form),
+            "question" => Some(OpcodeCategory::Question),
+            "option" => Some(OpcodeCategory::Option),
+            "statement" => Some(OpcodeCategory::Statement),
+            "condition" => Some(OpcodeCategory::Condition),
+            "expression" => Some(OpcodeCategory::Expression),
+            "storage" => Some(OpcodeCategory::Storage),
+            "default" => Some(OpcodeCategory::Default),
+            "modifier" => Some(OpcodeCategory::Modifier),
+            "extension" => Some(OpcodeCategory::Extension),
+            "other" => Some(OpcodeCategory::Other),
+            _ => None,
+        }
+    }
+}
+
+// Semantic type of a question, independent of the opcode that declares it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuestionType {
+    CheckBox,    // CheckBox
+    Enum,        // OneOf
+    Integer,     // Numeric
+    String,      // String and Password
+    Date,        // Date
+    Time,        // Time
+    OrderedList, // OrderedList
+    Action,      // Action
+    Goto,        // Ref
+}
+
+impl QuestionType {
+    // Name used in structured output
+    pub fn name(self) -> &'static str {
+        match self {
+            QuestionType::CheckBox => "checkbox",
+            QuestionType::Enum => "enum",
+            QuestionType::Integer => "integer",
+            QuestionType::String => "string",
+            QuestionType::Date => "date",
+            QuestionType::Time => "time",
+            QuestionType::OrderedList => "ordered-list",
+            QuestionType::Action => "action",
+            QuestionType::Goto => "goto",
+        }
+    }
+}
+
+// Opcode categories to extract, End opcodes follow the opcode that opened their scope
+#[derive(Debug, Default, Clone)]
+pub struct OpcodeFilter {
+    pub include: Vec<OpcodeCategory>, // Empty means all categories
+    pub exclude: Vec<OpcodeCategory>,
+}
+
+impl OpcodeFilter {
+    pub fn allows(&self, category: OpcodeCategory) -> bool {
+        (self.include.is_empty() || self.include.contains(&category)) && !self.exclude.contains(&category)
+    }
+}
+
+// How quotes, backslashes and control characters inside of quoted strings are written
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StringEscaping {
+    #[default]
+    None, // Strings are written as is
+    C,    // \" \\ \n \r \t and \xNN for other control characters
+    Json, // \" \\ \n \r \t and \uNNNN for other control characters
+}
+
+impl StringEscaping {
+    // Parse the name used on the command line
+    pub fn from_name(name: &str) -> Option<StringEscaping> {
+        match name {
+            "none" => Some(StringEscaping::None),
+            "c" => Some(StringEscaping::C),
+            "json" => Some(StringEscaping::Json),
+            _ => None,
+        }
+    }
+
+    pub fn escape(self, string: &str) -> Cow<'_, str> {
+        if self == StringEscaping::None || !string.chars().any(|c| c == '"' || c == '\\' || c.is_control()) {
+            return Cow::Borrowed(string);
+        }
+        let mut result = String::with_capacity(string.len() + 8);
+        for c in string.chars() {
+            match c {
+                '"' => result.push_str("\\\""),
+                '\\' => result.push_str("\\\\"),
+                '\n' => result.push_str("\\n"),
+                '\r' => result.push_str("\\r"),
+                '\t' => result.push_str("\\t"),
+                c if c.is_control() && self == StringEscaping::Json => write!(&mut result, "\\u{:04x}", c as u32).unwrap(),
+                // All control characters are below 0x100
+                c if c.is_control() => write!(&mut result, "\\x{:02x}", c as u32).unwrap(),
+                c => result.push(c),
+            }
+        }
+        Cow::Owned(result)
+    }
+}
+
+// What to do with pseudo-opcodes shorter than 2 bytes, which some vendors use as padding
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PaddingHandling {
+    #[default]
+    Stop,   // Parsing stops at the first one
+    Skip,   // They are skipped with a diagnostic
+    Record, // They are skipped with a diagnostic and printed as Padding
+}
+
+impl PaddingHandling {
+    // Parse the name used on the command line
+    pub fn from_name(name: &str) -> Option<PaddingHandling> {
+        match name {
+            "stop" => Some(PaddingHandling::Stop),
+            "skip" => Some(PaddingHandling::Skip),
+            "record" => Some(PaddingHandling::Record),
+            _ => None,
+        }
+    }
+}
+
+// How help strings of questions and statements are printed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HelpText {
+    #[default]
+    Full,
+    Omit,            // Help is not printed at all
+    Truncate(usize), // Help longer than this number of characters is cut and ends with "..."
+    Wrap(usize),     // Help is split into lines of at most this number of characters, continuation lines are indented
+}
+
+impl HelpText {
+    // Parse the name used on the command line, width is only used by truncate and wrap
+    pub fn from_name(name: &str, width: usize) -> Option<HelpText> {
+        match name {
+            "full" => Some(HelpText::Full),
+            "omit" => Some(HelpText::Omit),
+            "truncate" => Some(HelpText::Truncate(width)),
+            "wrap" => Some(HelpText::Wrap(width)),
+            _ => None,
+        }
+    }
+}
+
+// How GUIDs are shown in extracted text
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GuidFormat {
+    #[default]
+    Registry, // 7B59104A-C00D-4158-87FF-F04D6396A915, as shown by UEFITool
+    CStruct,  // { 0x7B59104A, 0xC00D, 0x4158, { 0x87, 0xFF, 0xF0, 0x4D, 0x63, 0x96, 0xA9, 0x15 } }
+    Token,    // EDK2 name like gEfiIfrTianoGuid for known GUIDs, registry format for others
+}
+
+impl GuidFormat {
+    // Parse the name used on the command line
+    pub fn from_name(name: &str) -> Option<GuidFormat> {
+        match name {
+            "registry" => Some(GuidFormat::Registry),
+            "c" => Some(GuidFormat::CStruct),
+            "token" => Some(GuidFormat::Token),
+            _ => None,
+        }
+    }
+
+    pub fn format(self, guid: &uefi_parser::Guid) -> String {
+        match self {
+            GuidFormat::Registry => guid.to_string(),
+            GuidFormat::CStruct => guid.c_struct(),
+            GuidFormat::Token => match uefi_parser::guid_token_name(guid) {
+                Some(name) => name.to_string(),
+                None => guid.to_string(),
+            },
+        }
+    }
+
+    // Convert all GUIDs of a rendered text written in registry format, GUIDs in quoted strings are kept as is.
+    // Quoted strings may span lines, i.e. wrapped help strings or strings with line breaks
+    pub fn apply(self, text: String) -> String {
+        if self == GuidFormat::Registry {
+            return text;
+        }
+        let bytes = text.as_bytes();
+        let mut result = String::with_capacity(text.len());
+        let mut in_quotes = false;
+        let mut copied = 0; // Everything before this index is already in the result
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'"' if i == 0 || bytes[i - 1] != b'\\' => in_quotes = !in_quotes,
+                byte if !in_quotes
+                    && byte.is_ascii_hexdigit()
+                    && (i == 0 || !bytes[i - 1].is_ascii_alphanumeric())
+                    && bytes.get(i + 36).is_none_or(|next| !next.is_ascii_alphanumeric()) =>
+                {
+                    if let Some(Ok(guid)) = text.get(i..i + 36).map(str::parse::<uefi_parser::Guid>) {
+                        result.push_str(&text[copied..i]);
+                        result.push_str(&self.format(&guid));
+                        copied = i + 36;
+                        i += 36;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        result.push_str(&text[copied..]);
+        result
+    }
+}
+
+// Part of a form package that is extracted, previews are meant for fast listings of large formsets
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Preview {
+    #[default]
+    Full,
+    Opcodes(usize), // Only this number of opcodes, followed by a line with the number of opcodes left out
+    Headers,        // Only FormSet, Form and FormMap opcodes and the End opcodes of their scopes
+}
+
+impl Preview {
+    // Parse the name used on the command line, count is only used by opcodes
+    pub fn from_name(name: &str, count: usize) -> Option<Preview> {
+        match name {
+            "full" => Some(Preview::Full),
+            "opcodes" => Some(Preview::Opcodes(count)),
+            "headers" => Some(Preview::Headers),
+            _ => None,
+        }
+    }
+
+    // Line written in place of the opcodes left out, if the limit of opcodes is reached
+    fn write_truncation(self, text: &mut String, rendered: usize, total: usize) -> bool {
+        match self {
+            Preview::Opcodes(limit) if rendered >= limit => {
+                writeln!(text, "Preview: {rendered} of {total} opcodes extracted").unwrap();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Split text into lines of at most width characters at spaces, line breaks of the text are kept.
+// Words longer than width get their own line
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            } else if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// Transform applied to every resolved string during extraction, before quoting and escaping
+#[derive(Clone)]
+pub enum StringProcessor {
+    StripPlaceholders,  // Remove vendor placeholders like "%01"
+    CollapseWhitespace, // Replace runs of whitespace with single spaces and trim the ends
+    Custom(String, Arc<dyn Fn(&str) -> String + Send + Sync>), // Name is only descriptive, extractions using it are not cached
+}
+
+impl StringProcessor {
+    // Parse the name used on the command line
+    pub fn from_name(name: &str) -> Option<StringProcessor> {
+        match name {
+            "strip-placeholders" => Some(StringProcessor::StripPlaceholders),
+            "collapse-whitespace" => Some(StringProcessor::CollapseWhitespace),
+            _ => None,
+        }
+    }
+
+    pub fn custom<F: Fn(&str) -> String + Send + Sync + 'static>(name: &str, transform: F) -> StringProcessor {
+        StringProcessor::Custom(String::from(name), Arc::new(transform))
+    }
+
+    pub fn apply(&self, string: &str) -> String {
+        match self {
+            StringProcessor::StripPlaceholders => {
+                let mut result = String::with_capacity(string.len());
+                let mut chars = string.chars().peekable();
+                while let Some(c) = chars.next() {
+                    if c == '%' && chars.peek().is_some_and(|next| next.is_ascii_digit()) {
+                        while chars.peek().is_some_and(|next| next.is_ascii_digit()) {
+                            chars.next();
+                        }
+                    } else {
+                        result.push(c);
+                    }
+                }
+                result
+            }
+            StringProcessor::CollapseWhitespace => string.split_whitespace().collect::<Vec<_>>().join(" "),
+            StringProcessor::Custom(_, transform) => transform(string),
+        }
+    }
+}
+
+impl std::fmt::Debug for StringProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StringProcessor::StripPlaceholders => write!(f, "StripPlaceholders"),
+            StringProcessor::CollapseWhitespace => write!(f, "CollapseWhitespace"),
+            StringProcessor::Custom(name, _) => write!(f, "Custom({name:?})"),
+        }
+    }
+}
+
+// How numbers printed in hexadecimal are shown, i.e. 0x1F, 31 or 0x1F (31)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NumericRadix {
+    #[default]
+    Hex,
+    Decimal,
+    Both,
+}
+
+impl NumericRadix {
+    // Parse the name used on the command line
+    pub fn from_name(name: &str) -> Option<NumericRadix> {
+        match name {
+            "hex" => Some(NumericRadix::Hex),
+            "dec" => Some(NumericRadix::Decimal),
+            "both" => Some(NumericRadix::Both),
+            _ => None,
+        }
+    }
+
+    // Convert all hexadecimal numbers of a rendered text, numbers in quoted strings are kept as is.
+    // Quoted strings may span lines, i.e. wrapped help strings or strings with line breaks
+    pub fn apply(self, text: String) -> String {
+        if self == NumericRadix::Hex {
+            return text;
+        }
+        let bytes = text.as_bytes();
+        let mut result = String::with_capacity(text.len());
+        let mut in_quotes = false;
+        let mut copied = 0; // Everything before this index is already in the result
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'"' if i == 0 || bytes[i - 1] != b'\\' => in_quotes = !in_quotes,
+                b'0' if !in_quotes
+                    && bytes.get(i + 1) == Some(&b'x')
+                    && (i == 0 || !bytes[i - 1].is_ascii_alphanumeric()) =>
+                {
+                    let end = i + 2 + bytes[i + 2..].iter().take_while(|b| b.is_ascii_hexdigit()).count();
+                    if let Ok(value) = u64::from_str_radix(&text[i + 2..end], 16) {
+                        result.push_str(&text[copied..i]);
+                        match self {
+                            NumericRadix::Decimal => write!(result, "{value}").unwrap(),
+                            _ => write!(result, "{} ({value})", &text[i..end]).unwrap(),
+                        }
+                        copied = end;
+                        i = end;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        result.push_str(&text[copied..]);
+        result
+    }
+}
+
+// Reasons of a failed checked extraction, see try_extract_uefi_ifr and try_extract_framework_ifr
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfrError {
+    NoPackage { location: ErrorLocation },                                  // No form package starts at this offset
+    Truncated { location: ErrorLocation, length: usize, available: usize }, // Package is longer than the rest of the input
+    InvalidOpcode { location: ErrorLocation },                              // Opcodes can't be parsed starting from this offset, or this one is too short for its type
+    InvalidString { string_id: u16 },                                       // String used by the form package was not valid UCS-2 or UTF-8
+}
+
+impl IfrError {
+    // Place in the input the error refers to with bytes around it, None for errors in strings
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            IfrError::NoPackage { location }
+            | IfrError::Truncated { location, .. }
+            | IfrError::InvalidOpcode { location } => Some(location),
+            IfrError::InvalidString { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for IfrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            IfrError::NoPackage { location } => write!(f, "No form package at {location}"),
+            IfrError::Truncated { location, length, available } => write!(
+                f,
+                "Form package at {location} is truncated, length 0x{length:X}, only 0x{available:X} bytes available"
+            ),
+            IfrError::InvalidOpcode { location } => write!(f, "Invalid opcode at {location}"),
+            IfrError::InvalidString { string_id } => write!(f, "Invalid characters in string 0x{string_id:X}"),
+        }
+    }
+}
+
+impl std::error::Error for IfrError {}
+
+// Settings that control how a form package is rendered into text
+#[derive(Debug, Default, Clone)]
+pub struct ExtractOptions {
+    pub verbose: bool, // Add offsets and raw bytes to all opcodes
+    pub offset_base: OffsetBase,
+    pub invalid_id_placeholder: Option<String>, // Text for StringIds that can't be resolved, "{id}" is replaced with the StringId
+    pub show_string_ids: bool,                  // Add StringIds after all resolved strings, i.e. "Setup" (0x2)
+    pub opcode_filter: OpcodeFilter,
+    pub escaping: StringEscaping, // Escaping of quoted strings
+    pub indent: Option<String>,   // Indentation added per scope depth, a tab if not set
+    pub opcode_name_width: usize, // Opcode names are padded with spaces to this width, so their data is aligned
+    pub padding: PaddingHandling, // Handling of padding in UEFI form packages
+    pub cache: Option<PathBuf>,   // Directory of cached extraction results, nothing is cached if not set or with custom string processors or Guid opcode decoders
+    pub radix: NumericRadix,      // Numbers are printed in hexadecimal by default
+    pub help: HelpText,           // Help strings are printed in full by default
+    pub string_processors: Vec<StringProcessor>, // Applied in order to every resolved string
+    pub nested_ifr: usize, // Levels of IFR embedded in Guid opcode payloads to render, none by default
+    pub preview: Preview,  // Whole form packages are extracted by default
+    pub guid_format: GuidFormat, // GUIDs are printed in registry format by default
+    pub guid_opcode_decoders: GuidOpcodeDecoders, // Used to render vendor Guid opcodes, extractions using them are not cached
+}
+
+impl ExtractOptions {
+    // Number and GUID formats applied to rendered text. Numbers are converted first,
+    // so hexadecimal numbers of C struct GUIDs are kept as is
+    fn format_text(&self, text: String) -> String {
+        self.guid_format.apply(self.radix.apply(text))
+    }
+
+    // Directory of cached results. Custom string processors and Guid opcode decoders are only known by their names,
+    // which don't tell different transforms apart (every Python lambda is "<lambda>"), so extractions using them
+    // aren't cached
+    fn cache_dir(&self) -> Option<&Path> {
+        if !self.guid_opcode_decoders.is_empty()
+            || self
+                .string_processors
+                .iter()
+                .any(|processor| matches!(processor, StringProcessor::Custom(..)))
+        {
+            return None;
+        }
+        self.cache.as_deref()
+    }
+
+    fn invalid_id_placeholder(&self) -> &str {
+        self.invalid_id_placeholder.as_deref().unwrap_or("InvalidId")
+    }
+
+    // Write indentation and the name of an opcode at the start of its line
+    fn write_opcode_name(&self, text: &mut String, depth: usize, opcode: &dyn std::fmt::Debug) {
+        text.push_str(&self.indent.as_deref().unwrap_or("\t").repeat(depth));
+        write!(text, "{:<1$} ", format!("{opcode:?}"), self.opcode_name_width).unwrap();
+    }
+
+    // Indent lines of wrapped help strings written since start one scope deeper than their opcode
+    fn indent_wrapped_help(&self, text: &mut String, start: usize, depth: usize) {
+        if !matches!(self.help, HelpText::Wrap(_)) || !text[start..].contains('\n') {
+            return;
+        }
+        let indentation = format!("\n{}", self.indent.as_deref().unwrap_or("\t").repeat(depth + 1));
+        let wrapped = text[start..].replace('\n', &indentation);
+        text.truncate(start);
+        text.push_str(&wrapped);
+    }
+
+    // Padding line in Record mode, offset is relative to the offset base
+    fn write_padding(&self, text: &mut String, offset: usize, length: usize, depth: usize) {
+        if self.padding != PaddingHandling::Record {
+            return;
+        }
+        if self.verbose {
+            write!(text, "0x{offset:08X} 0x{length:02X} {depth:02}: ").unwrap();
+        }
+        self.write_opcode_name(text, depth, &format_args!("Padding"));
+        writeln!(text, "Length: 0x{length:X}").unwrap();
+    }
+}
+
+// StringIds of a form package resolved using its string package, then using fallback string packages,
+// and then replaced with a placeholder
+pub struct StringMap<'a> {
+    primary: &'a HashMap<u16, String>,
+    fallbacks: Vec<&'a HashMap<u16, String>>,
+    placeholder: &'a str,
+    show_ids: bool,
+    escaping: StringEscaping,
+    help: HelpText,
+    processors: &'a [StringProcessor],
+}
+
+impl<'a> StringMap<'a> {
+    pub fn new(
+        primary: &'a HashMap<u16, String>,
+        fallbacks: Vec<&'a HashMap<u16, String>>,
+        placeholder: &'a str,
+        show_ids: bool,
+        escaping: StringEscaping,
+        help: HelpText,
+        processors: &'a [StringProcessor],
+    ) -> StringMap<'a> {
+        StringMap {
+            primary,
+            fallbacks,
+            placeholder,
+            show_ids,
+            escaping,
+            help,
+            processors,
+        }
+    }
+
+    // Map without fallbacks, escaping or string processors, for reports that only need plain strings
+    pub fn plain(primary: &'a HashMap<u16, String>, placeholder: &'a str) -> StringMap<'a> {
+        StringMap::new(primary, Vec::new(), placeholder, false, StringEscaping::None, HelpText::Full, &[])
+    }
+
+    // String as found in string packages, string processors are not applied
+    pub fn get(&self, string_id: u16) -> Option<&'a String> {
+        self.primary
+            .get(&string_id)
+            .or_else(|| self.fallbacks.iter().find_map(|strings| strings.get(&string_id)))
+    }
+
+    // String with all string processors applied, or the placeholder if it can't be found
+    pub fn resolve(&self, string_id: u16) -> Cow<'a, str> {
+        match self.get(string_id) {
+            Some(string) if self.processors.is_empty() => Cow::Borrowed(string),
+            Some(string) => Cow::Owned(
+                self.processors
+                    .iter()
+                    .fold(string.clone(), |string, processor| processor.apply(&string)),
+            ),
+            None => Cow::Owned(self.placeholder.replace("{id}", &format!("0x{string_id:X}"))),
+        }
+    }
+
+    // Resolved string in quotes, followed by its StringId if requested
+    pub fn quoted(&self, string_id: u16) -> String {
+        if self.show_ids {
+            format!("{} (0x{:X})", self.quote(&self.resolve(string_id)), string_id)
+        } else {
+            self.quote(&self.resolve(string_id))
+        }
+    }
+
+    // Help string with its label and a leading separator, i.e. ", Help: \"Text\"", empty if help is omitted.
+    // Wrapped lines are escaped separately, so they are separated by line breaks with any escaping
+    pub fn help_field(&self, string_id: u16) -> String {
+        let quoted = match self.help {
+            HelpText::Omit => return String::new(),
+            HelpText::Full => self.quote(&self.resolve(string_id)),
+            HelpText::Truncate(width) => {
+                let help = self.resolve(string_id);
+                match help.char_indices().nth(width) {
+                    Some((end, _)) => self.quote(&format!("{}...", help[..end].trim_end())),
+                    None => self.quote(&help),
+                }
+            }
+            HelpText::Wrap(width) => {
+                let lines: Vec<String> = wrap_text(&self.resolve(string_id), width)
+                    .iter()
+                    .map(|line| self.escaping.escape(line).into_owned())
+                    .collect();
+                format!("\"{}\"", lines.join("\n"))
+            }
+        };
+        if self.show_ids {
+            format!(", Help: {} (0x{:X})", quoted, string_id)
+        } else {
+            format!(", Help: {quoted}")
+        }
+    }
+
+    // Any other string in quotes, i.e. a VarStore name
+    pub fn quote(&self, string: &str) -> String {
+        format!("\"{}\"", self.escaping.escape(string))
+    }
+}
+
+pub const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+
+// Compute SHA-256 of a package or a whole input file
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+// Format a byte slice as a lowercase hex string, used for checksums
+pub fn hex_string(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(&mut result, "{byte:02x}").unwrap();
+    }
+    result
+}
+
+// Maximum number of payload bytes shown for Guid opcodes without a known decoder
+pub const GUID_PAYLOAD_PREVIEW_LENGTH: usize = 16;
+
+// Format the beginning of a byte slice as space-separated hex bytes
+pub fn hex_preview(bytes: &[u8], limit: usize) -> String {
+    let mut result = String::new();
+    for (index, byte) in bytes.iter().take(limit).enumerate() {
+        if index > 0 {
+            result.push(' ');
+        }
+        write!(&mut result, "{byte:02X}").unwrap();
+    }
+    if bytes.len() > limit {
+        result.push_str(" ...");
+    }
+    result
+}
+
+// Render VarStoreInfo of a question, which is either a byte offset or a name StringId
+fn uefi_var_store_info(
+    var_store_type: Option<uefi_parser::IfrVarStoreType>,
+    strings_map: &StringMap,
+    var_store_info: u16,
+) -> String {
+    match var_store_type {
+        Some(uefi_parser::IfrVarStoreType::NameValue)
+        | Some(uefi_parser::IfrVarStoreType::EfiVariable) => format!(
+            "VarName: {}",
+            strings_map.quoted(var_store_info)
+        ),
+        Some(uefi_parser::IfrVarStoreType::Buffer)
+        | Some(uefi_parser::IfrVarStoreType::EfiVariableBuffer) => {
+            format!("VarOffset: 0x{var_store_info:X}")
+        }
+        _ => format!("VarStoreInfo: 0x{var_store_info:X}"),
+    }
+}
+
+// Numeric value of a given width in bits, signed values are sign-extended and printed in decimal
+fn numeric_value(value: u64, bits: u32, signed: bool) -> String {
+    if signed {
+        let shift = 64 - bits;
+        format!("{}", ((value << shift) as i64) >> shift)
+    } else {
+        format!("0x{value:X}")
+    }
+}
+
+// Write Min, Max and Step of a numeric-like question, Step is an unsigned increment even for signed questions
+fn write_min_max(text: &mut String, bits: u32, min: u64, max: u64, step: u64, signed: bool) {
+    write!(
+        text,
+        "Min: {}, Max: {}, Step: 0x{:X}",
+        numeric_value(min, bits, signed),
+        numeric_value(max, bits, signed),
+        step
+    )
+    .unwrap();
+}
+
+// Width in bits, Min, Max and Step of UEFI OneOf and Numeric questions, only one of the arrays is filled
+fn uefi_min_max_step(
+    data8: [Option<u8>; 3],
+    data16: [Option<u16>; 3],
+    data32: [Option<u32>; 3],
+    data64: [Option<u64>; 3],
+) -> Option<(u32, [u64; 3])> {
+    match (data8, data16, data32, data64) {
+        ([Some(min), Some(max), Some(step)], _, _, _) => Some((8, [min as u64, max as u64, step as u64])),
+        (_, [Some(min), Some(max), Some(step)], _, _) => Some((16, [min as u64, max as u64, step as u64])),
+        (_, _, [Some(min), Some(max), Some(step)], _) => Some((32, [min as u64, max as u64, step as u64])),
+        (_, _, _, [Some(min), Some(max), Some(step)]) => Some((64, [min, max, step])),
+        _ => None,
+    }
+}
+
+// Value of a OneOfOption or Default, numbers of questions with signed display are printed as signed
+fn uefi_type_value(value: &uefi_parser::IfrTypeValue, signed: bool) -> String {
+    match (value, signed) {
+        (uefi_parser::IfrTypeValue::NumSize8(x), true) => numeric_value(*x as u64, 8, true),
+        (uefi_parser::IfrTypeValue::NumSize16(x), true) => numeric_value(*x as u64, 16, true),
+        (uefi_parser::IfrTypeValue::NumSize32(x), true) => numeric_value(*x as u64, 32, true),
+        (uefi_parser::IfrTypeValue::NumSize64(x), true) => numeric_value(*x, 64, true),
+        _ => value.to_string(),
+    }
+}
+
+// Describe how a Date or Time question stores its value
+// Fields are (name, offset from VarStoreInfo, type, suppress flag)
+fn write_date_time_storage(
+    text: &mut String,
+    var_offset: Option<u16>,
+    flags: u8,
+    fields: &[(&str, u16, &str, u8)],
+) {
+    let storage = uefi_parser::IfrDateTimeStorage::from(flags);
+    write!(text, ", Storage: {storage:?}").unwrap();
+    if let (uefi_parser::IfrDateTimeStorage::Normal, Some(var_offset)) = (storage, var_offset) {
+        write!(text, ", Layout:").unwrap();
+        for (index, field) in fields.iter().enumerate() {
+            if index > 0 {
+                write!(text, ",").unwrap();
+            }
+            write!(
+                text,
+                " {} at VarOffset 0x{:X} ({})",
+                field.0,
+                var_offset.wrapping_add(field.1),
+                field.2
+            )
+            .unwrap();
+            if flags & field.3 != 0 {
+                write!(text, " suppressed").unwrap();
+            }
+        }
+    }
+}
+
+// Take an operand from expression stack
+fn pop_operand(stack: &mut Vec<String>) -> String {
+    stack.pop().unwrap_or_else(|| String::from("?"))
+}
+
+// Reconstruct an expression from operations that follow its owner, until the end of owner's scope
+// Expressions are stored in postfix order, so a stack of already rendered operands is enough
+fn uefi_expression(
+    operations: &[uefi_parser::IfrOperation],
+    strings_map: &StringMap,
+) -> String {
+    let mut stack: Vec<String> = Vec::new();
+    let mut nested_depth: usize = 0;
+    for operation in operations {
+        if operation.OpCode == uefi_parser::IfrOpcode::End {
+            if nested_depth == 0 {
+                break;
+            }
+            nested_depth -= 1;
+            continue;
+        }
+        // Contents of nested scopes, i.e. Map pairs, are not part of the expression itself
+        if nested_depth > 0 {
+            if operation.ScopeStart {
+                nested_depth += 1;
+            }
+            continue;
+        }
+        if operation.ScopeStart {
+            nested_depth += 1;
+        }
+
+        let data = operation.Data.unwrap_or(&[]);
+        let unary = |stack: &mut Vec<String>, name: &str| {
+            let a = pop_operand(stack);
+            format!("{name}({a})")
+        };
+        let binary = |stack: &mut Vec<String>, operator: &str| {
+            let b = pop_operand(stack);
+            let a = pop_operand(stack);
+            format!("({a} {operator} {b})")
+        };
+        let ternary = |stack: &mut Vec<String>, name: &str| {
+            let c = pop_operand(stack);
+            let b = pop_operand(stack);
+            let a = pop_operand(stack);
+            format!("{name}({a}, {b}, {c})")
+        };
+        let item = match operation.OpCode {
+            // Operands
+            uefi_parser::IfrOpcode::QuestionRef1 => match uefi_parser::ifr_question_ref_1(data) {
+                Ok((_, qr)) => format!("QuestionId(0x{:X})", qr.QuestionId),
+                Err(_) => String::from("QuestionRef1(?)"),
+            },
+            uefi_parser::IfrOpcode::EqIdVal => match uefi_parser::ifr_eq_id_val(data) {
+                Ok((_, eq)) => format!("(QuestionId(0x{:X}) == 0x{:X})", eq.QuestionId, eq.Value),
+                Err(_) => String::from("EqIdVal(?)"),
+            },
+            uefi_parser::IfrOpcode::EqIdId => match uefi_parser::ifr_eq_id_id(data) {
+                Ok((_, eq)) => format!(
+                    "(QuestionId(0x{:X}) == QuestionId(0x{:X}))",
+                    eq.QuestionId, eq.OtherQuestionId
+                ),
+                Err(_) => String::from("EqIdId(?)"),
+            },
+            uefi_parser::IfrOpcode::EqIdValList => match uefi_parser::ifr_eq_id_val_list(data) {
+                Ok((_, eq)) => format!("(QuestionId(0x{:X}) in {:X?})", eq.QuestionId, eq.Values),
+                Err(_) => String::from("EqIdValList(?)"),
+            },
+            uefi_parser::IfrOpcode::RuleRef => match uefi_parser::ifr_rule_ref(data) {
+                Ok((_, rr)) => format!("RuleRef(0x{:X})", rr.RuleId),
+                Err(_) => String::from("RuleRef(?)"),
+            },
+            uefi_parser::IfrOpcode::StringRef1 => match uefi_parser::ifr_string_ref_1(data) {
+                Ok((_, st)) => strings_map.quoted(st.StringId),
+                Err(_) => String::from("StringRef1(?)"),
+            },
+            uefi_parser::IfrOpcode::Uint8 => match uefi_parser::ifr_uint8(data) {
+                Ok((_, u)) => format!("0x{:X}", u.Value),
+                Err(_) => String::from("Uint8(?)"),
+            },
+            uefi_parser::IfrOpcode::Uint16 => match uefi_parser::ifr_uint16(data) {
+                Ok((_, u)) => format!("0x{:X}", u.Value),
+                Err(_) => String::from("Uint16(?)"),
+            },
+            uefi_parser::IfrOpcode::Uint32 => match uefi_parser::ifr_uint32(data) {
+                Ok((_, u)) => format!("0x{:X}", u.Value),
+                Err(_) => String::from("Uint32(?)"),
+            },
+            uefi_parser::IfrOpcode::Uint64 => match uefi_parser::ifr_uint64(data) {
+                Ok((_, u)) => format!("0x{:X}", u.Value),
+                Err(_) => String::from("Uint64(?)"),
+            },
+            uefi_parser::IfrOpcode::Get => match uefi_parser::ifr_get(data) {
+                Ok((_, get)) => format!("Get(0x{:X}, 0x{:X})", get.VarStoreId, get.VarStoreInfo),
+                Err(_) => String::from("Get(?)"),
+            },
+            uefi_parser::IfrOpcode::Security => match uefi_parser::ifr_security(data) {
+                Ok((_, sec)) => format!("Security({})", sec.Guid),
+                Err(_) => String::from("Security(?)"),
+            },
+            uefi_parser::IfrOpcode::True => String::from("TRUE"),
+            uefi_parser::IfrOpcode::False => String::from("FALSE"),
+            uefi_parser::IfrOpcode::Zero => String::from("0"),
+            uefi_parser::IfrOpcode::One => String::from("1"),
+            uefi_parser::IfrOpcode::Ones => String::from("0xFFFFFFFFFFFFFFFF"),
+            uefi_parser::IfrOpcode::Undefined => String::from("UNDEFINED"),
+            uefi_parser::IfrOpcode::Version => String::from("Version"),
+            uefi_parser::IfrOpcode::This => String::from("This"),
+            uefi_parser::IfrOpcode::Read => String::from("Read"),
+            uefi_parser::IfrOpcode::Dup => stack.last().cloned().unwrap_or_else(|| String::from("?")),
+            // Unary operators
+            uefi_parser::IfrOpcode::Not => format!("!{}", pop_operand(&mut stack)),
+            uefi_parser::IfrOpcode::BitwiseNot => format!("~{}", pop_operand(&mut stack)),
+            uefi_parser::IfrOpcode::Length => unary(&mut stack, "Length"),
+            uefi_parser::IfrOpcode::ToBoolean => unary(&mut stack, "ToBoolean"),
+            uefi_parser::IfrOpcode::ToString => unary(&mut stack, "ToString"),
+            uefi_parser::IfrOpcode::ToUint => unary(&mut stack, "ToUint"),
+            uefi_parser::IfrOpcode::ToUpper => unary(&mut stack, "ToUpper"),
+            uefi_parser::IfrOpcode::ToLower => unary(&mut stack, "ToLower"),
+            uefi_parser::IfrOpcode::StringRef2 => unary(&mut stack, "StringRef2"),
+            uefi_parser::IfrOpcode::QuestionRef2 => unary(&mut stack, "QuestionRef2"),
+            uefi_parser::IfrOpcode::QuestionRef3 => unary(&mut stack, "QuestionRef3"),
+            uefi_parser::IfrOpcode::Set => unary(&mut stack, "Set"),
+            uefi_parser::IfrOpcode::Write => unary(&mut stack, "Write"),
+            uefi_parser::IfrOpcode::Map => unary(&mut stack, "Map"),
+            // Binary operators
+            uefi_parser::IfrOpcode::And => binary(&mut stack, "and"),
+            uefi_parser::IfrOpcode::Or => binary(&mut stack, "or"),
+            uefi_parser::IfrOpcode::Equal => binary(&mut stack, "=="),
+            uefi_parser::IfrOpcode::NotEqual => binary(&mut stack, "!="),
+            uefi_parser::IfrOpcode::GreaterThan => binary(&mut stack, ">"),
+            uefi_parser::IfrOpcode::GreaterEqual => binary(&mut stack, ">="),
+            uefi_parser::IfrOpcode::LessThan => binary(&mut stack, "<"),
+            uefi_parser::IfrOpcode::LessEqual => binary(&mut stack, "<="),
+            uefi_parser::IfrOpcode::BitwiseAnd => binary(&mut stack, "&"),
+            uefi_parser::IfrOpcode::BitwiseOr => binary(&mut stack, "|"),
+            uefi_parser::IfrOpcode::ShiftLeft => binary(&mut stack, "<<"),
+            uefi_parser::IfrOpcode::ShiftRight => binary(&mut stack, ">>"),
+            uefi_parser::IfrOpcode::Add => binary(&mut stack, "+"),
+            uefi_parser::IfrOpcode::Substract => binary(&mut stack, "-"),
+            uefi_parser::IfrOpcode::Multiply => binary(&mut stack, "*"),
+            uefi_parser::IfrOpcode::Divide => binary(&mut stack, "/"),
+            uefi_parser::IfrOpcode::Modulo => binary(&mut stack, "%"),
+            uefi_parser::IfrOpcode::Catenate => {
+                let b = pop_operand(&mut stack);
+                let a = pop_operand(&mut stack);
+                format!("Catenate({a}, {b})")
+            }
+            uefi_parser::IfrOpcode::Match => {
+                let b = pop_operand(&mut stack);
+                let a = pop_operand(&mut stack);
+                format!("Match({a}, {b})")
+            }
+            uefi_parser::IfrOpcode::Match2 => {
+                let b = pop_operand(&mut stack);
+                let a = pop_operand(&mut stack);
+                match uefi_parser::ifr_match_2(data) {
+                    Ok((_, m2)) => format!("Match2({a}, {b}, {})", m2.Guid),
+                    Err(_) => format!("Match2({a}, {b})"),
+                }
+            }
+            // Ternary operators
+            uefi_parser::IfrOpcode::Conditional => {
+                let c = pop_operand(&mut stack);
+                let b = pop_operand(&mut stack);
+                let a = pop_operand(&mut stack);
+                format!("({a} ? {b} : {c})")
+            }
+            uefi_parser::IfrOpcode::Mid => ternary(&mut stack, "Mid"),
+            uefi_parser::IfrOpcode::Find => ternary(&mut stack, "Find"),
+            uefi_parser::IfrOpcode::Token => ternary(&mut stack, "Token"),
+            uefi_parser::IfrOpcode::Span => ternary(&mut stack, "Span"),
+            // Anything else can't be a part of an expression
+            x => format!("{x:?}"),
+        };
+        stack.push(item);
+    }
+
+    stack.join(", ")
+}
+
+//
+// UEFI HII parsing
+//
+pub fn find_uefi_packages(data: &[u8]) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    find_uefi_packages_with_metrics(data, &mut ScanMetrics::new())
+}
+
+// Same as find_uefi_packages, also collecting scan metrics
+pub fn find_uefi_packages_with_metrics(
+    data: &[u8],
+    metrics: &mut ScanMetrics,
+) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    find_uefi_packages_with_options(data, &ScanOptions::default(), metrics)
+}
+
+// Same as find_uefi_packages_with_metrics, only searching a window of the input
+pub fn find_uefi_packages_with_options(
+    data: &[u8],
+    options: &ScanOptions,
+    metrics: &mut ScanMetrics,
+) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    scan_uefi_packages(data, options, metrics, false)
+}
+
+// Package search, string packages without form packages and vice versa are kept if keep_partial is set
+fn scan_uefi_packages(
+    data: &[u8],
+    options: &ScanOptions,
+    metrics: &mut ScanMetrics,
+    keep_partial: bool,
+) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    let mut strings = Vec::new(); // String-to-id maps for all found string packages
+
+    // Search for all string packages in the input file
+    let window = options.window(data.len());
+    let mut i = window.start;
+    let phase_started = Instant::now();
+    while i < window.end {
+        if metrics.report_progress("UEFI string packages", i - window.start).is_break() {
+            break;
+        }
+        i = options.next_offset(prefilter::UEFI_STRING_PACKAGE.next_candidate(data, i));
+        if i >= window.end {
+            break;
+        }
+        if let Ok((_, candidate)) = uefi_parser::hii_string_package_candidate(&data[i..]) {
+            let (offset, started, accepted) = (i, Instant::now(), strings.len());
+            if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
+                if let Ok((_, string_package)) =
+                    uefi_parser::hii_string_package(package.Data.unwrap_or(&[]))
+                {
+                    let mut string_id_map = HashMap::new(); // Map of StringIds to strings
+
+                    // Parse SIBT blocks
+                    if let Ok((_, sibt_blocks)) = uefi_parser::hii_sibt_blocks(string_package.Data)
+                    {
+                        string_id_map.insert(0_u16, String::new());
+                        let mut current_string_index = 1;
+                        for block in &sibt_blocks {
+                            match block.Type {
+                                // 0x00: End
+                                uefi_parser::HiiSibtType::End => {}
+                                // 0x10: StringScsu
+                                uefi_parser::HiiSibtType::StringScsu => {
+                                    if let Ok((_, string)) =
+                                        uefi_parser::sibt_string_scsu(block.Data.unwrap_or(&[]))
+                                    {
+                                        string_id_map.insert(current_string_index, string);
+                                        current_string_index += 1;
+                                    }
+                                }
+                                // 0x11: StringScsuFont
+                                uefi_parser::HiiSibtType::StringScsuFont => {
+                                    if let Ok((_, string)) =
+                                        uefi_parser::sibt_string_scsu_font(block.Data.unwrap_or(&[]))
+                                    {
+                                        string_id_map.insert(current_string_index, string);
+                                        current_string_index += 1;
+                                    }
+                                }
+                                // 0x12: StringsScsu
+                                uefi_parser::HiiSibtType::StringsScsu => {
+                                    if let Ok((_, strings)) =
+                                        uefi_parser::sibt_strings_scsu(block.Data.unwrap_or(&[]))
+                                    {
+                                        for string in strings {
+                                            string_id_map.insert(current_string_index, string);
+                                            current_string_index += 1;
+                                        }
+                                    }
+                                }
+                                // 0x13: StringsScsuFont
+                                uefi_parser::HiiSibtType::StringsScsuFont => {
+                                    if let Ok((_, strings)) =
+                                        uefi_parser::sibt_strings_scsu_font(block.Data.unwrap_or(&[]))
+                                    {
+                                        for string in strings {
+                                            string_id_map.insert(current_string_index, string);
+                                            current_string_index += 1;
+                                        }
+                                    }
+                                }
+                                // 0x14: StringUcs2
+                                uefi_parser::HiiSibtType::StringUcs2 => {
+                                    if let Ok((_, string)) =
+                                        uefi_parser::sibt_string_ucs2(block.Data.unwrap_or(&[]))
+                                    {
+                                        string_id_map.insert(current_string_index, string);
+                                        current_string_index += 1;
+                                    }
+                                }
+                                // 0x15: StringUcs2Font
+                                uefi_parser::HiiSibtType::StringUcs2Font => {
+                                    if let Ok((_, string)) =
+                                        uefi_parser::sibt_string_ucs2_font(block.Data.unwrap_or(&[]))
+                                    {
+                                        string_id_map.insert(current_string_index, string);
+                                        current_string_index += 1;
+                                    }
+                                }
+                                // 0x16: StringsUcs2
+                                uefi_parser::HiiSibtType::StringsUcs2 => {
+                                    if let Ok((_, strings)) =
+                                        uefi_parser::sibt_strings_ucs2(block.Data.unwrap_or(&[]))
+                                    {
+                                        for string in strings {
+                                            string_id_map.insert(current_string_index, string);
+                                            current_string_index += 1;
+                                        }
+                                    }
+                                }
+                                // 0x17: StringsUcs2Font
+                                uefi_parser::HiiSibtType::StringsUcs2Font => {
+                                    if let Ok((_, strings)) =
+                                        uefi_parser::sibt_strings_ucs2_font(block.Data.unwrap_or(&[]))
+                                    {
+                                        for string in strings {
+                                            string_id_map.insert(current_string_index, string);
+                                            current_string_index += 1;
+                                        }
+                                    }
+                                }
+                                // 0x20: Duplicate
+                                uefi_parser::HiiSibtType::Duplicate => {
+                                    current_string_index += 1;
+                                }
+                                // 0x21: Skip2
+                                uefi_parser::HiiSibtType::Skip2 => {
+                                    // Manual parsing of Data as u16
+                                    let count = block.Data.unwrap_or(&[]);
+                                    current_string_index +=
+                                        count[0] as u16 + 0x100 * count[1] as u16;
+                                }
+                                // 0x22: Skip1
+                                uefi_parser::HiiSibtType::Skip1 => {
+                                    // Manual parsing of Data as u8
+                                    let count = block.Data.unwrap_or(&[]);
+                                    current_string_index += count[0] as u16;
+                                }
+                                // Blocks below don't have any strings nor can they influence current_string_index
+                                // No need to parse them here
+                                // 0x30: Ext1
+                                uefi_parser::HiiSibtType::Ext1 => {}
+                                // 0x31: Ext2
+                                uefi_parser::HiiSibtType::Ext2 => {}
+                                // 0x32: Ext4
+                                uefi_parser::HiiSibtType::Ext4 => {}
+                                // Unknown SIBT block is impossible, because parsing will fail on it due to it's unknown length
+                                uefi_parser::HiiSibtType::Unknown(_) => {}
+                            }
+                        }
+
+                        // Add string
+                        let string = (
+                            i,
+                            candidate.len(),
+                            string_package.Language,
+                            string_id_map,
+                            sha256(candidate),
+                        );
+                        strings.push(string);
+                    }
+
+                    i += candidate.len();
+                } else {
+                    i += 1;
+                }
+            } else {
+                i += 1;
+            }
+            metrics.record_package("UEFI string", offset, candidate.len(), strings.len() > accepted, started);
+        } else {
+            i += 1;
+        }
+    }
+    metrics.record_phase("UEFI string packages", i.min(window.end) - window.start, phase_started);
+    // Partial results of cancelled scans are dropped
+    if metrics.cancelled {
+        return (Vec::new(), Vec::new());
+    }
+
+    // No need to continue if there are no string packages found
+    if strings.is_empty() && !keep_partial {
+        return (Vec::new(), Vec::new());
+    }
+
+    //
+    // Search for all form packages in the input file
+    //
+    let mut forms = Vec::new();
+    i = window.start;
+    let phase_started = Instant::now();
+    while i < window.end {
+        if metrics.report_progress("UEFI form packages", i - window.start).is_break() {
+            break;
+        }
+        i = options.next_offset(prefilter::UEFI_FORM_PACKAGE.next_candidate(data, i));
+        if i >= window.end {
+            break;
+        }
+        if let Ok((_, candidate)) = uefi_parser::hii_form_package_candidate(&data[i..]) {
+            let (offset, started, accepted) = (i, Instant::now(), forms.len());
+            if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
+                // Parse form package and obtain StringIds, counts and the estimated size of its text
+                let mut string_ids: Vec<u16> = Vec::new();
+                let (mut question_count, mut opcode_count, mut estimated_text_size) = (0, 0, 0);
+                if let Ok((_, operations)) = uefi_parser::ifr_operations(package.Data.unwrap_or(&[])) {
+                    //let mut current_operation: usize = 0;
+                    for operation in &operations {
+                        //current_operation += 1;
+                        //println!("Operation #{}, OpCode: {:?}, Length 0x{:X}, ScopeStart: {}", current_operation, operation.OpCode, operation.Length, operation.ScopeStart);
+                        match operation.OpCode {
+                            // 0x01: Form
+                            uefi_parser::IfrOpcode::Form => {
+                                if let Ok((_, form)) =
+                                    uefi_parser::ifr_form(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(form.TitleStringId);
+                                }
+                            }
+                            // 0x02: Subtitle
+                            uefi_parser::IfrOpcode::Subtitle => {
+                                if let Ok((_, sub)) =
+                                    uefi_parser::ifr_subtitle(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(sub.PromptStringId);
+                                    string_ids.push(sub.HelpStringId);
+                                }
+                            }
+                            // 0x03: Text
+                            uefi_parser::IfrOpcode::Text => {
+                                if let Ok((_, txt)) = uefi_parser::ifr_text(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(txt.PromptStringId);
+                                    string_ids.push(txt.HelpStringId);
+                                    string_ids.push(txt.TextId);
+                                }
+                            }
+                            // 0x04: Image
+                            uefi_parser::IfrOpcode::Image => {}
+                            // 0x05: OneOf
+                            uefi_parser::IfrOpcode::OneOf => {
+                                if let Ok((_, onf)) =
+                                    uefi_parser::ifr_one_of(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(onf.PromptStringId);
+                                    string_ids.push(onf.HelpStringId);
+                                }
+                            }
+                            // 0x06: CheckBox
+                            uefi_parser::IfrOpcode::CheckBox => {
+                                if let Ok((_, cb)) =
+                                    uefi_parser::ifr_check_box(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(cb.PromptStringId);
+                                    string_ids.push(cb.HelpStringId);
+                                }
+                            }
+                            // 0x07: Numeric
+                            uefi_parser::IfrOpcode::Numeric => {
+                                if let Ok((_, num)) =
+                                    uefi_parser::ifr_numeric(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(num.PromptStringId);
+                                    string_ids.push(num.HelpStringId);
+                                }
+                            }
+                            // 0x08: Password
+                            uefi_parser::IfrOpcode::Password => {
+                                if let Ok((_, pw)) =
+                                    uefi_parser::ifr_password(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(pw.PromptStringId);
+                                    string_ids.push(pw.HelpStringId);
+                                }
+                            }
+                            // 0x09: OneOfOption
+                            uefi_parser::IfrOpcode::OneOfOption => {
+                                if let Ok((_, opt)) =
+                                    uefi_parser::ifr_one_of_option(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(opt.OptionStringId);
+                                    match opt.Value {
+                                        uefi_parser::IfrTypeValue::String(x) => {
+                                            string_ids.push(x);
+                                        }
+                                        uefi_parser::IfrTypeValue::Action(x) => {
+                                            string_ids.push(x);
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            // 0x0A: SuppressIf
+                            uefi_parser::IfrOpcode::SuppressIf => {}
+                            // 0x0B: Locked
+                            uefi_parser::IfrOpcode::Locked => {}
+                            // 0x0C: Action
+                            uefi_parser::IfrOpcode::Action => {
+                                if let Ok((_, act)) =
+                                    uefi_parser::ifr_action(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(act.PromptStringId);
+                                    string_ids.push(act.HelpStringId);
+                                    if let Some(x) = act.ConfigStringId {
+                                        string_ids.push(x);
+                                    }
+                                }
+                            }
+                            // 0x0D: ResetButton
+                            uefi_parser::IfrOpcode::ResetButton => {
+                                if let Ok((_, rst)) =
+                                    uefi_parser::ifr_reset_button(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(rst.PromptStringId);
+                                    string_ids.push(rst.HelpStringId);
+                                }
+                            }
+                            // 0x0E: FormSet
+                            uefi_parser::IfrOpcode::FormSet => {
+                                if let Ok((_, form_set)) =
+                                    uefi_parser::ifr_form_set(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(form_set.TitleStringId);
+                                    string_ids.push(form_set.HelpStringId);
+                                }
+                            }
+                            // 0x0F: Ref
+                            uefi_parser::IfrOpcode::Ref => {
+                                if let Ok((_, rf)) = uefi_parser::ifr_ref(operation.Data.unwrap_or(&[])) {
+                                    string_ids.push(rf.PromptStringId);
+                                    string_ids.push(rf.HelpStringId);
+                                }
+                            }
+                            // 0x10: NoSubmitIf
+                            uefi_parser::IfrOpcode::NoSubmitIf => {
+                                if let Ok((_, ns)) =
+                                    uefi_parser::ifr_no_submit_if(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(ns.ErrorStringId);
+                                }
+                            }
+                            // 0x11: InconsistentIf
+                            uefi_parser::IfrOpcode::InconsistentIf => {
+                                if let Ok((_, inc)) =
+                                    uefi_parser::ifr_inconsistent_if(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(inc.ErrorStringId);
+                                }
+                            }
+                            // 0x12: EqIdVal
+                            uefi_parser::IfrOpcode::EqIdVal => {}
+                            // 0x13: EqIdId
+                            uefi_parser::IfrOpcode::EqIdId => {}
+                            // 0x14: EqIdValList
+                            uefi_parser::IfrOpcode::EqIdValList => {}
+                            // 0x15: And
+                            uefi_parser::IfrOpcode::And => {}
+                            // 0x16: Or
+                            uefi_parser::IfrOpcode::Or => {}
+                            // 0x17: Not
+                            uefi_parser::IfrOpcode::Not => {}
+                            // 0x18: Rule
+                            uefi_parser::IfrOpcode::Rule => {}
+                            // 0x19: GrayOutIf
+                            uefi_parser::IfrOpcode::GrayOutIf => {}
+                            // 0x1A: Date
+                            uefi_parser::IfrOpcode::Date => {
+                                if let Ok((_, dt)) = uefi_parser::ifr_date(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(dt.PromptStringId);
+                                    string_ids.push(dt.HelpStringId);
+                                }
+                            }
+                            // 0x1B: Time
+                            uefi_parser::IfrOpcode::Time => {
+                                if let Ok((_, time)) =
+                                    uefi_parser::ifr_time(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(time.PromptStringId);
+                                    string_ids.push(time.HelpStringId);
+                                }
+                            }
+                            // 0x1C: String
+                            uefi_parser::IfrOpcode::String => {
+                                if let Ok((_, st)) =
+                                    uefi_parser::ifr_string(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(st.PromptStringId);
+                                    string_ids.push(st.HelpStringId);
+                                }
+                            }
+                            // 0x1D: Refresh
+                            uefi_parser::IfrOpcode::Refresh => {}
+                            // 0x1E: DisableIf
+                            uefi_parser::IfrOpcode::DisableIf => {}
+                            // 0x1F: Animation
+                            uefi_parser::IfrOpcode::Animation => {}
+                            // 0x20: ToLower
+                            uefi_parser::IfrOpcode::ToLower => {}
+                            // 0x21: ToUpper
+                            uefi_parser::IfrOpcode::ToUpper => {}
+                            // 0x22: Map
+                            uefi_parser::IfrOpcode::Map => {}
+                            // 0x23: OrderedList
+                            uefi_parser::IfrOpcode::OrderedList => {
+                                if let Ok((_, ol)) =
+                                    uefi_parser::ifr_ordered_list(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(ol.PromptStringId);
+                                    string_ids.push(ol.HelpStringId);
+                                }
+                            }
+                            // 0x24: VarStore
+                            uefi_parser::IfrOpcode::VarStore => {}
+                            // 0x25: VarStoreNameValue
+                            uefi_parser::IfrOpcode::VarStoreNameValue => {}
+                            // 0x26: VarStoreEfi258
+                            uefi_parser::IfrOpcode::VarStoreEfi => {}
+                            // 0x27: VarStoreDevice
+                            uefi_parser::IfrOpcode::VarStoreDevice => {
+                                if let Ok((_, var_store)) =
+                                    uefi_parser::ifr_var_store_device(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(var_store.DevicePathStringId);
+                                }
+                            }
+                            // 0x28: Version
+                            uefi_parser::IfrOpcode::Version => {}
+                            // 0x29: End
+                            uefi_parser::IfrOpcode::End => {}
+                            // 0x2A: Match
+                            uefi_parser::IfrOpcode::Match => {}
+                            // 0x2B: Get
+                            uefi_parser::IfrOpcode::Get => {}
+                            // 0x2C: Set
+                            uefi_parser::IfrOpcode::Set => {}
+                            // 0x2D: Read
+                            uefi_parser::IfrOpcode::Read => {}
+                            // 0x2E: Write
+                            uefi_parser::IfrOpcode::Write => {}
+                            // 0x2F: Equal
+                            uefi_parser::IfrOpcode::Equal => {}
+                            // 0x30: NotEqual
+                            uefi_parser::IfrOpcode::NotEqual => {}
+                            // 0x31: GreaterThan
+                            uefi_parser::IfrOpcode::GreaterThan => {}
+                            // 0x32: GreaterEqual
+                            uefi_parser::IfrOpcode::GreaterEqual => {}
+                            // 0x33: LessThan
+                            uefi_parser::IfrOpcode::LessThan => {}
+                            // 0x34: LessEqual
+                            uefi_parser::IfrOpcode::LessEqual => {}
+                            // 0x35: BitwiseAnd
+                            uefi_parser::IfrOpcode::BitwiseAnd => {}
+                            // 0x36: BitwiseOr
+                            uefi_parser::IfrOpcode::BitwiseOr => {}
+                            // 0x37: BitwiseNot
+                            uefi_parser::IfrOpcode::BitwiseNot => {}
+                            // 0x38: ShiftLeft
+                            uefi_parser::IfrOpcode::ShiftLeft => {}
+                            // 0x39: ShiftRight
+                            uefi_parser::IfrOpcode::ShiftRight => {}
+                            // 0x3A: Add
+                            uefi_parser::IfrOpcode::Add => {}
+                            // 0x3B: Substract
+                            uefi_parser::IfrOpcode::Substract => {}
+                            // 0x3C: Multiply
+                            uefi_parser::IfrOpcode::Multiply => {}
+                            // 0x3D: Divide
+                            uefi_parser::IfrOpcode::Divide => {}
+                            // 0x3E: Modulo
+                            uefi_parser::IfrOpcode::Modulo => {}
+                            // 0x3F: RuleRef
+                            uefi_parser::IfrOpcode::RuleRef => {}
+                            // 0x40: QuestionRef1
+                            uefi_parser::IfrOpcode::QuestionRef1 => {}
+                            // 0x41: QuestionRef2
+                            uefi_parser::IfrOpcode::QuestionRef2 => {}
+                            // 0x42: Uint8
+                            uefi_parser::IfrOpcode::Uint8 => {}
+                            // 0x43: Uint16
+                            uefi_parser::IfrOpcode::Uint16 => {}
+                            // 0x44: Uint32
+                            uefi_parser::IfrOpcode::Uint32 => {}
+                            // 0x45: Uint64
+                            uefi_parser::IfrOpcode::Uint64 => {}
+                            // 0x46: True
+                            uefi_parser::IfrOpcode::True => {}
+                            // 0x47: False
+                            uefi_parser::IfrOpcode::False => {}
+                            // 0x48: ToUint
+                            uefi_parser::IfrOpcode::ToUint => {}
+                            // 0x49: ToString
+                            uefi_parser::IfrOpcode::ToString => {}
+                            // 0x4A: ToBoolean
+                            uefi_parser::IfrOpcode::ToBoolean => {}
+                            // 0x4B: Mid
+                            uefi_parser::IfrOpcode::Mid => {}
+                            // 0x4C: Find
+                            uefi_parser::IfrOpcode::Find => {}
+                            // 0x4D: Token
+                            uefi_parser::IfrOpcode::Token => {}
+                            // 0x4E: StringRef1
+                            uefi_parser::IfrOpcode::StringRef1 => {
+                                if let Ok((_, st)) =
+                                    uefi_parser::ifr_string_ref_1(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(st.StringId);
+                                }
+                            }
+                            // 0x4F: StringRef2
+                            uefi_parser::IfrOpcode::StringRef2 => {}
+                            // 0x50: Conditional
+                            uefi_parser::IfrOpcode::Conditional => {}
+                            // 0x51: QuestionRef3
+                            uefi_parser::IfrOpcode::QuestionRef3 => {
+                                if let Some(data) = operation.Data {
+                                    if let Ok((_, qr)) = uefi_parser::ifr_question_ref_3(data)
+                                    {
+                                        if let Some(x) = qr.DevicePathId {
+                                            string_ids.push(x);
+                                        }
+                                    }
+                                }
+                            }
+                            // 0x52: Zero
+                            uefi_parser::IfrOpcode::Zero => {}
+                            // 0x53: One
+                            uefi_parser::IfrOpcode::One => {}
+                            // 0x54: Ones
+                            uefi_parser::IfrOpcode::Ones => {}
+                            // 0x55: Undefined
+                            uefi_parser::IfrOpcode::Undefined => {}
+                            // 0x56: Length
+                            uefi_parser::IfrOpcode::Length => {}
+                            // 0x57: Dup
+                            uefi_parser::IfrOpcode::Dup => {}
+                            // 0x58: This
+                            uefi_parser::IfrOpcode::This => {}
+                            // 0x59: Span
+                            uefi_parser::IfrOpcode::Span => {}
+                            // 0x5A: Value
+                            uefi_parser::IfrOpcode::Value => {}
+                            // 0x5B: Default
+                            uefi_parser::IfrOpcode::Default => {
+                                if let Ok((_, def)) =
+                                    uefi_parser::ifr_default(operation.Data.unwrap_or(&[]))
+                                {
+                                    match def.Value {
+                                        uefi_parser::IfrTypeValue::String(x) => {
+                                            string_ids.push(x);
+                                        }
+                                        uefi_parser::IfrTypeValue::Action(x) => {
+                                            string_ids.push(x);
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            // 0x5C: DefaultStore
+                            uefi_parser::IfrOpcode::DefaultStore => {
+                                if let Ok((_, default_store)) =
+                                    uefi_parser::ifr_default_store(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(default_store.NameStringId);
+                                }
+                            }
+                            // 0x5D: FormMap
+                            uefi_parser::IfrOpcode::FormMap => {
+                                if let Ok((_, form_map)) =
+                                    uefi_parser::ifr_form_map(operation.Data.unwrap_or(&[]))
+                                {
+                                    for method in form_map.Methods {
+                                        string_ids.push(method.MethodTitleId);
+                                    }
+                                }
+                            }
+                            // 0x5E: Catenate
+                            uefi_parser::IfrOpcode::Catenate => {}
+                            // 0x5F: GUID
+                            uefi_parser::IfrOpcode::Guid => {
+                                if let Ok((_, guid)) =
+                                    uefi_parser::ifr_guid(operation.Data.unwrap_or(&[]))
+                                {
+                                    // This manual parsing here is ugly and can ultimately be done using nom,
+                                    // but it's done already and not that important anyway
+                                    match guid.Guid {
+                                        // Registered decoders take precedence over the built-in ones
+                                        _ if options.guid_opcode_decoders.contains(&guid.Guid) => {
+                                            string_ids.extend(
+                                                options.guid_opcode_decoders.string_ids(&guid.Guid, guid.Data),
+                                            );
+                                        }
+                                        uefi_parser::IFR_TIANO_GUID => {
+                                            if let Ok((_, edk2)) =
+                                                uefi_parser::ifr_guid_edk2(guid.Data)
+                                            {
+                                                match edk2.ExtendedOpCode {
+                                                    uefi_parser::IfrEdk2ExtendOpCode::Banner => {
+                                                        if let Ok((_, banner)) =
+                                                            uefi_parser::ifr_guid_edk2_banner(
+                                                                edk2.Data,
+                                                            )
+                                                        {
+                                                            string_ids.push(banner.TitleId);
+                                                        }
+                                                    }
+                                                    uefi_parser::IfrEdk2ExtendOpCode::Label => {}
+                                                    uefi_parser::IfrEdk2ExtendOpCode::Timeout => {}
+                                                    uefi_parser::IfrEdk2ExtendOpCode::Class => {}
+                                                    uefi_parser::IfrEdk2ExtendOpCode::SubClass => {}
+                                                    uefi_parser::IfrEdk2ExtendOpCode::Unknown(
+                                                        _,
+                                                    ) => {}
+                                                }
+                                            }
+                                        }
+                                        uefi_parser::IFR_FRAMEWORK_GUID => {
+                                            if let Ok((_, edk)) =
+                                                uefi_parser::ifr_guid_edk(guid.Data)
+                                            {
+                                                match edk.ExtendedOpCode {
+                                                    uefi_parser::IfrEdkExtendOpCode::OptionKey => {}
+                                                    uefi_parser::IfrEdkExtendOpCode::VarEqName => {
+                                                        if edk.Data.len() == 2 {
+                                                            let name_id = edk.Data[1] as u16 * 100
+                                                                + edk.Data[0] as u16;
+                                                            string_ids.push(name_id);
+                                                        }
+                                                    }
+                                                    uefi_parser::IfrEdkExtendOpCode::Unknown(_) => {
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            // 0x60: Security
+                            uefi_parser::IfrOpcode::Security => {}
+                            // 0x61: ModalTag
+                            uefi_parser::IfrOpcode::ModalTag => {}
+                            // 0x62: RefreshId
+                            uefi_parser::IfrOpcode::RefreshId => {}
+                            // 0x63: WarningIf
+                            uefi_parser::IfrOpcode::WarningIf => {
+                                if let Ok((_, warn)) =
+                                    uefi_parser::ifr_warning_if(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(warn.WarningStringId);
+                                }
+                            }
+                            // 0x64: Match2
+                            uefi_parser::IfrOpcode::Match2 => {}
+                            // Unknown operation
+                            uefi_parser::IfrOpcode::Unknown(_) => {}
+                        }
+                    }
+                    opcode_count = operations.len();
+                    question_count = operations.iter().filter(|operation| operation.OpCode.is_question()).count();
+                    estimated_text_size =
+                        estimate_text_size(operations.iter().map(|operation| operation.Length), string_ids.len());
+                }
+
+                // Find min and max StringId, and the number of unique ones
+                string_ids.sort();
+                string_ids.dedup();
+                if !string_ids.is_empty() {
+                    // Add the required information to forms
+                    let form = (
+                        i,
+                        candidate.len(),
+                        string_ids.len(),
+                        *string_ids.first().unwrap(),
+                        *string_ids.last().unwrap(),
+                        sha256(candidate),
+                        (question_count, opcode_count, estimated_text_size),
+                    );
+                    forms.push(form);
+                }
+
+                i += candidate.len();
+            } else {
+                i += 1;
+            }
+            metrics.record_package("UEFI form", offset, candidate.len(), forms.len() > accepted, started);
+        } else {
+            i += 1;
+        }
+    }
+    metrics.record_phase("UEFI form packages", i.min(window.end) - window.start, phase_started);
+    if metrics.cancelled {
+        return (Vec::new(), Vec::new());
+    }
+
+    // No need to continue if no forms are found
+    if forms.is_empty() && !keep_partial {
+        return (Vec::new(), Vec::new());
+    }
+
+    // Construct return value
+    let mut result_strings = Vec::new();
+    let mut result_forms = Vec::new();
+    for string in &strings {
+        result_strings.push(StringPackage {
+            offset: string.0,
+            length: string.1,
+            language: string.2.clone(),
+            string_id_map: string.3.clone(),
+            sha256: string.4,
+        });
+    }
+    for form in &forms {
+        result_forms.push(FormPackage {
+            offset: form.0,
+            length: form.1,
+            used_strings: form.2,
+            min_string_id: form.3,
+            max_string_id: form.4,
+            question_count: form.6.0,
+            opcode_count: form.6.1,
+            estimated_text_size: form.6.2,
+            sha256: form.5,
+            framework_var_stores: Vec::new(),
+        });
+    }
+
+    (result_strings, result_forms)
+}
+
+// Search for all image packages in the input file
+pub fn find_uefi_image_packages(data: &[u8]) -> Vec<ImagePackage> {
+    find_uefi_image_packages_with_options(data, &ScanOptions::default())
+}
+
+// Same as find_uefi_image_packages, only searching a window of the input
+pub fn find_uefi_image_packages_with_options(data: &[u8], options: &ScanOptions) -> Vec<ImagePackage> {
+    let mut result = Vec::new();
+
+    let window = options.window(data.len());
+    let mut i = window.start;
+    while i < window.end {
+        i = options.next_offset(prefilter::UEFI_IMAGE_PACKAGE.next_candidate(data, i));
+        if i >= window.end {
+            break;
+        }
+        if let Ok((_, candidate)) = uefi_parser::hii_image_package_candidate(&data[i..]) {
+            if let Ok((_, image_package)) = uefi_parser::hii_image_package(&candidate[4..]) {
+                let mut images = HashMap::new(); // Map of ImageIds to images
+
+                // Parse image blocks, ImageIds start from 1
+                let info_offset = image_package.ImageInfoOffset as usize;
+                if info_offset != 0 {
+                    if let Ok((_, blocks)) = uefi_parser::hii_image_blocks(&candidate[info_offset..]) {
+                        let mut current_image_id: u16 = 1;
+                        let mut block_offset = i + info_offset;
+                        for block in &blocks {
+                            let block_data = block.Data.unwrap_or(&[]);
+                            match block.Type {
+                                uefi_parser::HiiIibtType::Duplicate => {
+                                    let duplicated = block_data[0] as u16 + 0x100 * block_data[1] as u16;
+                                    if let Some(image) = images.get(&duplicated).cloned() {
+                                        images.insert(current_image_id, image);
+                                    }
+                                    current_image_id = current_image_id.wrapping_add(1);
+                                }
+                                uefi_parser::HiiIibtType::Skip2 => {
+                                    current_image_id = current_image_id.wrapping_add(
+                                        block_data[0] as u16 + 0x100 * block_data[1] as u16,
+                                    );
+                                }
+                                uefi_parser::HiiIibtType::Skip1 => {
+                                    current_image_id = current_image_id.wrapping_add(block_data[0] as u16);
+                                }
+                                // Extended blocks don't contain images
+                                uefi_parser::HiiIibtType::Ext1
+                                | uefi_parser::HiiIibtType::Ext2
+                                | uefi_parser::HiiIibtType::Ext4 => {}
+                                _ => {
+                                    let (width, height) =
+                                        uefi_parser::hii_image_dimensions(block.Type, block_data)
+                                            .unwrap_or((0, 0));
+                                    images.insert(
+                                        current_image_id,
+                                        ImageInfo {
+                                            offset: block_offset,
+                                            length: 1 + block_data.len(),
+                                            format: block.Type,
+                                            width,
+                                            height,
+                                        },
+                                    );
+                                    current_image_id = current_image_id.wrapping_add(1);
+                                }
+                            }
+                            block_offset += 1 + block_data.len();
+                        }
+                    }
+                }
+
+                result.push(ImagePackage {
+                    offset: i,
+                    length: candidate.len(),
+                    images,
+                    sha256: sha256(candidate),
+                });
+            }
+            i += candidate.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    result
+}
+
+// Search for all animation packages in the input file
+pub fn find_uefi_animation_packages(data: &[u8]) -> Vec<AnimationPackage> {
+    find_uefi_animation_packages_with_options(data, &ScanOptions::default())
+}
+
+// Same as find_uefi_animation_packages, only searching a window of the input
+pub fn find_uefi_animation_packages_with_options(data: &[u8], options: &ScanOptions) -> Vec<AnimationPackage> {
+    let mut result = Vec::new();
+
+    let window = options.window(data.len());
+    let mut i = window.start;
+    while i < window.end {
+        i = options.next_offset(prefilter::UEFI_ANIMATION_PACKAGE.next_candidate(data, i));
+        if i >= window.end {
+            break;
+        }
+        if let Ok((_, candidate)) = uefi_parser::hii_animation_package_candidate(&data[i..]) {
+            if let Ok((_, animation_package)) = uefi_parser::hii_animation_package(&candidate[4..]) {
+                let mut animations = HashMap::new(); // Map of AnimationIds to animations
+
+                // Parse animation blocks, AnimationIds start from 1
+                let info_offset = animation_package.AnimationInfoOffset as usize;
+                if info_offset != 0 {
+                    if let Ok((_, blocks)) = uefi_parser::hii_animation_blocks(&candidate[info_offset..]) {
+                        let mut current_animation_id: u16 = 1;
+                        let mut block_offset = i + info_offset;
+                        for block in &blocks {
+                            let block_data = block.Data.unwrap_or(&[]);
+                            match block.Type {
+                                uefi_parser::HiiAibtType::Duplicate => {
+                                    let duplicated = block_data[0] as u16 + 0x100 * block_data[1] as u16;
+                                    if let Some(animation) = animations.get(&duplicated).cloned() {
+                                        animations.insert(current_animation_id, animation);
+                                    }
+                                    current_animation_id = current_animation_id.wrapping_add(1);
+                                }
+                                uefi_parser::HiiAibtType::Skip2 => {
+                                    current_animation_id = current_animation_id.wrapping_add(
+                                        block_data[0] as u16 + 0x100 * block_data[1] as u16,
+                                    );
+                                }
+                                uefi_parser::HiiAibtType::Skip1 => {
+                                    current_animation_id =
+                                        current_animation_id.wrapping_add(block_data[0] as u16);
+                                }
+                                // Extended blocks don't contain animations
+                                uefi_parser::HiiAibtType::Ext1
+                                | uefi_parser::HiiAibtType::Ext2
+                                | uefi_parser::HiiAibtType::Ext4 => {}
+                                _ => {
+                                    let with_background = block.Type == uefi_parser::HiiAibtType::ClearImages
+                                        || block.Type == uefi_parser::HiiAibtType::ClearImagesLoop;
+                                    if let Ok((_, animation)) =
+                                        uefi_parser::hii_animation(block_data, with_background)
+                                    {
+                                        animations.insert(
+                                            current_animation_id,
+                                            AnimationInfo {
+                                                offset: block_offset,
+                                                length: 1 + block_data.len(),
+                                                kind: block.Type,
+                                                animation,
+                                            },
+                                        );
+                                    }
+                                    current_animation_id = current_animation_id.wrapping_add(1);
+                                }
+                            }
+                            block_offset += 1 + block_data.len();
+                        }
+                    }
+                }
+
+                result.push(AnimationPackage {
+                    offset: i,
+                    length: candidate.len(),
+                    animations,
+                    sha256: sha256(candidate),
+                });
+            }
+            i += candidate.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    result
+}
+
+// Bytes assumed for the line of every opcode, for every byte of its payload printed as fields,
+// and for every resolved string
+const ESTIMATED_LINE_SIZE: usize = 24;
+const ESTIMATED_SIZE_PER_PAYLOAD_BYTE: usize = 4;
+const ESTIMATED_STRING_SIZE: usize = 24;
+
+// Rough size of the text extracted from a form package given lengths of its opcodes and the number of
+// string references, good enough for progress estimates and sorting, not for allocating buffers
+fn estimate_text_size(lengths: impl Iterator<Item = u8>, string_references: usize) -> usize {
+    lengths
+        .map(|length| ESTIMATED_LINE_SIZE + ESTIMATED_SIZE_PER_PAYLOAD_BYTE * length as usize)
+        .sum::<usize>()
+        + ESTIMATED_STRING_SIZE * string_references
+}
+
+// Check if two UEFI HII packages belong to the same package list,
+// i.e. the first one can be reached from the second one by walking adjacent packages
+pub fn uefi_same_package_list(data: &[u8], first_offset: usize, second_offset: usize) -> bool {
+    let (mut current, target) = if first_offset <= second_offset {
+        (first_offset, second_offset)
+    } else {
+        (second_offset, first_offset)
+    };
+    while current < target {
+        match uefi_parser::hii_package(&data[current..]) {
+            Ok((_, package)) => {
+                if package.Type == uefi_parser::HiiPackageType::End {
+                    return false;
+                }
+                current += package.Length as usize;
+            }
+            Err(_) => return false,
+        }
+    }
+    current == target
+}
+
+// Pick a string package to resolve prompts of a form package in reports,
+// preferring en-US strings from the same package list, then any en-US strings, then anything
+pub fn uefi_preferred_string_package<'a>(
+    data: &[u8],
+    strings: &'a [StringPackage],
+    form_package: &FormPackage,
+) -> Option<&'a StringPackage> {
+    strings
+        .iter()
+        .find(|string| string.language == "en-US" && uefi_same_package_list(data, string.offset, form_package.offset))
+        .or_else(|| strings.iter().find(|string| string.language == "en-US"))
+        .or_else(|| strings.first())
+}
+
+// Pick image and animation packages from the same package list as a given form package,
+// string packages of other languages are used to resolve missing StringIds
+pub fn uefi_resources<'a>(
+    data: &[u8],
+    images: &'a [ImagePackage],
+    animations: &'a [AnimationPackage],
+    device_paths: &'a [DevicePathPackage],
+    strings: &'a [StringPackage],
+    form_package: &FormPackage,
+) -> HiiResources<'a> {
+    HiiResources {
+        image_package: images
+            .iter()
+            .find(|image| uefi_same_package_list(data, image.offset, form_package.offset)),
+        animation_package: animations
+            .iter()
+            .find(|animation| uefi_same_package_list(data, animation.offset, form_package.offset)),
+        device_path_package: uefi_device_path_package(data, device_paths, form_package),
+        string_packages: strings,
+    }
+}
+
+// All UEFI string packages in a given language combined into one to extract a form package,
+// packages from the same package list are used if there are any. Indices of combined packages are returned with it
+pub fn uefi_combined_string_package(
+    data: &[u8],
+    strings: &[StringPackage],
+    form_package: &FormPackage,
+    language: &str,
+) -> Option<(Vec<usize>, StringPackage)> {
+    let in_language: Vec<(usize, &StringPackage)> = strings
+        .iter()
+        .enumerate()
+        .filter(|(_, string)| string.language.eq_ignore_ascii_case(language))
+        .collect();
+    let same_package_list: Vec<(usize, &StringPackage)> = in_language
+        .iter()
+        .filter(|(_, string)| uefi_same_package_list(data, string.offset, form_package.offset))
+        .cloned()
+        .collect();
+    combine_indexed(if same_package_list.is_empty() { in_language } else { same_package_list })
+}
+
+fn combine_indexed(packages: Vec<(usize, &StringPackage)>) -> Option<(Vec<usize>, StringPackage)> {
+    let (indices, packages): (Vec<usize>, Vec<&StringPackage>) = packages.into_iter().unzip();
+    StringPackage::combine(&packages).map(|combined| (indices, combined))
+}
+
+// Find the extent of a UEFI HII package list containing a package at a given offset.
+// Adjacent packages are walked up to the End package, the package list header is included if its length matches
+fn uefi_package_list_extent(data: &[u8], package_offset: usize) -> Range<usize> {
+    // Package list header is a 16-byte GUID followed by a 32-bit length of the whole list.
+    // If there is one, the walk can't leave the list, otherwise it stops at anything that isn't a known package
+    if package_offset >= 20 {
+        let header = package_offset - 20;
+        let length = u32::from_le_bytes([data[header + 16], data[header + 17], data[header + 18], data[header + 19]]);
+        let list_end = header.saturating_add(length as usize);
+        if list_end > package_offset && list_end <= data.len() {
+            let end = uefi_package_walk_end(data, package_offset, list_end, true);
+            if end == list_end {
+                return header..end;
+            }
+        }
+    }
+    package_offset..uefi_package_walk_end(data, package_offset, data.len(), false)
+}
+
+// End of adjacent packages starting at a given offset, packages have to end before the limit.
+// Vendor-defined system packages are only trusted inside a package list whose length is known
+fn uefi_package_walk_end(data: &[u8], offset: usize, limit: usize, in_list: bool) -> usize {
+    let mut end = offset;
+    while let Ok((_, package)) = uefi_parser::hii_package(&data[end..limit]) {
+        match package.Type {
+            uefi_parser::HiiPackageType::Unknown(_) => break,
+            uefi_parser::HiiPackageType::System(_) if !in_list => break,
+            _ => (),
+        }
+        end += package.Length as usize;
+        if package.Type == uefi_parser::HiiPackageType::End {
+            break;
+        }
+    }
+    end
+}
+
+// Part of the input searched for packages, i.e. the HII region of an image as reported by UEFITool.
+// Packages have to start in the window, but they may end after it
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+    pub start_offset: usize,
+    pub end_offset: Option<usize>, // End of the input if not set
+    pub alignment: usize,          // Packages are only searched at multiples of it, 0 and 1 mean every offset
+    pub guid_opcode_decoders: GuidOpcodeDecoders, // Used to collect StringIds of vendor Guid opcodes
+}
+
+impl ScanOptions {
+    // Offsets of the input that are scanned
+    fn window(&self, length: usize) -> Range<usize> {
+        let end = self.end_offset.map_or(length, |end| end.min(length));
+        self.next_offset(self.start_offset).min(end)..end
+    }
+
+    // First offset at or after a given one where a package can start
+    fn next_offset(&self, offset: usize) -> usize {
+        if self.alignment <= 1 {
+            offset
+        } else {
+            offset.next_multiple_of(self.alignment)
+        }
+    }
+}
+
+// Format of HII packages found in the input file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HiiFlavor {
+    Uefi,
+    Framework,
+}
+
+// String and form packages of a single format, both lists are non-empty
+pub struct DetectedPackages {
+    pub flavor: HiiFlavor,
+    pub strings: Vec<StringPackage>,
+    pub forms: Vec<FormPackage>,
+}
+
+impl DetectedPackages {
+    // String package with a given language tag, see select_string_package
+    pub fn select_string_package(&self, language: &str) -> Option<&StringPackage> {
+        select_string_package(&self.strings, language)
+    }
+
+    // String package with the first available language tag, see select_string_package_by_preference
+    pub fn select_string_package_by_preference(&self, languages: &[&str]) -> Option<&StringPackage> {
+        select_string_package_by_preference(&self.strings, languages)
+    }
+}
+
+// Pick the first string package with a given language tag, tags are compared case-insensitively.
+// "*" matches any language
+pub fn select_string_package<'a>(strings: &'a [StringPackage], language: &str) -> Option<&'a StringPackage> {
+    strings
+        .iter()
+        .find(|string| language == "*" || string.language.eq_ignore_ascii_case(language))
+}
+
+// Pick a string package using language tags in order of preference, i.e. ["en-US", "en", "*"]
+pub fn select_string_package_by_preference<'a>(
+    strings: &'a [StringPackage],
+    languages: &[&str],
+) -> Option<&'a StringPackage> {
+    languages
+        .iter()
+        .find_map(|language| select_string_package(strings, language))
+}
+
+// Search for both UEFI and Framework packages, UEFI ones come first if both are found.
+// Formats without form packages or string packages are not returned
+pub fn find_packages_auto(data: &[u8]) -> Vec<DetectedPackages> {
+    find_packages_auto_with_summary(data).0
+}
+
+// Same as find_packages_auto, also returning a summary of the scan.
+// Packages and languages are only counted for the formats that are returned
+pub fn find_packages_auto_with_summary(data: &[u8]) -> (Vec<DetectedPackages>, ScanSummary) {
+    find_packages_auto_with_metrics(data, ScanMetrics::new())
+}
+
+// Same as find_packages_auto_with_summary, calling a progress callback periodically during the scan,
+// i.e. to show a progress bar for large images
+pub fn find_packages_auto_with_progress(
+    data: &[u8],
+    progress: impl Fn(ScanProgress) + Send + Sync + 'static,
+) -> (Vec<DetectedPackages>, ScanSummary) {
+    find_packages_auto_with_metrics(data, ScanMetrics::with_progress(progress))
+}
+
+// Same as find_packages_auto_with_summary, using given metrics with their progress callback and
+// cancellation token. Nothing is returned if the scan is cancelled, its summary tells so
+pub fn find_packages_auto_with_metrics(
+    data: &[u8],
+    metrics: ScanMetrics,
+) -> (Vec<DetectedPackages>, ScanSummary) {
+    find_packages_auto_with_options(data, &ScanOptions::default(), metrics)
+}
+
+// Same as find_packages_auto_with_metrics, only searching a window of the input
+pub fn find_packages_auto_with_options(
+    data: &[u8],
+    options: &ScanOptions,
+    mut metrics: ScanMetrics,
+) -> (Vec<DetectedPackages>, ScanSummary) {
+    let started = Instant::now();
+    let mut result = Vec::new();
+    for flavor in [HiiFlavor::Uefi, HiiFlavor::Framework] {
+        let (strings, forms) = match flavor {
+            HiiFlavor::Uefi => find_uefi_packages_with_options(data, options, &mut metrics),
+            HiiFlavor::Framework => find_framework_packages_with_options(data, options, &mut metrics),
+        };
+        if !strings.is_empty() && !forms.is_empty() {
+            result.push(DetectedPackages { flavor, strings, forms });
+        }
+    }
+
+    let mut summary = ScanSummary::new(&metrics, started.elapsed());
+    for packages in &result {
+        let (string_kind, form_kind) = match packages.flavor {
+            HiiFlavor::Uefi => ("UEFI string", "UEFI form"),
+            HiiFlavor::Framework => ("Framework string", "Framework form"),
+        };
+        summary.add_packages(string_kind, packages.strings.len());
+        summary.add_packages(form_kind, packages.forms.len());
+        for string in &packages.strings {
+            summary.add_language(&string.language);
+        }
+    }
+    (result, summary)
+}
+
+// Same as find_uefi_packages for a file that is read into memory, package offsets are into the returned data
+pub fn find_uefi_packages_in_file<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<(MappedInput, Vec<StringPackage>, Vec<FormPackage>)> {
+    let data = MappedInput::open(path)?;
+    let (strings, forms) = find_uefi_packages(&data);
+    Ok((data, strings, forms))
+}
+
+// Same as find_framework_packages for a file that is read into memory
+pub fn find_framework_packages_in_file<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<(MappedInput, Vec<StringPackage>, Vec<FormPackage>)> {
+    let data = MappedInput::open(path)?;
+    let (strings, forms) = find_framework_packages(&data);
+    Ok((data, strings, forms))
+}
+
+// Same as find_packages_auto_with_summary for a file that is read into memory
+pub fn find_packages_auto_in_file<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<(MappedInput, Vec<DetectedPackages>, ScanSummary)> {
+    let data = MappedInput::open(path)?;
+    let (packages, summary) = find_packages_auto_with_summary(&data);
+    Ok((data, packages, summary))
+}
+
+// Language tags of all UEFI and Framework string packages with the number of packages per language, sorted by tag
+pub fn list_languages(data: &[u8]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for packages in find_packages_auto(data) {
+        for string in &packages.strings {
+            *counts.entry(string.language.clone()).or_insert(0) += 1;
+        }
+    }
+    counts.into_iter().collect()
+}
+
+// Find byte ranges of the input that are not covered by any recognized HII package or UEFI HII package list
+pub fn find_uncovered_regions(data: &[u8]) -> Vec<Range<usize>> {
+    let mut covered: Vec<Range<usize>> = Vec::new();
+
+    let (uefi_strings, uefi_forms) = find_uefi_packages(data);
+    let uefi_images = find_uefi_image_packages(data);
+    let uefi_animations = find_uefi_animation_packages(data);
+    let uefi_offsets = uefi_strings
+        .iter()
+        .map(|package| package.offset)
+        .chain(uefi_forms.iter().map(|package| package.offset))
+        .chain(uefi_images.iter().map(|package| package.offset))
+        .chain(uefi_animations.iter().map(|package| package.offset));
+    for offset in uefi_offsets {
+        covered.push(uefi_package_list_extent(data, offset));
+    }
+
+    let (framework_strings, framework_forms) = find_framework_packages(data);
+    for package in &framework_strings {
+        covered.push(package.offset..package.offset + package.length);
+    }
+    for package in &framework_forms {
+        covered.push(package.offset..package.offset + package.length);
+    }
+
+    // Everything between merged covered ranges is uncovered
+    covered.sort_by_key(|range| range.start);
+    let mut uncovered = Vec::new();
+    let mut current = 0;
+    for range in covered {
+        if range.start > current {
+            uncovered.push(current..range.start);
+        }
+        current = current.max(range.end);
+    }
+    if current < data.len() {
+        uncovered.push(current..data.len());
+    }
+    uncovered
+}
+
+pub fn extract_uefi_ifr(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> String {
+    extract_uefi_ifr_with_diagnostics(data, form_package, string_package, resources, options, &mut Diagnostics::new())
+}
+
+// Same as extract_uefi_ifr, also collecting diagnostics. Nothing is collected if the result is taken from the cache
+pub fn extract_uefi_ifr_with_diagnostics(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+    diagnostics: &mut Diagnostics,
+) -> String {
+    let mut render = || {
+        let (text, found) = render_uefi_ifr(data, form_package, None, string_package, resources, options, &mut RenderOutput::Keep(&mut Vec::new()));
+        diagnostics.merge(found);
+        options.format_text(text)
+    };
+    match options.cache_dir() {
+        Some(dir) => extraction_cache::cached(dir, &uefi_cache_key("UEFI", form_package, string_package, resources, options), render),
+        None => render(),
+    }
+}
+
+// Same as extract_uefi_ifr_with_diagnostics, writing the text operation by operation instead of returning it,
+// so the whole text is never kept in memory. The cache is not used
+pub fn extract_uefi_ifr_to_writer(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+    writer: &mut impl io::Write,
+) -> io::Result<Diagnostics> {
+    stream_text(writer, options, |output| {
+        render_uefi_ifr(data, form_package, None, string_package, resources, options, output)
+    })
+}
+
+// Run a renderer streaming its text to a writer, writing stops at the first error
+fn stream_text(
+    writer: &mut impl io::Write,
+    options: &ExtractOptions,
+    render: impl FnOnce(&mut RenderOutput) -> (String, Diagnostics),
+) -> io::Result<Diagnostics> {
+    let mut error = None;
+    let mut sink = |text: &str| {
+        if error.is_none() {
+            if let Err(e) = writer.write_all(options.format_text(text.to_string()).as_bytes()) {
+                error = Some(e);
+            }
+        }
+    };
+    let (rest, diagnostics) = render(&mut RenderOutput::Stream(&mut sink));
+    sink(&rest);
+    match error {
+        Some(e) => Err(e),
+        None => Ok(diagnostics),
+    }
+}
+
+// Extract a single form with a given FormId, operations outside of any form are kept as formset context.
+// Only the formset context is returned if there is no such form, extract_uefi_form_with_diagnostics reports it
+pub fn extract_uefi_form(
+    data: &[u8],
+    form_package: &FormPackage,
+    form_id: u16,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> String {
+    extract_uefi_form_with_diagnostics(data, form_package, form_id, string_package, resources, options, &mut Diagnostics::new())
+}
+
+// Same as extract_uefi_form, also collecting diagnostics
+pub fn extract_uefi_form_with_diagnostics(
+    data: &[u8],
+    form_package: &FormPackage,
+    form_id: u16,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+    diagnostics: &mut Diagnostics,
+) -> String {
+    // Checked before the cache is used, so a wrong FormId is reported on cache hits too
+    if !uefi_has_form(data, form_package, form_id, options.padding) {
+        diagnostics.warn_at("Form not found", form_package.offset, format_args!("No Form or FormMap with FormId 0x{form_id:X}"));
+    }
+    let mut render = || {
+        let (text, found) = render_uefi_ifr(data, form_package, Some(form_id), string_package, resources, options, &mut RenderOutput::Keep(&mut Vec::new()));
+        diagnostics.merge(found);
+        options.format_text(text)
+    };
+    match options.cache_dir() {
+        Some(dir) => extraction_cache::cached(dir, &uefi_cache_key(&format!("UEFI form 0x{form_id:X}"), form_package, string_package, resources, options), render),
+        None => render(),
+    }
+}
+
+// Check if a form package has a Form or FormMap opcode with a given FormId
+fn uefi_has_form(data: &[u8], form_package: &FormPackage, form_id: u16, padding: PaddingHandling) -> bool {
+    let candidate = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..]) {
+        Ok((_, candidate)) => candidate,
+        Err(_) => return false,
+    };
+    let operations = match parse_uefi_operations(&candidate[4..], padding) {
+        Ok((operations, _)) => operations,
+        Err(_) => return false,
+    };
+    operations.iter().any(|operation| {
+        let data = operation.Data.unwrap_or(&[]);
+        match operation.OpCode {
+            uefi_parser::IfrOpcode::Form => uefi_parser::ifr_form(data).is_ok_and(|(_, form)| form.FormId == form_id),
+            uefi_parser::IfrOpcode::FormMap => {
+                uefi_parser::ifr_form_map(data).is_ok_and(|(_, form_map)| form_map.FormId == form_id)
+            }
+            _ => false,
+        }
+    })
+}
+
+// Cache key of a UEFI extraction, all packages that can be referenced are included
+fn uefi_cache_key(
+    kind: &str,
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> extraction_cache::CacheKey {
+    let mut key = extraction_cache::CacheKey::new(kind, options)
+        .package("Form", form_package.offset, &form_package.sha256)
+        .package("String", string_package.offset, &string_package.sha256);
+    for fallback in resources.string_packages {
+        key = key.package("Fallback string", fallback.offset, &fallback.sha256);
+    }
+    if let Some(image_package) = resources.image_package {
+        key = key.package("Image", image_package.offset, &image_package.sha256);
+    }
+    if let Some(animation_package) = resources.animation_package {
+        key = key.package("Animation", animation_package.offset, &animation_package.sha256);
+    }
+    if let Some(device_path_package) = resources.device_path_package {
+        key = key.package("Device path", device_path_package.offset, &device_path_package.sha256);
+    }
+    key
+}
+
+// Form package rendered with strings of one language
+#[derive(Debug, Clone)]
+pub struct LanguageExtraction {
+    pub language: String,
+    pub string_packages: Vec<usize>, // Indices of the string packages of this language combined to extract it
+    pub text: String,
+    pub diagnostics: Diagnostics,
+}
+
+// Extract a UEFI form package once per language of the string packages of its package list, or of all string
+// packages of the resources if there are none in it. Opcodes are parsed only once for all languages,
+// string packages of the same language are combined. Results are not cached
+pub fn extract_uefi_ifr_all_languages(
+    data: &[u8],
+    form_package: &FormPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> Vec<LanguageExtraction> {
+    let parsed = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate))
+    {
+        Ok((_, package)) => Some(parse_uefi_operations(package.Data.unwrap_or(&[]), options.padding)),
+        Err(_) => None,
+    };
+
+    let same_package_list = resources
+        .string_packages
+        .iter()
+        .any(|string| uefi_same_package_list(data, string.offset, form_package.offset));
+    let mut languages: Vec<&str> = Vec::new();
+    for string in resources.string_packages {
+        if (!same_package_list || uefi_same_package_list(data, string.offset, form_package.offset))
+            && !languages.contains(&string.language.as_str())
+        {
+            languages.push(&string.language);
+        }
+    }
+
+    let mut result = Vec::new();
+    for language in languages {
+        let (indices, string_package) =
+            match uefi_combined_string_package(data, resources.string_packages, form_package, language) {
+                Some(combined) => combined,
+                None => continue,
+            };
+        let strings_map = uefi_string_map(data, form_package, &string_package, resources, options);
+        let mut text = String::new();
+        let mut diagnostics = Diagnostics::new();
+        writeln!(&mut text, "Program version: {}, Extraction mode: UEFI", VERSION.unwrap_or("0.0.0")).unwrap();
+        if let Some(parsed) = &parsed {
+            let (operations_text, found) =
+                render_uefi_operations(form_package, parsed, None, &strings_map, resources, options, &mut RenderOutput::Keep(&mut Vec::new()));
+            text.push_str(&operations_text);
+            diagnostics.merge(found);
+        }
+        diagnostics.resolve_context(data);
+        result.push(LanguageExtraction {
+            language: string_package.language.clone(),
+            string_packages: indices,
+            text: options.format_text(text),
+            diagnostics,
+        });
+    }
+    result
+}
+
+// Extractions of the same form package in several languages as one text. Lines that are the same in all
+// languages are kept once, others are repeated for every language with its tag after their indentation.
+// None if the extractions don't line up, i.e. because help strings are wrapped at different lines
+pub fn combine_language_extractions(extractions: &[LanguageExtraction]) -> Option<String> {
+    let lines: Vec<Vec<&str>> = extractions.iter().map(|extraction| extraction.text.lines().collect()).collect();
+    let count = lines.first()?.len();
+    if lines.iter().any(|language_lines| language_lines.len() != count) {
+        return None;
+    }
+
+    let mut text = String::new();
+    for index in 0..count {
+        let first = lines[0][index];
+        if lines.iter().all(|language_lines| language_lines[index] == first) {
+            writeln!(&mut text, "{first}").unwrap();
+            continue;
+        }
+        for (extraction, language_lines) in extractions.iter().zip(&lines) {
+            let line = language_lines[index];
+            let content = line.trim_start();
+            let indentation = &line[..line.len() - content.len()];
+            writeln!(&mut text, "{indentation}[{}] {content}", extraction.language).unwrap();
+        }
+    }
+    Some(text)
+}
+
+// Same as extract_uefi_ifr, but fails if the form package can't be parsed completely
+// or any string in its StringId range was converted with replacement characters. Results are not cached
+pub fn try_extract_uefi_ifr(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> Result<String, IfrError> {
+    // Form package header is 4 bytes long, type is in the highest byte of the length
+    let rest = data.get(form_package.offset..).unwrap_or(&[]);
+    let candidate = match uefi_parser::hii_form_package_candidate(rest) {
+        Ok((_, candidate)) => candidate,
+        Err(_) => {
+            let header = rest.get(..4).map(|header| u32::from_le_bytes([header[0], header[1], header[2], header[3]]));
+            return Err(match header {
+                Some(header) if header >> 24 == 0x02 && (header & 0x00FF_FFFF) as usize > rest.len() => IfrError::Truncated {
+                    location: ErrorLocation::new(data, form_package.offset),
+                    length: (header & 0x00FF_FFFF) as usize,
+                    available: rest.len(),
+                },
+                _ => IfrError::NoPackage { location: ErrorLocation::new(data, form_package.offset) },
+            });
+        }
+    };
+    let payload = &candidate[4..];
+    let parsed = match options.padding {
+        PaddingHandling::Stop => uefi_parser::ifr_operations(payload)
+            .map(|(_, operations)| operations.iter().map(|operation| operation.Length as usize).sum())
+            .unwrap_or(0),
+        PaddingHandling::Skip | PaddingHandling::Record => {
+            let (operations, padding) = uefi_parser::ifr_operations_skipping_padding(payload);
+            operations.iter().map(|operation| operation.Length as usize).sum::<usize>()
+                + padding.iter().map(|range| range.len()).sum::<usize>()
+        }
+    };
+    if parsed < payload.len() {
+        return Err(IfrError::InvalidOpcode { location: ErrorLocation::new(data, form_package.offset + 4 + parsed) });
+    }
+    // Opcodes too short for their type can't be rendered, padding is skipped the same way the renderer skips it
+    if let Ok((operations, padding)) = parse_uefi_operations(payload, options.padding) {
+        let mut offset = 0;
+        for operation in &operations {
+            while let Some(range) = padding.iter().find(|range| range.start == offset) {
+                offset = range.end;
+            }
+            if operation.Length < operation.OpCode.minimum_length() {
+                return Err(IfrError::InvalidOpcode { location: ErrorLocation::new(data, form_package.offset + 4 + offset) });
+            }
+            offset += operation.Length as usize;
+        }
+    }
+    check_strings(form_package, string_package)?;
+    // Fields that don't fit their opcodes, i.e. list lengths, are only found while rendering, so the cache is not used
+    let options = ExtractOptions { cache: None, ..options.clone() };
+    let mut diagnostics = Diagnostics::new();
+    let text = extract_uefi_ifr_with_diagnostics(data, form_package, string_package, resources, &options, &mut diagnostics);
+    match first_parse_error(data, &diagnostics) {
+        Some(location) => Err(IfrError::InvalidOpcode { location }),
+        None => Ok(text),
+    }
+}
+
+// Location of the first opcode the renderer couldn't parse
+fn first_parse_error(data: &[u8], diagnostics: &Diagnostics) -> Option<ErrorLocation> {
+    diagnostics
+        .warnings()
+        .iter()
+        .filter(|warning| warning.kind.ends_with("parse error"))
+        .filter_map(|warning| warning.location.as_ref())
+        .map(|location| location.offset)
+        .min()
+        .map(|offset| ErrorLocation::new(data, offset))
+}
+
+// Strings of the StringId range of a form package must not contain replacement characters of lossy conversions
+fn check_strings(form_package: &FormPackage, string_package: &StringPackage) -> Result<(), IfrError> {
+    for string_id in form_package.min_string_id..=form_package.max_string_id {
+        if string_package.string_id_map.get(&string_id).is_some_and(|string| string.contains('\u{FFFD}')) {
+            return Err(IfrError::InvalidString { string_id });
+        }
+    }
+    Ok(())
+}
+
+// StringIds of a UEFI form package are resolved using string packages of other languages from the same package list
+// if its own string package doesn't have them
+fn uefi_string_map<'a>(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &'a StringPackage,
+    resources: &HiiResources<'a>,
+    options: &'a ExtractOptions,
+) -> StringMap<'a> {
+    let fallbacks = resources
+        .string_packages
+        .iter()
+        .filter(|string| {
+            string.offset != string_package.offset
+                && uefi_same_package_list(data, string.offset, form_package.offset)
+        })
+        .map(|string| &string.string_id_map)
+        .collect();
+    StringMap::new(&string_package.string_id_map, fallbacks, options.invalid_id_placeholder(), options.show_string_ids, options.escaping, options.help, &options.string_processors)
+}
+
+// What renderers do with the text of every rendered operation besides returning it
+enum RenderOutput<'a> {
+    Keep(&'a mut Vec<(usize, Range<usize>)>), // Offsets of operations with ranges of the text with their data are collected
+    Stream(&'a mut dyn FnMut(&str)),          // Text is passed on and dropped, so it is never kept whole
+}
+
+impl RenderOutput<'_> {
+    fn describe(&mut self, offset: usize, range: Range<usize>) {
+        if let RenderOutput::Keep(descriptions) = self {
+            descriptions.push((offset, range));
+        }
+    }
+
+    // Pass rendered text on when streaming, only whole lines are passed
+    fn flush(&mut self, text: &mut String) {
+        if let RenderOutput::Stream(sink) = self {
+            sink(text);
+            text.clear();
+        }
+    }
+}
+
+// Render a form package into text, offsets of all rendered operations and ranges of the text
+// with their data are added to the output unless it is streamed
+fn render_uefi_ifr(
+    data: &[u8],
+    form_package: &FormPackage,
+    form_id: Option<u16>,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+    output: &mut RenderOutput,
+) -> (String, Diagnostics) {
+    let mut text = String::new();
+    let mut diagnostics = Diagnostics::new();
+    let strings_map = &uefi_string_map(data, form_package, string_package, resources, options);
+
+    // Add version number and extraction mode
+    writeln!(&mut text, "Program version: {}, Extraction mode: UEFI", VERSION.unwrap_or("0.0.0")).unwrap();
+
+    if let Ok((_, candidate)) =
+        uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+    {
+        if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
+            // Parse form package and output its structure as human-readable strings
+            output.flush(&mut text);
+            let first_description = match output {
+                RenderOutput::Keep(descriptions) => descriptions.len(),
+                RenderOutput::Stream(_) => 0,
+            };
+            let (operations_text, operations_diagnostics) = render_uefi_operations(
+                form_package,
+                &parse_uefi_operations(package.Data.unwrap_or(&[]), options.padding),
+                form_id,
+                strings_map,
+                resources,
+                options,
+                output,
+            );
+            // Ranges of descriptions are relative to the text of the operations
+            if let RenderOutput::Keep(descriptions) = output {
+                for (_, range) in &mut descriptions[first_description..] {
+                    *range = range.start + text.len()..range.end + text.len();
+                }
+            }
+            text.push_str(&operations_text);
+            diagnostics.merge(operations_diagnostics);
+        }
+    }
+
+    diagnostics.resolve_context(data);
+    (text, diagnostics)
+}
+
+// Operations of a UEFI form package payload with ranges of padding skipped between them,
+// or the reason they can't be parsed
+type ParsedUefiOperations<'a> = Result<(Vec<uefi_parser::IfrOperation<'a>>, Vec<Range<usize>>), String>;
+
+// Parse a UEFI form package payload once, so it can be rendered several times
+fn parse_uefi_operations(payload: &[u8], handling: PaddingHandling) -> ParsedUefiOperations<'_> {
+    let mut padding = Vec::new();
+    let parsed = match handling {
+        PaddingHandling::Stop => uefi_parser::ifr_operations(payload),
+        PaddingHandling::Skip | PaddingHandling::Record => {
+            let (operations, ranges) = uefi_parser::ifr_operations_skipping_padding(payload);
+            padding = ranges;
+            if operations.is_empty() {
+                uefi_parser::ifr_operations(payload)
+            } else {
+                Ok((&payload[payload.len()..], operations))
+            }
+        }
+    };
+    match parsed {
+        Ok((_, operations)) => Ok((operations, padding)),
+        Err(e) => Err(format!("{e:?}")),
+    }
+}
+
+// Render parsed opcodes of a UEFI form package payload, offsets are counted from the start of the package
+fn render_uefi_operations(
+    form_package: &FormPackage,
+    parsed: &ParsedUefiOperations,
+    form_id: Option<u16>,
+    strings_map: &StringMap,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+    output: &mut RenderOutput,
+) -> (String, Diagnostics) {
+    let mut text = String::new();
+    let mut diagnostics = Diagnostics::new();
+
+    match parsed {
+        Ok((operations, padding)) => {
+            // Collect DefaultStores and varstores first, they are needed to resolve DefaultIds and VarStoreInfo
+            let mut default_stores = HashMap::new();
+            for operation in operations {
+                if operation.OpCode == uefi_parser::IfrOpcode::DefaultStore {
+                    if let Ok((_, default_store)) =
+                        uefi_parser::ifr_default_store(operation.Data.unwrap_or(&[]))
+                    {
+                        default_stores
+                            .insert(default_store.DefaultId, default_store.NameStringId);
+                    }
+                }
+            }
+            let declarations = var_stores::var_store_declarations(operations, form_package.offset + 4);
+            let var_stores: HashMap<u16, uefi_parser::IfrVarStoreType> = declarations
+                .iter()
+                .map(|var_store| (var_store.var_store_id, var_store.var_store_type()))
+                .collect();
+            // Varstore table goes before the opcodes, unless storage opcodes are filtered out
+            if options.opcode_filter.allows(OpcodeCategory::Storage) && options.preview != Preview::Headers {
+                text.push_str(&var_stores::var_store_table(&declarations));
+            }
+
+            let mut scope_depth: usize = 0;
+            let mut current_operation_offset = form_package.offset + 4; // Header size of UEFI HII form package is 4 bytes
+            let offset_origin = options.offset_base.origin(form_package.offset, 4);
+            let mut current_form: Option<(u16, usize)> = None; // FormId and scope depth of the form being walked
+            let mut signed_question: Option<usize> = None; // Scope depth of the signed OneOf or Numeric being walked
+            let mut bit_field_scope: Option<usize> = None; // Scope depth of the bit varstore Guid opcode being walked
+            let mut filtered_scopes: Vec<bool> = Vec::new(); // Visibility of the opcodes that opened all current scopes
+            let mut next_padding = 0; // Index of the next padding range
+            let mut rendered = 0; // Number of opcodes written, for previews
+            let mut truncated = false;
+            for (index, operation) in operations.iter().enumerate() {
+                if options.preview.write_truncation(&mut text, rendered, operations.len()) {
+                    truncated = true;
+                    break;
+                }
+
+                // Padding before this operation
+                while next_padding < padding.len()
+                    && form_package.offset + 4 + padding[next_padding].start == current_operation_offset
+                {
+                    let length = padding[next_padding].len();
+                    diagnostics.warn_at("IFR padding", current_operation_offset, format_args!("0x{length:X} bytes at offset 0x{current_operation_offset:X}"));
+                    options.write_padding(&mut text, current_operation_offset - offset_origin, length, scope_depth);
+                    current_operation_offset += length;
+                    next_padding += 1;
+                }
+
+                if operation.OpCode == uefi_parser::IfrOpcode::End && scope_depth >= 1 {
+                    scope_depth -= 1;
+                }
+                if signed_question.is_some_and(|depth| scope_depth < depth) {
+                    signed_question = None;
+                }
+                if bit_field_scope.is_some_and(|depth| scope_depth < depth) {
+                    bit_field_scope = None;
+                }
+                // Questions in the scope of a bit varstore Guid opcode keep offsets and widths in bits,
+                // the scope is tracked even if the Guid opcode itself is filtered out
+                if bit_field_scope.is_none()
+                    && operation.ScopeStart
+                    && uefi_parser::ifr_is_bit_var_store(operation.OpCode, operation.Data.unwrap_or(&[]))
+                {
+                    bit_field_scope = Some(scope_depth + 1);
+                }
+
+                let allowed = if operation.OpCode == uefi_parser::IfrOpcode::End {
+                    filtered_scopes.pop().unwrap_or(true)
+                } else {
+                    options.opcode_filter.allows(operation.OpCode.category())
+                        && (options.preview != Preview::Headers
+                            || matches!(
+                                operation.OpCode,
+                                uefi_parser::IfrOpcode::FormSet
+                                    | uefi_parser::IfrOpcode::Form
+                                    | uefi_parser::IfrOpcode::FormMap
+                            ))
+                };
+                if operation.ScopeStart {
+                    filtered_scopes.push(allowed);
+                }
+
+                // Skip operations of other forms if a single form is requested
+                if let Some(requested_form_id) = form_id {
+                    if current_form.is_none() {
+                        let data = operation.Data.unwrap_or(&[]);
+                        current_form = match operation.OpCode {
+                            uefi_parser::IfrOpcode::Form => uefi_parser::ifr_form(data)
+                                .ok()
+                                .map(|(_, form)| (form.FormId, scope_depth)),
+                            uefi_parser::IfrOpcode::FormMap => uefi_parser::ifr_form_map(data)
+                                .ok()
+                                .map(|(_, form_map)| (form_map.FormId, scope_depth)),
+                            _ => None,
+                        };
+                    }
+                    let visible = match current_form {
+                        Some((current_form_id, _)) => current_form_id == requested_form_id,
+                        None => true,
+                    };
+                    // Form scope is closed by the End at the same depth
+                    if let Some((_, form_depth)) = current_form {
+                        if operation.OpCode == uefi_parser::IfrOpcode::End && scope_depth == form_depth {
+                            current_form = None;
+                        }
+                    }
+                    if !visible {
+                        if operation.ScopeStart {
+                            scope_depth += 1;
+                        }
+                        current_operation_offset += operation.Length as usize;
+                        continue;
+                    }
+                }
+
+                // Skip operations of excluded categories
+                if !allowed {
+                    if operation.ScopeStart {
+                        scope_depth += 1;
+                    }
+                    current_operation_offset += operation.Length as usize;
+                    continue;
+                }
+
+                if options.verbose {
+                    // Fixed-width offset, length and scope depth columns
+                    write!(
+                        &mut text,
+                        "0x{:08X} 0x{:02X} {:02}: ",
+                        current_operation_offset - offset_origin,
+                        operation.Length,
+                        scope_depth
+                    )
+                    .unwrap();
+                }
+
+                options.write_opcode_name(&mut text, scope_depth, &operation.OpCode);
+                let description_start = text.len();
+                let line_depth = scope_depth;
+                let mut nested = String::new(); // Rendered IFR embedded in the payload of a Guid opcode
+
+                if operation.ScopeStart {
+                    scope_depth += 1;
+                }
+
+                match operation.OpCode {
+                    // 0x02: Subtitle
+                    uefi_parser::IfrOpcode::Subtitle => {
+                        match uefi_parser::ifr_subtitle(operation.Data.unwrap_or(&[])) {
+                            Ok((_, sub)) => {
+                                write!(
+                                    &mut text,
+                                    "Prompt: {}{}, Flags: 0x{:X}",
+                                    strings_map.quoted(sub.PromptStringId),
+                                    strings_map.help_field(sub.HelpStringId),
+                                    sub.Flags
+                                )
+                                .unwrap();
+                                if sub.Flags & uefi_parser::IfrSubtitleFlags::Horizontal as u8 != 0 {
+                                    write!(&mut text, ", Horizontal").unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Subtitle parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x04: Image
+                    uefi_parser::IfrOpcode::Image => {
+                        match uefi_parser::ifr_image(operation.Data.unwrap_or(&[])) {
+                            Ok((_, image)) => {
+                                write!(&mut text, "ImageId: 0x{:X}", image.ImageId).unwrap();
+                                // Resolve ImageId using the image package, if there is one
+                                if let Some(image_package) = resources.image_package {
+                                    match image_package.images.get(&image.ImageId) {
+                                        Some(info) => write!(
+                                            &mut text,
+                                            ", Image: {:?} {}x{} at offset 0x{:X}",
+                                            info.format, info.width, info.height, info.offset
+                                        )
+                                        .unwrap(),
+                                        None => {
+                                            write!(&mut text, ", Image: NotFound").unwrap();
+                                            diagnostics.warn_at("Dangling ImageId", current_operation_offset, format_args!("0x{:X} at offset 0x{current_operation_offset:X}", image.ImageId));
+                                        }
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Image parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x05: OneOf and 0x07: Numeric in a bit varstore
+                    uefi_parser::IfrOpcode::OneOf | uefi_parser::IfrOpcode::Numeric if bit_field_scope.is_some() => {
+                        match uefi_parser::ifr_bit_field_numeric(operation.Data.unwrap_or(&[])) {
+                            Ok((_, num)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, BitOffset: 0x{:X}, BitWidth: {}, Flags: 0x{:X}, ",
+                                        strings_map.quoted(num.PromptStringId),
+                                        strings_map.help_field(num.HelpStringId),
+                                        num.QuestionFlags,
+                                        num.QuestionId,
+                                        num.VarStoreId,
+                                        num.BitOffset,
+                                        num.bit_width(),
+                                        num.Flags).unwrap();
+                                let signed = uefi_parser::IfrNumericDisplay::from_bit_flags(num.Flags) == uefi_parser::IfrNumericDisplay::IntDec;
+                                let bits = (num.bit_width() as u32).clamp(1, 32);
+                                write_min_max(&mut text, bits, num.MinValue as u64, num.MaxValue as u64, num.Step as u64, signed);
+                                if signed && operation.ScopeStart {
+                                    signed_question = Some(scope_depth);
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at(&format!("{:?} parse error", operation.OpCode), current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x05: OneOf
+                    uefi_parser::IfrOpcode::OneOf => {
+                        match uefi_parser::ifr_one_of(operation.Data.unwrap_or(&[])) {
+                            Ok((_, onf)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, Flags: 0x{:X}, ", 
+                                        strings_map.quoted(onf.PromptStringId),
+                                        strings_map.help_field(onf.HelpStringId),
+                                        onf.QuestionFlags,
+                                        onf.QuestionId,
+                                        onf.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&onf.VarStoreId).copied(), strings_map, onf.VarStoreInfo),
+                                        onf.Flags).unwrap();
+                                let signed = uefi_parser::IfrNumericDisplay::from_flags(onf.Flags) == uefi_parser::IfrNumericDisplay::IntDec;
+                                if let Some((bits, [min, max, step])) = uefi_min_max_step(onf.MinMaxStepData8, onf.MinMaxStepData16, onf.MinMaxStepData32, onf.MinMaxStepData64) {
+                                    write!(&mut text, "Size: {bits}, ").unwrap();
+                                    write_min_max(&mut text, bits, min, max, step, signed);
+                                }
+                                // Values of options and defaults in the scope of a signed question are signed too
+                                if signed && operation.ScopeStart {
+                                    signed_question = Some(scope_depth);
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("OneOf parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x06: CheckBox
+                    uefi_parser::IfrOpcode::CheckBox => {
+                        match uefi_parser::ifr_check_box(operation.Data.unwrap_or(&[])) {
+                            Ok((_, cb)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, Flags: 0x{:X}", 
+                                        strings_map.quoted(cb.PromptStringId),
+                                        strings_map.help_field(cb.HelpStringId),
+                                        cb.QuestionFlags,
+                                        cb.QuestionId,
+                                        cb.VarStoreId,
+                                        // CheckBoxes in bit varstores take a single bit
+                                        match bit_field_scope {
+                                            Some(_) => format!("BitOffset: 0x{:X}, BitWidth: 1", cb.VarStoreInfo),
+                                            None => uefi_var_store_info(var_stores.get(&cb.VarStoreId).copied(), strings_map, cb.VarStoreInfo),
+                                        },
+                                        cb.Flags).unwrap();
+
+                                if cb.Flags & (uefi_parser::IfrCheckBoxDefaultFlags::Default as u8) > 0 {
+                                    write!(&mut text, ", Default: Enabled").unwrap();
+                                }
+                                else {
+                                    write!(&mut text, ", Default: Disabled").unwrap();
+                                }
+                                if cb.Flags & (uefi_parser::IfrCheckBoxDefaultFlags::MfgDefault as u8) > 0 {
+                                    write!(&mut text, ", MfgDefault: Enabled").unwrap();
+                                }
+                                else {
+                                    write!(&mut text, ", MfgDefault: Disabled").unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("CheckBox parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x07: Numeric
+                    uefi_parser::IfrOpcode::Numeric => {
+                        match uefi_parser::ifr_numeric(operation.Data.unwrap_or(&[])) {
+                            Ok((_, num)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, Flags: 0x{:X}, ", 
+                                        strings_map.quoted(num.PromptStringId),
+                                        strings_map.help_field(num.HelpStringId),
+                                        num.QuestionFlags,
+                                        num.QuestionId,
+                                        num.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&num.VarStoreId).copied(), strings_map, num.VarStoreInfo),
+                                        num.Flags).unwrap();
+                                let signed = uefi_parser::IfrNumericDisplay::from_flags(num.Flags) == uefi_parser::IfrNumericDisplay::IntDec;
+                                if let Some((bits, [min, max, step])) = uefi_min_max_step(num.MinMaxStepData8, num.MinMaxStepData16, num.MinMaxStepData32, num.MinMaxStepData64) {
+                                    write!(&mut text, "Size: {bits}, ").unwrap();
+                                    write_min_max(&mut text, bits, min, max, step, signed);
+                                }
+                                // Values of options and defaults in the scope of a signed question are signed too
+                                if signed && operation.ScopeStart {
+                                    signed_question = Some(scope_depth);
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Numeric parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x08: Password
+                    uefi_parser::IfrOpcode::Password => {
+                        match uefi_parser::ifr_password(operation.Data.unwrap_or(&[])) {
+                            Ok((_, pw)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, MinSize: 0x{:X}, MaxSize: 0x{:X}", 
+                                        strings_map.quoted(pw.PromptStringId),
+                                        strings_map.help_field(pw.HelpStringId),
+                                        pw.QuestionFlags,
+                                        pw.QuestionId,
+                                        pw.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&pw.VarStoreId).copied(), strings_map, pw.VarStoreInfo),
+                                        pw.MinSize,
+                                        pw.MaxSize).unwrap();
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Password parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x09: OneOfOption
+                    uefi_parser::IfrOpcode::OneOfOption => {
+                        match uefi_parser::ifr_one_of_option(operation.Data.unwrap_or(&[])) {
+                            Ok((_, opt)) => {
+                                write!(
+                                    &mut text,
+                                    "Option: {} ",
+                                    strings_map.quoted(opt.OptionStringId)
+                                )
+                                .unwrap();
+                                match opt.Value {
+                                    uefi_parser::IfrTypeValue::String(x) => {
+                                        write!(
+                                            &mut text,
+                                            "String: {}",
+                                            strings_map.quoted(x)
+                                        )
+                                        .unwrap();
+                                    }
+                                    uefi_parser::IfrTypeValue::Action(x) => {
+                                        write!(
+                                            &mut text,
+                                            "Action: {}",
+                                            strings_map.quoted(x)
+                                        )
+                                        .unwrap();
+                                    }
+                                    _ => {
+                                        write!(&mut text, "Value: {}", uefi_type_value(&opt.Value, signed_question.is_some())).unwrap();
+                                    }
+                                }
+
+                                if opt.Flags & (uefi_parser::IfrOneOfOptionDefaultFlags::Default as u8) > 0  {
+                                    write!(&mut text, ", Default").unwrap();
+                                }
+                                if opt.Flags & (uefi_parser::IfrOneOfOptionDefaultFlags::MfgDefault as u8) > 0 {
+                                    write!(&mut text, ", MfgDefault").unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("OneOfOption parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x0C: Action
+                    uefi_parser::IfrOpcode::Action => {
+                        match uefi_parser::ifr_action(operation.Data.unwrap_or(&[])) {
+                            Ok((_, act)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}", 
+                                        strings_map.quoted(act.PromptStringId),
+                                        strings_map.help_field(act.HelpStringId),
+                                        act.QuestionFlags,
+                                        act.QuestionId,
+                                        act.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&act.VarStoreId).copied(), strings_map, act.VarStoreInfo)).unwrap();
+                                if let Some(x) = act.ConfigStringId {
+                                    write!(
+                                        &mut text,
+                                        ", QuestionConfig: {}",
+                                        strings_map.quoted(x)
+                                    )
+                                    .unwrap();
+                                }
+
+
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Action parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x0D: ResetButton
+                    uefi_parser::IfrOpcode::ResetButton => {
+                        match uefi_parser::ifr_reset_button(operation.Data.unwrap_or(&[])) {
+                            Ok((_, rst)) => {
+                                write!(
+                                    &mut text,
+                                    "Prompt: {}{}, DefaultId: 0x{:X}",
+                                    strings_map.quoted(rst.PromptStringId),
+                                    strings_map.help_field(rst.HelpStringId),
+                                    rst.DefaultId
+                                )
+                                .unwrap();
+                                // Resolve DefaultId using declared DefaultStores, or default class names
+                                match default_stores.get(&rst.DefaultId) {
+                                    Some(name_id) => write!(
+                                        &mut text,
+                                        ", DefaultStore: {}",
+                                        strings_map.quoted(*name_id)
+                                    )
+                                    .unwrap(),
+                                    None => write!(
+                                        &mut text,
+                                        ", DefaultStore: {} (undeclared)",
+                                        uefi_parser::default_class_name(rst.DefaultId)
+                                    )
+                                    .unwrap(),
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("ResetButton parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x0F: Ref
+                    uefi_parser::IfrOpcode::Ref => {
+                        match uefi_parser::ifr_ref(operation.Data.unwrap_or(&[])) {
+                            Ok((_, rf)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}", 
+                                        strings_map.quoted(rf.PromptStringId),
+                                        strings_map.help_field(rf.HelpStringId),
+                                        rf.QuestionFlags,
+                                        rf.QuestionId,
+                                        rf.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&rf.VarStoreId).copied(), strings_map, rf.VarStoreInfo)).unwrap();
+                                if let Some(x) = rf.FormId {
+                                    write!(&mut text, ", FormId: 0x{x:X}").unwrap();
+                                }
+                                if let Some(x) = rf.RefQuestionId {
+                                    write!(&mut text, ", RefQuestionId: 0x{x:X}").unwrap();
+                                }
+                                if let Some(x) = rf.FormSetGuid {
+                                    write!(&mut text, ", FormSetGuid: {x}").unwrap();
+                                }
+                                if let Some(x) = rf.DevicePathId {
+                                    write!(&mut text, ", DevicePathId: 0x{x:X}").unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Ref parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x1A: Date
+                    uefi_parser::IfrOpcode::Date => {
+                        match uefi_parser::ifr_date(operation.Data.unwrap_or(&[])) {
+                            Ok((_, dt)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, Flags: 0x{:X}", 
+                                        strings_map.quoted(dt.PromptStringId),
+                                        strings_map.help_field(dt.HelpStringId),
+                                        dt.QuestionFlags,
+                                        dt.QuestionId,
+                                        dt.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&dt.VarStoreId).copied(), strings_map, dt.VarStoreInfo),
+                                        dt.Flags).unwrap();
+                                write_date_time_storage(
+                                    &mut text,
+                                    match var_stores.get(&dt.VarStoreId) {
+                                        Some(uefi_parser::IfrVarStoreType::Buffer)
+                                        | Some(uefi_parser::IfrVarStoreType::EfiVariableBuffer) => Some(dt.VarStoreInfo),
+                                        _ => None,
+                                    },
+                                    dt.Flags,
+                                    &[
+                                        ("Year", 0, "u16", uefi_parser::IfrDateFlags::YearSuppress as u8),
+                                        ("Month", 2, "u8", uefi_parser::IfrDateFlags::MonthSuppress as u8),
+                                        ("Day", 3, "u8", uefi_parser::IfrDateFlags::DaySuppress as u8),
+                                    ],
+                                );
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Date parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x1B: Time
+                    uefi_parser::IfrOpcode::Time => {
+                        match uefi_parser::ifr_time(operation.Data.unwrap_or(&[])) {
+                            Ok((_, time)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, Flags: 0x{:X}", 
+                                        strings_map.quoted(time.PromptStringId),
+                                        strings_map.help_field(time.HelpStringId),
+                                        time.QuestionFlags,
+                                        time.QuestionId,
+                                        time.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&time.VarStoreId).copied(), strings_map, time.VarStoreInfo),
+                                        time.Flags).unwrap();
+                                write_date_time_storage(
+                                    &mut text,
+                                    match var_stores.get(&time.VarStoreId) {
+                                        Some(uefi_parser::IfrVarStoreType::Buffer)
+                                        | Some(uefi_parser::IfrVarStoreType::EfiVariableBuffer) => Some(time.VarStoreInfo),
+                                        _ => None,
+                                    },
+                                    time.Flags,
+                                    &[
+                                        ("Hour", 0, "u8", uefi_parser::IfrTimeFlags::HourSuppress as u8),
+                                        ("Minute", 1, "u8", uefi_parser::IfrTimeFlags::MinuteSuppress as u8),
+                                        ("Second", 2, "u8", uefi_parser::IfrTimeFlags::SecondSuppress as u8),
+                                    ],
+                                );
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Time parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x1C: String
+                    uefi_parser::IfrOpcode::String => {
+                        match uefi_parser::ifr_string(operation.Data.unwrap_or(&[])) {
+                            Ok((_, st)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, MinSize: 0x{:X}, MaxSize: 0x{:X}, Flags: 0x{:X}", 
+                                        strings_map.quoted(st.PromptStringId),
+                                        strings_map.help_field(st.HelpStringId),
+                                        st.QuestionFlags,
+                                        st.QuestionId,
+                                        st.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&st.VarStoreId).copied(), strings_map, st.VarStoreInfo),
+                                        st.MinSize,
+                                        st.MaxSize,
+                                        st.Flags).unwrap();
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("String parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x1F: Animation
+                    uefi_parser::IfrOpcode::Animation => {
+                        match uefi_parser::ifr_animation(operation.Data.unwrap_or(&[])) {
+                            Ok((_, anim)) => {
+                                write!(&mut text, "AnimationId: 0x{:X}", anim.AnimationId)
+                                    .unwrap();
+                                // Resolve AnimationId using the animation package, if there is one
+                                if let Some(animation_package) = resources.animation_package {
+                                    match animation_package.animations.get(&anim.AnimationId) {
+                                        Some(info) => {
+                                            write!(
+                                                &mut text,
+                                                ", Animation: {:?} {}x{}, Cells: {}",
+                                                info.kind,
+                                                info.animation.Width,
+                                                info.animation.Height,
+                                                info.animation.Cells.len()
+                                            )
+                                            .unwrap();
+                                            // Report images that can't be found in the image package
+                                            if let Some(image_package) = resources.image_package {
+                                                let mut dangling: Vec<u16> = info
+                                                    .animation
+                                                    .Cells
+                                                    .iter()
+                                                    .map(|cell| cell.ImageId)
+                                                    .chain(Some(info.animation.DftImageId))
+                                                    .filter(|id| *id != 0 && !image_package.images.contains_key(id))
+                                                    .collect();
+                                                dangling.sort();
+                                                dangling.dedup();
+                                                if !dangling.is_empty() {
+                                                    write!(&mut text, ", DanglingImageIds: {dangling:X?}").unwrap();
+                                                }
+                                            }
+                                        }
+                                        None => {
+                                            write!(&mut text, ", Animation: NotFound").unwrap();
+                                            diagnostics.warn_at("Dangling AnimationId", current_operation_offset, format_args!("0x{:X} at offset 0x{current_operation_offset:X}", anim.AnimationId));
+                                        }
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Animation parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x23: OrderedList
+                    uefi_parser::IfrOpcode::OrderedList => {
+                        match uefi_parser::ifr_ordered_list(operation.Data.unwrap_or(&[])) {
+                            Ok((_, ol)) => {
+                                write!(&mut text, "Prompt: {}{}, QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, {}, MaxContainers: 0x{:X}, Flags: 0x{:X}", 
+                                        strings_map.quoted(ol.PromptStringId),
+                                        strings_map.help_field(ol.HelpStringId),
+                                        ol.QuestionFlags,
+                                        ol.QuestionId,
+                                        ol.VarStoreId,
+                                        uefi_var_store_info(var_stores.get(&ol.VarStoreId).copied(), strings_map, ol.VarStoreInfo),
+                                        ol.MaxContainers,
+                                        ol.Flags).unwrap();
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("OrderedList parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x26: VarStoreEfi
+                    uefi_parser::IfrOpcode::VarStoreEfi => {
+                        match uefi_parser::ifr_var_store_efi(operation.Data.unwrap_or(&[])) {
+                            Ok((_, var_store)) => {
+                                write!(&mut text, "Guid: {}, VarStoreId: 0x{:X}, Attributes: 0x{:X}", 
+                                        var_store.Guid,
+                                        var_store.VarStoreId,
+                                        var_store.Attributes
+                                ).unwrap();
+
+                                if let Some(size) = var_store.Size {
+                                    write!(&mut text, ", Size: 0x{size:X}"
+                                    ).unwrap();
+                                }
+
+                                if let Some(name) = var_store.Name {
+                                    write!(&mut text, ", Name: {}", strings_map.quote(&name)
+                                    ).unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("VarStoreEfi parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x27: VarStoreDevice
+                    uefi_parser::IfrOpcode::VarStoreDevice => {
+                        match uefi_parser::ifr_var_store_device(operation.Data.unwrap_or(&[])) {
+                            Ok((_, var_store)) => {
+                                write!(&mut text, "DevicePath: {}", strings_map.quoted(var_store.DevicePathStringId))
+                                    .unwrap();
+                                // Device path the package list was installed with, if it's included
+                                if let Some(device_path) = resources.device_path_package {
+                                    write!(&mut text, ", PackageListDevicePath: {}", device_path.text).unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("VarStoreDevice parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x2B: Get
+                    uefi_parser::IfrOpcode::Get => {
+                        match uefi_parser::ifr_get(operation.Data.unwrap_or(&[])) {
+                            Ok((_, get)) => {
+                                write!(
+                                    &mut text,
+                                    "VarStoreId: 0x{:X}, {}, VarStoreType: {:?}",
+                                    get.VarStoreId,
+                                    uefi_var_store_info(
+                                        Some(uefi_parser::IfrVarStoreType::from(get.VarStoreType)),
+                                        strings_map,
+                                        get.VarStoreInfo
+                                    ),
+                                    uefi_parser::IfrVarStoreType::from(get.VarStoreType)
+                                )
+                                .unwrap();
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Get parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x2C: Set
+                    uefi_parser::IfrOpcode::Set => {
+                        match uefi_parser::ifr_set(operation.Data.unwrap_or(&[])) {
+                            Ok((_, set)) => {
+                                write!(
+                                    &mut text,
+                                    "VarStoreId: 0x{:X}, {}, VarStoreType: {:?}",
+                                    set.VarStoreId,
+                                    uefi_var_store_info(
+                                        Some(uefi_parser::IfrVarStoreType::from(set.VarStoreType)),
+                                        strings_map,
+                                        set.VarStoreInfo
+                                    ),
+                                    uefi_parser::IfrVarStoreType::from(set.VarStoreType)
+                                )
+                                .unwrap();
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Set parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x51: QuestionRef3
+                    uefi_parser::IfrOpcode::QuestionRef3 => {
+                        if let Some(data) = operation.Data {
+                            match uefi_parser::ifr_question_ref_3(data) {
+                                Ok((_, qr)) => {
+                                    if let Some(x) = qr.DevicePathId {
+                                        write!(
+                                            &mut text,
+                                            "DevicePath: {}",
+                                            strings_map.quoted(x)
+                                        )
+                                        .unwrap();
+                                    }
+                                    if let Some(x) = qr.QuestionGuid {
+                                        if qr.DevicePathId.is_some() {
+                                            write!(&mut text, ", ").unwrap();
+                                        }
+                                        write!(&mut text, "Guid: {x}").unwrap();
+                                    }
+                                }
+                                Err(e) => {
+                                    write!(&mut text, "RawData: {data:02X?}").unwrap();
+                                    diagnostics.warn_at("QuestionRef3 parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                                }
+                            }
+                        }
+                    }
+                    // 0x5A: Value
+                    uefi_parser::IfrOpcode::Value => {
+                        // Value is computed by an expression in its scope
+                        if operation.ScopeStart {
+                            write!(
+                                &mut text,
+                                "Expression: {}",
+                                uefi_expression(&operations[index + 1..], strings_map)
+                            )
+                            .unwrap();
+                        }
+                    }
+                    // 0x5B: Default
+                    uefi_parser::IfrOpcode::Default => {
+                        match uefi_parser::ifr_default(operation.Data.unwrap_or(&[])) {
+                            Ok((_, def)) => {
+                                write!(&mut text, "DefaultId: 0x{:X} ", def.DefaultId).unwrap();
+                                match def.Value {
+                                    uefi_parser::IfrTypeValue::String(x) => {
+                                        write!(
+                                            &mut text,
+                                            "String: {}",
+                                            strings_map.quoted(x)
+                                        )
+                                        .unwrap();
+                                    }
+                                    uefi_parser::IfrTypeValue::Action(x) => {
+                                        write!(
+                                            &mut text,
+                                            "Action: {}",
+                                            strings_map.quoted(x)
+                                        )
+                                        .unwrap();
+                                    }
+                                    uefi_parser::IfrTypeValue::Date(_)
+                                    | uefi_parser::IfrTypeValue::Time(_) => {
+                                        // Show the stored representation as well, skipping DefaultId and Type
+                                        write!(
+                                            &mut text,
+                                            "Value: {}, Buffer: {}",
+                                            def.Value,
+                                            hex_preview(
+                                                &operation.Data.unwrap_or(&[])[3..],
+                                                usize::MAX
+                                            )
+                                        )
+                                        .unwrap();
+                                    }
+                                    _ => {
+                                        write!(&mut text, "Value: {}", uefi_type_value(&def.Value, signed_question.is_some())).unwrap();
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Default parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x5D: FormMap
+                    uefi_parser::IfrOpcode::FormMap => {
+                        match uefi_parser::ifr_form_map(operation.Data.unwrap_or(&[])) {
+                            Ok((_, form_map)) => {
+                                write!(&mut text, "FormId: 0x{:X}", form_map.FormId).unwrap();
+                                for method in form_map.Methods {
+                                    write!(
+                                        &mut text,
+                                        "| Guid: {}, Method: {}",
+                                        method.MethodIdentifier,
+                                        strings_map.quoted(method.MethodTitleId)
+                                    )
+                                    .unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("FormMap parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x5F: GUID
+                    uefi_parser::IfrOpcode::Guid => {
+                        match uefi_parser::ifr_guid(operation.Data.unwrap_or(&[])) {
+                            Ok((_, guid)) => {
+                                // This manual parsing here is ugly and can ultimately be done using nom,
+                                // but it's done already and not that important anyway
+                                // TODO: refactor later
+                                let mut done = false;
+                                match guid.Guid {
+                                    // Registered decoders take precedence over the built-in ones
+                                    _ if options.guid_opcode_decoders.contains(&guid.Guid) => {
+                                        if let Some(decoded) =
+                                            options.guid_opcode_decoders.render(&guid.Guid, guid.Data, strings_map)
+                                        {
+                                            write!(&mut text, "Guid: {}, {}", guid.Guid, decoded).unwrap();
+                                            done = true;
+                                        }
+                                    }
+                                    uefi_parser::IFR_TIANO_GUID => {
+                                        if let Ok((_, edk2)) =
+                                            uefi_parser::ifr_guid_edk2(guid.Data)
+                                        {
+                                            match edk2.ExtendedOpCode {
+                                                uefi_parser::IfrEdk2ExtendOpCode::Banner => {
+                                                    if let Ok((_, banner)) =
+                                                        uefi_parser::ifr_guid_edk2_banner(
+                                                            edk2.Data,
+                                                        )
+                                                    {
+                                                        write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, Title: {}, LineNumber: {}, Alignment: {:?}", 
+                                                            guid.Guid,
+                                                            edk2.ExtendedOpCode,
+                                                            strings_map.quoted(banner.TitleId),
+                                                            banner.LineNumber,
+                                                            banner.Alignment).unwrap();
+                                                        done = true;
+                                                    }
+                                                }
+                                                uefi_parser::IfrEdk2ExtendOpCode::Label => {
+                                                    if edk2.Data.len() == 2 {
+                                                        write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, LabelNumber: 0x{:X}", 
+                                                                guid.Guid,
+                                                                edk2.ExtendedOpCode,
+                                                                edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
+                                                        done = true;
+                                                    }
+                                                }
+                                                uefi_parser::IfrEdk2ExtendOpCode::Timeout => {
+                                                    if edk2.Data.len() == 2 {
+                                                        write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, Timeout: 0x{:X}", 
+                                                                guid.Guid,
+                                                                edk2.ExtendedOpCode,
+                                                                edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
+                                                        done = true;
+                                                    }
+                                                }
+                                                uefi_parser::IfrEdk2ExtendOpCode::Class => {
+                                                    if edk2.Data.len() == 2 {
+                                                        write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, Class: 0x{:X}", 
+                                                                guid.Guid,
+                                                                edk2.ExtendedOpCode,
+                                                                edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
+                                                        done = true;
+                                                    }
+                                                }
+                                                uefi_parser::IfrEdk2ExtendOpCode::SubClass => {
+                                                    if edk2.Data.len() == 2 {
+                                                        write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, SubClass: 0x{:X}", 
+                                                                guid.Guid,
+                                                                edk2.ExtendedOpCode,
+                                                                edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
+                                                        done = true;
+                                                    }
+                                                }
+                                                uefi_parser::IfrEdk2ExtendOpCode::Unknown(_) => {
+                                                }
+                                            }
+                                        }
+                                    }
+                                    uefi_parser::IFR_FRAMEWORK_GUID => {
+                                        if let Ok((_, edk)) =
+                                            uefi_parser::ifr_guid_edk(guid.Data)
+                                        {
+                                            match edk.ExtendedOpCode {
+                                                uefi_parser::IfrEdkExtendOpCode::OptionKey => {
+                                                    write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, QuestionId: 0x{:X}, Data: {:?}", 
+                                                                guid.Guid,
+                                                                edk.ExtendedOpCode,
+                                                                edk.QuestionId,
+                                                                edk.Data).unwrap();
+                                                    done = true;
+                                                }
+                                                uefi_parser::IfrEdkExtendOpCode::VarEqName => {
+                                                    if edk.Data.len() == 2 {
+                                                        let name_id = edk.Data[1] as u16
+                                                            * 100
+                                                            + edk.Data[0] as u16;
+                                                        write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, QuestionId: 0x{:X}, Name: {}", 
+                                                                guid.Guid,
+                                                                edk.ExtendedOpCode,
+                                                                edk.QuestionId,
+                                                                strings_map.quoted(name_id)).unwrap();
+                                                        done = true;
+                                                    }
+                                                }
+                                                uefi_parser::IfrEdkExtendOpCode::Unknown(_) => {}
+                                            }
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                                if !done {
+                                    // No decoder for this Guid opcode, show what we know about it
+                                    write!(&mut text, "Guid: {}", guid.Guid).unwrap();
+                                    if let Some(name) =
+                                        uefi_parser::guid_friendly_name(&guid.Guid)
+                                    {
+                                        write!(&mut text, ", Name: {name}").unwrap();
+                                    }
+                                    write!(
+                                        &mut text,
+                                        ", PayloadLength: 0x{:X}",
+                                        guid.Data.len()
+                                    )
+                                    .unwrap();
+                                    if !guid.Data.is_empty() {
+                                        write!(
+                                            &mut text,
+                                            ", Payload: {}",
+                                            hex_preview(
+                                                guid.Data,
+                                                GUID_PAYLOAD_PREVIEW_LENGTH
+                                            )
+                                        )
+                                        .unwrap();
+                                    }
+                                    if options.nested_ifr > 0 {
+                                        // Payload follows the opcode header and the GUID
+                                        let (nested_text, nested_diagnostics) = render_nested_uefi_ifr(
+                                            guid.Data,
+                                            current_operation_offset + 2 + 16,
+                                            offset_origin,
+                                            line_depth + 1,
+                                            strings_map,
+                                            resources,
+                                            options,
+                                        );
+                                        nested = nested_text;
+                                        diagnostics.merge(nested_diagnostics);
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("Guid parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // 0x63: WarningIf
+                    uefi_parser::IfrOpcode::WarningIf => {
+                        match uefi_parser::ifr_warning_if(operation.Data.unwrap_or(&[])) {
+                            Ok((_, wif)) => {
+                                write!(
+                                    &mut text,
+                                    "Warning: {}, ",
+                                    strings_map.quoted(wif.WarningStringId)
+                                )
+                                .unwrap();
+                                // Timeout is in seconds, zero means the warning stays until dismissed
+                                if wif.Timeout == 0 {
+                                    write!(&mut text, "Timeout: None").unwrap();
+                                } else {
+                                    write!(&mut text, "Timeout: {}s", wif.Timeout).unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at("WarningIf parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                            }
+                        }
+                    }
+                    // Unknown operation
+                    uefi_parser::IfrOpcode::Unknown(x) => {
+                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                            .unwrap();
+                        diagnostics.warn_at(&format!("IFR operation of unknown type 0x{x:X}"), current_operation_offset, format_args!("first at offset 0x{current_operation_offset:X}"));
+                    }
+                    // Everything else is rendered from the opcode table
+                    _ => {
+                        if let Err(e) = opcode_table::render_uefi_operation(operation, strings_map, &mut text) {
+                            write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                .unwrap();
+                            diagnostics.warn_at(&format!("{:?} parse error", operation.OpCode), current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                        }
+                    }
+                }
+                options.indent_wrapped_help(&mut text, description_start, line_depth);
+                output.describe(current_operation_offset, description_start..text.len());
+                current_operation_offset += operation.Length as usize;
+
+                if options.verbose {
+                    write!(&mut text, " {operation}").unwrap();
+                }
+
+                writeln!(&mut text).unwrap();
+                text.push_str(&nested);
+                output.flush(&mut text);
+                rendered += 1;
+            }
+
+            // Padding after the last operation, unless the preview ended before it
+            let trailing_padding = if truncated { &[][..] } else { &padding[next_padding..] };
+            for range in trailing_padding {
+                let offset = form_package.offset + 4 + range.start;
+                diagnostics.warn_at("IFR padding", offset, format_args!("0x{:X} bytes at offset 0x{offset:X}", range.len()));
+                options.write_padding(&mut text, offset - offset_origin, range.len(), scope_depth);
+            }
+        }
+        Err(e) => {
+            // Not even the first operation can be parsed
+            diagnostics.warn_at("IFR operations parse error", form_package.offset + 4, format_args!("{e}"));
+        }
+    }
+
+    (text, diagnostics)
+}
+
+// Number of opcodes of an IFR sequence embedded in a Guid opcode payload, None if the payload is not one.
+// The first opcode of the sequence must open a scope that is closed by its last opcode
+fn nested_uefi_operations(payload: &[u8]) -> Option<usize> {
+    let (rest, operations) = uefi_parser::ifr_operations(payload).ok()?;
+    if !rest.is_empty() || !operations.first()?.ScopeStart {
+        return None;
+    }
+    let mut depth: usize = 0;
+    for (index, operation) in operations.iter().enumerate() {
+        match operation.OpCode {
+            uefi_parser::IfrOpcode::Unknown(_) => return None,
+            uefi_parser::IfrOpcode::End => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+        if operation.ScopeStart {
+            depth += 1;
+        }
+        if depth == 0 && index + 1 < operations.len() {
+            return None;
+        }
+    }
+    if depth == 0 {
+        Some(operations.len())
+    } else {
+        None
+    }
+}
+
+// Render an IFR sequence embedded in a Guid opcode payload one scope deeper than its NestedIfr marker line,
+// nothing if the payload is not one
+fn render_nested_uefi_ifr(
+    payload: &[u8],
+    payload_offset: usize,
+    offset_origin: usize,
+    depth: usize,
+    strings_map: &StringMap,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> (String, Diagnostics) {
+    let mut text = String::new();
+    let count = match nested_uefi_operations(payload) {
+        Some(count) => count,
+        None => return (text, Diagnostics::new()),
+    };
+
+    // Embedded sequence is rendered as the payload of a form package without a header of its own,
+    // with absolute offsets that are rebased below
+    let form_package = FormPackage {
+        offset: payload_offset - 4,
+        length: payload.len() + 4,
+        used_strings: 0,
+        min_string_id: 0,
+        max_string_id: 0,
+        question_count: 0,
+        opcode_count: 0,
+        estimated_text_size: 0,
+        sha256: [0; 32],
+        framework_var_stores: Vec::new(),
+    };
+    let nested_options = ExtractOptions {
+        offset_base: OffsetBase::Absolute,
+        padding: PaddingHandling::Stop,
+        nested_ifr: options.nested_ifr - 1,
+        preview: Preview::Full,
+        ..options.clone()
+    };
+    let (nested, diagnostics) = render_uefi_operations(
+        &form_package,
+        &parse_uefi_operations(payload, PaddingHandling::Stop),
+        None,
+        strings_map,
+        resources,
+        &nested_options,
+        &mut RenderOutput::Keep(&mut Vec::new()),
+    );
+
+    if options.verbose {
+        write!(&mut text, "0x{:08X} 0x{:02X} {:02}: ", payload_offset - offset_origin, payload.len(), depth).unwrap();
+    }
+    options.write_opcode_name(&mut text, depth, &format_args!("NestedIfr"));
+    writeln!(&mut text, "Source: Guid payload, Length: 0x{:X}, Opcodes: {count}", payload.len()).unwrap();
+
+    let indentation = options.indent.as_deref().unwrap_or("\t").repeat(depth + 1);
+    for line in nested.lines() {
+        match line.split_once(": ") {
+            // Offset, length and scope depth columns of verbose mode stay in front of the indentation
+            Some((columns, rest)) if options.verbose && line.starts_with("0x") => {
+                let (offset, other) = columns.split_once(' ').unwrap_or((columns, ""));
+                let offset = usize::from_str_radix(&offset[2..], 16).unwrap_or(offset_origin);
+                writeln!(&mut text, "0x{:08X} {other}: {indentation}{rest}", offset - offset_origin).unwrap();
+            }
+            _ => writeln!(&mut text, "{indentation}{line}").unwrap(),
+        }
+    }
+    (text, diagnostics)
+}
+
+//
+// Framework HII parsing
+//
+pub fn find_framework_packages(data: &[u8]) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    find_framework_packages_with_metrics(data, &mut ScanMetrics::new())
+}
+
+// Same as find_framework_packages, also collecting scan metrics
+pub fn find_framework_packages_with_metrics(
+    data: &[u8],
+    metrics: &mut ScanMetrics,
+) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    find_framework_packages_with_options(data, &ScanOptions::default(), metrics)
+}
+
+// Same as find_framework_packages_with_metrics, only searching a window of the input
+pub fn find_framework_packages_with_options(
+    data: &[u8],
+    options: &ScanOptions,
+    metrics: &mut ScanMetrics,
+) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    scan_framework_packages(data, options, metrics, false)
+}
+
+// Package search, string packages without form packages and vice versa are kept if keep_partial is set
+fn scan_framework_packages(
+    data: &[u8],
+    options: &ScanOptions,
+    metrics: &mut ScanMetrics,
+    keep_partial: bool,
+) -> (Vec<StringPackage>, Vec<FormPackage>) {
+    let mut strings = Vec::new(); // String-to-id maps for all found string packages
+
+    // Search for all string packages in the input file
+    let window = options.window(data.len());
+    let mut i = window.start;
+    let phase_started = Instant::now();
+    while i < window.end {
+        if metrics.report_progress("Framework string packages", i - window.start).is_break() {
+            break;
+        }
+        i = options.next_offset(prefilter::FRAMEWORK_STRING_PACKAGE.next_candidate(data, i));
+        if i >= window.end {
+            break;
+        }
+        if let Ok((_, candidate)) = framework_parser::hii_string_package_candidate(&data[i..]) {
+            let (offset, started, accepted) = (i, Instant::now(), strings.len());
+            if let Ok((_, package)) = framework_parser::hii_package(candidate) {
+                if let Ok((_, string_package)) =
+                    framework_parser::hii_string_package(package.Data.unwrap_or(&[]))
+                {
+                    let mut string_id_map = HashMap::new(); // Map of StringIds to strings
+                    let mut language = String::from("Invalid");
+                    for (current_string_index, string) in string_package.Strings.iter().enumerate() {
+                        // This will always work in a properly formatted string package
+                        if string_package.StringPointers[current_string_index]
+                            == string_package.LanguageNameStringOffset
+                        {
+                            language = string_package.Strings[current_string_index].clone();
+                        }
+
+                        string_id_map.insert(current_string_index as u16, string.clone());
+                    }
+
+                    // Add string
+                    let string = (
+                        i,
+                        candidate.len(),
+                        language,
+                        string_id_map,
+                        sha256(candidate),
+                    );
+                    strings.push(string);
+
+                    i += candidate.len();
+                } else {
+                    i += 1;
+                }
+            } else {
+                i += 1;
+            }
+            metrics.record_package("Framework string", offset, candidate.len(), strings.len() > accepted, started);
+        } else {
+            i += 1;
+        }
+    }
+    metrics.record_phase("Framework string packages", i.min(window.end) - window.start, phase_started);
+    // Partial results of cancelled scans are dropped
+    if metrics.cancelled {
+        return (Vec::new(), Vec::new());
+    }
+
+    // No need to continue if there are no string packages found
+    if strings.is_empty() && !keep_partial {
+        return (Vec::new(), Vec::new());
+    }
+
+    //
+    // Search for all form packages in the input file
+    //
+    let mut forms = Vec::new();
+    i = window.start;
+    let phase_started = Instant::now();
+    while i < window.end {
+        if metrics.report_progress("Framework form packages", i - window.start).is_break() {
+            break;
+        }
+        i = options.next_offset(prefilter::FRAMEWORK_FORM_PACKAGE.next_candidate(data, i));
+        if i >= window.end {
+            break;
+        }
+        if let Ok((_, candidate)) = framework_parser::hii_form_package_candidate(&data[i..]) {
+            let (offset, started, accepted) = (i, Instant::now(), forms.len());
+            if let Ok((_, package)) = framework_parser::hii_package(candidate) {
+                // Parse form package and obtain StringIds, counts and the estimated size of its text
+                let mut string_ids: Vec<u16> = Vec::new();
+                let (mut question_count, mut opcode_count, mut estimated_text_size) = (0, 0, 0);
+                let mut var_stores: Vec<FrameworkVarStore> = Vec::new();
+                if let Ok((_, operations)) = framework_parser::ifr_operations(package.Data.unwrap_or(&[]))
+                {
+                    //let mut current_operation: usize = 0;
+                    for operation in &operations {
+                        //current_operation += 1;
+                        //println!("Operation #{}, OpCode: {:?}, Length 0x{:X}", current_operation, operation.OpCode, operation.Length);
+                        match operation.OpCode {
+                            framework_parser::IfrOpcode::Form => {
+                                if let Ok((_, form)) =
+                                    framework_parser::ifr_form(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(form.TitleStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Subtitle => {
+                                if let Ok((_, subtitile)) =
+                                    framework_parser::ifr_subtitle(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(subtitile.SubtitleStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Text => {
+                                if let Ok((_, text)) =
+                                    framework_parser::ifr_text(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(text.HelpStringId);
+                                    string_ids.push(text.TextStringId);
+                                    string_ids.push(text.TextTwoStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Graphic => {}
+                            framework_parser::IfrOpcode::OneOf => {
+                                if let Ok((_, oneof)) =
+                                    framework_parser::ifr_one_of(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(oneof.PromptStringId);
+                                    string_ids.push(oneof.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::CheckBox => {
+                                if let Ok((_, checkbox)) =
+                                    framework_parser::ifr_check_box(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(checkbox.PromptStringId);
+                                    string_ids.push(checkbox.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Numeric => {
+                                if let Ok((_, numeric)) =
+                                    framework_parser::ifr_numeric(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(numeric.PromptStringId);
+                                    string_ids.push(numeric.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Password => {
+                                if let Ok((_, password)) =
+                                    framework_parser::ifr_password(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(password.PromptStringId);
+                                    string_ids.push(password.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::OneOfOption => {
+                                if let Ok((_, oneofoption)) =
+                                    framework_parser::ifr_one_of_option(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(oneofoption.OptionStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::SuppressIf => {}
+                            framework_parser::IfrOpcode::EndForm => {}
+                            framework_parser::IfrOpcode::Hidden => {}
+                            framework_parser::IfrOpcode::EndFormSet => {}
+                            framework_parser::IfrOpcode::FormSet => {
+                                if let Ok((_, formset)) =
+                                    framework_parser::ifr_form_set(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(formset.TitleStringId);
+                                    string_ids.push(formset.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Ref => {
+                                if let Ok((_, rf)) =
+                                    framework_parser::ifr_ref(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(rf.PromptStringId);
+                                    string_ids.push(rf.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::End => {}
+                            framework_parser::IfrOpcode::InconsistentIf => {
+                                if let Ok((_, incif)) =
+                                    framework_parser::ifr_inconsistent_if(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(incif.PopupStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::EqIdVal => {}
+                            framework_parser::IfrOpcode::EqIdId => {}
+                            framework_parser::IfrOpcode::EqIdList => {}
+                            framework_parser::IfrOpcode::And => {}
+                            framework_parser::IfrOpcode::Or => {}
+                            framework_parser::IfrOpcode::Not => {}
+                            framework_parser::IfrOpcode::EndIf => {}
+                            framework_parser::IfrOpcode::GrayOutIf => {}
+                            framework_parser::IfrOpcode::Date => {
+                                if let Ok((_, date)) =
+                                    framework_parser::ifr_date(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(date.PromptStringId);
+                                    string_ids.push(date.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Time => {
+                                if let Ok((_, time)) =
+                                    framework_parser::ifr_time(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(time.PromptStringId);
+                                    string_ids.push(time.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::String => {
+                                if let Ok((_, str)) =
+                                    framework_parser::ifr_string(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(str.PromptStringId);
+                                    string_ids.push(str.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Label => {}
+                            framework_parser::IfrOpcode::SaveDefaults => {
+                                if let Ok((_, sd)) =
+                                    framework_parser::ifr_save_defaults(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(sd.PromptStringId);
+                                    string_ids.push(sd.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::RestoreDefaults => {
+                                if let Ok((_, rd)) =
+                                    framework_parser::ifr_restore_defaults(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(rd.PromptStringId);
+                                    string_ids.push(rd.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Banner => {
+                                if let Ok((_, banner)) =
+                                    framework_parser::ifr_banner(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(banner.TitleStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::Inventory => {
+                                if let Ok((_, inv)) =
+                                    framework_parser::ifr_inventory(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(inv.HelpStringId);
+                                    string_ids.push(inv.TextStringId);
+                                    string_ids.push(inv.TextTwoStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::EqVarVal => {}
+                            framework_parser::IfrOpcode::OrderedList => {
+                                if let Ok((_, ol)) =
+                                    framework_parser::ifr_ordered_list(operation.Data.unwrap_or(&[]))
+                                {
+                                    string_ids.push(ol.PromptStringId);
+                                    string_ids.push(ol.HelpStringId);
+                                }
+                            }
+                            framework_parser::IfrOpcode::VarStore => {
+                                if let Ok((_, vs)) =
+                                    framework_parser::ifr_var_store(operation.Data.unwrap_or(&[]))
+                                {
+                                    var_stores.push(FrameworkVarStore {
+                                        var_store_id: vs.VarStoreId,
+                                        guid: vs.Guid,
+                                        name: vs.Name,
+                                        size: vs.Size,
+                                    });
+                                }
+                            }
+                            framework_parser::IfrOpcode::VarStoreSelect => {}
+                            framework_parser::IfrOpcode::VarStoreSelectPair => {}
+                            framework_parser::IfrOpcode::True => {}
+                            framework_parser::IfrOpcode::False => {}
+                            framework_parser::IfrOpcode::Greater => {}
+                            framework_parser::IfrOpcode::GreaterEqual => {}
+                            framework_parser::IfrOpcode::OemDefined => {}
+                            framework_parser::IfrOpcode::Oem => {}
+                            framework_parser::IfrOpcode::NvAccessCommand => {}
+                            framework_parser::IfrOpcode::Unknown(_) => {}
+                        }
+                    }
+                    opcode_count = operations.len();
+                    question_count = operations
+                        .iter()
+                        .filter(|operation| {
+                            operation.OpCode != framework_parser::IfrOpcode::End
+                                && operation.OpCode.category() == OpcodeCategory::Question
+                        })
+                        .count();
+                    estimated_text_size =
+                        estimate_text_size(operations.iter().map(|operation| operation.Length), string_ids.len());
+                }
+
+                // Find min and max StringId, and the number of unique ones
+                string_ids.sort();
+                string_ids.dedup();
+                if !string_ids.is_empty() {
+                    // Add the required information to forms
+                    let form = (
+                        i,
+                        candidate.len(),
+                        string_ids.len(),
+                        *string_ids.first().unwrap(),
+                        *string_ids.last().unwrap(),
+                        sha256(candidate),
+                        var_stores,
+                        (question_count, opcode_count, estimated_text_size),
+                    );
+                    forms.push(form);
+                }
+
+                i += candidate.len();
+            } else {
+                i += 1;
+            }
+            metrics.record_package("Framework form", offset, candidate.len(), forms.len() > accepted, started);
+        } else {
+            i += 1;
+        }
+    }
+    metrics.record_phase("Framework form packages", i.min(window.end) - window.start, phase_started);
+    if metrics.cancelled {
+        return (Vec::new(), Vec::new());
+    }
+
+    // No need to continue if no forms are found
+    if forms.is_empty() && !keep_partial {
+        return (Vec::new(), Vec::new());
+    }
+
+    // Construct return value
+    let mut result_strings = Vec::new();
+    let mut result_forms = Vec::new();
+    for string in &strings {
+        result_strings.push(StringPackage {
+            offset: string.0,
+            length: string.1,
+            language: string.2.clone(),
+            string_id_map: string.3.clone(),
+            sha256: string.4,
+        });
+    }
+    for form in forms {
+        result_forms.push(FormPackage {
+            offset: form.0,
+            length: form.1,
+            used_strings: form.2,
+            min_string_id: form.3,
+            max_string_id: form.4,
+            question_count: form.7.0,
+            opcode_count: form.7.1,
+            estimated_text_size: form.7.2,
+            sha256: form.5,
+            framework_var_stores: form.6,
+        });
+    }
+
+    (result_strings, result_forms)
+}
+
+// Maximum number of bytes between two packs of the same Framework pack list.
+// Pack arrays produced by StrGather and VfrCompile are prefixed with their UINT32 length,
+// string pack arrays end with a zeroed EFI_HII_STRING_PACK, and arrays are aligned in the data section
+const FRAMEWORK_PACK_LIST_MAX_GAP: usize = 0x30;
+
+// Offset of the next Framework pack after the current one, skipping array length prefixes, terminators and padding.
+// A pack chained right after the current one may be of any known type, but anything found in a gap
+// has to be a string or form pack the real parsers accept, otherwise stray bytes would join pack lists
+fn framework_next_pack(data: &[u8], offset: usize) -> Option<usize> {
+    let mut current = offset;
+    while current < data.len() && current - offset <= FRAMEWORK_PACK_LIST_MAX_GAP {
+        if let Ok((_, package)) = framework_parser::hii_package(&data[current..]) {
+            let valid = match package.Type {
+                framework_parser::HiiPackageType::Unknown(_) => false,
+                _ if current == offset => true,
+                framework_parser::HiiPackageType::String => {
+                    framework_parser::hii_string_package_candidate(&data[current..]).is_ok()
+                        && framework_parser::hii_string_package(package.Data.unwrap_or(&[])).is_ok()
+                }
+                framework_parser::HiiPackageType::Ifr => {
+                    framework_parser::hii_form_package_candidate(&data[current..]).is_ok()
+                }
+                _ => false,
+            };
+            if valid {
+                return Some(current);
+            }
+        }
+        // Array length prefixes are 4 bytes long and everything else is at least 2-byte aligned
+        current += 2;
+    }
+    None
+}
+
+// Check if two Framework HII packs belong to the same pack list, i.e. were produced for the same driver.
+// Packs are walked from the first one, a handle pack starts a new pack list in exported HII data
+pub fn framework_same_pack_list(data: &[u8], first_offset: usize, second_offset: usize) -> bool {
+    let (mut current, target) = if first_offset <= second_offset {
+        (first_offset, second_offset)
+    } else {
+        (second_offset, first_offset)
+    };
+    while current < target {
+        let package = match framework_parser::hii_package(&data[current..]) {
+            Ok((_, package)) => package,
+            Err(_) => return false,
+        };
+        match framework_next_pack(data, current + package.Length) {
+            Some(next) if next <= target => {
+                if let Ok((_, next_package)) = framework_parser::hii_package(&data[next..]) {
+                    if next_package.Type == framework_parser::HiiPackageType::HandlePack {
+                        return false;
+                    }
+                }
+                current = next;
+            }
+            _ => return false,
+        }
+    }
+    current == target
+}
+
+// Pick string packages in a given language for a Framework form package,
+// preferring the ones from the same pack list and falling back to all of them
+pub fn framework_string_packages<'a>(
+    data: &[u8],
+    strings: &'a [StringPackage],
+    form_package: &FormPackage,
+    language: &str,
+) -> Vec<(usize, &'a StringPackage)> {
+    let in_language = strings
+        .iter()
+        .enumerate()
+        .filter(|(_, string)| string.language == language);
+    let same_pack_list: Vec<(usize, &StringPackage)> = in_language
+        .clone()
+        .filter(|(_, string)| framework_same_pack_list(data, string.offset, form_package.offset))
+        .collect();
+    if same_pack_list.is_empty() {
+        in_language.collect()
+    } else {
+        same_pack_list
+    }
+}
+
+// All Framework string packages in a given language combined into one to extract a form package,
+// see framework_string_packages for the packages used
+pub fn framework_combined_string_package(
+    data: &[u8],
+    strings: &[StringPackage],
+    form_package: &FormPackage,
+    language: &str,
+) -> Option<(Vec<usize>, StringPackage)> {
+    combine_indexed(framework_string_packages(data, strings, form_package, language))
+}
+
+pub fn extract_framework_ifr(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    options: &ExtractOptions,
+) -> String {
+    extract_framework_ifr_with_diagnostics(data, form_package, string_package, options, &mut Diagnostics::new())
+}
+
+// Same as extract_framework_ifr, also collecting diagnostics. Nothing is collected if the result is taken from the cache
+pub fn extract_framework_ifr_with_diagnostics(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    options: &ExtractOptions,
+    diagnostics: &mut Diagnostics,
+) -> String {
+    let mut render = || {
+        let (text, found) = render_framework_ifr(data, form_package, string_package, options, &mut RenderOutput::Keep(&mut Vec::new()));
+        diagnostics.merge(found);
+        options.format_text(text)
+    };
+    match options.cache_dir() {
+        Some(dir) => {
+            let key = extraction_cache::CacheKey::new("Framework", options)
+                .package("Form", form_package.offset, &form_package.sha256)
+                .package("String", string_package.offset, &string_package.sha256);
+            extraction_cache::cached(dir, &key, render)
+        }
+        None => render(),
+    }
+}
+
+// Same as extract_framework_ifr_with_diagnostics, writing the text operation by operation instead of returning it.
+// The cache is not used
+pub fn extract_framework_ifr_to_writer(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    options: &ExtractOptions,
+    writer: &mut impl io::Write,
+) -> io::Result<Diagnostics> {
+    stream_text(writer, options, |output| {
+        render_framework_ifr(data, form_package, string_package, options, output)
+    })
+}
+
+// Same as extract_framework_ifr, but fails if the form package can't be parsed completely
+// or any string in its StringId range was converted with replacement characters. Results are not cached
+pub fn try_extract_framework_ifr(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    options: &ExtractOptions,
+) -> Result<String, IfrError> {
+    // Form package header is 6 bytes long: 32-bit length and 16-bit type
+    let rest = data.get(form_package.offset..).unwrap_or(&[]);
+    let candidate = match framework_parser::hii_form_package_candidate(rest) {
+        Ok((_, candidate)) => candidate,
+        Err(_) => {
+            let header = rest.get(..6).map(|header| {
+                (
+                    u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize,
+                    u16::from_le_bytes([header[4], header[5]]),
+                )
+            });
+            return Err(match header {
+                Some((length, 0x03)) if length > rest.len() => IfrError::Truncated {
+                    location: ErrorLocation::new(data, form_package.offset),
+                    length,
+                    available: rest.len(),
+                },
+                _ => IfrError::NoPackage { location: ErrorLocation::new(data, form_package.offset) },
+            });
+        }
+    };
+    let payload = &candidate[6..];
+    let operations = framework_parser::ifr_operations(payload)
+        .map(|(_, operations)| operations)
+        .unwrap_or_default();
+    let parsed: usize = operations.iter().map(|operation| operation.Length as usize).sum();
+    if parsed < payload.len() {
+        return Err(IfrError::InvalidOpcode { location: ErrorLocation::new(data, form_package.offset + 6 + parsed) });
+    }
+    // Opcodes too short for their type can't be rendered
+    let mut offset = 0;
+    for operation in &operations {
+        if operation.Length < operation.OpCode.minimum_length() {
+            return Err(IfrError::InvalidOpcode { location: ErrorLocation::new(data, form_package.offset + 6 + offset) });
+        }
+        offset += operation.Length as usize;
+    }
+    check_strings(form_package, string_package)?;
+    let options = ExtractOptions { cache: None, ..options.clone() };
+    let mut diagnostics = Diagnostics::new();
+    let text = extract_framework_ifr_with_diagnostics(data, form_package, string_package, &options, &mut diagnostics);
+    match first_parse_error(data, &diagnostics) {
+        Some(location) => Err(IfrError::InvalidOpcode { location }),
+        None => Ok(text),
+    }
+}
+
+fn render_framework_ifr(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    options: &ExtractOptions,
+    output: &mut RenderOutput,
+) -> (String, Diagnostics) {
+    let mut text = String::new();
+    let mut diagnostics = Diagnostics::new();
+    let strings_map = &StringMap::new(&string_package.string_id_map, Vec::new(), options.invalid_id_placeholder(), options.show_string_ids, options.escaping, options.help, &options.string_processors);
+
+    // Add version number and extraction mode
+    writeln!(&mut text, "Program version: {}, Extraction mode: Framework", VERSION.unwrap_or("0.0.0")).unwrap();
+
+    if let Ok((_, candidate)) =
+        framework_parser::hii_form_package_candidate(&data[form_package.offset..])
+    {
+        if let Ok((_, package)) = framework_parser::hii_package(candidate) {
+            // Parse form package and output its structure as human-readable strings
+            output.flush(&mut text);
+            match framework_parser::ifr_operations(package.Data.unwrap_or(&[])) {
+                Ok((_, operations)) => {
+                    let mut scope_depth:usize = 0;
+                    let mut current_operation_offset = form_package.offset + 6; // Header size of Framework HII form package is 6 bytes
+                    let offset_origin = options.offset_base.origin(form_package.offset, 6);
+                    let mut rendered = 0; // Number of opcodes written, for previews
+                    for operation in &operations {
+                        if options.preview.write_truncation(&mut text, rendered, operations.len()) {
+                            break;
+                        }
+
+
+                        // Special case of operations that decrease scope_depth
+                        if operation.OpCode == framework_parser::IfrOpcode::EndFormSet
+                            || operation.OpCode == framework_parser::IfrOpcode::EndForm
+                        {
+                            scope_depth = scope_depth.saturating_sub(1);
+                        }
+
+                        // Skip operations of excluded categories, all Form opcodes are headers
+                        let category = operation.OpCode.category();
+                        if !options.opcode_filter.allows(category)
+                            || (options.preview == Preview::Headers && category != OpcodeCategory::Form)
+                        {
+                            if operation.OpCode == framework_parser::IfrOpcode::FormSet
+                                || operation.OpCode == framework_parser::IfrOpcode::Form
+                            {
+                                scope_depth += 1;
+                            }
+                            current_operation_offset += operation.Length as usize;
+                            continue;
+                        }
+
+                        if options.verbose {
+                            // Fixed-width offset, length and scope depth columns
+                            write!(
+                                &mut text,
+                                "0x{:08X} 0x{:02X} {:02}: ",
+                                current_operation_offset - offset_origin,
+                                operation.Length,
+                                scope_depth
+                            )
+                            .unwrap();
+                        }
+
+                        options.write_opcode_name(&mut text, scope_depth, &operation.OpCode);
+                        let description_start = text.len();
+
+                        match operation.OpCode {
+                            //0x14: EqIdList
+                            framework_parser::IfrOpcode::EqIdList => {
+                                match framework_parser::ifr_eq_id_list(operation.Data.unwrap_or(&[])) {
+                                    Ok((_, eqidlist)) => {
+                                        write!(
+                                            &mut text,
+                                            "QuestionId: 0x{:X}, Width: 0x{:X}, List: {{",
+                                            eqidlist.QuestionId, eqidlist.Width
+                                        )
+                                        .unwrap();
+                                        for item in &eqidlist.List {
+                                            write!(&mut text, " 0x{:X},", *item).unwrap();
+                                        }
+                                        write!(&mut text, " }}").unwrap();
+                                    }
+                                    Err(e) => {
+                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                            .unwrap();
+                                        diagnostics.warn_at("EqIdList parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                                    }
+                                }
+                            }
+                            // 0x07: Numeric
+                            framework_parser::IfrOpcode::Numeric => {
+                                match framework_parser::ifr_numeric(operation.Data.unwrap_or(&[])) {
+                                    Ok((_, num)) => {
+                                        write!(
+                                            &mut text,
+                                            "Prompt: {}{}, QuestionId: 0x{:X}, Width: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}, ",
+                                            strings_map.quoted(num.PromptStringId),
+                                            strings_map.help_field(num.HelpStringId),
+                                            num.QuestionId,
+                                            num.Width,
+                                            num.Flags,
+                                            num.Key
+                                        )
+                                        .unwrap();
+                                        // Framework has no display flags, all values are unsigned
+                                        write_min_max(&mut text, 16, num.Min as u64, num.Max as u64, num.Step as u64, false);
+                                        write!(&mut text, ", Default: {}", numeric_value(num.Default as u64, 16, false)).unwrap();
+                                    }
+                                    Err(e) => {
+                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                            .unwrap();
+                                        diagnostics.warn_at("Numeric parse error", current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                                    }
+                                }
+                            }
+                            //Unknown operation
+                            framework_parser::IfrOpcode::Unknown(x) => {
+                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                    .unwrap();
+                                diagnostics.warn_at(&format!("IFR operation of unknown type 0x{x:X}"), current_operation_offset, format_args!("first at offset 0x{current_operation_offset:X}"));
+                            }
+                            // Everything else is rendered from the opcode table
+                            _ => {
+                                if let Err(e) = opcode_table::render_framework_operation(operation, strings_map, &mut text) {
+                                    write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap_or(&[]))
+                                        .unwrap();
+                                    diagnostics.warn_at(&format!("{:?} parse error", operation.OpCode), current_operation_offset, format_args!("{e:?} at offset 0x{current_operation_offset:X}"));
+                                }
+                            }
+                        }
+
+                        options.indent_wrapped_help(&mut text, description_start, scope_depth);
+
+                        // Special case of operations that increase scope_depth
+                        if operation.OpCode == framework_parser::IfrOpcode::FormSet
+                            || operation.OpCode == framework_parser::IfrOpcode::Form
+                        {
+                            scope_depth += 1;
+                        }
+                        current_operation_offset += operation.Length as usize;
+                        
+                        if options.verbose {
+                            write!(&mut text, " {operation}").unwrap();
+                        }
+
+                        writeln!(&mut text).unwrap();
+                        output.flush(&mut text);
+                        rendered += 1;
+                    }
+                }
+                Err(e) => {
+                    // Not even the first operation can be parsed
+                    diagnostics.warn_at("IFR operations parse error", form_package.offset + 6, format_args!("{e:?}"));
+                }
+            }
+        }
+    }
+
+    diagnostics.resolve_context(data);
+    (text, diagnostics)
+}
+
+// Python module, built by maturin
+#[pymodule]
+fn ifrextractor(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<ifr_document::IfrDocument>()?;
+    m.add("IfrParseError", m.py().get_type_bound::<ifr_document::IfrParseError>())?;
+    m.add_function(wrap_pyfunction!(package_dump::py_dump_packages, m)?)?;
+    m.add_function(wrap_pyfunction!(image_export::py_export_images, m)?)?;
+    m.add_function(wrap_pyfunction!(device_paths::py_device_path_to_text, m)?)?;
+    Ok(())
+}
@@ -1,2076 +1,54 @@
-// Parser
-#[macro_use]
-extern crate nom;
-pub mod framework_parser;
-pub mod uefi_parser;
+extern crate ifrextractor;
 
-// Main
-use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
 use std::ffi::OsString;
-use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::prelude::*;
-use std::io::Write;
 use std::path::Path;
-use std::str;
 
-struct StringPackage {
-    offset: usize,
-    length: usize,
-    language: String,
-    string_id_map: HashMap<u16, String>,
-}
-
-struct FormPackage {
-    offset: usize,
-    length: usize,
-    used_strings: usize,
-    min_string_id: u16,
-    max_string_id: u16,
-}
-
-const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
-
-//
-// UEFI HII parsing
-//
-fn uefi_find_string_and_form_packages(data: &[u8]) -> (Vec<StringPackage>, Vec<FormPackage>) {
-    let mut strings = Vec::new(); // String-to-id maps for all found string packages
-
-    // Search for all string packages in the input file
-    let mut i = 0;
-    while i < data.len() {
-        if let Ok((_, candidate)) = uefi_parser::hii_string_package_candidate(&data[i..]) {
-            if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
-                if let Ok((_, string_package)) =
-                    uefi_parser::hii_string_package(package.Data.unwrap())
-                {
-                    let mut string_id_map = HashMap::new(); // Map of StringIds to strings
-
-                    // Parse SIBT blocks
-                    if let Ok((_, sibt_blocks)) = uefi_parser::hii_sibt_blocks(string_package.Data)
-                    {
-                        string_id_map.insert(0_u16, String::new());
-                        let mut current_string_index = 1;
-                        for block in &sibt_blocks {
-                            match block.Type {
-                                // 0x00: End
-                                uefi_parser::HiiSibtType::End => {}
-                                // 0x10: StringScsu
-                                uefi_parser::HiiSibtType::StringScsu => {
-                                    if let Ok((_, string)) =
-                                        uefi_parser::sibt_string_scsu(block.Data.unwrap())
-                                    {
-                                        string_id_map.insert(current_string_index, string);
-                                        current_string_index += 1;
-                                    }
-                                }
-                                // 0x11: StringScsuFont
-                                uefi_parser::HiiSibtType::StringScsuFont => {
-                                    if let Ok((_, string)) =
-                                        uefi_parser::sibt_string_scsu_font(block.Data.unwrap())
-                                    {
-                                        string_id_map.insert(current_string_index, string);
-                                        current_string_index += 1;
-                                    }
-                                }
-                                // 0x12: StringsScsu
-                                uefi_parser::HiiSibtType::StringsScsu => {
-                                    if let Ok((_, strings)) =
-                                        uefi_parser::sibt_strings_scsu(block.Data.unwrap())
-                                    {
-                                        for string in strings {
-                                            string_id_map.insert(current_string_index, string);
-                                            current_string_index += 1;
-                                        }
-                                    }
-                                }
-                                // 0x13: StringsScsuFont
-                                uefi_parser::HiiSibtType::StringsScsuFont => {
-                                    if let Ok((_, strings)) =
-                                        uefi_parser::sibt_strings_scsu_font(block.Data.unwrap())
-                                    {
-                                        for string in strings {
-                                            string_id_map.insert(current_string_index, string);
-                                            current_string_index += 1;
-                                        }
-                                    }
-                                }
-                                // 0x14: StringUcs2
-                                uefi_parser::HiiSibtType::StringUcs2 => {
-                                    if let Ok((_, string)) =
-                                        uefi_parser::sibt_string_ucs2(block.Data.unwrap())
-                                    {
-                                        string_id_map.insert(current_string_index, string);
-                                        current_string_index += 1;
-                                    }
-                                }
-                                // 0x15: StringUcs2Font
-                                uefi_parser::HiiSibtType::StringUcs2Font => {
-                                    if let Ok((_, string)) =
-                                        uefi_parser::sibt_string_ucs2_font(block.Data.unwrap())
-                                    {
-                                        string_id_map.insert(current_string_index, string);
-                                        current_string_index += 1;
-                                    }
-                                }
-                                // 0x16: StringsUcs2
-                                uefi_parser::HiiSibtType::StringsUcs2 => {
-                                    if let Ok((_, strings)) =
-                                        uefi_parser::sibt_strings_ucs2(block.Data.unwrap())
-                                    {
-                                        for string in strings {
-                                            string_id_map.insert(current_string_index, string);
-                                            current_string_index += 1;
-                                        }
-                                    }
-                                }
-                                // 0x17: StringsUcs2Font
-                                uefi_parser::HiiSibtType::StringsUcs2Font => {
-                                    if let Ok((_, strings)) =
-                                        uefi_parser::sibt_strings_ucs2_font(block.Data.unwrap())
-                                    {
-                                        for string in strings {
-                                            string_id_map.insert(current_string_index, string);
-                                            current_string_index += 1;
-                                        }
-                                    }
-                                }
-                                // 0x20: Duplicate
-                                uefi_parser::HiiSibtType::Duplicate => {
-                                    current_string_index += 1;
-                                }
-                                // 0x21: Skip2
-                                uefi_parser::HiiSibtType::Skip2 => {
-                                    // Manual parsing of Data as u16
-                                    let count = block.Data.unwrap();
-                                    current_string_index +=
-                                        count[0] as u16 + 0x100 * count[1] as u16;
-                                }
-                                // 0x22: Skip1
-                                uefi_parser::HiiSibtType::Skip1 => {
-                                    // Manual parsing of Data as u8
-                                    let count = block.Data.unwrap();
-                                    current_string_index += count[0] as u16;
-                                }
-                                // Blocks below don't have any strings nor can they influence current_string_index
-                                // No need to parse them here
-                                // 0x30: Ext1
-                                uefi_parser::HiiSibtType::Ext1 => {}
-                                // 0x31: Ext2
-                                uefi_parser::HiiSibtType::Ext2 => {}
-                                // 0x32: Ext4
-                                uefi_parser::HiiSibtType::Ext4 => {}
-                                // Unknown SIBT block is impossible, because parsing will fail on it due to it's unknown length
-                                uefi_parser::HiiSibtType::Unknown(_) => {}
-                            }
-                        }
-
-                        // Add string
-                        let string = (i, candidate.len(), string_package.Language, string_id_map);
-                        strings.push(string);
-                    }
-
-                    i += candidate.len();
-                } else {
-                    i += 1;
-                }
-            } else {
-                i += 1;
-            }
-        } else {
-            i += 1;
-        }
-    }
-
-    // No need to continue if there are no string packages found
-    if strings.is_empty() {
-        return (Vec::new(), Vec::new());
-    }
-
-    //
-    // Search for all form packages in the input file
-    //
-    let mut forms = Vec::new();
-    i = 0;
-    while i < data.len() {
-        if let Ok((_, candidate)) = uefi_parser::hii_form_package_candidate(&data[i..]) {
-            if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
-                // Parse form package and obtain StringIds
-                let mut string_ids: Vec<u16> = Vec::new();
-                if let Ok((_, operations)) = uefi_parser::ifr_operations(package.Data.unwrap()) {
-                    //let mut current_operation: usize = 0;
-                    for operation in &operations {
-                        //current_operation += 1;
-                        //println!("Operation #{}, OpCode: {:?}, Length 0x{:X}, ScopeStart: {}", current_operation, operation.OpCode, operation.Length, operation.ScopeStart);
-                        match operation.OpCode {
-                            // 0x01: Form
-                            uefi_parser::IfrOpcode::Form => {
-                                if let Ok((_, form)) =
-                                    uefi_parser::ifr_form(operation.Data.unwrap())
-                                {
-                                    string_ids.push(form.TitleStringId);
-                                }
-                            }
-                            // 0x02: Subtitle
-                            uefi_parser::IfrOpcode::Subtitle => {
-                                if let Ok((_, sub)) =
-                                    uefi_parser::ifr_subtitle(operation.Data.unwrap())
-                                {
-                                    string_ids.push(sub.PromptStringId);
-                                    string_ids.push(sub.HelpStringId);
-                                }
-                            }
-                            // 0x03: Text
-                            uefi_parser::IfrOpcode::Text => {
-                                if let Ok((_, txt)) = uefi_parser::ifr_text(operation.Data.unwrap())
-                                {
-                                    string_ids.push(txt.PromptStringId);
-                                    string_ids.push(txt.HelpStringId);
-                                    string_ids.push(txt.TextId);
-                                }
-                            }
-                            // 0x04: Image
-                            uefi_parser::IfrOpcode::Image => {}
-                            // 0x05: OneOf
-                            uefi_parser::IfrOpcode::OneOf => {
-                                if let Ok((_, onf)) =
-                                    uefi_parser::ifr_one_of(operation.Data.unwrap())
-                                {
-                                    string_ids.push(onf.PromptStringId);
-                                    string_ids.push(onf.HelpStringId);
-                                }
-                            }
-                            // 0x06: CheckBox
-                            uefi_parser::IfrOpcode::CheckBox => {
-                                if let Ok((_, cb)) =
-                                    uefi_parser::ifr_check_box(operation.Data.unwrap())
-                                {
-                                    string_ids.push(cb.PromptStringId);
-                                    string_ids.push(cb.HelpStringId);
-                                }
-                            }
-                            // 0x07: Numeric
-                            uefi_parser::IfrOpcode::Numeric => {
-                                if let Ok((_, num)) =
-                                    uefi_parser::ifr_numeric(operation.Data.unwrap())
-                                {
-                                    string_ids.push(num.PromptStringId);
-                                    string_ids.push(num.HelpStringId);
-                                }
-                            }
-                            // 0x08: Password
-                            uefi_parser::IfrOpcode::Password => {
-                                if let Ok((_, pw)) =
-                                    uefi_parser::ifr_password(operation.Data.unwrap())
-                                {
-                                    string_ids.push(pw.PromptStringId);
-                                    string_ids.push(pw.HelpStringId);
-                                }
-                            }
-                            // 0x09: OneOfOption
-                            uefi_parser::IfrOpcode::OneOfOption => {
-                                if let Ok((_, opt)) =
-                                    uefi_parser::ifr_one_of_option(operation.Data.unwrap())
-                                {
-                                    string_ids.push(opt.OptionStringId);
-                                    match opt.Value {
-                                        uefi_parser::IfrTypeValue::String(x) => {
-                                            string_ids.push(x);
-                                        }
-                                        uefi_parser::IfrTypeValue::Action(x) => {
-                                            string_ids.push(x);
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            // 0x0A: SuppressIf
-                            uefi_parser::IfrOpcode::SuppressIf => {}
-                            // 0x0B: Locked
-                            uefi_parser::IfrOpcode::Locked => {}
-                            // 0x0C: Action
-                            uefi_parser::IfrOpcode::Action => {
-                                if let Ok((_, act)) =
-                                    uefi_parser::ifr_action(operation.Data.unwrap())
-                                {
-                                    string_ids.push(act.PromptStringId);
-                                    string_ids.push(act.HelpStringId);
-                                    if let Some(x) = act.ConfigStringId {
-                                        string_ids.push(x);
-                                    }
-                                }
-                            }
-                            // 0x0D: ResetButton
-                            uefi_parser::IfrOpcode::ResetButton => {
-                                if let Ok((_, rst)) =
-                                    uefi_parser::ifr_reset_button(operation.Data.unwrap())
-                                {
-                                    string_ids.push(rst.PromptStringId);
-                                    string_ids.push(rst.HelpStringId);
-                                }
-                            }
-                            // 0x0E: FormSet
-                            uefi_parser::IfrOpcode::FormSet => {
-                                if let Ok((_, form_set)) =
-                                    uefi_parser::ifr_form_set(operation.Data.unwrap())
-                                {
-                                    string_ids.push(form_set.TitleStringId);
-                                    string_ids.push(form_set.HelpStringId);
-                                }
-                            }
-                            // 0x0F: Ref
-                            uefi_parser::IfrOpcode::Ref => {
-                                if let Ok((_, rf)) = uefi_parser::ifr_ref(operation.Data.unwrap()) {
-                                    string_ids.push(rf.PromptStringId);
-                                    string_ids.push(rf.HelpStringId);
-                                }
-                            }
-                            // 0x10: NoSubmitIf
-                            uefi_parser::IfrOpcode::NoSubmitIf => {
-                                if let Ok((_, ns)) =
-                                    uefi_parser::ifr_no_submit_if(operation.Data.unwrap())
-                                {
-                                    string_ids.push(ns.ErrorStringId);
-                                }
-                            }
-                            // 0x11: InconsistentIf
-                            uefi_parser::IfrOpcode::InconsistentIf => {
-                                if let Ok((_, inc)) =
-                                    uefi_parser::ifr_inconsistent_if(operation.Data.unwrap())
-                                {
-                                    string_ids.push(inc.ErrorStringId);
-                                }
-                            }
-                            // 0x12: EqIdVal
-                            uefi_parser::IfrOpcode::EqIdVal => {}
-                            // 0x13: EqIdId
-                            uefi_parser::IfrOpcode::EqIdId => {}
-                            // 0x14: EqIdValList
-                            uefi_parser::IfrOpcode::EqIdValList => {}
-                            // 0x15: And
-                            uefi_parser::IfrOpcode::And => {}
-                            // 0x16: Or
-                            uefi_parser::IfrOpcode::Or => {}
-                            // 0x17: Not
-                            uefi_parser::IfrOpcode::Not => {}
-                            // 0x18: Rule
-                            uefi_parser::IfrOpcode::Rule => {}
-                            // 0x19: GrayOutIf
-                            uefi_parser::IfrOpcode::GrayOutIf => {}
-                            // 0x1A: Date
-                            uefi_parser::IfrOpcode::Date => {
-                                if let Ok((_, dt)) = uefi_parser::ifr_date(operation.Data.unwrap())
-                                {
-                                    string_ids.push(dt.PromptStringId);
-                                    string_ids.push(dt.HelpStringId);
-                                }
-                            }
-                            // 0x1B: Time
-                            uefi_parser::IfrOpcode::Time => {
-                                if let Ok((_, time)) =
-                                    uefi_parser::ifr_time(operation.Data.unwrap())
-                                {
-                                    string_ids.push(time.PromptStringId);
-                                    string_ids.push(time.HelpStringId);
-                                }
-                            }
-                            // 0x1C: String
-                            uefi_parser::IfrOpcode::String => {
-                                if let Ok((_, st)) =
-                                    uefi_parser::ifr_string(operation.Data.unwrap())
-                                {
-                                    string_ids.push(st.PromptStringId);
-                                    string_ids.push(st.HelpStringId);
-                                }
-                            }
-                            // 0x1D: Refresh
-                            uefi_parser::IfrOpcode::Refresh => {}
-                            // 0x1E: DisableIf
-                            uefi_parser::IfrOpcode::DisableIf => {}
-                            // 0x1F: Animation
-                            uefi_parser::IfrOpcode::Animation => {}
-                            // 0x20: ToLower
-                            uefi_parser::IfrOpcode::ToLower => {}
-                            // 0x21: ToUpper
-                            uefi_parser::IfrOpcode::ToUpper => {}
-                            // 0x22: Map
-                            uefi_parser::IfrOpcode::Map => {}
-                            // 0x23: OrderedList
-                            uefi_parser::IfrOpcode::OrderedList => {
-                                if let Ok((_, ol)) =
-                                    uefi_parser::ifr_ordered_list(operation.Data.unwrap())
-                                {
-                                    string_ids.push(ol.PromptStringId);
-                                    string_ids.push(ol.HelpStringId);
-                                }
-                            }
-                            // 0x24: VarStore
-                            uefi_parser::IfrOpcode::VarStore => {}
-                            // 0x25: VarStoreNameValue
-                            uefi_parser::IfrOpcode::VarStoreNameValue => {}
-                            // 0x26: VarStoreEfi258
-                            uefi_parser::IfrOpcode::VarStoreEfi => {}
-                            // 0x27: VarStoreDevice
-                            uefi_parser::IfrOpcode::VarStoreDevice => {
-                                if let Ok((_, var_store)) =
-                                    uefi_parser::ifr_var_store_device(operation.Data.unwrap())
-                                {
-                                    string_ids.push(var_store.DevicePathStringId);
-                                }
-                            }
-                            // 0x28: Version
-                            uefi_parser::IfrOpcode::Version => {}
-                            // 0x29: End
-                            uefi_parser::IfrOpcode::End => {}
-                            // 0x2A: Match
-                            uefi_parser::IfrOpcode::Match => {}
-                            // 0x2B: Get
-                            uefi_parser::IfrOpcode::Get => {}
-                            // 0x2C: Set
-                            uefi_parser::IfrOpcode::Set => {}
-                            // 0x2D: Read
-                            uefi_parser::IfrOpcode::Read => {}
-                            // 0x2E: Write
-                            uefi_parser::IfrOpcode::Write => {}
-                            // 0x2F: Equal
-                            uefi_parser::IfrOpcode::Equal => {}
-                            // 0x30: NotEqual
-                            uefi_parser::IfrOpcode::NotEqual => {}
-                            // 0x31: GreaterThan
-                            uefi_parser::IfrOpcode::GreaterThan => {}
-                            // 0x32: GreaterEqual
-                            uefi_parser::IfrOpcode::GreaterEqual => {}
-                            // 0x33: LessThan
-                            uefi_parser::IfrOpcode::LessThan => {}
-                            // 0x34: LessEqual
-                            uefi_parser::IfrOpcode::LessEqual => {}
-                            // 0x35: BitwiseAnd
-                            uefi_parser::IfrOpcode::BitwiseAnd => {}
-                            // 0x36: BitwiseOr
-                            uefi_parser::IfrOpcode::BitwiseOr => {}
-                            // 0x37: BitwiseNot
-                            uefi_parser::IfrOpcode::BitwiseNot => {}
-                            // 0x38: ShiftLeft
-                            uefi_parser::IfrOpcode::ShiftLeft => {}
-                            // 0x39: ShiftRight
-                            uefi_parser::IfrOpcode::ShiftRight => {}
-                            // 0x3A: Add
-                            uefi_parser::IfrOpcode::Add => {}
-                            // 0x3B: Substract
-                            uefi_parser::IfrOpcode::Substract => {}
-                            // 0x3C: Multiply
-                            uefi_parser::IfrOpcode::Multiply => {}
-                            // 0x3D: Divide
-                            uefi_parser::IfrOpcode::Divide => {}
-                            // 0x3E: Modulo
-                            uefi_parser::IfrOpcode::Modulo => {}
-                            // 0x3F: RuleRef
-                            uefi_parser::IfrOpcode::RuleRef => {}
-                            // 0x40: QuestionRef1
-                            uefi_parser::IfrOpcode::QuestionRef1 => {}
-                            // 0x41: QuestionRef2
-                            uefi_parser::IfrOpcode::QuestionRef2 => {}
-                            // 0x42: Uint8
-                            uefi_parser::IfrOpcode::Uint8 => {}
-                            // 0x43: Uint16
-                            uefi_parser::IfrOpcode::Uint16 => {}
-                            // 0x44: Uint32
-                            uefi_parser::IfrOpcode::Uint32 => {}
-                            // 0x45: Uint64
-                            uefi_parser::IfrOpcode::Uint64 => {}
-                            // 0x46: True
-                            uefi_parser::IfrOpcode::True => {}
-                            // 0x47: False
-                            uefi_parser::IfrOpcode::False => {}
-                            // 0x48: ToUint
-                            uefi_parser::IfrOpcode::ToUint => {}
-                            // 0x49: ToString
-                            uefi_parser::IfrOpcode::ToString => {}
-                            // 0x4A: ToBoolean
-                            uefi_parser::IfrOpcode::ToBoolean => {}
-                            // 0x4B: Mid
-                            uefi_parser::IfrOpcode::Mid => {}
-                            // 0x4C: Find
-                            uefi_parser::IfrOpcode::Find => {}
-                            // 0x4D: Token
-                            uefi_parser::IfrOpcode::Token => {}
-                            // 0x4E: StringRef1
-                            uefi_parser::IfrOpcode::StringRef1 => {
-                                if let Ok((_, st)) =
-                                    uefi_parser::ifr_string_ref_1(operation.Data.unwrap())
-                                {
-                                    string_ids.push(st.StringId);
-                                }
-                            }
-                            // 0x4F: StringRef2
-                            uefi_parser::IfrOpcode::StringRef2 => {}
-                            // 0x50: Conditional
-                            uefi_parser::IfrOpcode::Conditional => {}
-                            // 0x51: QuestionRef3
-                            uefi_parser::IfrOpcode::QuestionRef3 => {
-                                if operation.Data.is_some() {
-                                    if let Ok((_, qr)) =
-                                        uefi_parser::ifr_question_ref_3(operation.Data.unwrap())
-                                    {
-                                        if let Some(x) = qr.DevicePathId {
-                                            string_ids.push(x);
-                                        }
-                                    }
-                                }
-                            }
-                            // 0x52: Zero
-                            uefi_parser::IfrOpcode::Zero => {}
-                            // 0x53: One
-                            uefi_parser::IfrOpcode::One => {}
-                            // 0x54: Ones
-                            uefi_parser::IfrOpcode::Ones => {}
-                            // 0x55: Undefined
-                            uefi_parser::IfrOpcode::Undefined => {}
-                            // 0x56: Length
-                            uefi_parser::IfrOpcode::Length => {}
-                            // 0x57: Dup
-                            uefi_parser::IfrOpcode::Dup => {}
-                            // 0x58: This
-                            uefi_parser::IfrOpcode::This => {}
-                            // 0x59: Span
-                            uefi_parser::IfrOpcode::Span => {}
-                            // 0x5A: Value
-                            uefi_parser::IfrOpcode::Value => {}
-                            // 0x5B: Default
-                            uefi_parser::IfrOpcode::Default => {
-                                if let Ok((_, def)) =
-                                    uefi_parser::ifr_default(operation.Data.unwrap())
-                                {
-                                    match def.Value {
-                                        uefi_parser::IfrTypeValue::String(x) => {
-                                            string_ids.push(x);
-                                        }
-                                        uefi_parser::IfrTypeValue::Action(x) => {
-                                            string_ids.push(x);
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            // 0x5C: DefaultStore
-                            uefi_parser::IfrOpcode::DefaultStore => {
-                                if let Ok((_, default_store)) =
-                                    uefi_parser::ifr_default_store(operation.Data.unwrap())
-                                {
-                                    string_ids.push(default_store.NameStringId);
-                                }
-                            }
-                            // 0x5D: FormMap
-                            uefi_parser::IfrOpcode::FormMap => {
-                                if let Ok((_, form_map)) =
-                                    uefi_parser::ifr_form_map(operation.Data.unwrap())
-                                {
-                                    for method in form_map.Methods {
-                                        string_ids.push(method.MethodTitleId);
-                                    }
-                                }
-                            }
-                            // 0x5E: Catenate
-                            uefi_parser::IfrOpcode::Catenate => {}
-                            // 0x5F: GUID
-                            uefi_parser::IfrOpcode::Guid => {
-                                if let Ok((_, guid)) =
-                                    uefi_parser::ifr_guid(operation.Data.unwrap())
-                                {
-                                    // This manual parsing here is ugly and can ultimately be done using nom,
-                                    // but it's done already and not that important anyway
-                                    match guid.Guid {
-                                        uefi_parser::IFR_TIANO_GUID => {
-                                            if let Ok((_, edk2)) =
-                                                uefi_parser::ifr_guid_edk2(guid.Data)
-                                            {
-                                                match edk2.ExtendedOpCode {
-                                                    uefi_parser::IfrEdk2ExtendOpCode::Banner => {
-                                                        if let Ok((_, banner)) =
-                                                            uefi_parser::ifr_guid_edk2_banner(
-                                                                edk2.Data,
-                                                            )
-                                                        {
-                                                            string_ids.push(banner.TitleId);
-                                                        }
-                                                    }
-                                                    uefi_parser::IfrEdk2ExtendOpCode::Label => {}
-                                                    uefi_parser::IfrEdk2ExtendOpCode::Timeout => {}
-                                                    uefi_parser::IfrEdk2ExtendOpCode::Class => {}
-                                                    uefi_parser::IfrEdk2ExtendOpCode::SubClass => {}
-                                                    uefi_parser::IfrEdk2ExtendOpCode::Unknown(
-                                                        _,
-                                                    ) => {}
-                                                }
-                                            }
-                                        }
-                                        uefi_parser::IFR_FRAMEWORK_GUID => {
-                                            if let Ok((_, edk)) =
-                                                uefi_parser::ifr_guid_edk(guid.Data)
-                                            {
-                                                match edk.ExtendedOpCode {
-                                                    uefi_parser::IfrEdkExtendOpCode::OptionKey => {}
-                                                    uefi_parser::IfrEdkExtendOpCode::VarEqName => {
-                                                        if edk.Data.len() == 2 {
-                                                            let name_id = edk.Data[1] as u16 * 100
-                                                                + edk.Data[0] as u16;
-                                                            string_ids.push(name_id);
-                                                        }
-                                                    }
-                                                    uefi_parser::IfrEdkExtendOpCode::Unknown(_) => {
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            // 0x60: Security
-                            uefi_parser::IfrOpcode::Security => {}
-                            // 0x61: ModalTag
-                            uefi_parser::IfrOpcode::ModalTag => {}
-                            // 0x62: RefreshId
-                            uefi_parser::IfrOpcode::RefreshId => {}
-                            // 0x63: WarningIf
-                            uefi_parser::IfrOpcode::WarningIf => {
-                                if let Ok((_, warn)) =
-                                    uefi_parser::ifr_warning_if(operation.Data.unwrap())
-                                {
-                                    string_ids.push(warn.WarningStringId);
-                                }
-                            }
-                            // 0x64: Match2
-                            uefi_parser::IfrOpcode::Match2 => {}
-                            // Unknown operation
-                            uefi_parser::IfrOpcode::Unknown(_) => {}
-                        }
-                    }
-                }
-
-                // Find min and max StringId, and the number of unique ones
-                string_ids.sort();
-                string_ids.dedup();
-                if !string_ids.is_empty() {
-                    // Add the required information to forms
-                    let form = (
-                        i,
-                        candidate.len(),
-                        string_ids.len(),
-                        *string_ids.first().unwrap(),
-                        *string_ids.last().unwrap(),
-                    );
-                    forms.push(form);
-                }
-
-                i += candidate.len();
-            } else {
-                i += 1;
-            }
-        } else {
-            i += 1;
-        }
-    }
-
-    // No need to continue if no forms are found
-    if forms.is_empty() {
-        return (Vec::new(), Vec::new());
-    }
-
-    // Construct return value
-    let mut result_strings = Vec::new();
-    let mut result_forms = Vec::new();
-    for string in &strings {
-        result_strings.push(StringPackage {
-            offset: string.0,
-            length: string.1,
-            language: string.2.clone(),
-            string_id_map: string.3.clone(),
-        });
-    }
-    for form in &forms {
-        result_forms.push(FormPackage {
-            offset: form.0,
-            length: form.1,
-            used_strings: form.2,
-            min_string_id: form.3,
-            max_string_id: form.4,
-        });
-    }
-
-    (result_strings, result_forms)
-}
+use ifrextractor::borrowed_strings::{borrowed_string_package, stripped_uefi_forms};
+use ifrextractor::device_paths::{device_path_to_text, find_uefi_device_path_packages};
+use ifrextractor::diagnostics::Diagnostics;
+use ifrextractor::form_graph::export_form_graph_dot;
+use ifrextractor::default_stores::default_stores;
+use ifrextractor::fonts::{find_uefi_font_packages, find_uefi_simple_font_packages, font_style_names, uefi_string_fonts};
+use ifrextractor::fingerprint::{uefi_form_fingerprint, FingerprintOptions};
+use ifrextractor::ifr_tree::parse_uefi_ifr;
+use ifrextractor::image_export::{export_images, ImageFormat};
+use ifrextractor::formset_inventory::{framework_formset_inventory, uefi_formset_inventory};
+use ifrextractor::fv::{
+    decompress_sections, find_firmware_volumes, section_path, uefi_package_origins, uefi_package_origins_with_decompression,
+};
+use ifrextractor::guid_packages::find_uefi_guid_packages;
+use ifrextractor::hidden_menus::{hidden_items_json, hidden_items_text, uefi_hidden_report};
+use ifrextractor::normalization::{normalize_uefi_form_packages, report_repairs};
+use ifrextractor::nvram_comparison::{compare_nvram_defaults, nvram_variables};
+use ifrextractor::package_dump::dump_packages;
+use ifrextractor::package_lists::uefi_package_lists;
+use ifrextractor::uefi_parser::HiiPackageType;
+use ifrextractor::provenance::Provenance;
+use ifrextractor::question_csv::export_questions_csv;
+use ifrextractor::knob_map::knob_map;
+use ifrextractor::mapped_file::MappedInput;
+use ifrextractor::question_defaults::question_defaults;
+use ifrextractor::scan_metrics::ScanMetrics;
+use ifrextractor::setup_var::setup_var_commands;
+use ifrextractor::string_diff::diff_strings;
+use ifrextractor::question_ids::uefi_question_id_report;
+use ifrextractor::validation::validate_uefi_form_package;
+use ifrextractor::variable_dependencies::{uefi_variable_dependencies, VariableReference};
+use ifrextractor::*;
 
-fn uefi_ifr_extract(
+// Write the extraction result next to the input file
+fn write_output(
     path: &OsStr,
-    data: &[u8],
-    form_package: &FormPackage,
+    provenance: &Provenance,
     form_package_index: usize,
     string_package: &StringPackage,
     string_package_index: usize,
-    verbose_mode: bool,
+    text: &str,
 ) {
-    let mut text = Vec::new();
-    let strings_map = &string_package.string_id_map;
-
-    // Add version number and extraction mode
-    writeln!(&mut text, "Program version: {}, Extraction mode: UEFI", VERSION.unwrap_or("0.0.0")).unwrap();
-
-    if let Ok((_, candidate)) =
-        uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
-    {
-        if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
-            // Parse form package and output its structure as human-readable strings
-            match uefi_parser::ifr_operations(package.Data.unwrap()) {
-                Ok((_, operations)) => {
-                    let mut scope_depth: usize = 0;
-                    let mut current_operation_offset = form_package.offset + 4; // Header size of UEFI HII form package is 4 bytes
-                    for operation in &operations {
-                        if operation.OpCode == uefi_parser::IfrOpcode::End && scope_depth >= 1 {
-                            scope_depth -= 1;
-                        }
-                        
-                        if verbose_mode {
-                            write!(
-                                &mut text,
-                                "0x{current_operation_offset:X}: "
-                            )
-                            .unwrap();
-                        }
-
-                        write!(
-                            &mut text,
-                            "{:\t<1$}{2:?} ",
-                            "", scope_depth, operation.OpCode
-                        )
-                        .unwrap();
-
-                        if operation.ScopeStart {
-                            scope_depth += 1;
-                        }
-
-                        match operation.OpCode {
-                            // 0x01: Form
-                            uefi_parser::IfrOpcode::Form => {
-                                match uefi_parser::ifr_form(operation.Data.unwrap()) {
-                                    Ok((_, form)) => {
-                                        write!(
-                                            &mut text,
-                                            "FormId: 0x{:X}, Title: \"{}\"",
-                                            form.FormId,
-                                            strings_map
-                                                .get(&form.TitleStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Form parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x02: Subtitle
-                            uefi_parser::IfrOpcode::Subtitle => {
-                                match uefi_parser::ifr_subtitle(operation.Data.unwrap()) {
-                                    Ok((_, sub)) => {
-                                        write!(
-                                            &mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", Flags: 0x{:X}",
-                                            strings_map
-                                                .get(&sub.PromptStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&sub.HelpStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            sub.Flags
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Subtitle parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x03: Text
-                            uefi_parser::IfrOpcode::Text => {
-                                match uefi_parser::ifr_text(operation.Data.unwrap()) {
-                                    Ok((_, txt)) => {
-                                        write!(
-                                            &mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", Text: \"{}\"",
-                                            strings_map
-                                                .get(&txt.PromptStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&txt.HelpStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&txt.TextId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Text parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x04: Image
-                            uefi_parser::IfrOpcode::Image => {
-                                match uefi_parser::ifr_image(operation.Data.unwrap()) {
-                                    Ok((_, image)) => {
-                                        write!(&mut text, "ImageId: 0x{:X}", image.ImageId).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Image parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x05: OneOf
-                            uefi_parser::IfrOpcode::OneOf => {
-                                match uefi_parser::ifr_one_of(operation.Data.unwrap()) {
-                                    Ok((_, onf)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarOffset: 0x{:X}, Flags: 0x{:X}, ", 
-                                                strings_map.get(&onf.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&onf.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                onf.QuestionFlags,
-                                                onf.QuestionId,
-                                                onf.VarStoreId,
-                                                onf.VarStoreInfo,
-                                                onf.Flags).unwrap();
-                                        if onf.MinMaxStepData8[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 8, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                onf.MinMaxStepData8[0].unwrap(),
-                                                onf.MinMaxStepData8[1].unwrap(),
-                                                onf.MinMaxStepData8[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                        if onf.MinMaxStepData16[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 16, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                onf.MinMaxStepData16[0].unwrap(),
-                                                onf.MinMaxStepData16[1].unwrap(),
-                                                onf.MinMaxStepData16[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                        if onf.MinMaxStepData32[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 32, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                onf.MinMaxStepData32[0].unwrap(),
-                                                onf.MinMaxStepData32[1].unwrap(),
-                                                onf.MinMaxStepData32[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                        if onf.MinMaxStepData64[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 64, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                onf.MinMaxStepData64[0].unwrap(),
-                                                onf.MinMaxStepData64[1].unwrap(),
-                                                onf.MinMaxStepData64[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("OneOf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x06: CheckBox
-                            uefi_parser::IfrOpcode::CheckBox => {
-                                match uefi_parser::ifr_check_box(operation.Data.unwrap()) {
-                                    Ok((_, cb)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarOffset: 0x{:X}, Flags: 0x{:X}", 
-                                                strings_map.get(&cb.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&cb.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                cb.QuestionFlags,
-                                                cb.QuestionId,
-                                                cb.VarStoreId,
-                                                cb.VarStoreInfo,
-                                                cb.Flags).unwrap();
-
-                                        if cb.Flags & (uefi_parser::IfrCheckBoxDefaultFlags::Default as u8) > 0 {
-                                            write!(&mut text, ", Default: Enabled").unwrap();
-                                        }
-                                        else {
-                                            write!(&mut text, ", Default: Disabled").unwrap();
-                                        }
-                                        if cb.Flags & (uefi_parser::IfrCheckBoxDefaultFlags::MfgDefault as u8) > 0 {
-                                            write!(&mut text, ", MfgDefault: Enabled").unwrap();
-                                        }
-                                        else {
-                                            write!(&mut text, ", MfgDefault: Disabled").unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("CheckBox parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x07: Numeric
-                            uefi_parser::IfrOpcode::Numeric => {
-                                match uefi_parser::ifr_numeric(operation.Data.unwrap()) {
-                                    Ok((_, num)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarOffset: 0x{:X}, Flags: 0x{:X}, ", 
-                                                strings_map.get(&num.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&num.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                num.QuestionFlags,
-                                                num.QuestionId,
-                                                num.VarStoreId,
-                                                num.VarStoreInfo,
-                                                num.Flags).unwrap();
-                                        if num.MinMaxStepData8[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 8, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                num.MinMaxStepData8[0].unwrap(),
-                                                num.MinMaxStepData8[1].unwrap(),
-                                                num.MinMaxStepData8[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                        if num.MinMaxStepData16[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 16, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                num.MinMaxStepData16[0].unwrap(),
-                                                num.MinMaxStepData16[1].unwrap(),
-                                                num.MinMaxStepData16[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                        if num.MinMaxStepData32[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 32, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                num.MinMaxStepData32[0].unwrap(),
-                                                num.MinMaxStepData32[1].unwrap(),
-                                                num.MinMaxStepData32[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                        if num.MinMaxStepData64[0].is_some() {
-                                            write!(
-                                                &mut text,
-                                                "Size: 64, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}",
-                                                num.MinMaxStepData64[0].unwrap(),
-                                                num.MinMaxStepData64[1].unwrap(),
-                                                num.MinMaxStepData64[2].unwrap()
-                                            )
-                                            .unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Numeric parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x08: Password
-                            uefi_parser::IfrOpcode::Password => {
-                                match uefi_parser::ifr_password(operation.Data.unwrap()) {
-                                    Ok((_, pw)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}, MinSize: 0x{:X}, MaxSize: 0x{:X}", 
-                                                strings_map.get(&pw.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&pw.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                pw.QuestionFlags,
-                                                pw.QuestionId,
-                                                pw.VarStoreId,
-                                                pw.VarStoreInfo,
-                                                pw.MinSize,
-                                                pw.MaxSize).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Password parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x09: OneOfOption
-                            uefi_parser::IfrOpcode::OneOfOption => {
-                                match uefi_parser::ifr_one_of_option(operation.Data.unwrap()) {
-                                    Ok((_, opt)) => {
-                                        write!(
-                                            &mut text,
-                                            "Option: \"{}\" ",
-                                            strings_map
-                                                .get(&opt.OptionStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                        match opt.Value {
-                                            uefi_parser::IfrTypeValue::String(x) => {
-                                                write!(
-                                                    &mut text,
-                                                    "String: \"{}\"",
-                                                    strings_map
-                                                        .get(&x)
-                                                        .unwrap_or(&String::from("InvalidId"))
-                                                )
-                                                .unwrap();
-                                            }
-                                            uefi_parser::IfrTypeValue::Action(x) => {
-                                                write!(
-                                                    &mut text,
-                                                    "Action: \"{}\"",
-                                                    strings_map
-                                                        .get(&x)
-                                                        .unwrap_or(&String::from("InvalidId"))
-                                                )
-                                                .unwrap();
-                                            }
-                                            _ => {
-                                                write!(&mut text, "Value: {}", opt.Value).unwrap();
-                                            }
-                                        }
-
-                                        if opt.Flags & (uefi_parser::IfrOneOfOptionDefaultFlags::Default as u8) > 0  {
-                                            write!(&mut text, ", Default").unwrap();
-                                        }
-                                        if opt.Flags & (uefi_parser::IfrOneOfOptionDefaultFlags::MfgDefault as u8) > 0 {
-                                            write!(&mut text, ", MfgDefault").unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("OneOfOption parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x0A: SuppressIf
-                            uefi_parser::IfrOpcode::SuppressIf => {}
-                            // 0x0B: Locked
-                            uefi_parser::IfrOpcode::Locked => {}
-                            // 0x0C: Action
-                            uefi_parser::IfrOpcode::Action => {
-                                match uefi_parser::ifr_action(operation.Data.unwrap()) {
-                                    Ok((_, act)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}", 
-                                                strings_map.get(&act.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&act.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                act.QuestionFlags,
-                                                act.QuestionId,
-                                                act.VarStoreId,
-                                                act.VarStoreInfo).unwrap();
-                                        if let Some(x) = act.ConfigStringId {
-                                            write!(
-                                                &mut text,
-                                                ", QuestionConfig: \"{}\"",
-                                                strings_map
-                                                    .get(&x)
-                                                    .unwrap_or(&String::from("InvalidId"))
-                                            )
-                                            .unwrap();
-                                        }
-
-
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Action parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x0D: ResetButton
-                            uefi_parser::IfrOpcode::ResetButton => {
-                                match uefi_parser::ifr_reset_button(operation.Data.unwrap()) {
-                                    Ok((_, rst)) => {
-                                        write!(
-                                            &mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", DefaultId: 0x{:X}",
-                                            strings_map
-                                                .get(&rst.PromptStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&rst.HelpStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            rst.DefaultId
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("ResetButton parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x0E: FormSet
-                            uefi_parser::IfrOpcode::FormSet => {
-                                match uefi_parser::ifr_form_set(operation.Data.unwrap()) {
-                                    Ok((_, form_set)) => {
-                                        write!(
-                                            &mut text,
-                                            "Guid: {}, Title: \"{}\", Help: \"{}\"",
-                                            form_set.Guid,
-                                            strings_map
-                                                .get(&form_set.TitleStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&form_set.HelpStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("FormSet parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x0F: Ref
-                            uefi_parser::IfrOpcode::Ref => {
-                                match uefi_parser::ifr_ref(operation.Data.unwrap()) {
-                                    Ok((_, rf)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}", 
-                                                strings_map.get(&rf.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&rf.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                rf.QuestionFlags,
-                                                rf.QuestionId,
-                                                rf.VarStoreId,
-                                                rf.VarStoreInfo).unwrap();
-                                        if let Some(x) = rf.FormId {
-                                            write!(&mut text, ", FormId: 0x{x:X}").unwrap();
-                                        }
-                                        if let Some(x) = rf.RefQuestionId {
-                                            write!(&mut text, ", RefQuestionId: 0x{x:X}").unwrap();
-                                        }
-                                        if let Some(x) = rf.FormSetGuid {
-                                            write!(&mut text, ", FormSetGuid: {x}").unwrap();
-                                        }
-                                        if let Some(x) = rf.DevicePathId {
-                                            write!(&mut text, ", DevicePathId: 0x{x:X}").unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Ref parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x10: NoSubmitIf
-                            uefi_parser::IfrOpcode::NoSubmitIf => {
-                                match uefi_parser::ifr_no_submit_if(operation.Data.unwrap()) {
-                                    Ok((_, ns)) => {
-                                        write!(
-                                            &mut text,
-                                            "Error: \"{}\"",
-                                            strings_map
-                                                .get(&ns.ErrorStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("NoSubmitIf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x11: InconsistentIf
-                            uefi_parser::IfrOpcode::InconsistentIf => {
-                                match uefi_parser::ifr_inconsistent_if(operation.Data.unwrap()) {
-                                    Ok((_, inc)) => {
-                                        write!(
-                                            &mut text,
-                                            "Error: \"{}\"",
-                                            strings_map
-                                                .get(&inc.ErrorStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("InconsistentIf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x12: EqIdVal
-                            uefi_parser::IfrOpcode::EqIdVal => {
-                                match uefi_parser::ifr_eq_id_val(operation.Data.unwrap()) {
-                                    Ok((_, eq)) => {
-                                        write!(
-                                            &mut text,
-                                            "QuestionId: 0x{:X}, Value: 0x{:X}",
-                                            eq.QuestionId, eq.Value
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!(" EqIdVal parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x13: EqIdId
-                            uefi_parser::IfrOpcode::EqIdId => {
-                                match uefi_parser::ifr_eq_id_id(operation.Data.unwrap()) {
-                                    Ok((_, eq)) => {
-                                        write!(
-                                            &mut text,
-                                            "QuestionId: 0x{:X}, OtherQuestionId: 0x{:X}",
-                                            eq.QuestionId, eq.OtherQuestionId
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("EqIdId parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x14: EqIdValList
-                            uefi_parser::IfrOpcode::EqIdValList => {
-                                match uefi_parser::ifr_eq_id_val_list(operation.Data.unwrap()) {
-                                    Ok((_, eql)) => {
-                                        write!(
-                                            &mut text,
-                                            "QuestionId: 0x{:X}, Values: {:?}",
-                                            eql.QuestionId, eql.Values
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("EqIdValList parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x15: And
-                            uefi_parser::IfrOpcode::And => {}
-                            // 0x16: Or
-                            uefi_parser::IfrOpcode::Or => {}
-                            // 0x17: Not
-                            uefi_parser::IfrOpcode::Not => {}
-                            // 0x18: Rule
-                            uefi_parser::IfrOpcode::Rule => {
-                                match uefi_parser::ifr_rule(operation.Data.unwrap()) {
-                                    Ok((_, rule)) => {
-                                        write!(&mut text, "RuleId: 0x{:X}", rule.RuleId).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Rule parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x19: GrayOutIf
-                            uefi_parser::IfrOpcode::GrayOutIf => {}
-                            // 0x1A: Date
-                            uefi_parser::IfrOpcode::Date => {
-                                match uefi_parser::ifr_date(operation.Data.unwrap()) {
-                                    Ok((_, dt)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}, Flags: 0x{:X}", 
-                                                strings_map.get(&dt.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&dt.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                dt.QuestionFlags,
-                                                dt.QuestionId,
-                                                dt.VarStoreId,
-                                                dt.VarStoreInfo,
-                                                dt.Flags).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Date parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x1B: Time
-                            uefi_parser::IfrOpcode::Time => {
-                                match uefi_parser::ifr_time(operation.Data.unwrap()) {
-                                    Ok((_, time)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}, Flags: 0x{:X}", 
-                                                strings_map.get(&time.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&time.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                time.QuestionFlags,
-                                                time.QuestionId,
-                                                time.VarStoreId,
-                                                time.VarStoreInfo,
-                                                time.Flags).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Time parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x1C: String
-                            uefi_parser::IfrOpcode::String => {
-                                match uefi_parser::ifr_string(operation.Data.unwrap()) {
-                                    Ok((_, st)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}, MinSize: 0x{:X}, MaxSize: 0x{:X}, Flags: 0x{:X}", 
-                                                strings_map.get(&st.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&st.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                st.QuestionFlags,
-                                                st.QuestionId,
-                                                st.VarStoreId,
-                                                st.VarStoreInfo,
-                                                st.MinSize,
-                                                st.MaxSize,
-                                                st.Flags).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("String parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x1D: Refresh
-                            uefi_parser::IfrOpcode::Refresh => {
-                                match uefi_parser::ifr_refresh(operation.Data.unwrap()) {
-                                    Ok((_, refr)) => {
-                                        write!(
-                                            &mut text,
-                                            "RefreshInterval: 0x{:X}",
-                                            refr.RefreshInterval
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Refresh parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x1E: DisableIf
-                            uefi_parser::IfrOpcode::DisableIf => {}
-                            // 0x1F: Animation
-                            uefi_parser::IfrOpcode::Animation => {
-                                match uefi_parser::ifr_animation(operation.Data.unwrap()) {
-                                    Ok((_, anim)) => {
-                                        write!(&mut text, "AnimationId: 0x{:X}", anim.AnimationId)
-                                            .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Animation parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x20: ToLower
-                            uefi_parser::IfrOpcode::ToLower => {}
-                            // 0x21: ToUpper
-                            uefi_parser::IfrOpcode::ToUpper => {}
-                            // 0x22: Map
-                            uefi_parser::IfrOpcode::Map => {}
-                            // 0x23: OrderedList
-                            uefi_parser::IfrOpcode::OrderedList => {
-                                match uefi_parser::ifr_ordered_list(operation.Data.unwrap()) {
-                                    Ok((_, ol)) => {
-                                        write!(&mut text, "Prompt: \"{}\", Help: \"{}\", QuestionFlags: 0x{:X}, QuestionId: 0x{:X}, VarStoreId: 0x{:X}, VarOffset: 0x{:X}, MaxContainers: 0x{:X}, Flags: 0x{:X}", 
-                                                strings_map.get(&ol.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                                strings_map.get(&ol.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                                ol.QuestionFlags,
-                                                ol.QuestionId,
-                                                ol.VarStoreId,
-                                                ol.VarStoreInfo,
-                                                ol.MaxContainers,
-                                                ol.Flags).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("OrderedList parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x24: VarStore
-                            uefi_parser::IfrOpcode::VarStore => {
-                                match uefi_parser::ifr_var_store(operation.Data.unwrap()) {
-                                    Ok((_, var_store)) => {
-                                        write!(
-                                            &mut text,
-                                            "Guid: {}, VarStoreId: 0x{:X}, Size: 0x{:X}, Name: \"{}\"",
-                                            var_store.Guid,
-                                            var_store.VarStoreId,
-                                            var_store.Size,
-                                            var_store.Name
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("VarStore parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x25: VarStoreNameValue
-                            uefi_parser::IfrOpcode::VarStoreNameValue => {
-                                match uefi_parser::ifr_var_store_name_value(operation.Data.unwrap())
-                                {
-                                    Ok((_, var_store)) => {
-                                        write!(
-                                            &mut text,
-                                            "Guid: {}, VarStoreId: 0x{:X}",
-                                            var_store.Guid, var_store.VarStoreId
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("VarStoreNameValue parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x26: VarStoreEfi
-                            uefi_parser::IfrOpcode::VarStoreEfi => {
-                                match uefi_parser::ifr_var_store_efi(operation.Data.unwrap()) {
-                                    Ok((_, var_store)) => {
-                                        write!(&mut text, "Guid: {}, VarStoreId: 0x{:X}, Attributes: 0x{:X}", 
-                                                var_store.Guid,
-                                                var_store.VarStoreId,
-                                                var_store.Attributes
-                                        ).unwrap();
-
-                                        if let Some(size) = var_store.Size {
-                                            write!(&mut text, ", Size: 0x{size:X}"
-                                            ).unwrap();
-                                        }
-
-                                        if let Some(name) = var_store.Name {
-                                            write!(&mut text, ", Name: \"{name}\""
-                                            ).unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("VarStoreEfi parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x27: VarStoreDevice
-                            uefi_parser::IfrOpcode::VarStoreDevice => {
-                                match uefi_parser::ifr_var_store_device(operation.Data.unwrap()) {
-                                    Ok((_, var_store)) => {
-                                        write!(
-                                            &mut text,
-                                            "DevicePath: \"{}\"",
-                                            strings_map
-                                                .get(&var_store.DevicePathStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("VarStoreDevice parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x28: Version
-                            uefi_parser::IfrOpcode::Version => {}
-                            // 0x29: End
-                            uefi_parser::IfrOpcode::End => {}
-                            // 0x2A: Match
-                            uefi_parser::IfrOpcode::Match => {}
-                            // 0x2B: Get
-                            uefi_parser::IfrOpcode::Get => {
-                                match uefi_parser::ifr_get(operation.Data.unwrap()) {
-                                    Ok((_, get)) => {
-                                        write!(
-                                            &mut text,
-                                            "VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}, VarStoreType: 0x{:X}",
-                                            get.VarStoreId, get.VarStoreInfo, get.VarStoreType
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Get parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x2C: Set
-                            uefi_parser::IfrOpcode::Set => {
-                                match uefi_parser::ifr_set(operation.Data.unwrap()) {
-                                    Ok((_, set)) => {
-                                        write!(
-                                            &mut text,
-                                            "VarStoreId: 0x{:X}, VarStoreInfo: 0x{:X}, VarStoreType: 0x{:X}",
-                                            set.VarStoreId, set.VarStoreInfo, set.VarStoreType
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Set parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x2D: Read
-                            uefi_parser::IfrOpcode::Read => {}
-                            // 0x2E: Write
-                            uefi_parser::IfrOpcode::Write => {}
-                            // 0x2F: Equal
-                            uefi_parser::IfrOpcode::Equal => {}
-                            // 0x30: NotEqual
-                            uefi_parser::IfrOpcode::NotEqual => {}
-                            // 0x31: GreaterThan
-                            uefi_parser::IfrOpcode::GreaterThan => {}
-                            // 0x32: GreaterEqual
-                            uefi_parser::IfrOpcode::GreaterEqual => {}
-                            // 0x33: LessThan
-                            uefi_parser::IfrOpcode::LessThan => {}
-                            // 0x34: LessEqual
-                            uefi_parser::IfrOpcode::LessEqual => {}
-                            // 0x35: BitwiseAnd
-                            uefi_parser::IfrOpcode::BitwiseAnd => {}
-                            // 0x36: BitwiseOr
-                            uefi_parser::IfrOpcode::BitwiseOr => {}
-                            // 0x37: BitwiseNot
-                            uefi_parser::IfrOpcode::BitwiseNot => {}
-                            // 0x38: ShiftLeft
-                            uefi_parser::IfrOpcode::ShiftLeft => {}
-                            // 0x39: ShiftRight
-                            uefi_parser::IfrOpcode::ShiftRight => {}
-                            // 0x3A: Add
-                            uefi_parser::IfrOpcode::Add => {}
-                            // 0x3B: Substract
-                            uefi_parser::IfrOpcode::Substract => {}
-                            // 0x3C: Multiply
-                            uefi_parser::IfrOpcode::Multiply => {}
-                            // 0x3D: Divide
-                            uefi_parser::IfrOpcode::Divide => {}
-                            // 0x3E: Modulo
-                            uefi_parser::IfrOpcode::Modulo => {}
-                            // 0x3F: RuleRef
-                            uefi_parser::IfrOpcode::RuleRef => {
-                                match uefi_parser::ifr_rule_ref(operation.Data.unwrap()) {
-                                    Ok((_, rule)) => {
-                                        write!(&mut text, "RuleId: 0x{:X}", rule.RuleId).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("RuleRef parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x40: QuestionRef1
-                            uefi_parser::IfrOpcode::QuestionRef1 => {
-                                match uefi_parser::ifr_question_ref_1(operation.Data.unwrap()) {
-                                    Ok((_, qr)) => {
-                                        write!(&mut text, "QuestionId: 0x{:X}", qr.QuestionId).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("QuestionRef1 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x41: QuestionRef2
-                            uefi_parser::IfrOpcode::QuestionRef2 => {}
-                            // 0x42: Uint8
-                            uefi_parser::IfrOpcode::Uint8 => {
-                                match uefi_parser::ifr_uint8(operation.Data.unwrap()) {
-                                    Ok((_, u)) => {
-                                        write!(&mut text, "Value: 0x{:X}", u.Value).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Uint8 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x43: Uint16
-                            uefi_parser::IfrOpcode::Uint16 => {
-                                match uefi_parser::ifr_uint16(operation.Data.unwrap()) {
-                                    Ok((_, u)) => {
-                                        write!(&mut text, "Value: 0x{:X}", u.Value).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Uint16 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x44: Uint32
-                            uefi_parser::IfrOpcode::Uint32 => {
-                                match uefi_parser::ifr_uint32(operation.Data.unwrap()) {
-                                    Ok((_, u)) => {
-                                        write!(&mut text, "Value: 0x{:X}", u.Value).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Uint32 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x45: Uint64
-                            uefi_parser::IfrOpcode::Uint64 => {
-                                match uefi_parser::ifr_uint64(operation.Data.unwrap()) {
-                                    Ok((_, u)) => {
-                                        write!(&mut text, "Value: 0x{:X}", u.Value).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Uint64 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x46: True
-                            uefi_parser::IfrOpcode::True => {}
-                            // 0x47: False
-                            uefi_parser::IfrOpcode::False => {}
-                            // 0x48: ToUint
-                            uefi_parser::IfrOpcode::ToUint => {}
-                            // 0x49: ToString
-                            uefi_parser::IfrOpcode::ToString => {
-                                match uefi_parser::ifr_to_string(operation.Data.unwrap()) {
-                                    Ok((_, ts)) => {
-                                        write!(&mut text, "Format: 0x{:X}", ts.Format).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("ToString parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x4A: ToBoolean
-                            uefi_parser::IfrOpcode::ToBoolean => {}
-                            // 0x4B: Mid
-                            uefi_parser::IfrOpcode::Mid => {}
-                            // 0x4C: Find
-                            uefi_parser::IfrOpcode::Find => {
-                                match uefi_parser::ifr_find(operation.Data.unwrap()) {
-                                    Ok((_, fnd)) => {
-                                        write!(&mut text, "Format: 0x{:X}", fnd.Format).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Find parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x4D: Token
-                            uefi_parser::IfrOpcode::Token => {}
-                            // 0x4E: StringRef1
-                            uefi_parser::IfrOpcode::StringRef1 => {
-                                match uefi_parser::ifr_string_ref_1(operation.Data.unwrap()) {
-                                    Ok((_, st)) => {
-                                        write!(
-                                            &mut text,
-                                            "String: \"{}\"",
-                                            strings_map
-                                                .get(&st.StringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("StringRef1 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x4F: StringRef2
-                            uefi_parser::IfrOpcode::StringRef2 => {}
-                            // 0x50: Conditional
-                            uefi_parser::IfrOpcode::Conditional => {}
-                            // 0x51: QuestionRef3
-                            uefi_parser::IfrOpcode::QuestionRef3 => {
-                                if operation.Data.is_some() {
-                                    match uefi_parser::ifr_question_ref_3(operation.Data.unwrap()) {
-                                        Ok((_, qr)) => {
-                                            if let Some(x) = qr.DevicePathId {
-                                                write!(
-                                                    &mut text,
-                                                    "DevicePath: \"{}\"",
-                                                    strings_map
-                                                        .get(&x)
-                                                        .unwrap_or(&String::from("InvalidId"))
-                                                )
-                                                .unwrap();
-                                            }
-                                            if let Some(x) = qr.QuestionGuid {
-                                                write!(&mut text, "Guid: {x}").unwrap();
-                                            }
-                                        }
-                                        Err(e) => {
-                                            write!(
-                                                &mut text,
-                                                "RawData: {:02X?}",
-                                                operation.Data.unwrap()
-                                            )
-                                            .unwrap();
-                                            println!("QuestionRef3 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                        }
-                                    }
-                                }
-                            }
-                            // 0x52: Zero
-                            uefi_parser::IfrOpcode::Zero => {}
-                            // 0x53: One
-                            uefi_parser::IfrOpcode::One => {}
-                            // 0x54: Ones
-                            uefi_parser::IfrOpcode::Ones => {}
-                            // 0x55: Undefined
-                            uefi_parser::IfrOpcode::Undefined => {}
-                            // 0x56: Length
-                            uefi_parser::IfrOpcode::Length => {}
-                            // 0x57: Dup
-                            uefi_parser::IfrOpcode::Dup => {}
-                            // 0x58: This
-                            uefi_parser::IfrOpcode::This => {}
-                            // 0x59: Span
-                            uefi_parser::IfrOpcode::Span => {
-                                match uefi_parser::ifr_span(operation.Data.unwrap()) {
-                                    Ok((_, span)) => {
-                                        write!(&mut text, "Flags: 0x{:X}", span.Flags).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Span parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x5A: Value
-                            uefi_parser::IfrOpcode::Value => {}
-                            // 0x5B: Default
-                            uefi_parser::IfrOpcode::Default => {
-                                match uefi_parser::ifr_default(operation.Data.unwrap()) {
-                                    Ok((_, def)) => {
-                                        write!(&mut text, "DefaultId: 0x{:X} ", def.DefaultId).unwrap();
-                                        match def.Value {
-                                            uefi_parser::IfrTypeValue::String(x) => {
-                                                write!(
-                                                    &mut text,
-                                                    "String: \"{}\"",
-                                                    strings_map
-                                                        .get(&x)
-                                                        .unwrap_or(&String::from("InvalidId"))
-                                                )
-                                                .unwrap();
-                                            }
-                                            uefi_parser::IfrTypeValue::Action(x) => {
-                                                write!(
-                                                    &mut text,
-                                                    "Action: \"{}\"",
-                                                    strings_map
-                                                        .get(&x)
-                                                        .unwrap_or(&String::from("InvalidId"))
-                                                )
-                                                .unwrap();
-                                            }
-                                            _ => {
-                                                write!(&mut text, "Value: {}", def.Value).unwrap();
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Default parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x5C: DefaultStore
-                            uefi_parser::IfrOpcode::DefaultStore => {
-                                match uefi_parser::ifr_default_store(operation.Data.unwrap()) {
-                                    Ok((_, default_store)) => {
-                                        write!(
-                                            &mut text,
-                                            "DefaultId: 0x{:X}, Name: \"{}\"",
-                                            default_store.DefaultId,
-                                            strings_map
-                                                .get(&default_store.NameStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("DefaultStore parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x5D: FormMap
-                            uefi_parser::IfrOpcode::FormMap => {
-                                match uefi_parser::ifr_form_map(operation.Data.unwrap()) {
-                                    Ok((_, form_map)) => {
-                                        write!(&mut text, "FormId: 0x{:X}", form_map.FormId).unwrap();
-                                        for method in form_map.Methods {
-                                            write!(
-                                                &mut text,
-                                                "| Guid: {}, Method: \"{}\"",
-                                                method.MethodIdentifier,
-                                                strings_map
-                                                    .get(&method.MethodTitleId)
-                                                    .unwrap_or(&String::from("InvalidId"))
-                                            )
-                                            .unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("FormMap parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x5E: Catenate
-                            uefi_parser::IfrOpcode::Catenate => {}
-                            // 0x5F: GUID
-                            uefi_parser::IfrOpcode::Guid => {
-                                match uefi_parser::ifr_guid(operation.Data.unwrap()) {
-                                    Ok((_, guid)) => {
-                                        // This manual parsing here is ugly and can ultimately be done using nom,
-                                        // but it's done already and not that important anyway
-                                        // TODO: refactor later
-                                        let mut done = false;
-                                        match guid.Guid {
-                                            uefi_parser::IFR_TIANO_GUID => {
-                                                if let Ok((_, edk2)) =
-                                                    uefi_parser::ifr_guid_edk2(guid.Data)
-                                                {
-                                                    match edk2.ExtendedOpCode {
-                                                        uefi_parser::IfrEdk2ExtendOpCode::Banner => {
-                                                            if let Ok((_, banner)) =
-                                                                uefi_parser::ifr_guid_edk2_banner(
-                                                                    edk2.Data,
-                                                                )
-                                                            {
-                                                                write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, Title: \"{}\", LineNumber: 0x{:X}, Alignment: 0x{:X} ", 
-                                                                    guid.Guid,
-                                                                    edk2.ExtendedOpCode,
-                                                                    strings_map.get(&banner.TitleId).unwrap_or(&String::from("InvalidId")),
-                                                                    banner.LineNumber,
-                                                                    banner.Alignment).unwrap();
-                                                                done = true;
-                                                            }
-                                                        }
-                                                        uefi_parser::IfrEdk2ExtendOpCode::Label => {
-                                                            if edk2.Data.len() == 2 {
-                                                                write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, LabelNumber: 0x{:X}", 
-                                                                        guid.Guid,
-                                                                        edk2.ExtendedOpCode,
-                                                                        edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
-                                                                done = true;
-                                                            }
-                                                        }
-                                                        uefi_parser::IfrEdk2ExtendOpCode::Timeout => {
-                                                            if edk2.Data.len() == 2 {
-                                                                write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, Timeout: 0x{:X}", 
-                                                                        guid.Guid,
-                                                                        edk2.ExtendedOpCode,
-                                                                        edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
-                                                                done = true;
-                                                            }
-                                                        }
-                                                        uefi_parser::IfrEdk2ExtendOpCode::Class => {
-                                                            if edk2.Data.len() == 2 {
-                                                                write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, Class: 0x{:X}", 
-                                                                        guid.Guid,
-                                                                        edk2.ExtendedOpCode,
-                                                                        edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
-                                                                done = true;
-                                                            }
-                                                        }
-                                                        uefi_parser::IfrEdk2ExtendOpCode::SubClass => {
-                                                            if edk2.Data.len() == 2 {
-                                                                write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, SubClass: 0x{:X}", 
-                                                                        guid.Guid,
-                                                                        edk2.ExtendedOpCode,
-                                                                        edk2.Data[1] as u16 * 100 + edk2.Data[0] as u16).unwrap();
-                                                                done = true;
-                                                            }
-                                                        }
-                                                        uefi_parser::IfrEdk2ExtendOpCode::Unknown(_) => {
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            uefi_parser::IFR_FRAMEWORK_GUID => {
-                                                if let Ok((_, edk)) =
-                                                    uefi_parser::ifr_guid_edk(guid.Data)
-                                                {
-                                                    match edk.ExtendedOpCode {
-                                                        uefi_parser::IfrEdkExtendOpCode::OptionKey => {
-                                                            write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, QuestionId: 0x{:X}, Data: {:?}", 
-                                                                        guid.Guid,
-                                                                        edk.ExtendedOpCode,
-                                                                        edk.QuestionId,
-                                                                        edk.Data).unwrap();
-                                                            done = true;
-                                                        }
-                                                        uefi_parser::IfrEdkExtendOpCode::VarEqName => {
-                                                            if edk.Data.len() == 2 {
-                                                                let name_id = edk.Data[1] as u16
-                                                                    * 100
-                                                                    + edk.Data[0] as u16;
-                                                                write!(&mut text, "Guid: {}, ExtendedOpCode: {:?}, QuestionId: 0x{:X}, Name: \"{}\"", 
-                                                                        guid.Guid,
-                                                                        edk.ExtendedOpCode,
-                                                                        edk.QuestionId,
-                                                                        strings_map.get(&name_id).unwrap_or(&String::from("InvalidId"))).unwrap();
-                                                                done = true;
-                                                            }
-                                                        }
-                                                        uefi_parser::IfrEdkExtendOpCode::Unknown(_) => {}
-                                                    }
-                                                }
-                                            }
-                                            _ => {}
-                                        }
-                                        if !done {
-                                            write!(
-                                                &mut text,
-                                                "Guid: {}, Optional data: {:?}",
-                                                guid.Guid, guid.Data
-                                            )
-                                            .unwrap();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Guid parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x60: Security
-                            uefi_parser::IfrOpcode::Security => {
-                                match uefi_parser::ifr_security(operation.Data.unwrap()) {
-                                    Ok((_, sec)) => {
-                                        write!(&mut text, "Guid: {}", sec.Guid).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Security parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x61: ModalTag
-                            uefi_parser::IfrOpcode::ModalTag => {}
-                            // 0x62: RefreshId
-                            uefi_parser::IfrOpcode::RefreshId => {
-                                match uefi_parser::ifr_refresh_id(operation.Data.unwrap()) {
-                                    Ok((_, rid)) => {
-                                        write!(&mut text, "Guid: {}", rid.Guid).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("RefreshId parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x63: WarningIf
-                            uefi_parser::IfrOpcode::WarningIf => {
-                                match uefi_parser::ifr_warning_if(operation.Data.unwrap()) {
-                                    Ok((_, warn)) => {
-                                        write!(
-                                            &mut text,
-                                            "Timeout: 0x{:X}, Warning: \"{}\"",
-                                            warn.Timeout,
-                                            strings_map
-                                                .get(&warn.WarningStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("WarningIf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // 0x64: Match2
-                            uefi_parser::IfrOpcode::Match2 => {
-                                match uefi_parser::ifr_match_2(operation.Data.unwrap()) {
-                                    Ok((_, m2)) => {
-                                        write!(&mut text, "Guid: {}", m2.Guid).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Match2 parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            // Unknown operation
-                            uefi_parser::IfrOpcode::Unknown(x) => {
-                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                    .unwrap();
-                                println!("IFR operation of unknown type 0x{x:X}");
-                            }
-                        }
-                        current_operation_offset += operation.Length as usize;
-
-                        if verbose_mode {
-                            write!(&mut text, " {operation}").unwrap();
-                        }
-
-                        writeln!(&mut text).unwrap();
-                    }
-                }
-                Err(e) => {
-                    println!("IFR operations parse error: {e:?}");
-                }
-            }
-        }
-    }
-
-    // Write the result
     let mut file_path = OsString::new();
     file_path.push(path);
     file_path.push(".");
@@ -2086,1090 +64,512 @@ fn uefi_ifr_extract(
         .create(true)
         .open(&file_path)
         .unwrap_or_else(|_| panic!("Can't create output file {:?}", &file_path));
+    writeln!(output_file, "{provenance}")
+        .unwrap_or_else(|_| panic!("{}", "Can't write to output file {file_path:?}"));
     output_file
-        .write_all(&text)
+        .write_all(text.as_bytes())
         .unwrap_or_else(|_| panic!("{}", "Can't write to output file {file_path:?}"));
 }
 
-//
-// Framework HII parsing
-//
-fn framework_find_string_and_form_packages(data: &[u8]) -> (Vec<StringPackage>, Vec<FormPackage>) {
-    let mut strings = Vec::new(); // String-to-id maps for all found string packages
+// Extraction functions printing their diagnostics, the library never prints anything itself
+fn extract_uefi_and_report(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> String {
+    let mut diagnostics = Diagnostics::new();
+    let text = extract_uefi_ifr_with_diagnostics(data, form_package, string_package, resources, options, &mut diagnostics);
+    print!("{diagnostics}");
+    text
+}
+
+fn extract_uefi_form_and_report(
+    data: &[u8],
+    form_package: &FormPackage,
+    form_id: u16,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    options: &ExtractOptions,
+) -> String {
+    let mut diagnostics = Diagnostics::new();
+    let text = extract_uefi_form_with_diagnostics(data, form_package, form_id, string_package, resources, options, &mut diagnostics);
+    print!("{diagnostics}");
+    text
+}
 
-    // Search for all string packages in the input file
-    let mut i = 0;
-    while i < data.len() {
-        if let Ok((_, candidate)) = framework_parser::hii_string_package_candidate(&data[i..]) {
-            if let Ok((_, package)) = framework_parser::hii_package(candidate) {
-                if let Ok((_, string_package)) =
-                    framework_parser::hii_string_package(package.Data.unwrap())
-                {
-                    let mut string_id_map = HashMap::new(); // Map of StringIds to strings
-                    let mut language = String::from("Invalid");
-                    for (current_string_index, string) in string_package.Strings.iter().enumerate() {
-                        // This will always work in a properly formatted string package
-                        if string_package.StringPointers[current_string_index]
-                            == string_package.LanguageNameStringOffset
-                        {
-                            language = string_package.Strings[current_string_index].clone();
-                        }
+fn extract_framework_and_report(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    options: &ExtractOptions,
+) -> String {
+    let mut diagnostics = Diagnostics::new();
+    let text = extract_framework_ifr_with_diagnostics(data, form_package, string_package, options, &mut diagnostics);
+    print!("{diagnostics}");
+    text
+}
 
-                        string_id_map.insert(current_string_index as u16, string.clone());
-                    }
+fn main() {
+    // Obtain program arguments
+    let mut args = std::env::args_os();
+
+    // Check if we have none
+    if args.len() <= 1 {
+        println!("
+IFRExtractor RS v{} - extracts HII string and form packages in UEFI Internal Form Representation (IFR) from a binary file into human-readable text
+Usage: ifrextractor file.bin list - list all string and form packages in the input file
+       ifrextractor file.bin package_lists - list all UEFI HII package lists with every package in them, fonts, device paths, keyboard layouts and GUID packages included
+       ifrextractor file.bin device_paths - list all UEFI HII device path packages as text with the form packages of their package lists
+       ifrextractor file.bin guid_packages - list all UEFI HII GUID packages of package lists with their GUIDs and payload sizes
+       ifrextractor file.bin fonts - list all UEFI HII font and simple font packages with their glyphs, and fonts declared in string packages with the font packages that draw them
+       ifrextractor file.bin languages - list all languages of string packages with the number of packages per language
+       ifrextractor file.bin formsets - quickly locate all formsets without parsing form and string packages
+       ifrextractor file.bin files [decompress] - report FFS files of all UEFI HII string and form packages in a full firmware image, optionally searching LZMA, Tiano and EFI 1.1 compressed sections too
+       ifrextractor file.bin decompress - extract all UEFI HII form packages found in compressed sections of a full firmware image using string packages in English from the same sections
+       ifrextractor file.bin hidden [json] - report UEFI HII forms and questions in SuppressIf, GrayOutIf and DisableIf scopes with their conditions, and forms no Ref leads to
+       ifrextractor file.bin graph - print the navigation graph of UEFI HII formsets, forms and Refs between them in GraphViz DOT format
+       ifrextractor file.bin csv - print all UEFI HII questions as CSV, one row per question with its formset, form, strings, storage, type, defaults and options, and the FFS file of the driver implementing it when the input is a firmware image
+       ifrextractor file.bin images [png|bmp] [directory] - write every image of UEFI HII image packages to its own file, into file.bin.images by default, converting bitmaps into PNG (default) or BMP and keeping JPEG and PNG images as they are
+       ifrextractor file.bin dump [directory] - write every HII package to its own file with a manifest, into file.bin.packages by default
+       ifrextractor file.bin summary - scan the input file and report bytes scanned, packages found per type, languages, elapsed time and rejected package candidates
+       ifrextractor file.bin metrics - scan the input file for all string and form packages and report scan timing and size metrics
+       ifrextractor file.bin window <start> <end> [alignment] - same as metrics, only searching for packages that start at offsets from start to end, optionally only at multiples of a given alignment
+       ifrextractor file.bin questions - report QuestionIds used by all UEFI HII formsets, with free ranges and duplicates
+       ifrextractor file.bin variables - report EFI variables and questions of other formsets that UEFI HII expressions depend on
+       ifrextractor file.bin defaults - report default values of all UEFI HII questions per default store, taken from Default opcodes and default flags
+       ifrextractor file.bin default_stores - report DefaultStores declared by all UEFI HII formsets with their DefaultIds, names and default classes
+       ifrextractor file.bin scopes - print opcodes of all UEFI HII form packages as a tree, one line per opcode with the index of the opcode whose scope it is in, its index in the form package that identifies it together with the package hash, and symbolic names of its flags
+       ifrextractor file.bin nvram <nvram.bin> - compare default values of all UEFI HII questions with current values of their variables in a VSS variable store dump, read with the declared width of every question
+       ifrextractor file.bin borrow <sibling.bin> [language] - extract UEFI HII form packages shipped without string packages using string packages in a given language (en-US by default) from package lists with the same GUID in a sibling image, i.e. a full variant of a cost-reduced build
+       ifrextractor file.bin string_diff <new.bin> - compare UEFI HII string packages with the ones of another image in the same languages, i.e. a newer release, and report added, removed and changed StringIds
+       ifrextractor file.bin setup_var <prompt|QuestionId> [value] - print setup_var and setup_var_cv commands that read or set the value of UEFI HII questions with a given prompt or QuestionId (i.e. 0x100), with their varstore names and GUIDs
+       ifrextractor file.bin fingerprint [ignored] - print structural fingerprints of all UEFI HII form packages, ignored is a comma-separated list of fields left out of them (strings, guids or none, strings by default), so the same formset can be recognized in images of different vendors
+       ifrextractor file.bin validate - check all UEFI HII form packages against the UEFI specification and report deviations
+       ifrextractor file.bin single <form_package_number> <string_package_number> [form_id] - extract a given form package using a given string package (use list command to obtain the package numbers), optionally only a form with a given FormId
+       ifrextractor file.bin lang <language> - extract all form packages using all string packages in a given language      
+       ifrextractor file.bin combined <language> - extract all form packages using all string packages in a given language combined into one, for strings split across several packages
+       ifrextractor file.bin all - extract all form package using all string packages
+       ifrextractor file.bin all_languages [combined] - extract all UEFI HII form packages once per language of their package list parsing each of them once, or print all languages in one combined view
+       ifrextractor file.bin verbose [absolute|package|payload] - extract all form packages using string packages in English, add raw bytes to all opcodes, print offsets from the start of the input file (default), the form package or its first opcode
+       ifrextractor file.bin ids - extract all form packages using string packages in English, add StringIds after all resolved strings
+       ifrextractor file.bin only <categories> - extract all form packages using string packages in English, only keep opcodes of given comma-separated categories (form, question, option, statement, condition, expression, storage, default, modifier, extension, other)
+       ifrextractor file.bin skip <categories> - extract all form packages using string packages in English, drop opcodes of given comma-separated categories
+       ifrextractor file.bin escape <none|c|json> - extract all form packages using string packages in English, escape quotes, backslashes and control characters in all quoted strings
+       ifrextractor file.bin indent <tab|spaces> [name_width] - extract all form packages using string packages in English, indent every scope with a tab or a given number of spaces, optionally pad opcode names to a given width
+       ifrextractor file.bin padding <stop|skip|record> - extract all UEFI HII form packages using string packages in English, stop at padding pseudo-opcodes shorter than 2 bytes (default), skip them or skip and print them
+       ifrextractor file.bin cache <directory> - extract all form packages using string packages in English, reuse results stored in a given directory for unchanged packages and store new ones there
+       ifrextractor file.bin radix <hex|dec|both> - extract all form packages using string packages in English, print numbers in hexadecimal (default), decimal or both
+       ifrextractor file.bin guids <registry|c|token> - extract all form packages using string packages in English, print GUIDs in registry format (default), as C struct initializers or as EDK2 token names where known
+       ifrextractor file.bin strings <processors> - extract all form packages using string packages in English, apply given comma-separated string processors (strip-placeholders, collapse-whitespace) in order to every resolved string
+       ifrextractor file.bin nested <levels> - extract all UEFI HII form packages using string packages in English, render opcode sequences embedded in payloads of unknown Guid opcodes up to a given number of levels deep, marked with NestedIfr lines
+       ifrextractor file.bin preview <headers|opcodes> [count] - extract all form packages using string packages in English, only FormSet and Form opcodes or only a given number of opcodes (20 by default) of each package
+       ifrextractor file.bin normalize - repair UEFI HII form packages with known vendor quirks (package and opcode lengths off by one, End opcodes missing at the package tail), then extract all form packages using string packages in English
+       ifrextractor file.bin help <full|omit|truncate|wrap> [width] - extract all form packages using string packages in English, print help strings in full (default), omit them, or truncate or wrap them at a given width (80 by default)
+       ifrextractor file.bin - default extraction mode (only try string packages in English)", 
+        VERSION.unwrap_or("0.0.0"));
+        std::process::exit(1);
+    }
 
-                    // Add string
-                    let string = (i, candidate.len(), language, string_id_map);
-                    strings.push(string);
+    // The only mandatory argument is a path to input file
+    let arg = args.nth(1).expect("Failed to obtain file path");
+    let path = Path::new(&arg);
 
-                    i += candidate.len();
-                } else {
-                    i += 1;
-                }
-            } else {
-                i += 1;
+    // Read input file into memory, pipes and process substitutions included
+    let mut data = MappedInput::open(path).expect("Can't open input file");
+
+    // Repair known vendor quirks before searching for packages, provenance still refers to the input file
+    let mut input = None;
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("normalize") {
+        let (normalized, repairs) = normalize_uefi_form_packages(&data);
+        let mut diagnostics = Diagnostics::new();
+        report_repairs(&repairs, &mut diagnostics);
+        print!("{diagnostics}");
+        input = Some(std::mem::replace(&mut data, MappedInput::from(normalized)));
+    }
+
+    // Quick inventory doesn't need full parsing of all packages, so it's done before it
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("formsets") {
+        let uefi_formsets = uefi_formset_inventory(&data);
+        let framework_formsets = framework_formset_inventory(&data);
+        if uefi_formsets.is_empty() && framework_formsets.is_empty() {
+            println!("No IFR data found");
+            std::process::exit(2);
+        }
+        for (name, formsets) in [("UEFI", uefi_formsets), ("Framework", framework_formsets)] {
+            if formsets.is_empty() {
+                continue;
+            }
+            println!("{name} HII formsets:");
+            for formset in &formsets {
+                println!(
+                    "Offset: 0x{:X}, Guid: {}, Title StringId: 0x{:X}, Package offset: 0x{:X}, Package length: 0x{:X}",
+                    formset.offset, formset.guid, formset.title_string_id, formset.package_offset, formset.package_length
+                );
             }
-        } else {
-            i += 1;
         }
+        return;
     }
 
-    // No need to continue if there are no string packages found
-    if strings.is_empty() {
-        return (Vec::new(), Vec::new());
+    // Attribute UEFI packages of a full firmware image to FFS files
+    if (env::args().len() == 3 || env::args().len() == 4) && env::args().nth(2).as_deref() == Some("files") {
+        let decompressing = match env::args().nth(3).as_deref() {
+            None => false,
+            Some("decompress") => true,
+            Some(mode) => {
+                println!("Unknown mode {mode}, expected decompress");
+                std::process::exit(1);
+            }
+        };
+        let (sections, origins) = if decompressing {
+            uefi_package_origins_with_decompression(&data)
+        } else {
+            (Vec::new(), uefi_package_origins(&data))
+        };
+        if origins.is_empty() {
+            println!("No UEFI HII packages found");
+            std::process::exit(2);
+        }
+        for origin in &origins {
+            match &origin.file {
+                Some(file) => print!(
+                    "Type: {}, Offset: 0x{:X}, File: {}, Name: {}, File offset: 0x{:X}",
+                    origin.kind,
+                    origin.offset,
+                    file.guid,
+                    file.name.as_deref().unwrap_or("None"),
+                    file.offset
+                ),
+                None => print!("Type: {}, Offset: 0x{:X}, File: None", origin.kind, origin.offset),
+            }
+            // Offsets of packages in compressed sections are relative to the decompressed data
+            match origin.section {
+                Some(index) => println!(
+                    ", Section: {} ({:?})",
+                    section_path(&sections, index)
+                        .iter()
+                        .map(|offset| format!("0x{offset:X}"))
+                        .collect::<Vec<_>>()
+                        .join(" > "),
+                    sections[index].compression
+                ),
+                None => println!(),
+            }
+        }
+        if decompressing {
+            println!("{} compressed section(s) decompressed", sections.len());
+            return;
+        }
+        let compressed = find_firmware_volumes(&data)
+            .iter()
+            .flat_map(|volume| volume.files.iter())
+            .filter(|file| file.compressed)
+            .count();
+        if compressed > 0 {
+            println!("{compressed} FFS file(s) with compressed sections were not searched, use files decompress to search them");
+        }
+        return;
     }
 
-    //
-    // Search for all form packages in the input file
-    //
-    let mut forms = Vec::new();
-    i = 0;
-    while i < data.len() {
-        if let Ok((_, candidate)) = framework_parser::hii_form_package_candidate(&data[i..]) {
-            if let Ok((_, package)) = framework_parser::hii_package(candidate) {
-                // Parse form package and obtain StringIds
-                let mut string_ids: Vec<u16> = Vec::new();
-                if let Ok((_, operations)) = framework_parser::ifr_operations(package.Data.unwrap())
-                {
-                    //let mut current_operation: usize = 0;
-                    for operation in &operations {
-                        //current_operation += 1;
-                        //println!("Operation #{}, OpCode: {:?}, Length 0x{:X}", current_operation, operation.OpCode, operation.Length);
-                        match operation.OpCode {
-                            framework_parser::IfrOpcode::Form => {
-                                if let Ok((_, form)) =
-                                    framework_parser::ifr_form(operation.Data.unwrap())
-                                {
-                                    string_ids.push(form.TitleStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Subtitle => {
-                                if let Ok((_, subtitile)) =
-                                    framework_parser::ifr_subtitle(operation.Data.unwrap())
-                                {
-                                    string_ids.push(subtitile.SubtitleStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Text => {
-                                if let Ok((_, text)) =
-                                    framework_parser::ifr_text(operation.Data.unwrap())
-                                {
-                                    string_ids.push(text.HelpStringId);
-                                    string_ids.push(text.TextStringId);
-                                    string_ids.push(text.TextTwoStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Graphic => {}
-                            framework_parser::IfrOpcode::OneOf => {
-                                if let Ok((_, oneof)) =
-                                    framework_parser::ifr_one_of(operation.Data.unwrap())
-                                {
-                                    string_ids.push(oneof.PromptStringId);
-                                    string_ids.push(oneof.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::CheckBox => {
-                                if let Ok((_, checkbox)) =
-                                    framework_parser::ifr_check_box(operation.Data.unwrap())
-                                {
-                                    string_ids.push(checkbox.PromptStringId);
-                                    string_ids.push(checkbox.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Numeric => {
-                                if let Ok((_, numeric)) =
-                                    framework_parser::ifr_numeric(operation.Data.unwrap())
-                                {
-                                    string_ids.push(numeric.PromptStringId);
-                                    string_ids.push(numeric.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Password => {
-                                if let Ok((_, password)) =
-                                    framework_parser::ifr_password(operation.Data.unwrap())
-                                {
-                                    string_ids.push(password.PromptStringId);
-                                    string_ids.push(password.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::OneOfOption => {
-                                if let Ok((_, oneofoption)) =
-                                    framework_parser::ifr_one_of_option(operation.Data.unwrap())
-                                {
-                                    string_ids.push(oneofoption.OptionStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::SuppressIf => {}
-                            framework_parser::IfrOpcode::EndForm => {}
-                            framework_parser::IfrOpcode::Hidden => {}
-                            framework_parser::IfrOpcode::EndFormSet => {}
-                            framework_parser::IfrOpcode::FormSet => {
-                                if let Ok((_, formset)) =
-                                    framework_parser::ifr_form_set(operation.Data.unwrap())
-                                {
-                                    string_ids.push(formset.TitleStringId);
-                                    string_ids.push(formset.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Ref => {
-                                if let Ok((_, rf)) =
-                                    framework_parser::ifr_ref(operation.Data.unwrap())
-                                {
-                                    string_ids.push(rf.PromptStringId);
-                                    string_ids.push(rf.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::End => {}
-                            framework_parser::IfrOpcode::InconsistentIf => {
-                                if let Ok((_, incif)) =
-                                    framework_parser::ifr_inconsistent_if(operation.Data.unwrap())
-                                {
-                                    string_ids.push(incif.PopupStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::EqIdVal => {}
-                            framework_parser::IfrOpcode::EqIdId => {}
-                            framework_parser::IfrOpcode::EqIdList => {}
-                            framework_parser::IfrOpcode::And => {}
-                            framework_parser::IfrOpcode::Or => {}
-                            framework_parser::IfrOpcode::Not => {}
-                            framework_parser::IfrOpcode::EndIf => {}
-                            framework_parser::IfrOpcode::GrayOutIf => {}
-                            framework_parser::IfrOpcode::Date => {
-                                if let Ok((_, date)) =
-                                    framework_parser::ifr_date(operation.Data.unwrap())
-                                {
-                                    string_ids.push(date.PromptStringId);
-                                    string_ids.push(date.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Time => {
-                                if let Ok((_, time)) =
-                                    framework_parser::ifr_time(operation.Data.unwrap())
-                                {
-                                    string_ids.push(time.PromptStringId);
-                                    string_ids.push(time.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::String => {
-                                if let Ok((_, str)) =
-                                    framework_parser::ifr_string(operation.Data.unwrap())
-                                {
-                                    string_ids.push(str.PromptStringId);
-                                    string_ids.push(str.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Label => {}
-                            framework_parser::IfrOpcode::SaveDefaults => {
-                                if let Ok((_, sd)) =
-                                    framework_parser::ifr_save_defaults(operation.Data.unwrap())
-                                {
-                                    string_ids.push(sd.PromptStringId);
-                                    string_ids.push(sd.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::RestoreDefaults => {
-                                if let Ok((_, rd)) =
-                                    framework_parser::ifr_restore_defaults(operation.Data.unwrap())
-                                {
-                                    string_ids.push(rd.PromptStringId);
-                                    string_ids.push(rd.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Banner => {
-                                if let Ok((_, banner)) =
-                                    framework_parser::ifr_banner(operation.Data.unwrap())
-                                {
-                                    string_ids.push(banner.TitleStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::Inventory => {
-                                if let Ok((_, inv)) =
-                                    framework_parser::ifr_inventory(operation.Data.unwrap())
-                                {
-                                    string_ids.push(inv.HelpStringId);
-                                    string_ids.push(inv.TextStringId);
-                                    string_ids.push(inv.TextTwoStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::EqVarVal => {}
-                            framework_parser::IfrOpcode::OrderedList => {
-                                if let Ok((_, ol)) =
-                                    framework_parser::ifr_ordered_list(operation.Data.unwrap())
-                                {
-                                    string_ids.push(ol.PromptStringId);
-                                    string_ids.push(ol.HelpStringId);
-                                }
-                            }
-                            framework_parser::IfrOpcode::VarStore => {}
-                            framework_parser::IfrOpcode::VarStoreSelect => {}
-                            framework_parser::IfrOpcode::VarStoreSelectPair => {}
-                            framework_parser::IfrOpcode::True => {}
-                            framework_parser::IfrOpcode::False => {}
-                            framework_parser::IfrOpcode::Greater => {}
-                            framework_parser::IfrOpcode::GreaterEqual => {}
-                            framework_parser::IfrOpcode::OemDefined => {}
-                            framework_parser::IfrOpcode::Oem => {}
-                            framework_parser::IfrOpcode::NvAccessCommand => {}
-                            framework_parser::IfrOpcode::Unknown(_) => {}
-                        }
+    // Extract form packages found in compressed sections using string packages in English from the same sections.
+    // Output files are named after offsets of the sections
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("decompress") {
+        let provenance = Provenance::new(
+            &path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            &data,
+            "decompress",
+        );
+        let mut found = false;
+        let sections = decompress_sections(&data);
+        for (index, section) in sections.iter().enumerate() {
+            let (strings, forms) = find_uefi_packages(&section.data);
+            let (images, animations) = (find_uefi_image_packages(&section.data), find_uefi_animation_packages(&section.data));
+            let device_paths = find_uefi_device_path_packages(&section.data);
+            let mut section_path_name = path.as_os_str().to_os_string();
+            for offset in section_path(&sections, index) {
+                section_path_name.push(format!(".{offset:X}"));
+            }
+            for (form_num, form) in forms.iter().enumerate() {
+                for (string_num, string) in strings.iter().enumerate() {
+                    if string.language == "en-US" {
+                        found = true;
+                        write_output(
+                            &section_path_name,
+                            &provenance,
+                            form_num,
+                            string,
+                            string_num,
+                            &extract_uefi_and_report(
+                                &section.data,
+                                form,
+                                string,
+                                &uefi_resources(&section.data, &images, &animations, &device_paths, &strings, form),
+                                &ExtractOptions::default(),
+                            ),
+                        );
                     }
                 }
-
-                // Find min and max StringId, and the number of unique ones
-                string_ids.sort();
-                string_ids.dedup();
-                if !string_ids.is_empty() {
-                    // Add the required information to forms
-                    let form = (
-                        i,
-                        candidate.len(),
-                        string_ids.len(),
-                        *string_ids.first().unwrap(),
-                        *string_ids.last().unwrap(),
-                    );
-                    forms.push(form);
-                }
-
-                i += candidate.len();
-            } else {
-                i += 1;
             }
-        } else {
-            i += 1;
         }
+        if !found {
+            println!("No UEFI HII form packages with en-US string packages found in compressed sections");
+            std::process::exit(2);
+        }
+        return;
     }
 
-    // No need to continue if no forms are found
-    if forms.is_empty() {
-        return (Vec::new(), Vec::new());
+    // Navigation graph of forms in GraphViz DOT format
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("graph") {
+        print!("{}", export_form_graph_dot(&data));
+        return;
     }
 
-    // Construct return value
-    let mut result_strings = Vec::new();
-    let mut result_forms = Vec::new();
-    for string in &strings {
-        result_strings.push(StringPackage {
-            offset: string.0,
-            length: string.1,
-            language: string.2.clone(),
-            string_id_map: string.3.clone(),
-        });
-    }
-    for form in &forms {
-        result_forms.push(FormPackage {
-            offset: form.0,
-            length: form.1,
-            used_strings: form.2,
-            min_string_id: form.3,
-            max_string_id: form.4,
-        });
+    // Export all UEFI questions as CSV
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("csv") {
+        print!("{}", export_questions_csv(&data));
+        return;
     }
 
-    (result_strings, result_forms)
-}
-
-fn framework_ifr_extract(
-    path: &OsStr,
-    data: &[u8],
-    form_package: &FormPackage,
-    form_package_index: usize,
-    string_package: &StringPackage,
-    string_package_index: usize,
-    verbose_mode: bool,
-) {
-    let mut text = Vec::new();
-    let strings_map = &string_package.string_id_map;
-
-    // Add version number and extraction mode
-    writeln!(&mut text, "Program version: {}, Extraction mode: Framework", VERSION.unwrap_or("0.0.0")).unwrap();
-
-    if let Ok((_, candidate)) =
-        framework_parser::hii_form_package_candidate(&data[form_package.offset..])
+    // Report forms and questions that may be hidden from users
+    if (env::args().len() == 3 || env::args().len() == 4)
+        && env::args().nth(2).as_deref() == Some("hidden")
     {
-        if let Ok((_, package)) = framework_parser::hii_package(candidate) {
-            // Parse form package and output its structure as human-readable strings
-            match framework_parser::ifr_operations(package.Data.unwrap()) {
-                Ok((_, operations)) => {
-                    let mut scope_depth:usize = 0;
-                    let mut current_operation_offset = form_package.offset + 6; // Header size of Framework HII form package is 6 bytes
-                    for operation in &operations {
-                        // Special case of operations that decrease scope_depth
-                        if operation.OpCode == framework_parser::IfrOpcode::EndFormSet
-                            || operation.OpCode == framework_parser::IfrOpcode::EndForm
-                        {
-                            scope_depth = scope_depth.saturating_sub(1);
-                        }
-
-                        if verbose_mode {
-                            write!(
-                                &mut text,
-                                "0x{current_operation_offset:X}: "
-                            )
-                            .unwrap();
-                        }
-
-                        write!(
-                            &mut text,
-                            "{:\t<1$}{2:?} ",
-                            "", scope_depth, operation.OpCode
-                        )
-                        .unwrap();
-
-                        match operation.OpCode {
-                            //0x01: Form
-                            framework_parser::IfrOpcode::Form => {
-                                match framework_parser::ifr_form(operation.Data.unwrap()) {
-                                    Ok((_, form)) => {
-                                        write!(
-                                            &mut text,
-                                            "Title: \"{}\", FormId: 0x{:X}",
-                                            strings_map
-                                                .get(&form.TitleStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            form.FormId
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Form parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-
-                                scope_depth += 1;
-                            }
-                            //0x02: Subtitle
-                            framework_parser::IfrOpcode::Subtitle => {
-                                match framework_parser::ifr_subtitle(operation.Data.unwrap()) {
-                                    Ok((_, subtitle)) => {
-                                        write!(
-                                            &mut text,
-                                            "Subtitle: \"{}\"",
-                                            strings_map
-                                                .get(&subtitle.SubtitleStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Subtitle parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x03: Text
-                            framework_parser::IfrOpcode::Text => {
-                                match framework_parser::ifr_text(operation.Data.unwrap()) {
-                                    Ok((_, txt)) => {
-                                        write!(&mut text,
-                                            "Text: \"{}\", TextTwo: \"{}\", Help: \"{}\", Flags: 0x{:X}, Key: 0x{:X}",
-                                            strings_map.get(&txt.TextStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&txt.TextTwoStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&txt.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            txt.Flags,
-                                            txt.Key
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Text parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x04: Graphic, should be unused
-                            framework_parser::IfrOpcode::Graphic => {}
-                            //0x05: OneOf
-                            framework_parser::IfrOpcode::OneOf => {
-                                match framework_parser::ifr_one_of(operation.Data.unwrap()) {
-                                    Ok((_, oneof)) => {
-                                        write!(
-                                            &mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, Width: 0x{:X}",
-                                            strings_map
-                                                .get(&oneof.PromptStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&oneof.HelpStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            oneof.QuestionId,
-                                            oneof.Width
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("OneOf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x06: CheckBox
-                            framework_parser::IfrOpcode::CheckBox => {
-                                match framework_parser::ifr_check_box(operation.Data.unwrap()) {
-                                    Ok((_, checkbox)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, Width: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}",
-                                            strings_map.get(&checkbox.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&checkbox.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            checkbox.QuestionId,
-                                            checkbox.Width,
-                                            checkbox.Flags,
-                                            checkbox.Key
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("CheckBox parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x07: Numeric
-                            framework_parser::IfrOpcode::Numeric => {
-                                match framework_parser::ifr_numeric(operation.Data.unwrap()) {
-                                    Ok((_, numeric)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, Width: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}, Default: 0x{:X}",
-                                            strings_map.get(&numeric.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&numeric.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            numeric.QuestionId,
-                                            numeric.Width,
-                                            numeric.Flags,
-                                            numeric.Key,
-                                            numeric.Min,
-                                            numeric.Max,
-                                            numeric.Step,
-                                            numeric.Default
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Numeric parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x08: Password
-                            framework_parser::IfrOpcode::Password => {
-                                match framework_parser::ifr_password(operation.Data.unwrap()) {
-                                    Ok((_, password)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, Width: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}, MinSize: 0x{:X}, MaxSize: 0x{:X}, Encoding 0x{:X}",
-                                            strings_map.get(&password.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&password.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            password.QuestionId,
-                                            password.Width,
-                                            password.Flags,
-                                            password.Key,
-                                            password.MinSize,
-                                            password.MaxSize,
-                                            password.Encoding
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Password parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x09: OneOfOption
-                            framework_parser::IfrOpcode::OneOfOption => {
-                                match framework_parser::ifr_one_of_option(operation.Data.unwrap()) {
-                                    Ok((_, oneofopt)) => {
-                                        write!(
-                                            &mut text,
-                                            "Option: \"{}\", Value: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}",
-                                            strings_map
-                                                .get(&oneofopt.OptionStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            oneofopt.Value,
-                                            oneofopt.Flags,
-                                            oneofopt.Key
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("OneOfOption parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x0A: SuppressIf
-                            framework_parser::IfrOpcode::SuppressIf => {
-                                match framework_parser::ifr_supress_if(operation.Data.unwrap()) {
-                                    Ok((_, supressif)) => {
-                                        write!(&mut text, "Flags: 0x{:X}", supressif.Flags)
-                                            .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("SupressIf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x0B: EndForm
-                            framework_parser::IfrOpcode::EndForm => {}
-                            //0x0C: Hidden
-                            framework_parser::IfrOpcode::Hidden => {
-                                match framework_parser::ifr_hidden(operation.Data.unwrap()) {
-                                    Ok((_, hidden)) => {
-                                        write!(
-                                            &mut text,
-                                            "Value: 0x{:X}, Key: 0x{:X}",
-                                            hidden.Value, hidden.Key
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Hidden parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x0D: EndFormSet
-                            framework_parser::IfrOpcode::EndFormSet => {}
-                            //0x0E: FormSet
-                            framework_parser::IfrOpcode::FormSet => {
-                                match framework_parser::ifr_form_set(operation.Data.unwrap()) {
-                                    Ok((_, formset)) => {
-                                        write!(&mut text,
-                                            "Title: \"{}\", Help: \"{}\", Guid: {}, CallbackHandle: 0x{:X}, Class: 0x{:X}, SubClass: 0x{:X}, NvDataSize: 0x{:X}",
-                                            strings_map.get(&formset.TitleStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&formset.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            formset.Guid,
-                                            formset.CallbackHandle,
-                                            formset.Class,
-                                            formset.SubClass,
-                                            formset.NvDataSize
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("FromSet parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-
-                                scope_depth += 1;
-                            }
-                            //0x0F: Ref
-                            framework_parser::IfrOpcode::Ref => {
-                                match framework_parser::ifr_ref(operation.Data.unwrap()) {
-                                    Ok((_, rf)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", FormId: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}",
-                                            strings_map.get(&rf.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&rf.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            rf.FormId,
-                                            rf.Flags,
-                                            rf.Key
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Ref parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x10: End
-                            framework_parser::IfrOpcode::End => {}
-                            //0x11: InconsistentIf
-                            framework_parser::IfrOpcode::InconsistentIf => {
-                                match framework_parser::ifr_inconsistent_if(operation.Data.unwrap())
-                                {
-                                    Ok((_, incif)) => {
-                                        write!(
-                                            &mut text,
-                                            "Popup: \"{}\", Flags: 0x{:X}",
-                                            strings_map
-                                                .get(&incif.PopupStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            incif.Flags
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("InconsistentIf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x12: EqIdVal
-                            framework_parser::IfrOpcode::EqIdVal => {
-                                match framework_parser::ifr_eq_id_val(operation.Data.unwrap()) {
-                                    Ok((_, eqidval)) => {
-                                        write!(
-                                            &mut text,
-                                            "QuestionId: 0x{:X}, Value: 0x{:X}",
-                                            eqidval.QuestionId, eqidval.Value
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("EqIdVal parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x13: EqIdId
-                            framework_parser::IfrOpcode::EqIdId => {
-                                match framework_parser::ifr_eq_id_id(operation.Data.unwrap()) {
-                                    Ok((_, eqidid)) => {
-                                        write!(
-                                            &mut text,
-                                            "QuestionId1: 0x{:X}, QuestionId2: 0x{:X}",
-                                            eqidid.QuestionId1, eqidid.QuestionId2
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("EqIdId parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x14: EqIdList
-                            framework_parser::IfrOpcode::EqIdList => {
-                                match framework_parser::ifr_eq_id_list(operation.Data.unwrap()) {
-                                    Ok((_, eqidlist)) => {
-                                        write!(
-                                            &mut text,
-                                            "QuestionId: 0x{:X}, Width: 0x{:X}, List: {{",
-                                            eqidlist.QuestionId, eqidlist.Width
-                                        )
-                                        .unwrap();
-                                        for item in &eqidlist.List {
-                                            write!(&mut text, " 0x{:X},", *item).unwrap();
-                                        }
-                                        write!(&mut text, " }}").unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("EqIdList parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x15: And
-                            framework_parser::IfrOpcode::And => {}
-                            //0x16: Or
-                            framework_parser::IfrOpcode::Or => {}
-                            //0x17: Not
-                            framework_parser::IfrOpcode::Not => {}
-                            //0x18: EndIf
-                            framework_parser::IfrOpcode::EndIf => {}
-                            //0x19: GrayOutIf
-                            framework_parser::IfrOpcode::GrayOutIf => {
-                                match framework_parser::ifr_grayout_if(operation.Data.unwrap()) {
-                                    Ok((_, grif)) => {
-                                        write!(&mut text, "Flags: 0x{:X}", grif.Flags).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("GrayoutIf parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x1A: Date
-                            framework_parser::IfrOpcode::Date => {
-                                match framework_parser::ifr_date(operation.Data.unwrap()) {
-                                    Ok((_, date)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, Width: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}, Default: 0x{:X}",
-                                            strings_map.get(&date.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&date.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            date.QuestionId,
-                                            date.Width,
-                                            date.Flags,
-                                            date.Key,
-                                            date.Min,
-                                            date.Max,
-                                            date.Step,
-                                            date.Default
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Date parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x1B: Time
-                            framework_parser::IfrOpcode::Time => {
-                                match framework_parser::ifr_time(operation.Data.unwrap()) {
-                                    Ok((_, time)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, Width: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}, Min: 0x{:X}, Max: 0x{:X}, Step: 0x{:X}, Default: 0x{:X}",
-                                            strings_map.get(&time.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&time.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            time.QuestionId,
-                                            time.Width,
-                                            time.Flags,
-                                            time.Key,
-                                            time.Min,
-                                            time.Max,
-                                            time.Step,
-                                            time.Default
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Time parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x1C: String
-                            framework_parser::IfrOpcode::String => {
-                                match framework_parser::ifr_string(operation.Data.unwrap()) {
-                                    Ok((_, str)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, Width: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}, MinSize: 0x{:X}, MaxSize: 0x{:X}",
-                                            strings_map.get(&str.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&str.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            str.QuestionId,
-                                            str.Width,
-                                            str.Flags,
-                                            str.Key,
-                                            str.MinSize,
-                                            str.MaxSize
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("String parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x1D: Label
-                            framework_parser::IfrOpcode::Label => {
-                                match framework_parser::ifr_label(operation.Data.unwrap()) {
-                                    Ok((_, label)) => {
-                                        write!(&mut text, "LabelId: 0x{:X}", label.LabelId)
-                                            .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Label parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x1E: SaveDefaults
-                            framework_parser::IfrOpcode::SaveDefaults => {
-                                match framework_parser::ifr_save_defaults(operation.Data.unwrap()) {
-                                    Ok((_, sd)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", FormId: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}",
-                                            strings_map.get(&sd.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&sd.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            sd.FormId,
-                                            sd.Flags,
-                                            sd.Key
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("SaveDefaults parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x1F: RestoreDefaults
-                            framework_parser::IfrOpcode::RestoreDefaults => {
-                                match framework_parser::ifr_restore_defaults(
-                                    operation.Data.unwrap(),
-                                ) {
-                                    Ok((_, rd)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", FormId: 0x{:X}, Flags: 0x{:X}, Key: 0x{:X}",
-                                            strings_map.get(&rd.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&rd.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            rd.FormId,
-                                            rd.Flags,
-                                            rd.Key
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("RestoreDefaults parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x20: Banner
-                            framework_parser::IfrOpcode::Banner => {
-                                match framework_parser::ifr_banner(operation.Data.unwrap()) {
-                                    Ok((_, banner)) => {
-                                        write!(
-                                            &mut text,
-                                            "Title: \"{}\", LineNumber: 0x{:X}, Alignment: 0x{:X}",
-                                            strings_map
-                                                .get(&banner.TitleStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            banner.LineNumber,
-                                            banner.Alignment
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Banner parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x21: Inventory
-                            framework_parser::IfrOpcode::Inventory => {
-                                match framework_parser::ifr_inventory(operation.Data.unwrap()) {
-                                    Ok((_, inventory)) => {
-                                        write!(
-                                            &mut text,
-                                            "Text: \"{}\", TextTwo: \"{}\", Help: \"{}\"",
-                                            strings_map
-                                                .get(&inventory.TextStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&inventory.TextTwoStringId)
-                                                .unwrap_or(&String::from("InvalidId")),
-                                            strings_map
-                                                .get(&inventory.HelpStringId)
-                                                .unwrap_or(&String::from("InvalidId"))
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("Inventory parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x22: EqVarVal
-                            framework_parser::IfrOpcode::EqVarVal => {
-                                match framework_parser::ifr_eq_var_val(operation.Data.unwrap()) {
-                                    Ok((_, eqvarval)) => {
-                                        write!(
-                                            &mut text,
-                                            "VariableId: 0x{:X}, Value: 0x{:X}",
-                                            eqvarval.VariableId, eqvarval.Value
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("EqVarVal parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x23: OrderedList
-                            framework_parser::IfrOpcode::OrderedList => {
-                                match framework_parser::ifr_ordered_list(operation.Data.unwrap()) {
-                                    Ok((_, ol)) => {
-                                        write!(&mut text,
-                                            "Prompt: \"{}\", Help: \"{}\", QuestionId: 0x{:X}, MaxEntries: 0x{:X}",
-                                            strings_map.get(&ol.PromptStringId).unwrap_or(&String::from("InvalidId")),
-                                            strings_map.get(&ol.HelpStringId).unwrap_or(&String::from("InvalidId")),
-                                            ol.QuestionId,
-                                            ol.MaxEntries
-                                        ).unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("OrderedList parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x24: VarStore
-                            framework_parser::IfrOpcode::VarStore => {
-                                match framework_parser::ifr_var_store(operation.Data.unwrap()) {
-                                    Ok((_, vs)) => {
-                                        write!(
-                                            &mut text,
-                                            "VarstoreId: 0x{:X}, Guid: {}, Name: \"{}\", Size: 0x{:X}",
-                                            vs.VarStoreId, vs.Guid, vs.Name, vs.Size
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("VarStore parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x25: VarStoreSelect
-                            framework_parser::IfrOpcode::VarStoreSelect => {
-                                match framework_parser::ifr_var_store_select(
-                                    operation.Data.unwrap(),
-                                ) {
-                                    Ok((_, vss)) => {
-                                        write!(&mut text, "VarstoreId: 0x{:X}", vss.VarStoreId)
-                                            .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("VarStoreSelect parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x26: VarStoreSelectPair
-                            framework_parser::IfrOpcode::VarStoreSelectPair => {
-                                match framework_parser::ifr_var_store_select_pair(
-                                    operation.Data.unwrap(),
-                                ) {
-                                    Ok((_, vssp)) => {
-                                        write!(
-                                            &mut text,
-                                            "VarstoreId: 0x{:X}, SecondaryVarStoreId: 0x{:X}",
-                                            vssp.VarStoreId, vssp.SecondaryVarStoreId
-                                        )
-                                        .unwrap();
-                                    }
-                                    Err(e) => {
-                                        write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                            .unwrap();
-                                        println!("VarStoreSelectPair parse error: {e:?} at offset 0x{current_operation_offset:X}");
-                                    }
-                                }
-                            }
-                            //0x27: True
-                            framework_parser::IfrOpcode::True => {}
-                            //0x28: False
-                            framework_parser::IfrOpcode::False => {}
-                            //0x29: Greater
-                            framework_parser::IfrOpcode::Greater => {}
-                            //0x2A: GreaterEqual
-                            framework_parser::IfrOpcode::GreaterEqual => {}
-                            //0x2B: OemDefined
-                            framework_parser::IfrOpcode::OemDefined => {}
-                            //0xFE: Oem
-                            framework_parser::IfrOpcode::Oem => {}
-                            //0xFF: NvAccessCommand
-                            framework_parser::IfrOpcode::NvAccessCommand => {}
-                            //Unknown operation
-                            framework_parser::IfrOpcode::Unknown(x) => {
-                                write!(&mut text, "RawData: {:02X?}", operation.Data.unwrap())
-                                    .unwrap();
-                                println!("IFR operation of unknown type 0x{x:X}");
-                            }
-                        }
-                        current_operation_offset += operation.Length as usize;
-                        
-                        if verbose_mode {
-                            write!(&mut text, " {operation}").unwrap();
-                        }
-
-                        writeln!(&mut text).unwrap();
-                    }
-                }
-                Err(e) => {
-                    println!("IFR operations parse error: {e:?}");
-                }
+        let json = match env::args().nth(3).as_deref() {
+            None => false,
+            Some("json") => true,
+            Some(format) => {
+                println!("Unknown format {format}, expected json");
+                std::process::exit(1);
             }
+        };
+        let items = uefi_hidden_report(&data);
+        if json {
+            print!("{}", hidden_items_json(&items));
+        } else if items.is_empty() {
+            println!("No hidden UEFI HII forms or questions found");
+        } else {
+            print!("{}", hidden_items_text(&items));
         }
+        return;
     }
 
-    // Write the result
-    let mut file_path = OsString::new();
-    file_path.push(path);
-    file_path.push(".");
-    file_path.push(form_package_index.to_string());
-    file_path.push(".");
-    file_path.push(string_package_index.to_string());
-    file_path.push(".");
-    file_path.push(string_package.language.clone());
-    file_path.push(".ifr.txt");
-    let mut output_file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(&file_path)
-        .unwrap_or_else(|_| panic!("Can't create output file {:?}", &file_path));
-    output_file
-        .write_all(&text)
-        .unwrap_or_else(|_| panic!("{}", "Can't write to output file {file_path:?}"));
-}
-
-fn main() {
-    // Obtain program arguments
-    let mut args = std::env::args_os();
+    // Carve all packages into separate files, next to the input file by default
+    if (env::args().len() == 3 || env::args().len() == 4) && env::args().nth(2).as_deref() == Some("dump") {
+        let dir = match env::args_os().nth(3) {
+            Some(dir) => std::path::PathBuf::from(dir),
+            None => {
+                let mut dir = path.as_os_str().to_os_string();
+                dir.push(".packages");
+                std::path::PathBuf::from(dir)
+            }
+        };
+        let packages = dump_packages(&data, &dir).unwrap_or_else(|e| {
+            println!("Can't write packages to {}: {e}", dir.display());
+            std::process::exit(1);
+        });
+        if packages.is_empty() {
+            println!("No HII packages found");
+            std::process::exit(2);
+        }
+        println!("{} packages written to {}", packages.len(), dir.display());
+        return;
+    }
 
-    // Check if we have none
-    if args.len() <= 1 {
-        println!("
-IFRExtractor RS v{} - extracts HII string and form packages in UEFI Internal Form Representation (IFR) from a binary file into human-readable text
-Usage: ifrextractor file.bin list - list all string and form packages in the input file
-       ifrextractor file.bin single <form_package_number> <string_package_number> - extract a given form package using a given string package (use list command to obtain the package numbers)
-       ifrextractor file.bin lang <language> - extract all form packages using all string packages in a given language      
-       ifrextractor file.bin all - extract all form package using all string packages
-       ifrextractor file.bin verbose - extract all form packages using string packages in English, add raw bytes to all opcodes
-       ifrextractor file.bin - default extraction mode (only try string packages in English)", 
-        VERSION.unwrap_or("0.0.0"));
-        std::process::exit(1);
+    if (3..=5).contains(&env::args().len()) && env::args().nth(2).as_deref() == Some("images") {
+        let format = match env::args().nth(3) {
+            Some(name) => ImageFormat::from_name(&name).unwrap_or_else(|| {
+                println!("Unknown image format {name}, png or bmp expected");
+                std::process::exit(1);
+            }),
+            None => ImageFormat::default(),
+        };
+        let dir = match env::args_os().nth(4) {
+            Some(dir) => std::path::PathBuf::from(dir),
+            None => {
+                let mut dir = path.as_os_str().to_os_string();
+                dir.push(".images");
+                std::path::PathBuf::from(dir)
+            }
+        };
+        let images = export_images(&data, &dir, format).unwrap_or_else(|e| {
+            println!("Can't write images to {}: {e}", dir.display());
+            std::process::exit(1);
+        });
+        if images.is_empty() {
+            println!("No UEFI HII images found");
+            std::process::exit(2);
+        }
+        for image in &images {
+            println!(
+                "Package offset: 0x{:X}, ImageId: 0x{:X}, Width: {}, Height: {}, File: {}",
+                image.package_offset, image.image_id, image.width, image.height, image.file_name
+            );
+        }
+        println!("{} images written to {}", images.len(), dir.display());
+        return;
     }
 
-    // The only mandatory argument is a path to input file
-    let arg = args.nth(1).expect("Failed to obtain file path");
-    let path = Path::new(&arg);
+    // Scan metrics are reported even if nothing is found
+    let window_args: Vec<String> = env::args().skip(2).collect();
+    if (window_args.len() == 3 || window_args.len() == 4) && window_args[0] == "window" {
+        let numbers: Vec<usize> = window_args[1..]
+            .iter()
+            .map(|arg| {
+                match arg.strip_prefix("0x") {
+                    Some(hex) => usize::from_str_radix(hex, 16),
+                    None => arg.parse(),
+                }
+                .unwrap_or_else(|_| {
+                    println!("Can't parse {arg} as a number");
+                    std::process::exit(1);
+                })
+            })
+            .collect();
+        let options = ScanOptions {
+            start_offset: numbers[0],
+            end_offset: Some(numbers[1]),
+            alignment: numbers.get(2).copied().unwrap_or(1),
+            ..Default::default()
+        };
+        let mut uefi_metrics = ScanMetrics::new();
+        let (uefi_strings, uefi_forms) = find_uefi_packages_with_options(&data, &options, &mut uefi_metrics);
+        println!(
+            "UEFI HII: {} string packages, {} form packages",
+            uefi_strings.len(),
+            uefi_forms.len()
+        );
+        print!("{uefi_metrics}");
+        let mut framework_metrics = ScanMetrics::new();
+        let (framework_strings, framework_forms) =
+            find_framework_packages_with_options(&data, &options, &mut framework_metrics);
+        println!(
+            "Framework HII: {} string packages, {} form packages",
+            framework_strings.len(),
+            framework_forms.len()
+        );
+        print!("{framework_metrics}");
+        return;
+    }
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("metrics") {
+        let mut uefi_metrics = ScanMetrics::new();
+        let (uefi_strings, uefi_forms) = find_uefi_packages_with_metrics(&data, &mut uefi_metrics);
+        println!(
+            "UEFI HII: {} string packages, {} form packages",
+            uefi_strings.len(),
+            uefi_forms.len()
+        );
+        print!("{uefi_metrics}");
+        let mut framework_metrics = ScanMetrics::new();
+        let (framework_strings, framework_forms) = find_framework_packages_with_metrics(&data, &mut framework_metrics);
+        println!(
+            "Framework HII: {} string packages, {} form packages",
+            framework_strings.len(),
+            framework_forms.len()
+        );
+        print!("{framework_metrics}");
+        return;
+    }
 
-    // Open input file
-    let mut file = File::open(path).expect("Can't open input file");
+    // Find all string and form packages in both UEFI and Framework HII formats
+    let mut uefi_ifr_found = false;
+    let mut framework_ifr_found = false;
+    let (mut uefi_strings, mut uefi_forms) = (Vec::new(), Vec::new());
+    let (mut framework_strings, mut framework_forms) = (Vec::new(), Vec::new());
+    let (detected, summary) = find_packages_auto_with_summary(&data);
+    for packages in detected {
+        match packages.flavor {
+            HiiFlavor::Uefi => {
+                uefi_ifr_found = true;
+                uefi_strings = packages.strings;
+                uefi_forms = packages.forms;
+            }
+            HiiFlavor::Framework => {
+                framework_ifr_found = true;
+                framework_strings = packages.strings;
+                framework_forms = packages.forms;
+            }
+        }
+    }
 
-    // Read the whole file as binary data
-    let mut data = Vec::new();
-    file.read_to_end(&mut data).expect("Can't read input file");
+    // Find all image, animation and device path packages in UEFI HII format, they are only used to resolve references
+    let (uefi_images, uefi_animations, uefi_device_paths) = if uefi_ifr_found {
+        (
+            find_uefi_image_packages(&data),
+            find_uefi_animation_packages(&data),
+            find_uefi_device_path_packages(&data),
+        )
+    } else {
+        (Vec::new(), Vec::new(), Vec::new())
+    };
 
-    // Find all string and form packages in UEFI HII format
-    let mut uefi_ifr_found = true;
-    let (uefi_strings, uefi_forms) = uefi_find_string_and_form_packages(&data);
-    if uefi_strings.is_empty() || uefi_forms.is_empty() {
-        uefi_ifr_found = false;
+    // Scan summary is reported even if nothing is found
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("summary") {
+        let mut summary = summary;
+        summary.add_packages("UEFI image", uefi_images.len());
+        summary.add_packages("UEFI animation", uefi_animations.len());
+        summary.add_packages("UEFI device path", uefi_device_paths.len());
+        print!("{summary}");
+        return;
     }
 
-    // Find all string and form packages in Framework HII format
-    let mut framework_ifr_found = true;
-    let (framework_strings, framework_forms) = framework_find_string_and_form_packages(&data);
-    if framework_strings.is_empty() || framework_forms.is_empty() {
-        framework_ifr_found = false;
+    // Fonts are listed even if no IFR is found, i.e. for font drivers
+    if env::args().len() == 3 && env::args().nth(2).as_deref() == Some("fonts") {
+        let fonts = find_uefi_font_packages(&data);
+        let simple_fonts = find_uefi_simple_font_packages(&data);
+        if fonts.is_empty() && simple_fonts.is_empty() {
+            println!("No UEFI HII font packages found");
+            std::process::exit(2);
+        }
+        for (font_num, font) in fonts.iter().enumerate() {
+            println!(
+                "Font package {}: Offset: 0x{:X}, Length: 0x{:X}, Family: \"{}\", Style: {}, Cell: {}x{}, Glyphs: {}",
+                font_num,
+                font.offset,
+                font.length,
+                font.family,
+                font_style_names(font.style).join(" | "),
+                font.cell.Width,
+                font.cell.Height,
+                font.glyphs.len()
+            );
+        }
+        for (font_num, font) in simple_fonts.iter().enumerate() {
+            let wide = font.glyphs.iter().filter(|glyph| glyph.wide).count();
+            println!(
+                "Simple font package {}: Offset: 0x{:X}, Length: 0x{:X}, Narrow glyphs: {}, Wide glyphs: {}",
+                font_num,
+                font.offset,
+                font.length,
+                font.glyphs.len() - wide,
+                wide
+            );
+        }
+        for (string_num, string) in uefi_strings.iter().enumerate() {
+            let string_fonts = uefi_string_fonts(&data, string);
+            for declaration in &string_fonts.declarations {
+                let strings = string_fonts
+                    .string_fonts
+                    .values()
+                    .filter(|font_id| **font_id == declaration.font_id)
+                    .count();
+                println!(
+                    "String package {}, Language: {}: FontId: {}, Name: \"{}\", Size: {}, Style: {}, Strings: {}, Font package: {}",
+                    string_num,
+                    string.language,
+                    declaration.font_id,
+                    declaration.name,
+                    declaration.size,
+                    font_style_names(declaration.style).join(" | "),
+                    strings,
+                    declaration
+                        .font_package(&fonts)
+                        .and_then(|font| fonts.iter().position(|other| other.offset == font.offset))
+                        .map_or_else(|| String::from("None"), |font_num| font_num.to_string())
+                );
+            }
+        }
+        return;
     }
 
     // Exit early if nothing is found
@@ -3180,8 +580,16 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
 
     // Parse the other arguments
     let collected_args: Vec<String> = env::args().collect();
-    if collected_args.len() == 2 {
-        // Extract all form packages using all string packages with english language
+    let provenance = Provenance::new(
+        &path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        input.as_deref().unwrap_or(&data),
+        &collected_args[2..].join(" "),
+    );
+    if collected_args.len() == 2 || (collected_args.len() == 3 && collected_args[2] == "normalize") {
+        // Extract all form packages using all string packages with english language, repaired ones in normalize mode
         if uefi_ifr_found {
             println!("Extracting all UEFI HII form packages using en-US UEFI HII string packages");
             let mut found = false;
@@ -3189,14 +597,19 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
                 for (string_num, string) in uefi_strings.iter().enumerate() {
                     if string.language == "en-US" {
                         found = true;
-                        uefi_ifr_extract(
+                        write_output(
                             path.as_os_str(),
-                            &data,
-                            form,
+                            &provenance,
                             form_num,
                             string,
                             string_num,
-                            false
+                            &extract_uefi_and_report(
+                                &data,
+                                form,
+                                string,
+                                &uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form),
+                                &ExtractOptions::default(),
+                            ),
                         );
                     }
                 }
@@ -3209,19 +622,16 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
             println!("Extracting all Framework HII form packages using eng Framework HII string packages");
             let mut found = false;
             for (form_num, form) in framework_forms.iter().enumerate() {
-                for (string_num, string) in framework_strings.iter().enumerate() {
-                    if string.language == "eng" {
-                        found = true;
-                        framework_ifr_extract(
-                            path.as_os_str(),
-                            &data,
-                            form,
-                            form_num,
-                            string,
-                            string_num,
-                            false
-                        );
-                    }
+                for (string_num, string) in framework_string_packages(&data, &framework_strings, form, "eng") {
+                    found = true;
+                    write_output(
+                        path.as_os_str(),
+                        &provenance,
+                        form_num,
+                        string,
+                        string_num,
+                        &extract_framework_and_report(&data, form, string, &ExtractOptions::default()),
+                    );
                 }
             }
             if !found {
@@ -3229,23 +639,188 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
                 std::process::exit(2);
             }
         }
-    } else if collected_args.len() == 3 && collected_args[2] == "verbose" {
-        // Extract all form packages using all string packages with english language in verbose mode
+    } else if ((collected_args.len() == 3 || collected_args.len() == 4) && collected_args[2] == "verbose")
+        || (collected_args.len() == 3 && collected_args[2] == "ids")
+        || (collected_args.len() == 4
+            && (collected_args[2] == "only"
+                || collected_args[2] == "skip"
+                || collected_args[2] == "escape"
+                || collected_args[2] == "padding"
+                || collected_args[2] == "cache"
+                || collected_args[2] == "radix"
+                || collected_args[2] == "guids"
+                || collected_args[2] == "strings"
+                || collected_args[2] == "nested"))
+        || ((collected_args.len() == 4 || collected_args.len() == 5) && collected_args[2] == "indent")
+        || ((collected_args.len() == 4 || collected_args.len() == 5) && collected_args[2] == "help")
+        || ((collected_args.len() == 4 || collected_args.len() == 5) && collected_args[2] == "preview")
+    {
+        // Extract all form packages using all string packages with english language in verbose, ids, filtered, escape, indent, padding, cache, radix, guids, strings, nested, help or preview mode
+        let mode = collected_args[2].as_str();
+        let offset_base = match collected_args.get(3) {
+            Some(name) if mode == "verbose" => OffsetBase::from_name(name).unwrap_or_else(|| {
+                println!("Unknown offset base {name}, expected absolute, package or payload");
+                std::process::exit(1);
+            }),
+            _ => OffsetBase::Absolute,
+        };
+        let mut opcode_filter = OpcodeFilter::default();
+        if mode == "only" || mode == "skip" {
+            let categories: Vec<OpcodeCategory> = collected_args[3]
+                .split(',')
+                .map(|name| {
+                    OpcodeCategory::from_name(name).unwrap_or_else(|| {
+                        println!("Unknown opcode category {name}, expected form, question, option, statement, condition, expression, storage, default, modifier, extension or other");
+                        std::process::exit(1);
+                    })
+                })
+                .collect();
+            if mode == "only" {
+                opcode_filter.include = categories;
+            } else {
+                opcode_filter.exclude = categories;
+            }
+        }
+        let escaping = match collected_args.get(3) {
+            Some(name) if mode == "escape" => StringEscaping::from_name(name).unwrap_or_else(|| {
+                println!("Unknown escaping {name}, expected none, c or json");
+                std::process::exit(1);
+            }),
+            _ => StringEscaping::None,
+        };
+        let padding = match collected_args.get(3) {
+            Some(name) if mode == "padding" => PaddingHandling::from_name(name).unwrap_or_else(|| {
+                println!("Unknown padding handling {name}, expected stop, skip or record");
+                std::process::exit(1);
+            }),
+            _ => PaddingHandling::Stop,
+        };
+        let radix = match collected_args.get(3) {
+            Some(name) if mode == "radix" => NumericRadix::from_name(name).unwrap_or_else(|| {
+                println!("Unknown radix {name}, expected hex, dec or both");
+                std::process::exit(1);
+            }),
+            _ => NumericRadix::Hex,
+        };
+        let guid_format = match collected_args.get(3) {
+            Some(name) if mode == "guids" => GuidFormat::from_name(name).unwrap_or_else(|| {
+                println!("Unknown GUID format {name}, expected registry, c or token");
+                std::process::exit(1);
+            }),
+            _ => GuidFormat::Registry,
+        };
+        let help = match collected_args.get(3) {
+            Some(name) if mode == "help" => {
+                let width = match collected_args.get(4) {
+                    Some(width) => width.parse().ok().filter(|&width| width > 0).unwrap_or_else(|| {
+                        println!("Invalid help width {width}, expected a positive number");
+                        std::process::exit(1);
+                    }),
+                    None => 80,
+                };
+                HelpText::from_name(name, width).unwrap_or_else(|| {
+                    println!("Unknown help handling {name}, expected full, omit, truncate or wrap");
+                    std::process::exit(1);
+                })
+            }
+            _ => HelpText::Full,
+        };
+        let string_processors: Vec<StringProcessor> = match collected_args.get(3) {
+            Some(names) if mode == "strings" => names
+                .split(',')
+                .map(|name| {
+                    StringProcessor::from_name(name).unwrap_or_else(|| {
+                        println!("Unknown string processor {name}, expected strip-placeholders or collapse-whitespace");
+                        std::process::exit(1);
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let nested_ifr = match collected_args.get(3) {
+            Some(levels) if mode == "nested" => levels.parse().unwrap_or_else(|_| {
+                println!("Invalid nesting level count {levels}, expected a number");
+                std::process::exit(1);
+            }),
+            _ => 0,
+        };
+        let preview = match collected_args.get(3) {
+            Some(name) if mode == "preview" => {
+                let count = match collected_args.get(4) {
+                    Some(count) => count.parse().unwrap_or_else(|_| {
+                        println!("Invalid opcode count {count}, expected a number");
+                        std::process::exit(1);
+                    }),
+                    None => 20,
+                };
+                Preview::from_name(name, count).unwrap_or_else(|| {
+                    println!("Unknown preview {name}, expected full, headers or opcodes");
+                    std::process::exit(1);
+                })
+            }
+            _ => Preview::Full,
+        };
+        let mut indent = None;
+        let mut opcode_name_width = 0;
+        if mode == "indent" {
+            indent = match collected_args[3].as_str() {
+                "tab" => Some(String::from("\t")),
+                width => match width.parse::<usize>() {
+                    Ok(width) => Some(" ".repeat(width)),
+                    Err(_) => {
+                        println!("Unknown indentation {width}, expected tab or a number of spaces");
+                        std::process::exit(1);
+                    }
+                },
+            };
+            if let Some(width) = collected_args.get(4) {
+                opcode_name_width = width.parse().unwrap_or_else(|_| {
+                    println!("Invalid opcode name width {width}, expected a number");
+                    std::process::exit(1);
+                });
+            }
+        }
+        let options = ExtractOptions {
+            verbose: mode == "verbose",
+            offset_base,
+            show_string_ids: mode == "ids",
+            opcode_filter,
+            escaping,
+            indent,
+            opcode_name_width,
+            padding,
+            cache: match collected_args.get(3) {
+                Some(dir) if mode == "cache" => Some(std::path::PathBuf::from(dir)),
+                _ => None,
+            },
+            radix,
+            help,
+            string_processors,
+            nested_ifr,
+            preview,
+            guid_format,
+            ..Default::default()
+        };
         if uefi_ifr_found {
-            println!("Extracting all UEFI HII form packages using en-US UEFI HII string packages in verbose mode");
+            println!("Extracting all UEFI HII form packages using en-US UEFI HII string packages in {mode} mode");
             let mut found = false;
             for (form_num, form) in uefi_forms.iter().enumerate() {
                 for (string_num, string) in uefi_strings.iter().enumerate() {
                     if string.language == "en-US" {
                         found = true;
-                        uefi_ifr_extract(
+                        write_output(
                             path.as_os_str(),
-                            &data,
-                            form,
+                            &provenance,
                             form_num,
                             string,
                             string_num,
-                            true
+                            &extract_uefi_and_report(
+                                &data,
+                                form,
+                                string,
+                                &uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form),
+                                &options,
+                            ),
                         );
                     }
                 }
@@ -3255,22 +830,19 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
                 std::process::exit(2);
             }
         } else if framework_ifr_found {
-            println!("Extracting all Framework HII form packages using eng Framework HII string packages in vebose mode");
+            println!("Extracting all Framework HII form packages using eng Framework HII string packages in {mode} mode");
             let mut found = false;
             for (form_num, form) in framework_forms.iter().enumerate() {
-                for (string_num, string) in framework_strings.iter().enumerate() {
-                    if string.language == "eng" {
-                        found = true;
-                        framework_ifr_extract(
-                            path.as_os_str(),
-                            &data,
-                            form,
-                            form_num,
-                            string,
-                            string_num,
-                            true
-                        );
-                    }
+                for (string_num, string) in framework_string_packages(&data, &framework_strings, form, "eng") {
+                    found = true;
+                    write_output(
+                        path.as_os_str(),
+                        &provenance,
+                        form_num,
+                        string,
+                        string_num,
+                        &extract_framework_and_report(&data, form, string, &options),
+                    );
                 }
             }
             if !found {
@@ -3278,39 +850,532 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
                 std::process::exit(2);
             }
         }
+    } else if collected_args.len() == 3 && collected_args[2] == "questions" {
+        // Report QuestionId allocation of every formset
+        if !uefi_ifr_found {
+            println!("QuestionId report is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let string_package = uefi_preferred_string_package(&data, &uefi_strings, form);
+            for formset in uefi_question_id_report(&data, form, string_package) {
+                println!(
+                    "Form package {}, FormSet \"{}\", Guid: {}, Questions: {}",
+                    form_num,
+                    formset.title,
+                    formset.guid,
+                    formset.questions.len()
+                );
+                let ranges = |ranges: &[std::ops::RangeInclusive<u16>]| {
+                    if ranges.is_empty() {
+                        return String::from("None");
+                    }
+                    ranges
+                        .iter()
+                        .map(|range| format!("0x{:X}-0x{:X}", range.start(), range.end()))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                };
+                println!("    Used: {}", ranges(&formset.used));
+                println!("    Free: {}", ranges(&formset.gaps));
+                if !formset.duplicates.is_empty() {
+                    println!("    Duplicates: {:X?}", formset.duplicates);
+                }
+                for question in &formset.questions {
+                    println!(
+                        "    0x{:04X} {:<8} {:?} \"{}\" at offset 0x{:X}",
+                        question.question_id,
+                        if question.explicit { "explicit" } else { "auto" },
+                        question.opcode,
+                        question.prompt,
+                        question.offset
+                    );
+                }
+            }
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "variables" {
+        // Report external dependencies of expressions
+        if !uefi_ifr_found {
+            println!("Variable dependency report is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let string_package = uefi_preferred_string_package(&data, &uefi_strings, form);
+            let dependencies = uefi_variable_dependencies(&data, form, string_package);
+            println!("Form package {}: {} dependencies", form_num, dependencies.len());
+            for dependency in &dependencies {
+                let owner = dependency
+                    .owner
+                    .map(|owner| format!("{owner:?}"))
+                    .unwrap_or_else(|| String::from("None"));
+                match &dependency.reference {
+                    VariableReference::Variable {
+                        name,
+                        guid,
+                        var_store_id,
+                        var_store_type,
+                        var_store_info,
+                    } => println!(
+                        "    0x{:08X} {}: Variable \"{}\", Guid: {}, VarStoreId: 0x{:X}, VarStoreType: {:?}, VarStoreInfo: 0x{:X}",
+                        dependency.offset,
+                        owner,
+                        name,
+                        guid.map(|guid| guid.to_string()).unwrap_or_else(|| String::from("Undeclared")),
+                        var_store_id,
+                        var_store_type,
+                        var_store_info
+                    ),
+                    VariableReference::ExternalQuestion {
+                        device_path,
+                        formset_guid,
+                    } => println!(
+                        "    0x{:08X} {}: Question of DevicePath \"{}\", FormSetGuid: {}",
+                        dependency.offset,
+                        owner,
+                        device_path.as_deref().unwrap_or(""),
+                        formset_guid.map(|guid| guid.to_string()).unwrap_or_else(|| String::from("None"))
+                    ),
+                }
+            }
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "defaults" {
+        // Report default values of every question per default store
+        if !uefi_ifr_found {
+            println!("Default value report is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let string_package = match uefi_preferred_string_package(&data, &uefi_strings, form) {
+                Some(string_package) => string_package,
+                None => continue,
+            };
+            let resources = uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form);
+            let questions = question_defaults(&data, form, string_package, &resources);
+            println!("Form package {}: {} question(s) with defaults", form_num, questions.len());
+            for question in &questions {
+                println!(
+                    "    0x{:08X} {:?} \"{}\", QuestionId: 0x{:X}, Type: {}{}",
+                    question.offset,
+                    question.opcode,
+                    question.prompt.text.as_deref().unwrap_or(""),
+                    question.question_id,
+                    question.question_type.name(),
+                    question
+                        .bit_width
+                        .map(|bit_width| format!(", BitWidth: {bit_width}"))
+                        .unwrap_or_default()
+                );
+                for default in &question.defaults {
+                    println!(
+                        "        DefaultId: 0x{:X} \"{}\", Value: {}, Source: {:?} at 0x{:08X}",
+                        default.default_id,
+                        default.store_name,
+                        default
+                            .value
+                            .as_ref()
+                            .map(|value| value.to_string())
+                            .unwrap_or_else(|| String::from("Expression")),
+                        default.opcode,
+                        default.offset
+                    );
+                }
+            }
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "default_stores" {
+        // Report declared default stores, so defaults can be requested by name
+        if !uefi_ifr_found {
+            println!("DefaultStore report is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let string_package = match uefi_preferred_string_package(&data, &uefi_strings, form) {
+                Some(string_package) => string_package,
+                None => continue,
+            };
+            let resources = uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form);
+            let stores = default_stores(&data, form, string_package, &resources);
+            println!("Form package {}: {} DefaultStore(s)", form_num, stores.len());
+            for store in &stores {
+                println!(
+                    "    0x{:08X} FormSet: {}, DefaultId: 0x{:X}, Name: \"{}\", Class: {}",
+                    store.offset,
+                    store.formset_guid,
+                    store.default_id,
+                    store.name,
+                    store.default_class()
+                );
+            }
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "scopes" {
+        // Print parent links of all opcodes, End opcodes are not a part of the tree.
+        // Opcode indices with the package hash are stable identifiers of the opcodes
+        if !uefi_ifr_found {
+            println!("Scope tree is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let string_package = match uefi_preferred_string_package(&data, &uefi_strings, form) {
+                Some(string_package) => string_package,
+                None => continue,
+            };
+            let resources = uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form);
+            let tree = parse_uefi_ifr(&data, form, string_package, &resources);
+            let scopes = tree.scopes();
+            println!("Form package {}: {} opcode(s), Sha256: {}", form_num, scopes.len(), hex_string(&form.sha256));
+            for (index, entry) in scopes.iter().enumerate() {
+                // Symbolic names of flags that have any set
+                let flags: Vec<String> = entry
+                    .node
+                    .flag_fields()
+                    .iter()
+                    .filter(|field| !field.names.is_empty())
+                    .map(|field| format!("{}: {}", field.label, field.names.join("|")))
+                    .collect();
+                let mut line = format!(
+                    "    {}: Parent: {}, Opcode: {}, 0x{:08X} {}{:?} {}",
+                    index,
+                    entry.parent.map(|parent| parent.to_string()).unwrap_or_else(|| String::from("None")),
+                    entry.node.id.index,
+                    entry.node.offset,
+                    "\t".repeat(entry.depth),
+                    entry.node.opcode,
+                    entry.node.description.trim_start()
+                );
+                if !flags.is_empty() {
+                    line = format!("{} ({})", line.trim_end(), flags.join("; "));
+                }
+                println!("{}", line.trim_end());
+            }
+        }
+    } else if collected_args.len() == 4 && collected_args[2] == "nvram" {
+        // Compare default values with current values from an NVRAM dump
+        if !uefi_ifr_found {
+            println!("NVRAM comparison is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        let nvram = std::fs::read(&collected_args[3]).expect("Can't read NVRAM dump");
+        let variables = nvram_variables(&nvram);
+        if variables.is_empty() {
+            println!("No variables found in NVRAM dump");
+            std::process::exit(2);
+        }
+        let mut diagnostics = Diagnostics::new();
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let string_package = match uefi_preferred_string_package(&data, &uefi_strings, form) {
+                Some(string_package) => string_package,
+                None => continue,
+            };
+            let resources = uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form);
+            let comparisons =
+                compare_nvram_defaults(&data, form, string_package, &resources, &variables, &mut diagnostics);
+            println!(
+                "Form package {}: {} default(s) compared, {} differ from NVRAM",
+                form_num,
+                comparisons.len(),
+                comparisons.iter().filter(|comparison| !comparison.matches()).count()
+            );
+            for comparison in &comparisons {
+                println!(
+                    "    0x{:08X} {:?} \"{}\", QuestionId: 0x{:X}, {}, DefaultId: 0x{:X} \"{}\", Default: {}, Current: {}{}{}",
+                    comparison.offset,
+                    comparison.opcode,
+                    comparison.prompt.text.as_deref().unwrap_or(""),
+                    comparison.question_id,
+                    comparison.location(),
+                    comparison.default_id,
+                    comparison.store_name,
+                    comparison.default,
+                    comparison
+                        .current
+                        .as_ref()
+                        .map(|value| value.to_string())
+                        .unwrap_or_else(|| String::from("Unreadable")),
+                    comparison
+                        .current_text
+                        .as_ref()
+                        .map(|text| format!(" \"{text}\""))
+                        .unwrap_or_default(),
+                    if comparison.matches() { "" } else { " (differs)" }
+                );
+            }
+        }
+        print!("{diagnostics}");
+    } else if collected_args.len() == 4 && collected_args[2] == "string_diff" {
+        // Compare string packages with the ones of another image, matched by language and order
+        if !uefi_ifr_found {
+            println!("String comparison is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        let new_data = std::fs::read(&collected_args[3]).expect("Can't read the other image");
+        let (new_strings, _) = find_uefi_packages(&new_data);
+        for (string_num, string) in uefi_strings.iter().enumerate() {
+            // Packages of the same language are matched in order of appearance
+            let index = uefi_strings[..string_num]
+                .iter()
+                .filter(|other| other.language == string.language)
+                .count();
+            let new_string = match new_strings
+                .iter()
+                .filter(|other| other.language == string.language)
+                .nth(index)
+            {
+                Some(new_string) => new_string,
+                None => {
+                    println!("String package {}, Language: {}: not present in the other image", string_num, string.language);
+                    continue;
+                }
+            };
+            let diff = diff_strings(string, new_string);
+            println!(
+                "String package {}, Language: {}: {} added, {} removed, {} changed",
+                string_num,
+                string.language,
+                diff.added.len(),
+                diff.removed.len(),
+                diff.changed.len()
+            );
+            for entry in &diff.added {
+                println!("    Added 0x{:X}: \"{}\"", entry.string_id, entry.text);
+            }
+            for entry in &diff.removed {
+                println!("    Removed 0x{:X}: \"{}\"", entry.string_id, entry.text);
+            }
+            for change in &diff.changed {
+                println!("    Changed 0x{:X}: \"{}\" -> \"{}\"", change.string_id, change.old, change.new);
+            }
+        }
+    } else if (collected_args.len() == 4 || collected_args.len() == 5) && collected_args[2] == "borrow" {
+        // Extract form packages of package lists without string packages using strings of a sibling image,
+        // output files are named after the first borrowed package in the sibling image
+        if !uefi_ifr_found {
+            println!("Borrowing string packages is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        let language = collected_args.get(4).map_or("en-US", String::as_str);
+        let stripped = stripped_uefi_forms(&data, &uefi_forms);
+        if stripped.is_empty() {
+            println!("No UEFI HII form packages without string packages found");
+            std::process::exit(2);
+        }
+        let sibling_data = MappedInput::open(&collected_args[3]).expect("Can't open the sibling image");
+        let (sibling_strings, _) = find_uefi_packages(&sibling_data);
+        for (form_num, guid) in stripped {
+            let form = &uefi_forms[form_num];
+            match borrowed_string_package(&sibling_data, &sibling_strings, &guid, language) {
+                Some((indices, string)) => {
+                    println!(
+                        "Form package {form_num}, Package list: {guid}: borrowing {language} string package {} of the sibling image",
+                        indices[0]
+                    );
+                    write_output(
+                        path.as_os_str(),
+                        &provenance,
+                        form_num,
+                        &string,
+                        indices[0],
+                        &extract_uefi_and_report(
+                            &data,
+                            form,
+                            &string,
+                            &uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &[], form),
+                            &ExtractOptions::default(),
+                        ),
+                    );
+                }
+                None => println!("Form package {form_num}, Package list: {guid}: no {language} string packages in the sibling image"),
+            }
+        }
+    } else if (collected_args.len() == 4 || collected_args.len() == 5) && collected_args[2] == "setup_var" {
+        // Generate commands that read or change a question value
+        if !uefi_ifr_found {
+            println!("setup_var commands are only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        let query = &collected_args[3];
+        let value = collected_args.get(4).map(|arg| {
+            match arg.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => arg.parse(),
+            }
+            .expect("Can't parse value argument as a number")
+        });
+        let mut found = false;
+        for form in &uefi_forms {
+            let string_package = uefi_preferred_string_package(&data, &uefi_strings, form);
+            for commands in setup_var_commands(&knob_map(&data, form, string_package), query, value) {
+                found = true;
+                print!("{}", commands.script());
+            }
+        }
+        if !found {
+            println!("No question {query} stored in a named varstore found, the value doesn't fit it or the question is in a bit varstore");
+            std::process::exit(2);
+        }
+    } else if (collected_args.len() == 3 || collected_args.len() == 4) && collected_args[2] == "fingerprint" {
+        // Hash opcode structure of form packages, leaving out fields that differ between vendors
+        if !uefi_ifr_found {
+            println!("Fingerprints are only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        let mut options = FingerprintOptions::default();
+        if let Some(ignored) = collected_args.get(3) {
+            options.ignore_string_ids = false;
+            for field in ignored.split(',') {
+                match field {
+                    "strings" => options.ignore_string_ids = true,
+                    "guids" => options.ignore_guids = true,
+                    "none" => {}
+                    _ => {
+                        println!("Unknown ignored field: {field}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            match uefi_form_fingerprint(&data, form, &options) {
+                Some(hash) => println!(
+                    "Form package {} at offset 0x{:X}: {}",
+                    form_num,
+                    form.offset,
+                    hex_string(&hash)
+                ),
+                None => println!("Form package {} at offset 0x{:X}: can't be parsed", form_num, form.offset),
+            }
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "validate" {
+        // Check form packages in strict mode, exit with an error if there are any deviations
+        if !uefi_ifr_found {
+            println!("Validation is only supported for UEFI HII");
+            std::process::exit(2);
+        }
+        let mut total = 0;
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let deviations = validate_uefi_form_package(&data, form);
+            println!(
+                "Form package {} at offset 0x{:X}: {} deviation(s)",
+                form_num,
+                form.offset,
+                deviations.len()
+            );
+            for deviation in &deviations {
+                match deviation.opcode {
+                    Some(opcode) => println!("    0x{:08X} {:?}: {}", deviation.offset, opcode, deviation.message),
+                    None => println!("    0x{:08X}: {}", deviation.offset, deviation.message),
+                }
+            }
+            total += deviations.len();
+        }
+        if total > 0 {
+            std::process::exit(3);
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "package_lists" {
+        for list in uefi_package_lists(&data) {
+            println!(
+                "Package list: Offset: 0x{:X}, Length: 0x{:X}, Guid: {}",
+                list.offset,
+                list.length,
+                list.guid.map_or_else(|| String::from("None"), |guid| guid.to_string())
+            );
+            for package in &list.packages {
+                print!(
+                    "    Type: {:?}, Offset: 0x{:X}, Length: 0x{:X}",
+                    package.package_type, package.offset, package.length
+                );
+                match package.guid {
+                    Some(guid) => println!(", Guid: {guid}"),
+                    None if package.package_type == HiiPackageType::DevicePath => println!(
+                        ", DevicePath: {}",
+                        device_path_to_text(&package.data(&data)[4..]).unwrap_or_else(|| String::from("Invalid"))
+                    ),
+                    None => println!(),
+                }
+            }
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "device_paths" {
+        if uefi_device_paths.is_empty() {
+            println!("No UEFI HII device path packages found");
+        }
+        for (device_path_num, device_path) in uefi_device_paths.iter().enumerate() {
+            let forms: Vec<String> = uefi_forms
+                .iter()
+                .enumerate()
+                .filter(|(_, form)| uefi_same_package_list(&data, device_path.offset, form.offset))
+                .map(|(form_num, _)| form_num.to_string())
+                .collect();
+            println!(
+                "Device path package {}: Offset: 0x{:X}, Length: 0x{:X}, DevicePath: {}, Form packages: {}",
+                device_path_num,
+                device_path.offset,
+                device_path.length,
+                device_path.text,
+                if forms.is_empty() { String::from("None") } else { forms.join(", ") }
+            );
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "guid_packages" {
+        let guid_packages = find_uefi_guid_packages(&data);
+        if guid_packages.is_empty() {
+            println!("No UEFI HII GUID packages found");
+        }
+        for (package_num, package) in guid_packages.iter().enumerate() {
+            println!(
+                "GUID package {}: Offset: 0x{:X}, Length: 0x{:X}, Guid: {}, Package list: {}, Payload: 0x{:X} bytes, SHA256: {}",
+                package_num,
+                package.offset,
+                package.length,
+                package.guid,
+                package.package_list_guid.map_or_else(|| String::from("None"), |guid| guid.to_string()),
+                package.payload(&data).len(),
+                hex_string(&package.sha256)
+            );
+        }
+    } else if collected_args.len() == 3 && collected_args[2] == "languages" {
+        for (language, count) in list_languages(&data) {
+            println!("Language: {language}, String packages: {count}");
+        }
     } else if collected_args.len() == 3 && collected_args[2] == "list" {
         if uefi_ifr_found {
             println!("UEFI HII form packages:");
             for (form_num, form) in uefi_forms.iter().enumerate() {
-                println!("Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Used strings: {}, Min StringId: 0x{:X}, Max StringId: 0x{:X}",
-                        form_num, form.offset, form.length, form.used_strings, form.min_string_id, form.max_string_id);
+                println!("Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Used strings: {}, Min StringId: 0x{:X}, Max StringId: 0x{:X}, Questions: {}, Opcodes: {}, Estimated text size: 0x{:X}, SHA256: {}",
+                        form_num, form.offset, form.length, form.used_strings, form.min_string_id, form.max_string_id,
+                        form.question_count, form.opcode_count, form.estimated_text_size, hex_string(&form.sha256));
             }
             println!("UEFI HII string packages:");
             for (string_num, string) in uefi_strings.iter().enumerate() {
                 println!(
-                    "Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Language: {}, Total strings: {}",
+                    "Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Language: {}, Total strings: {}, SHA256: {}",
                     string_num,
                     string.offset,
                     string.length,
                     string.language,
-                    string.string_id_map.len()
+                    string.string_id_map.len(),
+                    hex_string(&string.sha256)
                 );
             }
         } else if framework_ifr_found {
             println!("Framework HII form packages:");
             for (form_num, form) in framework_forms.iter().enumerate() {
-                println!("Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Used strings: {}, Min StringId: 0x{:X}, Max StringId: 0x{:X}",
-                        form_num, form.offset, form.length, form.used_strings, form.min_string_id, form.max_string_id);
+                println!("Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Used strings: {}, Min StringId: 0x{:X}, Max StringId: 0x{:X}, Questions: {}, Opcodes: {}, Estimated text size: 0x{:X}, SHA256: {}",
+                        form_num, form.offset, form.length, form.used_strings, form.min_string_id, form.max_string_id,
+                        form.question_count, form.opcode_count, form.estimated_text_size, hex_string(&form.sha256));
+                for var_store in &form.framework_var_stores {
+                    println!(
+                        "    VarStore: Id: 0x{:X}, Guid: {}, Name: {}, Size: 0x{:X}",
+                        var_store.var_store_id, var_store.guid, var_store.name, var_store.size
+                    );
+                }
             }
             println!("Framework HII string packages:");
             for (string_num, string) in framework_strings.iter().enumerate() {
                 println!(
-                    "Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Language: {}, Total strings: {}",
+                    "Index: {}, Offset: 0x{:X}, Length: 0x{:X}, Language: {}, Total strings: {}, SHA256: {}",
                     string_num,
                     string.offset,
                     string.length,
                     string.language,
-                    string.string_id_map.len()
+                    string.string_id_map.len(),
+                    hex_string(&string.sha256)
                 );
             }
         }
@@ -3319,21 +1384,33 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
             println!("Extracting all UEFI HII form packages using all UEFI HII string packages");
             for (form_num, form) in uefi_forms.iter().enumerate() {
                 for (string_num, string) in uefi_strings.iter().enumerate() {
-                    uefi_ifr_extract(path.as_os_str(), &data, form, form_num, string, string_num, false);
+                    write_output(
+                        path.as_os_str(),
+                        &provenance,
+                        form_num,
+                        string,
+                        string_num,
+                        &extract_uefi_and_report(
+                            &data,
+                            form,
+                            string,
+                            &uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form),
+                            &ExtractOptions::default(),
+                        ),
+                    );
                 }
             }
         } else if framework_ifr_found {
             println!("Extracting all Framework HII form packages using all Framework HII string packages");
             for (form_num, form) in framework_forms.iter().enumerate() {
                 for (string_num, string) in framework_strings.iter().enumerate() {
-                    framework_ifr_extract(
+                    write_output(
                         path.as_os_str(),
-                        &data,
-                        form,
+                        &provenance,
                         form_num,
                         string,
                         string_num,
-                        false
+                        &extract_framework_and_report(&data, form, string, &ExtractOptions::default()),
                     );
                 }
             }
@@ -3350,14 +1427,19 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
                 for (string_num, string) in uefi_strings.iter().enumerate() {
                     if string.language == collected_args[3] {
                         found = true;
-                        uefi_ifr_extract(
+                        write_output(
                             path.as_os_str(),
-                            &data,
-                            form,
+                            &provenance,
                             form_num,
                             string,
                             string_num,
-                            false
+                            &extract_uefi_and_report(
+                                &data,
+                                form,
+                                string,
+                                &uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form),
+                                &ExtractOptions::default(),
+                            ),
                         );
                     }
                 }
@@ -3373,19 +1455,16 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
             );
             let mut found = false;
             for (form_num, form) in framework_forms.iter().enumerate() {
-                for (string_num, string) in framework_strings.iter().enumerate() {
-                    if string.language == collected_args[3] {
-                        found = true;
-                        framework_ifr_extract(
-                            path.as_os_str(),
-                            &data,
-                            form,
-                            form_num,
-                            string,
-                            string_num,
-                            false
-                        );
-                    }
+                for (string_num, string) in framework_string_packages(&data, &framework_strings, form, &collected_args[3]) {
+                    found = true;
+                    write_output(
+                        path.as_os_str(),
+                        &provenance,
+                        form_num,
+                        string,
+                        string_num,
+                        &extract_framework_and_report(&data, form, string, &ExtractOptions::default()),
+                    );
                 }
             }
             if !found {
@@ -3396,7 +1475,95 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
                 std::process::exit(2);
             }
         }
-    } else if collected_args.len() == 5 && collected_args[2] == "single" {
+    } else if collected_args.len() == 4 && collected_args[2] == "combined" {
+        // Extract all form packages using all string packages in a given language combined into one,
+        // output files are named after the first combined package
+        let language = &collected_args[3];
+        if uefi_ifr_found {
+            println!("Extracting all UEFI HII form packages using combined {language} string packages");
+            let mut found = false;
+            for (form_num, form) in uefi_forms.iter().enumerate() {
+                if let Some((indices, string)) = uefi_combined_string_package(&data, &uefi_strings, form, language) {
+                    found = true;
+                    write_output(
+                        path.as_os_str(),
+                        &provenance,
+                        form_num,
+                        &string,
+                        indices[0],
+                        &extract_uefi_and_report(
+                            &data,
+                            form,
+                            &string,
+                            &uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form),
+                            &ExtractOptions::default(),
+                        ),
+                    );
+                }
+            }
+            if !found {
+                println!("No {language} UEFI HII string packages found");
+                std::process::exit(2);
+            }
+        } else if framework_ifr_found {
+            println!("Extracting all Framework HII form packages using combined {language} Framework HII string packages");
+            let mut found = false;
+            for (form_num, form) in framework_forms.iter().enumerate() {
+                if let Some((indices, string)) = framework_combined_string_package(&data, &framework_strings, form, language) {
+                    found = true;
+                    write_output(
+                        path.as_os_str(),
+                        &provenance,
+                        form_num,
+                        &string,
+                        indices[0],
+                        &extract_framework_and_report(&data, form, &string, &ExtractOptions::default()),
+                    );
+                }
+            }
+            if !found {
+                println!("No {language} Framework HII string packages found");
+                std::process::exit(2);
+            }
+        }
+    } else if (collected_args.len() == 3 || (collected_args.len() == 4 && collected_args[3] == "combined"))
+        && collected_args[2] == "all_languages"
+    {
+        // Extract all UEFI form packages once per language of their package list, parsing each of them once
+        if !uefi_ifr_found {
+            println!("No UEFI HII form packages found");
+            std::process::exit(2);
+        }
+        let combined = collected_args.len() == 4;
+        println!("Extracting all UEFI HII form packages in all languages");
+        for (form_num, form) in uefi_forms.iter().enumerate() {
+            let resources = uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, form);
+            let extractions = extract_uefi_ifr_all_languages(&data, form, &resources, &ExtractOptions::default());
+            // Diagnostics are the same for all languages
+            if let Some(first) = extractions.first() {
+                print!("{}", first.diagnostics);
+            }
+            if combined {
+                match combine_language_extractions(&extractions) {
+                    Some(text) => print!("Form package {form_num}:\n{text}"),
+                    None => println!("Form package {form_num}: extractions of its languages don't line up"),
+                }
+                continue;
+            }
+            for extraction in &extractions {
+                let string_num = extraction.string_packages[0];
+                write_output(path.as_os_str(), &provenance, form_num, &uefi_strings[string_num], string_num, &extraction.text);
+            }
+        }
+    } else if (collected_args.len() == 5 || collected_args.len() == 6) && collected_args[2] == "single" {
+        // Optional FormId to extract a single form of the form package
+        let form_id = collected_args.get(5).map(|arg| {
+            match arg.strip_prefix("0x") {
+                Some(hex) => u16::from_str_radix(hex, 16),
+                None => arg.parse(),
+            }
+            .expect("Can't parse form_id argument as a number")
+        });
         if uefi_ifr_found {
             // Extract the exact single combination
             let form_package_num: usize = collected_args[3]
@@ -3424,14 +1591,31 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
             println!(
                 "Extracting UEFI HII form package #{form_package_num} using UEFI HII string package #{string_package_num}"
             );
-            uefi_ifr_extract(
+            let resources = uefi_resources(&data, &uefi_images, &uefi_animations, &uefi_device_paths, &uefi_strings, &uefi_forms[form_package_num]);
+            let text = match form_id {
+                Some(form_id) => extract_uefi_form_and_report(
+                    &data,
+                    &uefi_forms[form_package_num],
+                    form_id,
+                    &uefi_strings[string_package_num],
+                    &resources,
+                    &ExtractOptions::default(),
+                ),
+                None => extract_uefi_and_report(
+                    &data,
+                    &uefi_forms[form_package_num],
+                    &uefi_strings[string_package_num],
+                    &resources,
+                    &ExtractOptions::default(),
+                ),
+            };
+            write_output(
                 path.as_os_str(),
-                &data,
-                &uefi_forms[form_package_num],
+                &provenance,
                 form_package_num,
                 &uefi_strings[string_package_num],
                 string_package_num,
-                false
+                &text,
             );
         } else if framework_ifr_found {
             let form_package_num: usize = collected_args[3]
@@ -3456,17 +1640,19 @@ Usage: ifrextractor file.bin list - list all string and form packages in the inp
                 );
                 std::process::exit(4);
             }
+            if form_id.is_some() {
+                println!("Single form extraction is only supported for UEFI HII, extracting the whole form package");
+            }
             println!(
                 "Extracting Framework HII form package #{form_package_num} using Framework HII string package #{string_package_num}"
             );
-            framework_ifr_extract(
+            write_output(
                 path.as_os_str(),
-                &data,
-                &framework_forms[form_package_num],
+                &provenance,
                 form_package_num,
                 &framework_strings[string_package_num],
                 string_package_num,
-                false
+                &extract_framework_and_report(&data, &framework_forms[form_package_num], &framework_strings[string_package_num], &ExtractOptions::default()),
             );
         }
     } else {
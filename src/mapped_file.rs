@@ -0,0 +1,137 @@
+// Input data read from a file, or memory-mapped on request so large images are scanned without reading
+// them into memory first. Mapping falls back to reading the whole file on platforms without mmap
+// and for pipes, devices and other files that aren't regular ones
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::ops::Deref;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+
+enum Contents {
+    #[cfg(unix)]
+    Mapped {
+        address: *mut libc::c_void,
+        length: usize,
+    },
+    Owned(Vec<u8>),
+}
+
+// Read-only view of input data, either mapped from a file or owned
+pub struct MappedInput {
+    contents: Contents,
+}
+
+// Mapping is private and read-only, so it can be shared between threads like a Vec<u8>
+unsafe impl Send for MappedInput {}
+unsafe impl Sync for MappedInput {}
+
+fn read_file(mut file: File) -> io::Result<MappedInput> {
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(MappedInput::from(data))
+}
+
+impl MappedInput {
+    // Read the whole file into memory
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<MappedInput> {
+        read_file(File::open(path)?)
+    }
+
+    /// Map a regular file into memory, other files are read.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated or modified by this or any other process while the returned data
+    /// or anything borrowed from it is alive, accessing the mapping would be undefined behavior then
+    #[cfg(unix)]
+    pub unsafe fn map<P: AsRef<Path>>(path: P) -> io::Result<MappedInput> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let length = metadata.len() as usize;
+        // Pipes and devices report no length, empty mappings are not allowed
+        if !metadata.is_file() || length == 0 {
+            return read_file(file);
+        }
+        let address = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                length,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(MappedInput {
+            contents: Contents::Mapped { address, length },
+        })
+    }
+
+    /// Same as open, there is no mmap.
+    ///
+    /// # Safety
+    ///
+    /// Nothing is mapped, it is unsafe only to have the same signature on all platforms
+    #[cfg(not(unix))]
+    pub unsafe fn map<P: AsRef<Path>>(path: P) -> io::Result<MappedInput> {
+        MappedInput::open(path)
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self.contents, Contents::Owned(_))
+    }
+}
+
+impl From<Vec<u8>> for MappedInput {
+    fn from(data: Vec<u8>) -> MappedInput {
+        MappedInput {
+            contents: Contents::Owned(data),
+        }
+    }
+}
+
+impl Deref for MappedInput {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.contents {
+            #[cfg(unix)]
+            Contents::Mapped { address, length } => unsafe {
+                std::slice::from_raw_parts(*address as *const u8, *length)
+            },
+            Contents::Owned(data) => data,
+        }
+    }
+}
+
+impl AsRef<[u8]> for MappedInput {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for MappedInput {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Contents::Mapped { address, length } = self.contents {
+            unsafe {
+                libc::munmap(address, length);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for MappedInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("MappedInput")
+            .field("length", &self.len())
+            .field("mapped", &self.is_mapped())
+            .finish()
+    }
+}
@@ -0,0 +1,260 @@
+// Optional repair of known vendor quirks in UEFI form packages, so the strict parser accepts them:
+// package lengths off by one, opcode lengths off by one and End opcodes missing at the package tail.
+// The result is a repaired copy of the input, inserted End opcodes shift everything after them
+use std::fmt;
+
+use diagnostics::Diagnostics;
+use uefi_parser;
+
+// Single repair, offsets are in the original input data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    PackageLength {
+        offset: usize,
+        old: usize,
+        new: usize,
+    }, // Offset of the form package
+    OpcodeLength {
+        offset: usize,
+        opcode: uefi_parser::IfrOpcode,
+        old: u8,
+        new: u8,
+    }, // Offset of the opcode
+    MissingEnd {
+        offset: usize,
+        count: usize,
+    }, // End opcodes are inserted at this offset
+}
+
+impl Repair {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Repair::PackageLength { .. } => "Form package length repaired",
+            Repair::OpcodeLength { .. } => "Opcode length repaired",
+            Repair::MissingEnd { .. } => "Missing End opcodes added",
+        }
+    }
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Repair::PackageLength { offset, old, new } => {
+                write!(
+                    f,
+                    "0x{new:X} instead of 0x{old:X} for the package at offset 0x{offset:X}"
+                )
+            }
+            Repair::OpcodeLength {
+                offset,
+                opcode,
+                old,
+                new,
+            } => {
+                write!(
+                    f,
+                    "0x{new:X} instead of 0x{old:X} for {opcode:?} at offset 0x{offset:X}"
+                )
+            }
+            Repair::MissingEnd { offset, count } => write!(f, "{count} at offset 0x{offset:X}"),
+        }
+    }
+}
+
+// Operation at the start of input that is known and fits into it, with its length
+fn known_operation(input: &[u8]) -> Option<(uefi_parser::IfrOpcode, usize, bool)> {
+    match uefi_parser::ifr_operation(input) {
+        Ok((_, operation)) if !matches!(operation.OpCode, uefi_parser::IfrOpcode::Unknown(_)) => {
+            Some((
+                operation.OpCode,
+                operation.Length as usize,
+                operation.ScopeStart,
+            ))
+        }
+        _ => None,
+    }
+}
+
+// Given number of operations starting at offset are known, or the package ends at the expected offset
+// or at the end of the data before that
+fn plausible_at(payload: &[u8], offset: usize, end: usize, count: usize) -> bool {
+    let mut offset = offset;
+    for _ in 0..count {
+        if offset == end || offset == payload.len() {
+            return true;
+        }
+        match payload.get(offset..).and_then(known_operation) {
+            Some((_, length, _)) => offset += length,
+            None => return false,
+        }
+    }
+    true
+}
+
+// Repairs of a single form package candidate, payload is the package data after its header
+// with one more byte if available, end is the payload length according to the header
+fn repair_package(payload: &[u8], end: usize, package_offset: usize) -> Vec<Repair> {
+    let mut repairs = Vec::new();
+    let mut offset = 0;
+    let mut depth: usize = 0;
+    while offset < payload.len() {
+        let (opcode, length, scope_start) = match known_operation(&payload[offset..]) {
+            Some(operation) => operation,
+            None => break,
+        };
+        if opcode == uefi_parser::IfrOpcode::End {
+            depth = depth.saturating_sub(1);
+        } else if scope_start {
+            depth += 1;
+        }
+        // Nothing follows the End of the formset, its length is checked against the package length instead
+        if depth == 0 {
+            offset += length;
+            break;
+        }
+        // Opcode length off by one makes the next operation garbage, while the shifted one starts a few plausible ones
+        let mut length = length;
+        if !plausible_at(payload, offset + length, end, 1) {
+            let fixed = [length + 1, length - 1].iter().copied().find(|fixed| {
+                (2..=0x7F).contains(fixed)
+                    && offset + fixed <= payload.len()
+                    && plausible_at(payload, offset + fixed, end, 3)
+            });
+            if let Some(fixed) = fixed {
+                repairs.push(Repair::OpcodeLength {
+                    offset: package_offset + 4 + offset,
+                    opcode,
+                    old: length as u8,
+                    new: fixed as u8,
+                });
+                length = fixed;
+            }
+        }
+        offset += length;
+    }
+
+    if depth == 0 && offset != end && offset.abs_diff(end) == 1 {
+        // Package length off by one
+        repairs.push(Repair::PackageLength {
+            offset: package_offset,
+            old: end + 4,
+            new: offset + 4,
+        });
+    } else if depth > 0 && offset == end {
+        // Package ends with open scopes
+        repairs.push(Repair::MissingEnd {
+            offset: package_offset + 4 + end,
+            count: depth,
+        });
+    } else if depth > 0 || offset != end {
+        // Can't be repaired
+        repairs.clear();
+    }
+    repairs
+}
+
+// Form package with repairs applied, the input starts with its header
+fn apply_repairs(package: &[u8], package_offset: usize, repairs: &[Repair]) -> Vec<u8> {
+    let mut result = package.to_vec();
+    let mut length = package.len();
+    for repair in repairs {
+        match repair {
+            Repair::PackageLength { new, .. } => {
+                length = *new;
+                result.resize(length, 0);
+            }
+            Repair::OpcodeLength { offset, new, .. } => {
+                let index = offset - package_offset + 1;
+                result[index] = (result[index] & 0x80) | new;
+            }
+            Repair::MissingEnd { count, .. } => {
+                for _ in 0..*count {
+                    result.extend_from_slice(&[0x29, 0x02]);
+                }
+                length = result.len();
+            }
+        }
+    }
+    let header = (length as u32 & 0x00FF_FFFF) | 0x0200_0000;
+    result[..4].copy_from_slice(&header.to_le_bytes());
+    result
+}
+
+// Package must be accepted by the strict parser, parse completely and close the formset scope with its last opcode
+fn accepted(package: &[u8]) -> bool {
+    let operations = match uefi_parser::hii_form_package_candidate(package)
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::ifr_operations(&candidate[4..]).ok())
+    {
+        Some(([], operations)) => operations,
+        _ => return false,
+    };
+    let mut depth: usize = 0;
+    for (index, operation) in operations.iter().enumerate() {
+        if operation.OpCode == uefi_parser::IfrOpcode::End {
+            depth = depth.saturating_sub(1);
+        } else if operation.ScopeStart {
+            depth += 1;
+        }
+        if depth == 0 {
+            return index == operations.len() - 1;
+        }
+    }
+    false
+}
+
+// Copy of the input with all repairable UEFI form packages repaired, and the list of repairs done
+pub fn normalize_uefi_form_packages(data: &[u8]) -> (Vec<u8>, Vec<Repair>) {
+    let mut result = Vec::with_capacity(data.len());
+    let mut repairs = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i + 6 <= data.len() {
+        let header = u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        let length = (header & 0x00FF_FFFF) as usize;
+        // Package type 0x02 starting with a FormSet opcode
+        if header >> 24 != 0x02 || data[i + 4] != 0x0E || length <= 6 || i + length > data.len() {
+            i += 1;
+            continue;
+        }
+        if accepted(&data[i..i + length]) {
+            i += length;
+            continue;
+        }
+        let payload = &data[i + 4..data.len().min(i + length + 1)];
+        let package_repairs = repair_package(payload, length - 4, i);
+        if package_repairs.is_empty() {
+            i += 1;
+            continue;
+        }
+        // Package one byte longer than its header says takes that byte too
+        let package_end = package_repairs
+            .iter()
+            .find_map(|repair| match repair {
+                Repair::PackageLength { new, .. } => Some(i + new),
+                _ => None,
+            })
+            .map_or(i + length, |end| end.max(i + length));
+        let repaired = apply_repairs(&data[i..package_end], i, &package_repairs);
+        if !accepted(&repaired) {
+            i += 1;
+            continue;
+        }
+        result.extend_from_slice(&data[copied..i]);
+        result.extend_from_slice(&repaired);
+        // Extra byte of a package one byte shorter than its header says is kept after it,
+        // so only inserted End opcodes shift the rest of the input
+        copied = i + repaired.len().min(package_end - i);
+        repairs.extend(package_repairs);
+        i = copied;
+    }
+    result.extend_from_slice(&data[copied..]);
+    (result, repairs)
+}
+
+// Record repairs as diagnostics, the first one of every kind is kept with its details
+pub fn report_repairs(repairs: &[Repair], diagnostics: &mut Diagnostics) {
+    for repair in repairs {
+        diagnostics.warn(repair.kind(), format_args!("{repair}"));
+    }
+}
@@ -0,0 +1,403 @@
+// Comparison of question defaults with current values read from an NVRAM dump. Values are read with the width
+// and storage a question declares, bit fields of EdkiiIfrBitVarstore questions included, and compared as numbers
+use super::{FormPackage, HiiResources, QuestionType, StringPackage};
+use diagnostics::Diagnostics;
+use ifr_tree::{parse_uefi_ifr, IfrNode, ResolvedString};
+use knob_map::value_type_size;
+use option_diff::{question_option_sets, resolve_option_text};
+use question_defaults::question_defaults;
+use question_value::{decode_question_value, QuestionStorage, QuestionValueType};
+use uefi_parser;
+use var_stores::uefi_var_stores;
+
+// EFI variable found in a VSS variable store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NvramVariable {
+    pub offset: usize, // Offset of the variable header in the NVRAM dump
+    pub name: String,
+    pub guid: uefi_parser::Guid,
+    pub attributes: u32,
+    pub data: Vec<u8>,
+}
+
+const VARIABLE_START_ID: [u8; 2] = [0xAA, 0x55];
+const VAR_ADDED: u8 = 0x3F;
+const VAR_ADDED_IN_DELETED_TRANSITION: u8 = 0x3E; // Old instance of a variable being updated
+
+// Variable at the start of input with its State and total length. Authenticated variables have
+// MonotonicCount, TimeStamp and PubKeyIndex between Attributes and NameSize
+fn vss_variable(input: &[u8]) -> Option<(u8, NvramVariable, usize)> {
+    if !input.starts_with(&VARIABLE_START_ID) || input.len() < 8 {
+        return None;
+    }
+    let dword = |index: usize| {
+        input
+            .get(index..index + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    // Header length and offset of NameSize, DataSize and VendorGuid follow it
+    for (header_length, name_size_offset) in [(32, 8), (60, 36)] {
+        let (name_size, data_size) = match (dword(name_size_offset), dword(name_size_offset + 4)) {
+            (Some(name_size), Some(data_size)) => (name_size as usize, data_size as usize),
+            _ => continue,
+        };
+        let length = header_length + name_size + data_size;
+        if name_size < 2 || !name_size.is_multiple_of(2) || length > input.len() {
+            continue;
+        }
+        // Name is a null-terminated UCS-2 string
+        let name: Vec<u16> = input[header_length..header_length + name_size]
+            .chunks(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+        let (terminator, name) = match name.split_last() {
+            Some(split) => split,
+            None => continue,
+        };
+        if *terminator != 0 || name.contains(&0) {
+            continue;
+        }
+        let name = match String::from_utf16(name) {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let guid = match uefi_parser::guid(&input[name_size_offset + 8..]) {
+            Ok((_, guid)) => guid,
+            Err(_) => continue,
+        };
+        let variable = NvramVariable {
+            offset: 0,
+            name,
+            guid,
+            attributes: dword(4).unwrap_or(0),
+            data: input[header_length + name_size..length].to_vec(),
+        };
+        return Some((input[2], variable, length));
+    }
+    None
+}
+
+// Variables of all VSS variable stores in an NVRAM dump. Deleted variables are skipped,
+// the last added instance of a variable replaces the previous ones
+pub fn nvram_variables(data: &[u8]) -> Vec<NvramVariable> {
+    let mut result: Vec<(u8, NvramVariable)> = Vec::new();
+    let mut i = 0;
+    while i + 2 <= data.len() {
+        let (state, mut variable, length) = match vss_variable(&data[i..]) {
+            Some(found) => found,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        variable.offset = i;
+        i += length;
+        if state != VAR_ADDED && state != VAR_ADDED_IN_DELETED_TRANSITION {
+            continue;
+        }
+        match result
+            .iter_mut()
+            .find(|(_, other)| other.name == variable.name && other.guid == variable.guid)
+        {
+            // Instance in deleted transition is only used until the new one is found
+            Some(other) => {
+                if state == VAR_ADDED || other.0 != VAR_ADDED {
+                    *other = (state, variable);
+                }
+            }
+            None => result.push((state, variable)),
+        }
+    }
+    result.into_iter().map(|(_, variable)| variable).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NvramComparison {
+    pub offset: usize, // Offset of the question opcode in the input data
+    pub opcode: uefi_parser::IfrOpcode,
+    pub question_type: QuestionType,
+    pub question_id: u16,
+    pub prompt: ResolvedString,
+    pub variable: String, // Name of the EFI variable of the varstore
+    pub storage: QuestionStorage,
+    pub default_id: u16,
+    pub store_name: String,
+    pub default: uefi_parser::IfrTypeValue,
+    pub current: Option<uefi_parser::IfrTypeValue>, // None if it can't be read with the declared storage
+    pub current_text: Option<String>, // Option selected by the current value, OneOf questions only
+}
+
+// Value of numeric and boolean values
+fn numeric_value(value: &uefi_parser::IfrTypeValue) -> Option<u64> {
+    match *value {
+        uefi_parser::IfrTypeValue::NumSize8(x) => Some(x as u64),
+        uefi_parser::IfrTypeValue::NumSize16(x) => Some(x as u64),
+        uefi_parser::IfrTypeValue::NumSize32(x) => Some(x as u64),
+        uefi_parser::IfrTypeValue::NumSize64(x) => Some(x),
+        uefi_parser::IfrTypeValue::Boolean(x) => Some(x as u64),
+        _ => None,
+    }
+}
+
+// Width of numeric values in bits
+fn value_width(value: &uefi_parser::IfrTypeValue) -> Option<usize> {
+    match *value {
+        uefi_parser::IfrTypeValue::NumSize8(_) => Some(8),
+        uefi_parser::IfrTypeValue::NumSize16(_) => Some(16),
+        uefi_parser::IfrTypeValue::NumSize32(_) => Some(32),
+        uefi_parser::IfrTypeValue::NumSize64(_) => Some(64),
+        _ => None,
+    }
+}
+
+impl NvramComparison {
+    // Numbers are compared truncated to the declared width, any non-zero default is true for booleans
+    // and buffers match if the rest of the storage after the default is zeroed
+    pub fn matches(&self) -> bool {
+        let current = match &self.current {
+            Some(current) => current,
+            None => return false,
+        };
+        match (numeric_value(&self.default), numeric_value(current)) {
+            (Some(default), Some(value)) => {
+                let default = if self.storage.bit_width < 64 {
+                    default & ((1 << self.storage.bit_width) - 1)
+                } else {
+                    default
+                };
+                match current {
+                    uefi_parser::IfrTypeValue::Boolean(_) => (default != 0) as u64 == value,
+                    _ => default == value,
+                }
+            }
+            _ => match (&self.default, current) {
+                (
+                    uefi_parser::IfrTypeValue::Buffer(default),
+                    uefi_parser::IfrTypeValue::Buffer(value),
+                ) => {
+                    default.len() <= value.len()
+                        && default[..] == value[..default.len()]
+                        && value[default.len()..].iter().all(|byte| *byte == 0)
+                }
+                (default, current) => default == current,
+            },
+        }
+    }
+
+    // Variable name with the offset and width of the value, in bits for bit fields
+    pub fn location(&self) -> String {
+        let range = self.storage.byte_range();
+        if self.storage.bit_offset == range.start * 8 && self.storage.bit_width == range.len() * 8 {
+            format!("{}[0x{:X}:{}]", self.variable, range.start, range.len())
+        } else {
+            format!(
+                "{}[bit 0x{:X}:{}]",
+                self.variable, self.storage.bit_offset, self.storage.bit_width
+            )
+        }
+    }
+}
+
+// Storage and value type of a question, VarOffset is given in bits for questions in bit varstores
+fn question_storage(
+    node: &IfrNode,
+    var_offset: usize,
+    bit_field: bool,
+) -> Option<(QuestionStorage, QuestionValueType)> {
+    let flags = *node.data.get(11)?;
+    match node.opcode {
+        // Width in bits is kept in the lower bits of Flags of bit questions
+        uefi_parser::IfrOpcode::OneOf | uefi_parser::IfrOpcode::Numeric if bit_field => Some((
+            QuestionStorage::bits(var_offset, (flags & 0x3F) as usize),
+            QuestionValueType::Numeric,
+        )),
+        uefi_parser::IfrOpcode::OneOf | uefi_parser::IfrOpcode::Numeric => Some((
+            QuestionStorage::numeric(var_offset, flags),
+            QuestionValueType::Numeric,
+        )),
+        uefi_parser::IfrOpcode::CheckBox if bit_field => Some((
+            QuestionStorage::bits(var_offset, 1),
+            QuestionValueType::Boolean,
+        )),
+        uefi_parser::IfrOpcode::CheckBox => Some((
+            QuestionStorage::bytes(var_offset, 1),
+            QuestionValueType::Boolean,
+        )),
+        // Only dates and times with normal storage are kept in varstores
+        uefi_parser::IfrOpcode::Date if flags & 0x30 == 0 => Some((
+            QuestionStorage::bytes(var_offset, 4),
+            QuestionValueType::Date,
+        )),
+        uefi_parser::IfrOpcode::Time if flags & 0x30 == 0 => Some((
+            QuestionStorage::bytes(var_offset, 3),
+            QuestionValueType::Time,
+        )),
+        // MaxContainers values of the type of the first option
+        uefi_parser::IfrOpcode::OrderedList => {
+            let value_type = node
+                .children
+                .iter()
+                .find(|child| child.opcode == uefi_parser::IfrOpcode::OneOfOption)
+                .and_then(|option| option.data.get(3).copied())
+                .unwrap_or(0);
+            Some((
+                QuestionStorage::bytes(var_offset, flags as usize * value_type_size(value_type)),
+                QuestionValueType::Buffer,
+            ))
+        }
+        _ => None,
+    }
+}
+
+// Compare defaults of all questions of a UEFI form package kept in named varstores with current values
+// of their variables, strings are resolved the same way extract_uefi_ifr does. Variables that aren't found,
+// don't have the declared size or are too short for a question, and defaults wider than their questions
+// are reported as diagnostics
+pub fn compare_nvram_defaults(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+    variables: &[NvramVariable],
+    diagnostics: &mut Diagnostics,
+) -> Vec<NvramComparison> {
+    let tree = parse_uefi_ifr(data, form_package, string_package, resources);
+    let defaults = question_defaults(data, form_package, string_package, resources);
+    let option_sets = question_option_sets(data, form_package, string_package, resources);
+    let var_stores = uefi_var_stores(data, form_package);
+
+    let mut checked_sizes: Vec<usize> = Vec::new();
+    let mut result = Vec::new();
+    for (node, bit_field) in tree.questions() {
+        let (header, question) = match (
+            &node.question,
+            defaults
+                .iter()
+                .find(|question| question.offset == node.offset),
+        ) {
+            (Some(header), Some(question)) => (header, question),
+            _ => continue,
+        };
+        // Name/value varstores keep every value in its own variable, questions without storage use VarStoreId 0
+        let (var_store, name) =
+            match var_stores
+                .iter()
+                .find_map(|var_store| match &var_store.name {
+                    Some(name) if var_store.var_store_id == header.var_store_id => {
+                        Some((var_store, name))
+                    }
+                    _ => None,
+                }) {
+                Some(found) => found,
+                None => continue,
+            };
+        let (storage, value_type) =
+            match question_storage(node, header.var_store_info as usize, bit_field) {
+                Some(storage) => storage,
+                None => continue,
+            };
+        let variable = match variables
+            .iter()
+            .find(|variable| variable.name == *name && variable.guid == var_store.guid)
+        {
+            Some(variable) => variable,
+            None => {
+                diagnostics.warn(
+                    "Variable not found in NVRAM",
+                    format_args!(
+                        "{name} {} for QuestionId 0x{:X} at offset 0x{:X}",
+                        var_store.guid, header.question_id, node.offset
+                    ),
+                );
+                continue;
+            }
+        };
+        if let Some(size) = var_store.size {
+            if variable.data.len() != size as usize && !checked_sizes.contains(&variable.offset) {
+                checked_sizes.push(variable.offset);
+                diagnostics.warn(
+                    "Variable size mismatch",
+                    format_args!(
+                        "0x{:X} bytes instead of 0x{size:X} declared by VarStoreId 0x{:X} for {name} {}",
+                        variable.data.len(),
+                        header.var_store_id,
+                        var_store.guid
+                    ),
+                );
+            }
+        }
+        let current = match decode_question_value(&storage, value_type, &variable.data) {
+            Ok(current) => Some(current),
+            Err(error) => {
+                diagnostics.warn(
+                    "Question value can't be read",
+                    format_args!(
+                        "{error:?} for {} bits at bit 0x{:X} of {name} (0x{:X} bytes), QuestionId 0x{:X} at offset 0x{:X}",
+                        storage.bit_width,
+                        storage.bit_offset,
+                        variable.data.len(),
+                        header.question_id,
+                        node.offset
+                    ),
+                );
+                None
+            }
+        };
+
+        let current_text = match (
+            option_sets.iter().find(|set| {
+                set.offset == node.offset && set.opcode == uefi_parser::IfrOpcode::OneOf
+            }),
+            current.as_ref().and_then(numeric_value),
+        ) {
+            (Some(set), Some(value)) => Some(resolve_option_text(set, value)),
+            _ => None,
+        };
+
+        for default in &question.defaults {
+            // Values given by expressions can't be compared
+            let value = match &default.value {
+                Some(value) => value.clone(),
+                None => continue,
+            };
+            if let (Some(width), Some(number)) = (value_width(&value), numeric_value(&value)) {
+                // Bit questions use 32-bit defaults, only the value has to fit
+                if bit_field && storage.bit_width < 64 && number >> storage.bit_width != 0 {
+                    diagnostics.warn(
+                        "Default wider than bit question",
+                        format_args!(
+                            "{value} for {}-bit QuestionId 0x{:X} at offset 0x{:X}, DefaultId 0x{:X}",
+                            storage.bit_width, header.question_id, node.offset, default.default_id
+                        ),
+                    );
+                } else if !bit_field
+                    && value_type == QuestionValueType::Numeric
+                    && width != storage.bit_width
+                {
+                    diagnostics.warn(
+                        "Default width mismatch",
+                        format_args!(
+                            "{width}-bit default for {}-bit QuestionId 0x{:X} at offset 0x{:X}, DefaultId 0x{:X}",
+                            storage.bit_width, header.question_id, node.offset, default.default_id
+                        ),
+                    );
+                }
+            }
+            result.push(NvramComparison {
+                offset: node.offset,
+                opcode: node.opcode,
+                question_type: header.question_type,
+                question_id: header.question_id,
+                prompt: header.prompt.clone(),
+                variable: name.clone(),
+                storage,
+                default_id: default.default_id,
+                store_name: default.store_name.clone(),
+                default: value,
+                current: current.clone(),
+                current_text: current_text.clone(),
+            });
+        }
+    }
+    result
+}
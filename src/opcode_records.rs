@@ -0,0 +1,98 @@
+// Flat list of all opcodes of a form package with their scopes, so consumers can rebuild
+// or flatten the hierarchy without tracking End opcodes themselves
+use super::FormPackage;
+use framework_parser;
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpcodeRecord<'a, Opcode> {
+    pub offset: usize, // Offset of the opcode in the input data
+    pub opcode: Opcode,
+    pub length: u8,
+    pub scope_start: bool, // Opcode opens a scope that is closed by a matching End
+    pub depth: u32, // Number of scopes the opcode is in, End has the depth of the opcode it closes
+    pub data: &'a [u8], // Opcode data without the header
+}
+
+// All opcodes of a UEFI form package, empty if the package can't be parsed
+pub fn uefi_opcode_records<'a>(
+    data: &'a [u8],
+    form_package: &FormPackage,
+) -> Vec<OpcodeRecord<'a, uefi_parser::IfrOpcode>> {
+    let mut result = Vec::new();
+    let operations = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate).ok())
+        .and_then(|(_, package)| uefi_parser::ifr_operations(package.Data.unwrap_or(&[])).ok())
+    {
+        Some((_, operations)) => operations,
+        None => return result,
+    };
+
+    // Form package header is 4 bytes long
+    let mut offset = form_package.offset + 4;
+    let mut depth: u32 = 0;
+    for operation in operations {
+        if operation.OpCode == uefi_parser::IfrOpcode::End {
+            depth = depth.saturating_sub(1);
+        }
+        result.push(OpcodeRecord {
+            offset,
+            opcode: operation.OpCode,
+            length: operation.Length,
+            scope_start: operation.ScopeStart,
+            depth,
+            data: operation.Data.unwrap_or(&[]),
+        });
+        if operation.ScopeStart {
+            depth += 1;
+        }
+        offset += operation.Length as usize;
+    }
+    result
+}
+
+// All opcodes of a Framework form package. Framework IFR has no scope bit,
+// FormSet and Form open scopes closed by EndFormSet and EndForm
+pub fn framework_opcode_records<'a>(
+    data: &'a [u8],
+    form_package: &FormPackage,
+) -> Vec<OpcodeRecord<'a, framework_parser::IfrOpcode>> {
+    let mut result = Vec::new();
+    let operations =
+        match framework_parser::hii_form_package_candidate(&data[form_package.offset..])
+            .ok()
+            .and_then(|(_, candidate)| framework_parser::hii_package(candidate).ok())
+            .and_then(|(_, package)| {
+                framework_parser::ifr_operations(package.Data.unwrap_or(&[])).ok()
+            }) {
+            Some((_, operations)) => operations,
+            None => return result,
+        };
+
+    // Form package header is 6 bytes long
+    let mut offset = form_package.offset + 6;
+    let mut depth: u32 = 0;
+    for operation in operations {
+        if operation.OpCode == framework_parser::IfrOpcode::EndFormSet
+            || operation.OpCode == framework_parser::IfrOpcode::EndForm
+        {
+            depth = depth.saturating_sub(1);
+        }
+        let scope_start = operation.OpCode == framework_parser::IfrOpcode::FormSet
+            || operation.OpCode == framework_parser::IfrOpcode::Form;
+        result.push(OpcodeRecord {
+            offset,
+            opcode: operation.OpCode,
+            length: operation.Length,
+            scope_start,
+            depth,
+            data: operation.Data.unwrap_or(&[]),
+        });
+        if scope_start {
+            depth += 1;
+        }
+        offset += operation.Length as usize;
+    }
+    result
+}
@@ -0,0 +1,409 @@
+// Declarative opcode rendering tables
+//
+// Every opcode is listed once with the parser for its data and the fields to print.
+// Opcodes marked Custom need more context than a single operation (strings of other packages,
+// varstores, scopes) and are rendered by hand in extract_uefi_ifr and extract_framework_ifr.
+use std::fmt::Write;
+
+use super::StringMap;
+use framework_parser;
+use nom;
+use uefi_parser;
+
+// How a single field of a parsed opcode is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldFormat {
+    Hex,      // 0x1F
+    Decimal,  // 31
+    StringId, // "Resolved string" or a placeholder
+    Help,     // Help StringId, printed as configured in ExtractOptions
+    Text,     // "Inline string"
+    Guid,     // 01234567-89AB-CDEF-0123-456789ABCDEF
+    Debug,    // Debug representation of enums and lists
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpcodeRendering {
+    Empty,  // No data is printed
+    Fields, // Fields listed in the table are printed
+    Custom, // Rendered by hand
+}
+
+#[derive(Debug)]
+pub struct FieldEntry {
+    pub label: &'static str,
+    pub field: &'static str,
+    pub format: FieldFormat,
+}
+
+#[derive(Debug)]
+pub struct OpcodeEntry<Opcode: 'static> {
+    pub opcode: Opcode,
+    pub name: &'static str,
+    pub rendering: OpcodeRendering,
+    pub fields: &'static [FieldEntry],
+}
+
+macro_rules! opcode_table {
+    (
+        $table:ident, $lookup:ident, $render:ident, $parser:ident;
+        $( $name:ident => $rendering:ident $( ( $function:ident ) )* {
+            $( $label:expr => $field:ident as $format:ident ),*
+        } )*
+    ) => {
+        pub static $table: &[OpcodeEntry<$parser::IfrOpcode>] = &[
+            $( OpcodeEntry {
+                opcode: $parser::IfrOpcode::$name,
+                name: stringify!($name),
+                rendering: OpcodeRendering::$rendering,
+                fields: &[ $( FieldEntry {
+                    label: $label,
+                    field: stringify!($field),
+                    format: FieldFormat::$format,
+                } ),* ],
+            } ),*
+        ];
+
+        // Find the table entry of a given opcode
+        pub fn $lookup(opcode: $parser::IfrOpcode) -> Option<&'static OpcodeEntry<$parser::IfrOpcode>> {
+            $table.iter().find(|entry| entry.opcode == opcode)
+        }
+
+        // Render data of an operation with Fields or Empty rendering, Custom ones are left as is
+        pub(crate) fn $render<'a>(
+            operation: &$parser::IfrOperation<'a>,
+            strings_map: &StringMap,
+            text: &mut String,
+        ) -> Result<(), nom::Err<&'a [u8]>> {
+            match operation.OpCode {
+                $( $parser::IfrOpcode::$name => {
+                    opcode_table!(@render $rendering $( $function )*, $parser, operation, strings_map, text,
+                        $( $label => $field as $format ),*)
+                } )*
+                _ => Ok(()),
+            }
+        }
+    };
+
+    (@render Fields $function:ident, $parser:ident, $operation:ident, $strings_map:ident, $text:ident,
+        $( $label:expr => $field:ident as $format:ident ),*) => {{
+        let (_, parsed) = $parser::$function($operation.Data.unwrap_or(&[]))?;
+        let mut line = String::new();
+        $(
+            opcode_table!(@labeled $format, $label, line, $strings_map, parsed.$field);
+        )*
+        $text.push_str(line.strip_prefix(", ").unwrap_or(&line));
+        Ok(())
+    }};
+    (@render $rendering:ident, $parser:ident, $operation:ident, $strings_map:ident, $text:ident,) => {
+        Ok(())
+    };
+
+    // Help strings can be omitted together with their label
+    (@labeled Help, $label:expr, $line:ident, $strings_map:ident, $value:expr) => {
+        $line.push_str(&$strings_map.help_field($value))
+    };
+    (@labeled $format:ident, $label:expr, $line:ident, $strings_map:ident, $value:expr) => {{
+        write!(&mut $line, ", {}: ", $label).unwrap();
+        opcode_table!(@field $format, $line, $strings_map, $value);
+    }};
+
+    (@field Hex, $line:ident, $strings_map:ident, $value:expr) => {
+        write!(&mut $line, "0x{:X}", $value).unwrap()
+    };
+    (@field Decimal, $line:ident, $strings_map:ident, $value:expr) => {
+        write!(&mut $line, "{}", $value).unwrap()
+    };
+    (@field StringId, $line:ident, $strings_map:ident, $value:expr) => {
+        write!(&mut $line, "{}", $strings_map.quoted($value)).unwrap()
+    };
+    (@field Text, $line:ident, $strings_map:ident, $value:expr) => {
+        write!(&mut $line, "{}", $strings_map.quote(&$value)).unwrap()
+    };
+    (@field Guid, $line:ident, $strings_map:ident, $value:expr) => {
+        write!(&mut $line, "{}", $value).unwrap()
+    };
+    (@field Debug, $line:ident, $strings_map:ident, $value:expr) => {
+        write!(&mut $line, "{:?}", $value).unwrap()
+    };
+}
+
+opcode_table! {
+    UEFI_OPCODE_TABLE, uefi_opcode_entry, render_uefi_operation, uefi_parser;
+    Form => Fields(ifr_form) { "FormId" => FormId as Hex, "Title" => TitleStringId as StringId }
+    Subtitle => Custom {}
+    Text => Fields(ifr_text) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "Text" => TextId as StringId
+    }
+    Image => Custom {}
+    OneOf => Custom {}
+    CheckBox => Custom {}
+    Numeric => Custom {}
+    Password => Custom {}
+    OneOfOption => Custom {}
+    SuppressIf => Empty {}
+    Locked => Empty {}
+    Action => Custom {}
+    ResetButton => Custom {}
+    FormSet => Fields(ifr_form_set) {
+        "Guid" => Guid as Guid,
+        "Title" => TitleStringId as StringId,
+        "Help" => HelpStringId as Help
+    }
+    Ref => Custom {}
+    NoSubmitIf => Fields(ifr_no_submit_if) { "Error" => ErrorStringId as StringId }
+    InconsistentIf => Fields(ifr_inconsistent_if) { "Error" => ErrorStringId as StringId }
+    EqIdVal => Fields(ifr_eq_id_val) { "QuestionId" => QuestionId as Hex, "Value" => Value as Hex }
+    EqIdId => Fields(ifr_eq_id_id) {
+        "QuestionId" => QuestionId as Hex,
+        "OtherQuestionId" => OtherQuestionId as Hex
+    }
+    EqIdValList => Fields(ifr_eq_id_val_list) { "QuestionId" => QuestionId as Hex, "Values" => Values as Debug }
+    And => Empty {}
+    Or => Empty {}
+    Not => Empty {}
+    Rule => Fields(ifr_rule) { "RuleId" => RuleId as Hex }
+    GrayOutIf => Empty {}
+    Date => Custom {}
+    Time => Custom {}
+    String => Custom {}
+    Refresh => Fields(ifr_refresh) { "RefreshInterval" => RefreshInterval as Hex }
+    DisableIf => Empty {}
+    Animation => Custom {}
+    ToLower => Empty {}
+    ToUpper => Empty {}
+    Map => Empty {}
+    OrderedList => Custom {}
+    VarStore => Fields(ifr_var_store) {
+        "Guid" => Guid as Guid,
+        "VarStoreId" => VarStoreId as Hex,
+        "Size" => Size as Hex,
+        "Name" => Name as Text
+    }
+    VarStoreNameValue => Fields(ifr_var_store_name_value) { "Guid" => Guid as Guid, "VarStoreId" => VarStoreId as Hex }
+    VarStoreEfi => Custom {}
+    VarStoreDevice => Custom {}
+    Version => Empty {}
+    End => Empty {}
+    Match => Empty {}
+    Get => Custom {}
+    Set => Custom {}
+    Read => Empty {}
+    Write => Empty {}
+    Equal => Empty {}
+    NotEqual => Empty {}
+    GreaterThan => Empty {}
+    GreaterEqual => Empty {}
+    LessThan => Empty {}
+    LessEqual => Empty {}
+    BitwiseAnd => Empty {}
+    BitwiseOr => Empty {}
+    BitwiseNot => Empty {}
+    ShiftLeft => Empty {}
+    ShiftRight => Empty {}
+    Add => Empty {}
+    Substract => Empty {}
+    Multiply => Empty {}
+    Divide => Empty {}
+    Modulo => Empty {}
+    RuleRef => Fields(ifr_rule_ref) { "RuleId" => RuleId as Hex }
+    QuestionRef1 => Fields(ifr_question_ref_1) { "QuestionId" => QuestionId as Hex }
+    QuestionRef2 => Empty {}
+    Uint8 => Fields(ifr_uint8) { "Value" => Value as Hex }
+    Uint16 => Fields(ifr_uint16) { "Value" => Value as Hex }
+    Uint32 => Fields(ifr_uint32) { "Value" => Value as Hex }
+    Uint64 => Fields(ifr_uint64) { "Value" => Value as Hex }
+    True => Empty {}
+    False => Empty {}
+    ToUint => Empty {}
+    ToString => Fields(ifr_to_string) { "Format" => Format as Hex }
+    ToBoolean => Empty {}
+    Mid => Empty {}
+    Find => Fields(ifr_find) { "Format" => Format as Hex }
+    Token => Empty {}
+    StringRef1 => Fields(ifr_string_ref_1) { "String" => StringId as StringId }
+    StringRef2 => Empty {}
+    Conditional => Empty {}
+    QuestionRef3 => Custom {}
+    Zero => Empty {}
+    One => Empty {}
+    Ones => Empty {}
+    Undefined => Empty {}
+    Length => Empty {}
+    Dup => Empty {}
+    This => Empty {}
+    Span => Fields(ifr_span) { "Flags" => Flags as Hex }
+    Value => Custom {}
+    Default => Custom {}
+    DefaultStore => Fields(ifr_default_store) { "DefaultId" => DefaultId as Hex, "Name" => NameStringId as StringId }
+    FormMap => Custom {}
+    Catenate => Empty {}
+    Guid => Custom {}
+    Security => Fields(ifr_security) { "Guid" => Guid as Guid }
+    ModalTag => Empty {}
+    RefreshId => Fields(ifr_refresh_id) { "Guid" => Guid as Guid }
+    WarningIf => Custom {}
+    Match2 => Fields(ifr_match_2) { "Guid" => Guid as Guid }
+}
+
+opcode_table! {
+    FRAMEWORK_OPCODE_TABLE, framework_opcode_entry, render_framework_operation, framework_parser;
+    Form => Fields(ifr_form) { "Title" => TitleStringId as StringId, "FormId" => FormId as Hex }
+    Subtitle => Fields(ifr_subtitle) { "Subtitle" => SubtitleStringId as StringId }
+    Text => Fields(ifr_text) {
+        "Text" => TextStringId as StringId,
+        "TextTwo" => TextTwoStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex
+    }
+    Graphic => Empty {}
+    OneOf => Fields(ifr_one_of) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "QuestionId" => QuestionId as Hex,
+        "Width" => Width as Hex
+    }
+    CheckBox => Fields(ifr_check_box) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "QuestionId" => QuestionId as Hex,
+        "Width" => Width as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex
+    }
+    Numeric => Custom {}
+    Password => Fields(ifr_password) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "QuestionId" => QuestionId as Hex,
+        "Width" => Width as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex,
+        "MinSize" => MinSize as Hex,
+        "MaxSize" => MaxSize as Hex,
+        "Encoding" => Encoding as Hex
+    }
+    OneOfOption => Fields(ifr_one_of_option) {
+        "Option" => OptionStringId as StringId,
+        "Value" => Value as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex
+    }
+    SuppressIf => Fields(ifr_supress_if) { "Flags" => Flags as Hex }
+    EndForm => Empty {}
+    Hidden => Fields(ifr_hidden) { "Value" => Value as Hex, "Key" => Key as Hex }
+    EndFormSet => Empty {}
+    FormSet => Fields(ifr_form_set) {
+        "Title" => TitleStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "Guid" => Guid as Guid,
+        "CallbackHandle" => CallbackHandle as Hex,
+        "Class" => Class as Hex,
+        "SubClass" => SubClass as Hex,
+        "NvDataSize" => NvDataSize as Hex
+    }
+    Ref => Fields(ifr_ref) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "FormId" => FormId as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex
+    }
+    End => Empty {}
+    InconsistentIf => Fields(ifr_inconsistent_if) { "Popup" => PopupStringId as StringId, "Flags" => Flags as Hex }
+    EqIdVal => Fields(ifr_eq_id_val) { "QuestionId" => QuestionId as Hex, "Value" => Value as Hex }
+    EqIdId => Fields(ifr_eq_id_id) { "QuestionId1" => QuestionId1 as Hex, "QuestionId2" => QuestionId2 as Hex }
+    EqIdList => Custom {}
+    And => Empty {}
+    Or => Empty {}
+    Not => Empty {}
+    EndIf => Empty {}
+    GrayOutIf => Fields(ifr_grayout_if) { "Flags" => Flags as Hex }
+    Date => Fields(ifr_date) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "QuestionId" => QuestionId as Hex,
+        "Width" => Width as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex,
+        "Min" => Min as Hex,
+        "Max" => Max as Hex,
+        "Step" => Step as Hex,
+        "Default" => Default as Hex
+    }
+    Time => Fields(ifr_time) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "QuestionId" => QuestionId as Hex,
+        "Width" => Width as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex,
+        "Min" => Min as Hex,
+        "Max" => Max as Hex,
+        "Step" => Step as Hex,
+        "Default" => Default as Hex
+    }
+    String => Fields(ifr_string) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "QuestionId" => QuestionId as Hex,
+        "Width" => Width as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex,
+        "MinSize" => MinSize as Hex,
+        "MaxSize" => MaxSize as Hex
+    }
+    Label => Fields(ifr_label) { "LabelId" => LabelId as Hex }
+    SaveDefaults => Fields(ifr_save_defaults) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "FormId" => FormId as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex
+    }
+    RestoreDefaults => Fields(ifr_restore_defaults) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "FormId" => FormId as Hex,
+        "Flags" => Flags as Hex,
+        "Key" => Key as Hex
+    }
+    Banner => Fields(ifr_banner) {
+        "Title" => TitleStringId as StringId,
+        "LineNumber" => LineNumber as Decimal,
+        "Alignment" => Alignment as Debug
+    }
+    Inventory => Fields(ifr_inventory) {
+        "Text" => TextStringId as StringId,
+        "TextTwo" => TextTwoStringId as StringId,
+        "Help" => HelpStringId as Help
+    }
+    EqVarVal => Fields(ifr_eq_var_val) { "VariableId" => VariableId as Hex, "Value" => Value as Hex }
+    OrderedList => Fields(ifr_ordered_list) {
+        "Prompt" => PromptStringId as StringId,
+        "Help" => HelpStringId as Help,
+        "QuestionId" => QuestionId as Hex,
+        "MaxEntries" => MaxEntries as Hex
+    }
+    VarStore => Fields(ifr_var_store) {
+        "VarstoreId" => VarStoreId as Hex,
+        "Guid" => Guid as Guid,
+        "Name" => Name as Text,
+        "Size" => Size as Hex
+    }
+    VarStoreSelect => Fields(ifr_var_store_select) { "VarstoreId" => VarStoreId as Hex }
+    VarStoreSelectPair => Fields(ifr_var_store_select_pair) {
+        "VarstoreId" => VarStoreId as Hex,
+        "SecondaryVarStoreId" => SecondaryVarStoreId as Hex
+    }
+    True => Empty {}
+    False => Empty {}
+    Greater => Empty {}
+    GreaterEqual => Empty {}
+    OemDefined => Empty {}
+    Oem => Empty {}
+    NvAccessCommand => Empty {}
+}
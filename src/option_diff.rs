@@ -0,0 +1,168 @@
+// Comparison of option lists of the same question in two images, BIOS updates often drop menu choices silently
+use super::{uefi_string_map, ExtractOptions, FormPackage, HiiResources, StringPackage};
+use ifr_tree::{parse_uefi_ifr, IfrNode, ResolvedString};
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionOption {
+    pub offset: usize, // Offset of the OneOfOption opcode in the input data
+    pub value: uefi_parser::IfrTypeValue,
+    pub text: ResolvedString,
+}
+
+// OneOf or OrderedList question with all its options, including the ones in conditional scopes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionOptionSet {
+    pub offset: usize, // Offset of the question opcode in the input data
+    pub opcode: uefi_parser::IfrOpcode,
+    pub question_id: u16,
+    pub var_store_id: u16,
+    pub var_store_info: u16, // VarOffset or VarName StringId, depending on the varstore
+    pub prompt: ResolvedString,
+    pub options: Vec<QuestionOption>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionSetDiff {
+    pub old_offset: usize,
+    pub new_offset: usize,
+    pub question_id: u16, // QuestionId in the new image
+    pub prompt: ResolvedString,
+    pub added: Vec<QuestionOption>, // Options of the new image with values not present in the old one
+    pub removed: Vec<QuestionOption>, // Options of the old image with values not present in the new one
+}
+
+// Collect option sets of all OneOf and OrderedList questions of a UEFI form package,
+// strings are resolved the same way extract_uefi_ifr does
+pub fn question_option_sets(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+) -> Vec<QuestionOptionSet> {
+    let options = ExtractOptions::default();
+    let strings_map = uefi_string_map(data, form_package, string_package, resources, &options);
+    let tree = parse_uefi_ifr(data, form_package, string_package, resources);
+
+    let mut result = Vec::new();
+    for node in tree.walk() {
+        if node.opcode != uefi_parser::IfrOpcode::OneOf
+            && node.opcode != uefi_parser::IfrOpcode::OrderedList
+        {
+            continue;
+        }
+        let header = match &node.question {
+            Some(header) => header,
+            None => continue,
+        };
+        let mut set = QuestionOptionSet {
+            offset: node.offset,
+            opcode: node.opcode,
+            question_id: header.question_id,
+            var_store_id: header.var_store_id,
+            var_store_info: header.var_store_info,
+            prompt: header.prompt.clone(),
+            options: Vec::new(),
+        };
+        collect_options(node, &mut |option_node: &IfrNode| {
+            if let Ok((_, option)) = uefi_parser::ifr_one_of_option(&option_node.data) {
+                set.options.push(QuestionOption {
+                    offset: option_node.offset,
+                    value: option.Value,
+                    text: ResolvedString {
+                        string_id: option.OptionStringId,
+                        text: strings_map.get(option.OptionStringId).cloned(),
+                    },
+                });
+            }
+        });
+        result.push(set);
+    }
+    result
+}
+
+// Value of numeric option values, i.e. as stored in a varstore
+fn option_number(value: &uefi_parser::IfrTypeValue) -> Option<u64> {
+    match *value {
+        uefi_parser::IfrTypeValue::NumSize8(x) => Some(x as u64),
+        uefi_parser::IfrTypeValue::NumSize16(x) => Some(x as u64),
+        uefi_parser::IfrTypeValue::NumSize32(x) => Some(x as u64),
+        uefi_parser::IfrTypeValue::NumSize64(x) => Some(x),
+        _ => None,
+    }
+}
+
+// Display text of the option of a question with a given stored value. Values without an option,
+// i.e. set by another tool or left from a removed menu choice, are written as numbers
+pub fn resolve_option_text(question: &QuestionOptionSet, raw_value: u64) -> String {
+    match question
+        .options
+        .iter()
+        .find(|option| option_number(&option.value) == Some(raw_value))
+    {
+        Some(option) => match &option.text.text {
+            Some(text) => text.clone(),
+            None => format!("StringId 0x{:X}", option.text.string_id),
+        },
+        None => format!("Unknown value 0x{raw_value:X}"),
+    }
+}
+
+// OneOfOption opcodes in the scope of a question, nested questions are skipped
+fn collect_options(node: &IfrNode, add: &mut dyn FnMut(&IfrNode)) {
+    for child in &node.children {
+        if child.opcode == uefi_parser::IfrOpcode::OneOfOption {
+            add(child);
+        } else if child.question.is_none() {
+            collect_options(child, add);
+        }
+    }
+}
+
+// Options of the first list with values not present in the second one
+fn option_difference(first: &[QuestionOption], second: &[QuestionOption]) -> Vec<QuestionOption> {
+    first
+        .iter()
+        .filter(|option| !second.iter().any(|other| other.value == option.value))
+        .cloned()
+        .collect()
+}
+
+// Compare options of the same question in two images by their values
+pub fn diff_question_options(old: &QuestionOptionSet, new: &QuestionOptionSet) -> OptionSetDiff {
+    OptionSetDiff {
+        old_offset: old.offset,
+        new_offset: new.offset,
+        question_id: new.question_id,
+        prompt: new.prompt.clone(),
+        added: option_difference(&new.options, &old.options),
+        removed: option_difference(&old.options, &new.options),
+    }
+}
+
+// Match questions of two images by their varstore and VarOffset, or by QuestionId for questions without storage
+// or if no question uses the same storage, and report the ones with added or removed options
+pub fn diff_option_sets(
+    old: &[QuestionOptionSet],
+    new: &[QuestionOptionSet],
+) -> Vec<OptionSetDiff> {
+    let mut result = Vec::new();
+    for set in new {
+        let same_storage = old.iter().find(|other| {
+            set.var_store_id != 0
+                && other.var_store_id == set.var_store_id
+                && other.var_store_info == set.var_store_info
+        });
+        let matched = same_storage.or_else(|| {
+            old.iter()
+                .find(|other| other.question_id == set.question_id)
+        });
+        if let Some(other) = matched {
+            let diff = diff_question_options(other, set);
+            if !diff.added.is_empty() || !diff.removed.is_empty() {
+                result.push(diff);
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,130 @@
+// Carving of all discovered HII packages into separate binary files with a manifest
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pyo3::prelude::*;
+
+use super::{
+    find_framework_packages, find_uefi_animation_packages, find_uefi_image_packages,
+    find_uefi_packages,
+};
+use device_paths::find_uefi_device_path_packages;
+use fonts::{find_uefi_font_packages, find_uefi_simple_font_packages};
+use formset_inventory::{framework_formset_inventory, uefi_formset_inventory};
+use guid_packages::find_uefi_guid_packages;
+use uefi_parser::Guid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpedPackage {
+    pub kind: &'static str, // i.e. "UEFI string"
+    pub offset: usize,
+    pub length: usize,
+    pub guid: Option<Guid>, // FormSet GUID of form packages, package GUID of GUID packages, None for other packages
+    pub file_name: String,  // Relative to the output directory
+}
+
+// Write every discovered package with its header to its own file in a given directory,
+// and describe all of them in manifest.txt there. Packages are returned in order of their offsets
+pub fn dump_packages(data: &[u8], dir: &Path) -> io::Result<Vec<DumpedPackage>> {
+    let mut packages: Vec<(&'static str, usize, usize, Option<Guid>)> = Vec::new();
+
+    let (uefi_strings, uefi_forms) = find_uefi_packages(data);
+    let uefi_formsets = uefi_formset_inventory(data);
+    for string in &uefi_strings {
+        packages.push(("UEFI string", string.offset, string.length, None));
+    }
+    for form in &uefi_forms {
+        let guid = uefi_formsets
+            .iter()
+            .find(|formset| formset.package_offset == form.offset)
+            .map(|formset| formset.guid);
+        packages.push(("UEFI form", form.offset, form.length, guid));
+    }
+    for image in &find_uefi_image_packages(data) {
+        packages.push(("UEFI image", image.offset, image.length, None));
+    }
+    for animation in &find_uefi_animation_packages(data) {
+        packages.push(("UEFI animation", animation.offset, animation.length, None));
+    }
+    for font in &find_uefi_font_packages(data) {
+        packages.push(("UEFI font", font.offset, font.length, None));
+    }
+    for font in &find_uefi_simple_font_packages(data) {
+        packages.push(("UEFI simple font", font.offset, font.length, None));
+    }
+    for device_path in &find_uefi_device_path_packages(data) {
+        packages.push(("UEFI device path", device_path.offset, device_path.length, None));
+    }
+    for package in &find_uefi_guid_packages(data) {
+        packages.push(("UEFI GUID", package.offset, package.length, Some(package.guid)));
+    }
+
+    let (framework_strings, framework_forms) = find_framework_packages(data);
+    let framework_formsets = framework_formset_inventory(data);
+    for string in &framework_strings {
+        packages.push(("Framework string", string.offset, string.length, None));
+    }
+    for form in &framework_forms {
+        let guid = framework_formsets
+            .iter()
+            .find(|formset| formset.package_offset == form.offset)
+            .map(|formset| formset.guid);
+        packages.push(("Framework form", form.offset, form.length, guid));
+    }
+    packages.sort_by_key(|&(_, offset, _, _)| offset);
+
+    fs::create_dir_all(dir)?;
+    let mut result = Vec::new();
+    let mut manifest = String::new();
+    for (kind, offset, length, guid) in packages {
+        // i.e. 00000010_uefi_string.bin
+        let file_name = format!(
+            "{:08X}_{}.bin",
+            offset,
+            kind.to_lowercase().replace(' ', "_")
+        );
+        fs::write(dir.join(&file_name), &data[offset..offset + length])?;
+        manifest.push_str(&format!(
+            "Type: {}, Offset: 0x{:X}, Length: 0x{:X}, Guid: {}, File: {}\n",
+            kind,
+            offset,
+            length,
+            guid.map_or_else(|| String::from("None"), |guid| guid.to_string()),
+            file_name
+        ));
+        result.push(DumpedPackage {
+            kind,
+            offset,
+            length,
+            guid,
+            file_name,
+        });
+    }
+    fs::write(dir.join("manifest.txt"), manifest)?;
+    Ok(result)
+}
+
+// Dumped package as returned to Python: type, offset, length, guid, file name
+type PyDumpedPackage = (String, usize, usize, Option<String>, String);
+
+#[pyfunction]
+#[pyo3(name = "dump_packages")]
+pub fn py_dump_packages(
+    py: Python<'_>,
+    data: Vec<u8>,
+    dir: PathBuf,
+) -> PyResult<Vec<PyDumpedPackage>> {
+    Ok(py.allow_threads(|| dump_packages(&data, &dir))?
+        .into_iter()
+        .map(|package| {
+            (
+                String::from(package.kind),
+                package.offset,
+                package.length,
+                package.guid.map(|guid| guid.to_string()),
+                package.file_name,
+            )
+        })
+        .collect())
+}
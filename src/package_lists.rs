@@ -0,0 +1,182 @@
+// Every package of UEFI HII package lists, including fonts, device paths, keyboard layouts, GUID packages
+// and other kinds that are not parsed any further, so callers can carve or inspect them themselves
+use std::ops::Range;
+
+use super::{
+    find_uefi_animation_packages, find_uefi_image_packages, scan_uefi_packages, ScanOptions,
+};
+use device_paths::find_uefi_device_path_packages;
+use fonts::{find_uefi_font_packages, find_uefi_simple_font_packages};
+use scan_metrics::ScanMetrics;
+use uefi_parser::{self, Guid, HiiPackageType};
+
+// Package list headers are searched at most this far before the first known package of a list
+const MAX_HEADER_DISTANCE: usize = 0x10_0000;
+
+// Package list header is a 16-byte GUID followed by a 32-bit length of the whole list
+const HEADER_SIZE: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HiiPackageEntry {
+    pub package_type: HiiPackageType,
+    pub offset: usize,      // Offset of the package header in the input data
+    pub length: usize,      // Header included
+    pub guid: Option<Guid>, // GUID of GUID packages, None for other packages
+}
+
+impl HiiPackageEntry {
+    // Whole package with its header
+    pub fn data<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        &input[self.offset..self.offset + self.length]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HiiPackageList {
+    pub offset: usize, // Offset of the header, or of the first package if no header is found
+    pub length: usize,
+    pub guid: Option<Guid>, // PackageListGuid, None if no header is found
+    pub packages: Vec<HiiPackageEntry>, // In order, the End package included
+}
+
+impl HiiPackageList {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.length
+    }
+
+    // Whole package list with its header
+    pub fn data<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        &input[self.range()]
+    }
+}
+
+// Adjacent packages from start up to the End package or limit
+fn walk_packages(data: &[u8], start: usize, limit: usize) -> Vec<HiiPackageEntry> {
+    let mut result = Vec::new();
+    let mut current = start;
+    while current < limit {
+        let package = match uefi_parser::hii_package(&data[current..limit]) {
+            Ok((_, package)) => package,
+            Err(_) => break,
+        };
+        let guid = match (package.Type, package.Data) {
+            (HiiPackageType::Guid, Some(payload)) if payload.len() >= 16 => {
+                let mut bytes = [0u8; 16];
+                bytes.copy_from_slice(&payload[..16]);
+                Some(Guid::from_bytes_le(&bytes))
+            }
+            _ => None,
+        };
+        result.push(HiiPackageEntry {
+            package_type: package.Type,
+            offset: current,
+            length: package.Length as usize,
+            guid,
+        });
+        current += package.Length as usize;
+        if package.Type == HiiPackageType::End {
+            break;
+        }
+    }
+    result
+}
+
+// Package list with a header that contains a package at a given offset, if there is one.
+// The header is valid if walking its packages reaches that package and ends with an End package at its length
+fn list_with_header(data: &[u8], package_offset: usize) -> Option<HiiPackageList> {
+    let lowest = package_offset.saturating_sub(MAX_HEADER_DISTANCE);
+    let highest = package_offset.checked_sub(HEADER_SIZE)?;
+    for header in (lowest..=highest).rev() {
+        let length = u32::from_le_bytes([
+            data[header + 16],
+            data[header + 17],
+            data[header + 18],
+            data[header + 19],
+        ]) as usize;
+        if length < HEADER_SIZE + 4
+            || header + length <= package_offset
+            || header + length > data.len()
+        {
+            continue;
+        }
+        let packages = walk_packages(data, header + HEADER_SIZE, header + length);
+        let ends_at_length = packages.last().is_some_and(|last| {
+            last.package_type == HiiPackageType::End && last.offset + last.length == header + length
+        });
+        if ends_at_length
+            && packages
+                .iter()
+                .any(|package| package.offset == package_offset)
+        {
+            let mut guid = [0u8; 16];
+            guid.copy_from_slice(&data[header..header + 16]);
+            return Some(HiiPackageList {
+                offset: header,
+                length,
+                guid: Some(Guid::from_bytes_le(&guid)),
+                packages,
+            });
+        }
+    }
+    None
+}
+
+// Find all UEFI HII package lists that contain a string, form, image, animation, font, simple font or device path package,
+// in order of their offsets. Lists without a header start at their first known package
+pub fn uefi_package_lists(data: &[u8]) -> Vec<HiiPackageList> {
+    let (strings, forms) =
+        scan_uefi_packages(data, &ScanOptions::default(), &mut ScanMetrics::new(), true);
+    let mut offsets: Vec<usize> = strings
+        .iter()
+        .map(|package| package.offset)
+        .chain(forms.iter().map(|package| package.offset))
+        .chain(
+            find_uefi_image_packages(data)
+                .iter()
+                .map(|package| package.offset),
+        )
+        .chain(
+            find_uefi_animation_packages(data)
+                .iter()
+                .map(|package| package.offset),
+        )
+        .chain(
+            find_uefi_font_packages(data)
+                .iter()
+                .map(|package| package.offset),
+        )
+        .chain(
+            find_uefi_simple_font_packages(data)
+                .iter()
+                .map(|package| package.offset),
+        )
+        .chain(
+            find_uefi_device_path_packages(data)
+                .iter()
+                .map(|package| package.offset),
+        )
+        .collect();
+    offsets.sort_unstable();
+
+    let mut result: Vec<HiiPackageList> = Vec::new();
+    for offset in offsets {
+        if result.iter().any(|list| list.range().contains(&offset)) {
+            continue;
+        }
+        let list = list_with_header(data, offset).unwrap_or_else(|| {
+            let packages = walk_packages(data, offset, data.len());
+            let end = packages
+                .last()
+                .map_or(offset, |last| last.offset + last.length);
+            HiiPackageList {
+                offset,
+                length: end - offset,
+                guid: None,
+                packages,
+            }
+        });
+        result.push(list);
+    }
+    result.sort_by_key(|list| list.offset);
+    result
+}
@@ -0,0 +1,80 @@
+// Signatures of package headers searched for with memchr before package parsers are tried,
+// so scans skip most offsets of the input instead of attempting a parse at every byte
+use memchr::memmem;
+
+use scan_metrics::PROGRESS_INTERVAL;
+
+// Bytes every header of a package type has at a fixed offset from its start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderSignature {
+    pub offset: usize,
+    pub bytes: &'static [u8],
+}
+
+// Type in the highest byte of the length, followed by HeaderSize 0x34
+pub const UEFI_STRING_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 3,
+    bytes: &[0x04, 0x34, 0x00, 0x00, 0x00],
+};
+
+// Type in the highest byte of the length, followed by the FormSet opcode
+pub const UEFI_FORM_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 3,
+    bytes: &[0x02, 0x0E],
+};
+
+pub const UEFI_IMAGE_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 3,
+    bytes: &[0x06],
+};
+
+pub const UEFI_FONT_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 3,
+    bytes: &[0x05],
+};
+
+pub const UEFI_SIMPLE_FONT_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 3,
+    bytes: &[0x07],
+};
+
+pub const UEFI_DEVICE_PATH_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 3,
+    bytes: &[0x08],
+};
+
+pub const UEFI_ANIMATION_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 3,
+    bytes: &[0x0A],
+};
+
+// 16-bit type after the 32-bit length
+pub const FRAMEWORK_STRING_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 4,
+    bytes: &[0x02, 0x00],
+};
+
+// 16-bit type after the 32-bit length, followed by the FormSet opcode
+pub const FRAMEWORK_FORM_PACKAGE: HeaderSignature = HeaderSignature {
+    offset: 4,
+    bytes: &[0x03, 0x00, 0x0E],
+};
+
+impl HeaderSignature {
+    // First offset at or after start where a header with this signature can begin, the length of the input
+    // if there is none. At most PROGRESS_INTERVAL bytes are searched, so scan loops still report progress
+    // and check for cancellation. The offset right after the searched window is returned if nothing is found in it
+    pub fn next_candidate(&self, data: &[u8], start: usize) -> usize {
+        let from = start + self.offset;
+        if from >= data.len() {
+            return data.len();
+        }
+        let to = data.len().min(from + PROGRESS_INTERVAL);
+        match memmem::find(&data[from..to], self.bytes) {
+            Some(position) => start + position,
+            None if to == data.len() => data.len(),
+            // Signature may cross the end of the window
+            None => start + (to - from) - (self.bytes.len() - 1),
+        }
+    }
+}
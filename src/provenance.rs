@@ -0,0 +1,71 @@
+// Provenance information attached to every extraction result,
+// so archived artifacts can be traced back to their input
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{hex_string, sha256, VERSION};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub source_name: String,
+    pub source_sha256: [u8; 32],
+    pub options: String,
+    pub version: String,
+    pub timestamp: u64, // Seconds since UNIX epoch
+}
+
+impl Provenance {
+    pub fn new(source_name: &str, data: &[u8], options: &str) -> Provenance {
+        Provenance {
+            source_name: String::from(source_name),
+            source_sha256: sha256(data),
+            options: String::from(options),
+            version: String::from(VERSION.unwrap_or("0.0.0")),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    // Timestamp in RFC 3339 format, always in UTC
+    pub fn timestamp_string(&self) -> String {
+        let days = (self.timestamp / 86400) as i64;
+        let seconds = self.timestamp % 86400;
+
+        // Convert days since epoch to a civil date
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Source: \"{}\", SHA256: {}, Options: \"{}\", Program version: {}, Timestamp: {}",
+            self.source_name,
+            hex_string(&self.source_sha256),
+            self.options,
+            self.version,
+            self.timestamp_string()
+        )
+    }
+}
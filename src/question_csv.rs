@@ -0,0 +1,216 @@
+// Table of all questions with their storage, defaults and options, exported as CSV for spreadsheets
+use super::{
+    find_uefi_packages, uefi_preferred_string_package, uefi_resources, uefi_string_map,
+    ExtractOptions, FormPackage, HiiResources, QuestionType, StringPackage,
+};
+use fv::{ffs_file_at, find_firmware_volumes, FfsFile};
+use ifr_tree::{parse_uefi_ifr, IfrNode};
+use knob_map::knob_map;
+use question_value::QuestionStorage;
+use question_defaults::{question_defaults, QuestionDefault};
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionOption {
+    pub value: uefi_parser::IfrTypeValue,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionRow {
+    pub formset_guid: uefi_parser::Guid,
+    pub form_title: String,
+    pub subtitle: Option<String>, // Subtitle the question is displayed under, if any
+    pub prompt: String,
+    pub help: String,
+    pub question_id: u16,
+    pub question_type: QuestionType,
+    pub var_store: Option<String>, // Variable name, or name of the pair for name/value varstores
+    pub var_offset: Option<u16>,   // None for name/value varstores and questions without storage
+    pub size: Option<usize>,       // None for questions without storage
+    pub bit_field: Option<QuestionStorage>, // Only for questions in bit varstores, VarOffset and Size cover the bytes it touches
+    pub defaults: Vec<QuestionDefault>,
+    pub options: Vec<QuestionOption>, // OneOf and OrderedList only
+    pub module_guid: Option<uefi_parser::Guid>, // FFS file of the driver the form package is in, if known
+    pub module_name: Option<String>,            // From the user interface section of that file
+}
+
+const CSV_HEADER: &str =
+    "FormSetGuid,FormTitle,Subtitle,Prompt,Help,QuestionId,VarStore,VarOffset,Size,BitOffset,BitWidth,Type,Defaults,Options,ModuleGuid,ModuleName";
+
+// OneOfOption opcodes in the scope of a question, including conditional ones, nested questions are skipped
+fn collect_options<'a>(node: &'a IfrNode, result: &mut Vec<&'a IfrNode>) {
+    for child in &node.children {
+        if child.opcode == uefi_parser::IfrOpcode::OneOfOption {
+            result.push(child);
+        } else if child.question.is_none() {
+            collect_options(child, result);
+        }
+    }
+}
+
+// Rows of all questions of a UEFI form package in order of appearance, Refs and Actions included.
+// Strings are resolved the same way extract_uefi_ifr does
+pub fn question_rows(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+) -> Vec<QuestionRow> {
+    let options = ExtractOptions::default();
+    let strings_map = uefi_string_map(data, form_package, string_package, resources, &options);
+    let tree = parse_uefi_ifr(data, form_package, string_package, resources);
+    let knobs = knob_map(data, form_package, Some(string_package));
+    let defaults = question_defaults(data, form_package, string_package, resources);
+
+    let mut result = Vec::new();
+    for root in &tree.roots {
+        if root.opcode != uefi_parser::IfrOpcode::FormSet {
+            continue;
+        }
+        let formset_guid = match uefi_parser::ifr_form_set(&root.data) {
+            Ok((_, form_set)) => form_set.Guid,
+            Err(_) => continue,
+        };
+        // Forms are direct children of their formset
+        for child in &root.children {
+            let form = match tree.forms.iter().find(|form| form.offset == child.offset) {
+                Some(form) => form,
+                None => continue,
+            };
+            for (index, node) in form.questions.iter().enumerate() {
+                let header = match &node.question {
+                    Some(header) => header,
+                    None => continue,
+                };
+                let knob = knobs.iter().find(|knob| knob.offset == node.offset);
+                let mut option_nodes = Vec::new();
+                collect_options(node, &mut option_nodes);
+                let subtitle = form
+                    .groups
+                    .iter()
+                    .find(|group| group.questions.contains(&index))
+                    .and_then(|group| group.prompt.as_ref());
+                result.push(QuestionRow {
+                    formset_guid,
+                    form_title: strings_map.resolve(form.title.string_id).into_owned(),
+                    subtitle: subtitle
+                        .map(|prompt| strings_map.resolve(prompt.string_id).into_owned()),
+                    prompt: strings_map.resolve(header.prompt.string_id).into_owned(),
+                    help: strings_map.resolve(header.help.string_id).into_owned(),
+                    question_id: header.question_id,
+                    question_type: header.question_type,
+                    var_store: knob.and_then(|knob| knob.var_store_name.clone()),
+                    var_offset: knob.and_then(|knob| knob.var_offset),
+                    size: knob.map(|knob| knob.size),
+                    bit_field: knob.and_then(|knob| knob.bit_field),
+                    defaults: defaults
+                        .iter()
+                        .find(|question| question.offset == node.offset)
+                        .map(|question| question.defaults.clone())
+                        .unwrap_or_default(),
+                    options: option_nodes
+                        .iter()
+                        .filter_map(|option| uefi_parser::ifr_one_of_option(&option.data).ok())
+                        .map(|(_, option)| QuestionOption {
+                            value: option.Value,
+                            text: strings_map.resolve(option.OptionStringId).into_owned(),
+                        })
+                        .collect(),
+                    module_guid: None,
+                    module_name: None,
+                });
+            }
+        }
+    }
+    result
+}
+
+// Attribute rows to the FFS file their form package was found in, so reports can name the driver
+// implementing every question. Rows are left as they are if the file is unknown
+pub fn set_question_module(rows: &mut [QuestionRow], file: Option<&FfsFile>) {
+    if let Some(file) = file {
+        for row in rows {
+            row.module_guid = Some(file.guid);
+            row.module_name = file.name.clone();
+        }
+    }
+}
+
+// Field quoted if it contains separators, quotes or line breaks, quotes are doubled
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+// CSV with a header row and one row per question. Defaults are "store: value" pairs,
+// options are "value = text" pairs, both separated by semicolons
+pub fn questions_csv(rows: &[QuestionRow]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push_str("\r\n");
+    for row in rows {
+        let defaults: Vec<String> = row
+            .defaults
+            .iter()
+            .map(|default| match &default.value {
+                Some(value) => format!("{}: {}", default.store_name, value),
+                None => format!("{}: Expression", default.store_name),
+            })
+            .collect();
+        let options: Vec<String> = row
+            .options
+            .iter()
+            .map(|option| format!("{} = {}", option.value, option.text))
+            .collect();
+        let fields = [
+            row.formset_guid.to_string(),
+            row.form_title.clone(),
+            row.subtitle.clone().unwrap_or_default(),
+            row.prompt.clone(),
+            row.help.clone(),
+            format!("0x{:X}", row.question_id),
+            row.var_store.clone().unwrap_or_default(),
+            row.var_offset
+                .map(|offset| format!("0x{offset:X}"))
+                .unwrap_or_default(),
+            row.size.map(|size| size.to_string()).unwrap_or_default(),
+            row.bit_field
+                .map(|bits| format!("0x{:X}", bits.bit_offset))
+                .unwrap_or_default(),
+            row.bit_field
+                .map(|bits| bits.bit_width.to_string())
+                .unwrap_or_default(),
+            String::from(row.question_type.name()),
+            defaults.join("; "),
+            options.join("; "),
+            row.module_guid
+                .map(|guid| guid.to_string())
+                .unwrap_or_default(),
+            row.module_name.clone().unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+// Questions of all UEFI form packages of an image as CSV, strings are resolved using preferred string packages.
+// Questions of form packages in FFS files of firmware volumes are attributed to their drivers
+pub fn export_questions_csv(data: &[u8]) -> String {
+    let (strings, forms) = find_uefi_packages(data);
+    let volumes = find_firmware_volumes(data);
+    let mut rows = Vec::new();
+    for form in &forms {
+        if let Some(string_package) = uefi_preferred_string_package(data, &strings, form) {
+            let resources = uefi_resources(data, &[], &[], &[], &strings, form);
+            let mut form_rows = question_rows(data, form, string_package, &resources);
+            set_question_module(&mut form_rows, ffs_file_at(&volumes, form.offset));
+            rows.append(&mut form_rows);
+        }
+    }
+    questions_csv(&rows)
+}
@@ -0,0 +1,177 @@
+// Default values of every question per default store, collected from Default opcodes in question scopes,
+// default flags of OneOfOption opcodes and CheckBox flags
+use std::collections::HashMap;
+
+use super::{
+    uefi_string_map, ExtractOptions, FormPackage, HiiResources, QuestionType, StringPackage,
+};
+use ifr_tree::{parse_uefi_ifr, IfrNode, ResolvedString};
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionDefault {
+    pub default_id: u16,
+    pub store_name: String, // Name of the DefaultStore, Standard and Manufacturing for undeclared DefaultIds 0 and 1
+    pub offset: usize,      // Offset of the opcode the value comes from
+    pub opcode: uefi_parser::IfrOpcode, // Default, OneOfOption or CheckBox
+    pub value: Option<uefi_parser::IfrTypeValue>, // None if the value is given by an expression
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionDefaults {
+    pub offset: usize, // Offset of the question opcode in the input data
+    pub opcode: uefi_parser::IfrOpcode,
+    pub question_type: QuestionType,
+    pub question_id: u16,
+    pub prompt: ResolvedString,
+    pub bit_width: Option<usize>, // Only for questions in bit varstores, values are stored in that many bits whatever their type
+    pub defaults: Vec<QuestionDefault>, // Ordered by DefaultId
+}
+
+// Default opcodes and OneOfOption opcodes in the scope of a question, nested questions are skipped
+fn collect_defaults<'a>(node: &'a IfrNode, result: &mut Vec<&'a IfrNode>) {
+    for child in &node.children {
+        if child.opcode == uefi_parser::IfrOpcode::Default
+            || child.opcode == uefi_parser::IfrOpcode::OneOfOption
+        {
+            result.push(child);
+        } else if child.question.is_none() {
+            collect_defaults(child, result);
+        }
+    }
+}
+
+// Collect defaults of all questions of a UEFI form package that have any, strings are resolved
+// the same way extract_uefi_ifr does. Default opcodes take precedence over default flags for the same DefaultId
+pub fn question_defaults(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: &StringPackage,
+    resources: &HiiResources,
+) -> Vec<QuestionDefaults> {
+    let options = ExtractOptions::default();
+    let strings_map = uefi_string_map(data, form_package, string_package, resources, &options);
+    let tree = parse_uefi_ifr(data, form_package, string_package, resources);
+    let nodes = tree.walk();
+    let questions = tree.questions();
+
+    // Names of declared default stores
+    let mut store_names: HashMap<u16, String> = HashMap::new();
+    for node in &nodes {
+        if node.opcode == uefi_parser::IfrOpcode::DefaultStore {
+            if let Ok((_, default_store)) = uefi_parser::ifr_default_store(&node.data) {
+                store_names.insert(
+                    default_store.DefaultId,
+                    strings_map.resolve(default_store.NameStringId).into_owned(),
+                );
+            }
+        }
+    }
+    let store_name = |default_id: u16| match store_names.get(&default_id) {
+        Some(name) => name.clone(),
+        None => match default_id {
+            0 => String::from("Standard"),
+            1 => String::from("Manufacturing"),
+            _ => format!("DefaultId 0x{default_id:X}"),
+        },
+    };
+
+    let mut result = Vec::new();
+    for &(node, bit_field) in &questions {
+        let header = match &node.question {
+            Some(header) => header,
+            None => continue,
+        };
+        // Values from flags first, Default opcodes replace them
+        let mut flag_defaults = Vec::new();
+        let mut opcode_defaults = Vec::new();
+        if node.opcode == uefi_parser::IfrOpcode::CheckBox {
+            if let Ok((_, check_box)) = uefi_parser::ifr_check_box(&node.data) {
+                for (default_id, flag) in [
+                    (0, uefi_parser::IfrCheckBoxDefaultFlags::Default),
+                    (1, uefi_parser::IfrCheckBoxDefaultFlags::MfgDefault),
+                ] {
+                    flag_defaults.push((
+                        default_id,
+                        node.offset,
+                        node.opcode,
+                        uefi_parser::IfrTypeValue::Boolean(check_box.Flags & flag as u8 != 0),
+                    ));
+                }
+            }
+        }
+        let mut children = Vec::new();
+        collect_defaults(node, &mut children);
+        for child in children {
+            if child.opcode == uefi_parser::IfrOpcode::OneOfOption {
+                if let Ok((_, option)) = uefi_parser::ifr_one_of_option(&child.data) {
+                    for (default_id, flag) in [
+                        (0, uefi_parser::IfrOneOfOptionDefaultFlags::Default),
+                        (1, uefi_parser::IfrOneOfOptionDefaultFlags::MfgDefault),
+                    ] {
+                        if option.Flags & flag as u8 != 0 {
+                            flag_defaults.push((
+                                default_id,
+                                child.offset,
+                                child.opcode,
+                                option.Value.clone(),
+                            ));
+                        }
+                    }
+                }
+            } else if let Ok((_, default)) = uefi_parser::ifr_default(&child.data) {
+                // Default with a scope takes its value from the Value opcode in it
+                let value = if child.children.is_empty() {
+                    Some(default.Value)
+                } else {
+                    None
+                };
+                opcode_defaults.push((default.DefaultId, child.offset, child.opcode, value));
+            }
+        }
+
+        let mut defaults: Vec<QuestionDefault> = Vec::new();
+        for (default_id, offset, opcode, value) in flag_defaults
+            .into_iter()
+            .map(|(default_id, offset, opcode, value)| (default_id, offset, opcode, Some(value)))
+            .chain(opcode_defaults)
+        {
+            let default = QuestionDefault {
+                default_id,
+                store_name: store_name(default_id),
+                offset,
+                opcode,
+                value,
+            };
+            match defaults
+                .iter_mut()
+                .find(|other| other.default_id == default_id)
+            {
+                Some(other) => *other = default,
+                None => defaults.push(default),
+            }
+        }
+        if defaults.is_empty() {
+            continue;
+        }
+        defaults.sort_by_key(|default| default.default_id);
+        // OneOf and Numeric keep their width in bits in Flags, CheckBoxes take a single bit
+        let bit_width = match node.opcode {
+            uefi_parser::IfrOpcode::OneOf | uefi_parser::IfrOpcode::Numeric if bit_field => {
+                node.data.get(11).map(|flags| (flags & 0x3F) as usize)
+            }
+            uefi_parser::IfrOpcode::CheckBox if bit_field => Some(1),
+            _ => None,
+        };
+        result.push(QuestionDefaults {
+            offset: node.offset,
+            opcode: node.opcode,
+            question_type: header.question_type,
+            question_id: header.question_id,
+            prompt: header.prompt.clone(),
+            bit_width,
+            defaults,
+        });
+    }
+    result
+}
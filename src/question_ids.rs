@@ -0,0 +1,128 @@
+// QuestionId allocation per formset, to find free ids when extending a vendor formset
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use super::{FormPackage, StringMap, StringPackage};
+use uefi_parser;
+
+// Single question and the QuestionId it uses
+#[derive(Debug, Clone)]
+pub struct QuestionIdUse {
+    pub question_id: u16,
+    pub opcode: uefi_parser::IfrOpcode,
+    pub prompt: String,
+    pub offset: usize,  // Offset of the question opcode in the input data
+    pub explicit: bool, // Not a part of the sequential allocation VfrCompile uses for questions without an id
+}
+
+// QuestionIds of a single formset
+#[derive(Debug, Clone)]
+pub struct FormSetQuestionIds {
+    pub guid: uefi_parser::Guid,
+    pub title: String,
+    pub questions: Vec<QuestionIdUse>, // In order of declaration
+    pub used: Vec<RangeInclusive<u16>>,
+    pub gaps: Vec<RangeInclusive<u16>>, // Free ids between the lowest and the highest used one
+    pub duplicates: Vec<u16>,           // Ids used by more than one question
+}
+
+// Questions calling back to the driver must have explicit ids for the driver to handle them
+const QUESTION_FLAG_CALLBACK: u8 = 0x04;
+
+// Merge sorted and deduplicated ids into ranges of consecutive ids
+fn id_ranges(ids: &[u16]) -> Vec<RangeInclusive<u16>> {
+    let mut result: Vec<RangeInclusive<u16>> = Vec::new();
+    for &id in ids {
+        match result.last_mut() {
+            Some(range) if *range.end() as u32 + 1 == id as u32 => *range = *range.start()..=id,
+            _ => result.push(id..=id),
+        }
+    }
+    result
+}
+
+// Fill used ranges, gaps and duplicates, and guess which ids were assigned explicitly.
+// VfrCompile assigns ids to questions without one sequentially starting from 1, skipping ids already taken
+fn finish_formset(formset: &mut FormSetQuestionIds) {
+    let mut ids: Vec<u16> = formset.questions.iter().map(|question| question.question_id).collect();
+    ids.sort_unstable();
+    for pair in ids.windows(2) {
+        if pair[0] == pair[1] && formset.duplicates.last() != Some(&pair[0]) {
+            formset.duplicates.push(pair[0]);
+        }
+    }
+    ids.dedup();
+    formset.used = id_ranges(&ids);
+    formset.gaps = formset
+        .used
+        .windows(2)
+        .map(|pair| *pair[0].end() + 1..=*pair[1].start() - 1)
+        .collect();
+
+    let mut next_auto: u16 = 1;
+    for question in formset.questions.iter_mut() {
+        if !question.explicit && question.question_id == next_auto {
+            next_auto = next_auto.wrapping_add(1);
+        } else {
+            question.explicit = true;
+        }
+    }
+}
+
+// Collect QuestionIds of all formsets of a form package, prompts are resolved using a given string package
+pub fn uefi_question_id_report(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: Option<&StringPackage>,
+) -> Vec<FormSetQuestionIds> {
+    let mut result: Vec<FormSetQuestionIds> = Vec::new();
+    let no_strings = HashMap::new();
+    let strings_map = &StringMap::plain(
+        string_package.map_or(&no_strings, |strings| &strings.string_id_map),
+        "StringId {id}",
+    );
+
+    if let Ok((_, candidate)) = uefi_parser::hii_form_package_candidate(&data[form_package.offset..]) {
+        if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
+            if let Ok((_, operations)) = uefi_parser::ifr_operations(package.Data.unwrap_or(&[])) {
+                // Form package header is 4 bytes long
+                let mut offset = form_package.offset + 4;
+                for operation in &operations {
+                    let op_data = operation.Data.unwrap_or(&[]);
+                    if operation.OpCode == uefi_parser::IfrOpcode::FormSet {
+                        if let Ok((_, form_set)) = uefi_parser::ifr_form_set(op_data) {
+                            result.push(FormSetQuestionIds {
+                                guid: form_set.Guid,
+                                title: strings_map.resolve(form_set.TitleStringId).into_owned(),
+                                questions: Vec::new(),
+                                used: Vec::new(),
+                                gaps: Vec::new(),
+                                duplicates: Vec::new(),
+                            });
+                        }
+                    } else if operation.OpCode.is_question() && op_data.len() >= 11 {
+                        // All questions start with a question header:
+                        // PromptStringId, HelpStringId, QuestionId, VarStoreId, VarStoreInfo, Flags
+                        if let Some(formset) = result.last_mut() {
+                            formset.questions.push(QuestionIdUse {
+                                question_id: u16::from_le_bytes([op_data[4], op_data[5]]),
+                                opcode: operation.OpCode,
+                                prompt: strings_map
+                                    .resolve(u16::from_le_bytes([op_data[0], op_data[1]]))
+                                    .into_owned(),
+                                offset,
+                                explicit: op_data[10] & QUESTION_FLAG_CALLBACK != 0,
+                            });
+                        }
+                    }
+                    offset += operation.Length as usize;
+                }
+            }
+        }
+    }
+
+    for formset in result.iter_mut() {
+        finish_formset(formset);
+    }
+    result
+}
@@ -0,0 +1,119 @@
+// Questions together with the opcodes that modify them from inside their scope,
+// so every question can be inspected on its own without walking its siblings
+use std::collections::HashMap;
+
+use super::{uefi_expression, FormPackage, StringMap, StringPackage};
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuestionModifier {
+    Refresh { interval: u8 }, // Seconds
+    RefreshId { guid: uefi_parser::Guid },
+    Locked,
+    InconsistentIf { message: String, expression: String },
+    NoSubmitIf { message: String, expression: String },
+    WarningIf { message: String, timeout: u8, expression: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct ModifiedQuestion {
+    pub opcode: uefi_parser::IfrOpcode,
+    pub question_id: u16,
+    pub prompt: String,
+    pub offset: usize, // Offset of the question opcode in the input data
+    pub modifiers: Vec<QuestionModifier>,
+}
+
+// Collect all questions of a form package with their modifiers, strings are resolved using a given string package
+pub fn uefi_question_modifiers(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: Option<&StringPackage>,
+) -> Vec<ModifiedQuestion> {
+    let mut result: Vec<ModifiedQuestion> = Vec::new();
+    let no_strings = HashMap::new();
+    let strings_map = &StringMap::plain(
+        string_package.map_or(&no_strings, |strings| &strings.string_id_map),
+        "InvalidId",
+    );
+
+    let operations = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate).ok())
+        .and_then(|(_, package)| uefi_parser::ifr_operations(package.Data.unwrap_or(&[])).ok())
+    {
+        Some((_, operations)) => operations,
+        None => return result,
+    };
+
+    // Every open scope remembers the question that opened it, if any
+    let mut scopes: Vec<Option<usize>> = Vec::new();
+    // Form package header is 4 bytes long
+    let mut offset = form_package.offset + 4;
+    for (index, operation) in operations.iter().enumerate() {
+        let op_data = operation.Data.unwrap_or(&[]);
+        let expression = || uefi_expression(&operations[index + 1..], strings_map);
+        let modifier = match operation.OpCode {
+            uefi_parser::IfrOpcode::Refresh => uefi_parser::ifr_refresh(op_data)
+                .ok()
+                .map(|(_, refresh)| QuestionModifier::Refresh {
+                    interval: refresh.RefreshInterval,
+                }),
+            uefi_parser::IfrOpcode::RefreshId => uefi_parser::ifr_refresh_id(op_data)
+                .ok()
+                .map(|(_, refresh)| QuestionModifier::RefreshId { guid: refresh.Guid }),
+            uefi_parser::IfrOpcode::Locked => Some(QuestionModifier::Locked),
+            uefi_parser::IfrOpcode::InconsistentIf => uefi_parser::ifr_inconsistent_if(op_data)
+                .ok()
+                .map(|(_, incif)| QuestionModifier::InconsistentIf {
+                    message: strings_map.resolve(incif.ErrorStringId).into_owned(),
+                    expression: expression(),
+                }),
+            uefi_parser::IfrOpcode::NoSubmitIf => uefi_parser::ifr_no_submit_if(op_data)
+                .ok()
+                .map(|(_, nsif)| QuestionModifier::NoSubmitIf {
+                    message: strings_map.resolve(nsif.ErrorStringId).into_owned(),
+                    expression: expression(),
+                }),
+            uefi_parser::IfrOpcode::WarningIf => uefi_parser::ifr_warning_if(op_data)
+                .ok()
+                .map(|(_, wif)| QuestionModifier::WarningIf {
+                    message: strings_map.resolve(wif.WarningStringId).into_owned(),
+                    timeout: wif.Timeout,
+                    expression: expression(),
+                }),
+            _ => None,
+        };
+
+        // Modifiers belong to the question whose scope they are directly in
+        if let (Some(modifier), Some(&Some(question))) = (modifier, scopes.last()) {
+            result[question].modifiers.push(modifier);
+        }
+
+        if operation.OpCode.is_question() && op_data.len() >= 6 {
+            result.push(ModifiedQuestion {
+                opcode: operation.OpCode,
+                question_id: u16::from_le_bytes([op_data[4], op_data[5]]),
+                prompt: strings_map
+                    .resolve(u16::from_le_bytes([op_data[0], op_data[1]]))
+                    .into_owned(),
+                offset,
+                modifiers: Vec::new(),
+            });
+        }
+
+        if operation.OpCode == uefi_parser::IfrOpcode::End {
+            scopes.pop();
+        } else if operation.ScopeStart {
+            let question = if operation.OpCode.is_question() && op_data.len() >= 6 {
+                Some(result.len() - 1)
+            } else {
+                None
+            };
+            scopes.push(question);
+        }
+        offset += operation.Length as usize;
+    }
+
+    result
+}
@@ -0,0 +1,195 @@
+// Conversion between logical question values and their varstore representation
+use std::ops::Range;
+
+use uefi_parser::{HiiDate, HiiTime, IfrTypeValue};
+
+// Location of a question value in its varstore, all values are stored little-endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuestionStorage {
+    pub bit_offset: usize,
+    pub bit_width: usize,
+}
+
+// Kind of value a question keeps in its varstore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestionValueType {
+    Numeric, // OneOf and Numeric
+    Boolean, // CheckBox
+    Date,
+    Time,
+    Buffer, // OrderedList and other raw storage
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuestionValueError {
+    OutOfBounds,      // Storage doesn't fit into the varstore
+    ValueTooWide,     // Value doesn't fit into the storage
+    UnalignedStorage, // Buffers can't be stored in bit fields
+    UnsupportedValue, // No varstore representation for this value
+}
+
+impl QuestionStorage {
+    // Byte-aligned storage, VarOffset and width of a question in a buffer varstore
+    pub fn bytes(offset: usize, width: usize) -> QuestionStorage {
+        QuestionStorage {
+            bit_offset: offset * 8,
+            bit_width: width * 8,
+        }
+    }
+
+    // Bit field storage, VarOffset and width are given in bits
+    pub fn bits(bit_offset: usize, bit_width: usize) -> QuestionStorage {
+        QuestionStorage {
+            bit_offset,
+            bit_width,
+        }
+    }
+
+    // Byte-aligned storage of a OneOf or Numeric question, width is encoded in the lower bits of its Flags
+    pub fn numeric(offset: usize, flags: u8) -> QuestionStorage {
+        QuestionStorage::bytes(offset, 1 << (flags & 0x03))
+    }
+
+    // Range of varstore bytes touched by this storage
+    pub fn byte_range(&self) -> Range<usize> {
+        self.bit_offset / 8..(self.bit_offset + self.bit_width).div_ceil(8)
+    }
+
+    fn is_byte_aligned(&self) -> bool {
+        self.bit_offset.is_multiple_of(8) && self.bit_width.is_multiple_of(8)
+    }
+
+    fn read_bits(&self, varstore: &[u8]) -> Result<u64, QuestionValueError> {
+        if self.bit_width > 64 || self.byte_range().end > varstore.len() {
+            return Err(QuestionValueError::OutOfBounds);
+        }
+        let mut value = 0u64;
+        for i in 0..self.bit_width {
+            let position = self.bit_offset + i;
+            if varstore[position / 8] & (1 << (position % 8)) != 0 {
+                value |= 1 << i;
+            }
+        }
+        Ok(value)
+    }
+
+    fn write_bits(&self, value: u64, varstore: &mut [u8]) -> Result<(), QuestionValueError> {
+        if self.bit_width > 64 || self.byte_range().end > varstore.len() {
+            return Err(QuestionValueError::OutOfBounds);
+        }
+        if self.bit_width < 64 && value >> self.bit_width != 0 {
+            return Err(QuestionValueError::ValueTooWide);
+        }
+        for i in 0..self.bit_width {
+            let position = self.bit_offset + i;
+            if value & (1 << i) != 0 {
+                varstore[position / 8] |= 1 << (position % 8);
+            } else {
+                varstore[position / 8] &= !(1 << (position % 8));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Write a logical value into the varstore, bits outside of the storage are left intact
+pub fn encode_question_value(
+    storage: &QuestionStorage,
+    value: &IfrTypeValue,
+    varstore: &mut [u8],
+) -> Result<(), QuestionValueError> {
+    match *value {
+        IfrTypeValue::NumSize8(x) => storage.write_bits(x as u64, varstore),
+        IfrTypeValue::NumSize16(x) => storage.write_bits(x as u64, varstore),
+        IfrTypeValue::NumSize32(x) => storage.write_bits(x as u64, varstore),
+        IfrTypeValue::NumSize64(x) => storage.write_bits(x, varstore),
+        IfrTypeValue::Boolean(x) => storage.write_bits(x as u64, varstore),
+        // EFI_HII_DATE is Year (u16), Month (u8), Day (u8)
+        IfrTypeValue::Date(x) => storage.write_bits(
+            x.Year as u64 | (x.Month as u64) << 16 | (x.Day as u64) << 24,
+            varstore,
+        ),
+        // EFI_HII_TIME is Hour (u8), Minute (u8), Second (u8)
+        IfrTypeValue::Time(x) => storage.write_bits(
+            x.Hour as u64 | (x.Minute as u64) << 8 | (x.Second as u64) << 16,
+            varstore,
+        ),
+        IfrTypeValue::Buffer(ref x) => {
+            if !storage.is_byte_aligned() {
+                return Err(QuestionValueError::UnalignedStorage);
+            }
+            let range = storage.byte_range();
+            if range.end > varstore.len() {
+                return Err(QuestionValueError::OutOfBounds);
+            }
+            if x.len() > range.len() {
+                return Err(QuestionValueError::ValueTooWide);
+            }
+            varstore[range.start..range.start + x.len()].copy_from_slice(x);
+            varstore[range.start + x.len()..range.end].fill(0);
+            Ok(())
+        }
+        _ => Err(QuestionValueError::UnsupportedValue),
+    }
+}
+
+// Exact bytes to write at byte_range() of the varstore, bit fields are merged into the current varstore contents
+pub fn question_value_bytes(
+    storage: &QuestionStorage,
+    value: &IfrTypeValue,
+    varstore: &[u8],
+) -> Result<Vec<u8>, QuestionValueError> {
+    let range = storage.byte_range();
+    if range.end > varstore.len() {
+        return Err(QuestionValueError::OutOfBounds);
+    }
+    let mut bytes = varstore[range.clone()].to_vec();
+    let local = QuestionStorage::bits(storage.bit_offset - range.start * 8, storage.bit_width);
+    encode_question_value(&local, value, &mut bytes)?;
+    Ok(bytes)
+}
+
+// Read a logical value of a given type from the varstore
+pub fn decode_question_value(
+    storage: &QuestionStorage,
+    value_type: QuestionValueType,
+    varstore: &[u8],
+) -> Result<IfrTypeValue, QuestionValueError> {
+    match value_type {
+        QuestionValueType::Numeric => {
+            let x = storage.read_bits(varstore)?;
+            Ok(match storage.bit_width {
+                0..=8 => IfrTypeValue::NumSize8(x as u8),
+                9..=16 => IfrTypeValue::NumSize16(x as u16),
+                17..=32 => IfrTypeValue::NumSize32(x as u32),
+                _ => IfrTypeValue::NumSize64(x),
+            })
+        }
+        QuestionValueType::Boolean => Ok(IfrTypeValue::Boolean(storage.read_bits(varstore)? != 0)),
+        QuestionValueType::Date => {
+            let x = storage.read_bits(varstore)?;
+            Ok(IfrTypeValue::Date(HiiDate {
+                Year: x as u16,
+                Month: (x >> 16) as u8,
+                Day: (x >> 24) as u8,
+            }))
+        }
+        QuestionValueType::Time => {
+            let x = storage.read_bits(varstore)?;
+            Ok(IfrTypeValue::Time(HiiTime {
+                Hour: x as u8,
+                Minute: (x >> 8) as u8,
+                Second: (x >> 16) as u8,
+            }))
+        }
+        QuestionValueType::Buffer => {
+            if !storage.is_byte_aligned() {
+                return Err(QuestionValueError::UnalignedStorage);
+            }
+            varstore
+                .get(storage.byte_range())
+                .map(|x| IfrTypeValue::Buffer(x.to_vec()))
+                .ok_or(QuestionValueError::OutOfBounds)
+        }
+    }
+}
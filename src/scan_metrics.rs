@@ -0,0 +1,234 @@
+// Metrics collected while scanning the input file for packages, to understand performance problems with specific images
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Single package candidate that passed the quick header check
+#[derive(Debug, Clone)]
+pub struct PackageMetrics {
+    pub kind: &'static str, // i.e. "UEFI string"
+    pub offset: usize,
+    pub length: usize,
+    pub accepted: bool, // False if the candidate failed to parse
+    pub duration: Duration,
+}
+
+// Progress of a scan passed to progress callbacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanProgress {
+    pub phase: &'static str,    // i.e. "UEFI string packages"
+    pub bytes_processed: usize, // Over all phases so far, every phase scans the whole input once
+    pub packages_found: usize,
+}
+
+// Bytes scanned between two progress reports of the same phase
+pub const PROGRESS_INTERVAL: usize = 0x10_0000;
+
+// Optional progress callback with the offset of its next report, and an optional cancellation token
+#[derive(Default, Clone)]
+pub struct ProgressReporter {
+    callback: Option<Arc<dyn Fn(ScanProgress) + Send + Sync>>,
+    next_offset: usize,
+    cancellation: Option<Arc<AtomicBool>>, // Scans stop once it is set
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("callback", &self.callback.is_some())
+            .field("next_offset", &self.next_offset)
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ScanMetrics {
+    pub bytes_scanned: usize, // Every phase scans the whole input once
+    pub candidates_tried: usize,
+    pub packages_accepted: usize,
+    pub packages_rejected: usize,
+    pub phases: Vec<(&'static str, Duration)>, // In order of execution
+    pub packages: Vec<PackageMetrics>,
+    pub progress: ProgressReporter,
+    pub cancelled: bool, // Scan was stopped by the cancellation token, its results are empty
+}
+
+impl ScanMetrics {
+    pub fn new() -> ScanMetrics {
+        ScanMetrics::default()
+    }
+
+    // Metrics that report progress every PROGRESS_INTERVAL bytes of every phase and at the end of it
+    pub fn with_progress(callback: impl Fn(ScanProgress) + Send + Sync + 'static) -> ScanMetrics {
+        ScanMetrics {
+            progress: ProgressReporter {
+                callback: Some(Arc::new(callback)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    // Stop scanning as soon as a token is set, i.e. from another thread when a user aborts a long scan
+    pub fn with_cancellation(mut self, token: Arc<AtomicBool>) -> ScanMetrics {
+        self.progress.cancellation = Some(token);
+        self
+    }
+
+    // Called by scan loops with the current offset of a phase, cheap if no report is due.
+    // Break means the scan was cancelled and the loop has to stop
+    pub fn report_progress(&mut self, phase: &'static str, offset: usize) -> ControlFlow<()> {
+        if let Some(token) = &self.progress.cancellation {
+            if self.cancelled || token.load(Ordering::Relaxed) {
+                self.cancelled = true;
+                return ControlFlow::Break(());
+            }
+        }
+        if offset < self.progress.next_offset {
+            return ControlFlow::Continue(());
+        }
+        if let Some(callback) = &self.progress.callback {
+            callback(ScanProgress {
+                phase,
+                bytes_processed: self.bytes_scanned + offset,
+                packages_found: self.packages_accepted,
+            });
+            self.progress.next_offset = offset + PROGRESS_INTERVAL;
+        } else {
+            self.progress.next_offset = usize::MAX;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn record_package(
+        &mut self,
+        kind: &'static str,
+        offset: usize,
+        length: usize,
+        accepted: bool,
+        started: Instant,
+    ) {
+        self.candidates_tried += 1;
+        if accepted {
+            self.packages_accepted += 1;
+        } else {
+            self.packages_rejected += 1;
+        }
+        self.packages.push(PackageMetrics {
+            kind,
+            offset,
+            length,
+            accepted,
+            duration: started.elapsed(),
+        });
+    }
+
+    pub fn record_phase(&mut self, name: &'static str, bytes: usize, started: Instant) {
+        self.bytes_scanned += bytes;
+        self.phases.push((name, started.elapsed()));
+        // Final report of the phase, the next one starts from its beginning
+        self.progress.next_offset = 0;
+        let _ = self.report_progress(name, 0);
+        self.progress.next_offset = 0;
+    }
+}
+
+impl fmt::Display for ScanMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Bytes scanned: 0x{:X}, Candidates tried: {}, Packages accepted: {}, Packages rejected: {}",
+            self.bytes_scanned, self.candidates_tried, self.packages_accepted, self.packages_rejected
+        )?;
+        for (name, duration) in &self.phases {
+            writeln!(f, "Phase: {name}, Duration: {duration:?}")?;
+        }
+        for package in &self.packages {
+            writeln!(
+                f,
+                "Package: {}, Offset: 0x{:X}, Length: 0x{:X}, {}, Duration: {:?}",
+                package.kind,
+                package.offset,
+                package.length,
+                if package.accepted {
+                    "Accepted"
+                } else {
+                    "Rejected"
+                },
+                package.duration
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Image-level summary of a scan, so callers can report it without going through all packages again
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScanSummary {
+    pub bytes_scanned: usize,
+    pub package_counts: Vec<(&'static str, usize)>, // Packages found per kind, i.e. "UEFI form"
+    pub languages: Vec<String>, // Languages of string packages in order of their first appearance
+    pub elapsed: Duration,
+    pub diagnostics: usize, // Package candidates that failed to parse
+    pub cancelled: bool,
+}
+
+impl ScanSummary {
+    pub fn new(metrics: &ScanMetrics, elapsed: Duration) -> ScanSummary {
+        ScanSummary {
+            bytes_scanned: metrics.bytes_scanned,
+            elapsed,
+            diagnostics: metrics.packages_rejected,
+            cancelled: metrics.cancelled,
+            ..Default::default()
+        }
+    }
+
+    // Kinds without packages are not listed
+    pub fn add_packages(&mut self, kind: &'static str, count: usize) {
+        if count == 0 {
+            return;
+        }
+        match self.package_counts.iter_mut().find(|(other, _)| *other == kind) {
+            Some((_, total)) => *total += count,
+            None => self.package_counts.push((kind, count)),
+        }
+    }
+
+    pub fn add_language(&mut self, language: &str) {
+        if !self.languages.iter().any(|other| other == language) {
+            self.languages.push(String::from(language));
+        }
+    }
+
+    // Number of packages of a given kind, 0 if none were found
+    pub fn count(&self, kind: &str) -> usize {
+        self.package_counts
+            .iter()
+            .find(|(other, _)| *other == kind)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+impl fmt::Display for ScanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Bytes scanned: 0x{:X}, Elapsed: {:?}, Diagnostics: {}",
+            self.bytes_scanned, self.elapsed, self.diagnostics
+        )?;
+        for (kind, count) in &self.package_counts {
+            writeln!(f, "Packages: {kind}, Count: {count}")?;
+        }
+        if !self.languages.is_empty() {
+            writeln!(f, "Languages: {}", self.languages.join(", "))?;
+        }
+        if self.cancelled {
+            writeln!(f, "Scan cancelled")?;
+        }
+        Ok(())
+    }
+}
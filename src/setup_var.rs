@@ -0,0 +1,123 @@
+// Ready-to-run commands of the modified GRUB shell used by firmware modders to read or change a question value:
+// setup_var works on the Setup variable a byte at a time, setup_var_cv works on any variable by its name
+use knob_map::Knob;
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetupVarCommands {
+    pub question_id: u16,
+    pub prompt: String,
+    pub var_store_name: String,
+    pub var_store_guid: uefi_parser::Guid,
+    pub offset: u16,
+    pub size: usize,
+    pub value: Option<u64>, // None for commands that print the current value
+    pub mask: Option<u64>, // Bits of the value read from offset that belong to a question in a bit varstore
+}
+
+impl SetupVarCommands {
+    // Commands for a knob, None for name/value and undeclared varstores, or a value that doesn't fit the question.
+    // Questions in bit varstores share bytes with other questions and the commands write whole bytes,
+    // so only commands that print their bytes are made for them, with a mask of their bits
+    pub fn from_knob(knob: &Knob, value: Option<u64>) -> Option<SetupVarCommands> {
+        let fits = value.is_none_or(|value| knob.size >= 8 || value >> (knob.size * 8) == 0);
+        if !fits || (value.is_some() && (knob.size > 8 || knob.bit_field.is_some())) {
+            return None;
+        }
+        let mask = match knob.bit_field {
+            Some(bits) if bits.bit_width + bits.bit_offset % 8 > 64 => return None,
+            Some(bits) => Some((((1u128 << bits.bit_width) - 1) << (bits.bit_offset % 8)) as u64),
+            None => None,
+        };
+        Some(SetupVarCommands {
+            question_id: knob.question_id,
+            prompt: knob.prompt.clone(),
+            var_store_name: knob.var_store_name.clone()?,
+            var_store_guid: knob.var_store_guid?,
+            offset: knob.var_offset?,
+            size: knob.size,
+            value,
+            mask,
+        })
+    }
+
+    // One setup_var command per byte of the little-endian value, empty for variables other than Setup
+    pub fn setup_var(&self) -> Vec<String> {
+        if self.var_store_name != "Setup" {
+            return Vec::new();
+        }
+        (0..self.size)
+            .map(|index| {
+                let offset = self.offset as usize + index;
+                match self.value {
+                    Some(value) => format!(
+                        "setup_var 0x{:X} 0x{:X}",
+                        offset,
+                        (value >> (index * 8)) as u8
+                    ),
+                    None => format!("setup_var 0x{offset:X}"),
+                }
+            })
+            .collect()
+    }
+
+    pub fn setup_var_cv(&self) -> String {
+        let command = format!(
+            "setup_var_cv {} 0x{:X} 0x{:X}",
+            self.var_store_name, self.offset, self.size
+        );
+        match self.value {
+            Some(value) => format!("{command} 0x{value:X}"),
+            None => command,
+        }
+    }
+
+    // Comment naming the question and its variable, followed by both kinds of commands
+    pub fn script(&self) -> String {
+        let mut script = format!(
+            "# \"{}\", QuestionId: 0x{:X}, VarStore: {} {}, Offset: 0x{:X}, Size: 0x{:X}",
+            self.prompt,
+            self.question_id,
+            self.var_store_name,
+            self.var_store_guid,
+            self.offset,
+            self.size
+        );
+        if let Some(mask) = self.mask {
+            script.push_str(&format!(", Mask: 0x{mask:X}"));
+        }
+        script.push('\n');
+        for command in self.setup_var() {
+            script.push_str(&command);
+            script.push('\n');
+        }
+        script.push_str(&self.setup_var_cv());
+        script.push('\n');
+        script
+    }
+}
+
+// Question given by its prompt, compared case-insensitively, or by its QuestionId in hexadecimal, i.e. 0x100
+fn knob_matches(knob: &Knob, query: &str) -> bool {
+    match query
+        .strip_prefix("0x")
+        .or_else(|| query.strip_prefix("0X"))
+        .and_then(|id| u16::from_str_radix(id, 16).ok())
+    {
+        Some(question_id) => knob.question_id == question_id,
+        None => knob.prompt.eq_ignore_ascii_case(query),
+    }
+}
+
+// Commands for all knobs matching a prompt or QuestionId, several questions can share the same prompt
+pub fn setup_var_commands(
+    knobs: &[Knob],
+    query: &str,
+    value: Option<u64>,
+) -> Vec<SetupVarCommands> {
+    knobs
+        .iter()
+        .filter(|knob| knob_matches(knob, query))
+        .filter_map(|knob| SetupVarCommands::from_knob(knob, value))
+        .collect()
+}
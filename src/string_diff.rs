@@ -0,0 +1,66 @@
+// Comparison of two string packages by their StringIds, i.e. the same language in two releases of a BIOS
+use super::StringPackage;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringEntry {
+    pub string_id: u16,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedString {
+    pub string_id: u16,
+    pub old: String,
+    pub new: String,
+}
+
+// All lists are sorted by StringId
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringPackageDiff {
+    pub added: Vec<StringEntry>, // StringIds of the new package not present in the old one
+    pub removed: Vec<StringEntry>, // StringIds of the old package not present in the new one
+    pub changed: Vec<ChangedString>,
+}
+
+impl StringPackageDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Strings of a package with StringIds not present in another one
+fn string_difference(package: &StringPackage, other: &StringPackage) -> Vec<StringEntry> {
+    let mut result: Vec<StringEntry> = package
+        .string_id_map
+        .iter()
+        .filter(|(string_id, _)| !other.string_id_map.contains_key(string_id))
+        .map(|(string_id, text)| StringEntry {
+            string_id: *string_id,
+            text: text.clone(),
+        })
+        .collect();
+    result.sort_by_key(|entry| entry.string_id);
+    result
+}
+
+// Compare strings of two packages with the same StringIds, languages of the packages are not checked
+pub fn diff_strings(a: &StringPackage, b: &StringPackage) -> StringPackageDiff {
+    let mut changed: Vec<ChangedString> = b
+        .string_id_map
+        .iter()
+        .filter_map(|(string_id, new)| match a.string_id_map.get(string_id) {
+            Some(old) if old != new => Some(ChangedString {
+                string_id: *string_id,
+                old: old.clone(),
+                new: new.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+    changed.sort_by_key(|change| change.string_id);
+    StringPackageDiff {
+        added: string_difference(b, a),
+        removed: string_difference(a, b),
+        changed,
+    }
+}
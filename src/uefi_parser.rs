@@ -3,19 +3,17 @@
 
 extern crate nom;
 
-use nom::{le_u16, le_u32, le_u64, le_u8, rest, IResult};
+use nom::{le_i16, le_u16, le_u32, le_u64, le_u8, rest, IResult};
 use std::fmt;
+use std::ops::Range;
+
+use OpcodeCategory;
+use QuestionType;
 
 //
 // Common data types
 //
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct Guid {
-    pub data1: u32,
-    pub data2: u16,
-    pub data3: u16,
-    pub data4: [u8; 8],
-}
+pub use guid::Guid;
 
 pub fn guid(input: &[u8]) -> IResult<&[u8], Guid> {
     do_parse!(
@@ -33,26 +31,6 @@ pub fn guid(input: &[u8]) -> IResult<&[u8], Guid> {
     )
 }
 
-impl fmt::Display for Guid {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
-            self.data1,
-            self.data2,
-            self.data3,
-            self.data4[0],
-            self.data4[1],
-            self.data4[2],
-            self.data4[3],
-            self.data4[4],
-            self.data4[5],
-            self.data4[6],
-            self.data4[7]
-        )
-    }
-}
-
 //
 // HII package header
 //
@@ -73,6 +51,7 @@ pub enum HiiPackageType {
     Images,
     SimpleFonts,
     DevicePath,
+    Animations,
     End,
     System(u8),
     Unknown(u8),
@@ -89,6 +68,7 @@ impl From<u8> for HiiPackageType {
             0x06 => HiiPackageType::Images,
             0x07 => HiiPackageType::SimpleFonts,
             0x08 => HiiPackageType::DevicePath,
+            0x0A => HiiPackageType::Animations,
             0xDF => HiiPackageType::End,
             0xE0..=0xFF => HiiPackageType::System(n),
             _ => HiiPackageType::Unknown(n),
@@ -96,7 +76,7 @@ impl From<u8> for HiiPackageType {
     }
 }
 
-pub fn hii_package(input: &[u8]) -> IResult<&[u8], HiiPackage> {
+pub fn hii_package(input: &[u8]) -> IResult<&[u8], HiiPackage<'_>> {
     do_parse!(
         input,
         len_raw: le_u32
@@ -151,6 +131,45 @@ fn hii_form_package_candidate_helper(input: &[u8]) -> IResult<&[u8], usize> {
     )
 }
 
+pub fn hii_image_package_candidate(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    do_parse!(
+        input,
+        len: peek!(hii_image_package_candidate_helper) >> dat: take!(len) >> (dat)
+    )
+}
+
+fn hii_image_package_candidate_helper(input: &[u8]) -> IResult<&[u8], usize> {
+    do_parse!(
+        input,
+        len_raw: le_u32 >>
+        len: verify!(value!(len_raw & 0x00FFFFFF), |val: u32| val > 0x0C) >> // Total length of the package is sane
+        verify!(value!(len_raw & 0xFF000000), |val: u32| val == 0x06000000) >> // Package type is 0x06
+        verify!(le_u32, |val: u32| val == 0 || (val >= 0x0C && val < len)) >> // ImageInfoOffset is inside the package
+        verify!(le_u32, |val: u32| val == 0 || (val >= 0x0C && val < len)) >> // PaletteInfoOffset is inside the package
+        take!(len - 0x0C) >>
+        ( len as usize )
+    )
+}
+
+pub fn hii_animation_package_candidate(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    do_parse!(
+        input,
+        len: peek!(hii_animation_package_candidate_helper) >> dat: take!(len) >> (dat)
+    )
+}
+
+fn hii_animation_package_candidate_helper(input: &[u8]) -> IResult<&[u8], usize> {
+    do_parse!(
+        input,
+        len_raw: le_u32 >>
+        len: verify!(value!(len_raw & 0x00FFFFFF), |val: u32| val > 0x08) >> // Total length of the package is sane
+        verify!(value!(len_raw & 0xFF000000), |val: u32| val == 0x0A000000) >> // Package type is 0x0A
+        verify!(le_u32, |val: u32| val == 0 || (val >= 0x08 && val < len)) >> // AnimationInfoOffset is inside the package
+        take!(len - 0x08) >>
+        ( len as usize )
+    )
+}
+
 //
 // HII string package
 //
@@ -164,7 +183,7 @@ pub struct HiiStringPackage<'a> {
     pub Data: &'a [u8],
 }
 
-pub fn hii_string_package(input: &[u8]) -> IResult<&[u8], HiiStringPackage> {
+pub fn hii_string_package(input: &[u8]) -> IResult<&[u8], HiiStringPackage<'_>> {
     do_parse!(
         input,
         hs  : verify!(le_u32, |val: u32| val == 0x34) >>
@@ -235,11 +254,11 @@ pub struct HiiSibtBlock<'a> {
     pub Data: Option<&'a [u8]>,
 }
 
-pub fn hii_sibt_blocks(input: &[u8]) -> IResult<&[u8], Vec<HiiSibtBlock>> {
+pub fn hii_sibt_blocks(input: &[u8]) -> IResult<&[u8], Vec<HiiSibtBlock<'_>>> {
     do_parse!(input, v: many1!(complete!(hii_sibt_block)) >> (v))
 }
 
-pub fn hii_sibt_block(input: &[u8]) -> IResult<&[u8], HiiSibtBlock> {
+pub fn hii_sibt_block(input: &[u8]) -> IResult<&[u8], HiiSibtBlock<'_>> {
     do_parse!(
         input,
         typ: peek!(le_u8)
@@ -250,7 +269,7 @@ pub fn hii_sibt_block(input: &[u8]) -> IResult<&[u8], HiiSibtBlock> {
                 0x12 => peek!(do_parse!(cnt: le_u16 >>
                                         v: count!(do_parse!(s: scsu_string >> (s.len())), cnt as usize) >>
                                         ( v.iter().sum() ))) | // One u16 as count, and a number of SCSU strings
-                0x12 => peek!(do_parse!(take!(1) >>
+                0x13 => peek!(do_parse!(take!(1) >>
                                         cnt: le_u16 >>
                                         v: count!(do_parse!(s: scsu_string >> (s.len())), cnt as usize) >>
                                         ( v.iter().sum() ))) | // One u8, one u16 as count and a number of SCSU strings
@@ -267,17 +286,14 @@ pub fn hii_sibt_block(input: &[u8]) -> IResult<&[u8], HiiSibtBlock> {
                 0x21 => value!(2) | // Skip2 block has one u16
                 0x22 => value!(1) | // Skip1 block has one u8
                 0x30 => peek!(do_parse!(le_u8 >>
-                                  l: le_u8 >>
-                                  take!(l as usize) >>
-                                  ( l as usize ))) | // Obtain length from Ext1 block
+                                  l: verify!(le_u8, |val: u8| val >= 3) >>
+                                  ( l as usize - 1 ))) | // Length of Ext1 block includes the header
                 0x31 => peek!(do_parse!(le_u8 >>
-                                  l: le_u16 >>
-                                  take!(l as usize) >>
-                                  ( l as usize ))) | // Obtain length from Ext2 block
+                                  l: verify!(le_u16, |val: u16| val >= 4) >>
+                                  ( l as usize - 1 ))) | // Length of Ext2 block includes the header
                 0x32 => peek!(do_parse!(le_u8 >>
-                                  l: le_u32 >>
-                                  take!(l as usize) >>
-                                  ( l as usize )))  // Obtain length from Ext4 block
+                                  l: verify!(le_u32, |val: u32| val >= 6) >>
+                                  ( l as usize - 1 )))  // Length of Ext4 block includes the header
             )
             >> dat: cond_with_error!(len > 0, take!(len))
             >> (HiiSibtBlock {
@@ -391,6 +407,669 @@ pub fn sibt_strings_ucs2_font(input: &[u8]) -> IResult<&[u8], Vec<String>> {
     )
 }
 
+// Font of the SIBT_FONT block, data of the Ext2 block starts with its BlockType2
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiSibtFont {
+    pub FontId: u8,
+    pub FontSize: u16,
+    pub FontStyle: u32,
+    pub FontName: String,
+}
+
+pub fn sibt_font(input: &[u8]) -> IResult<&[u8], HiiSibtFont> {
+    do_parse!(
+        input,
+        verify!(le_u8, |val: u8| val == 0x40) // EFI_HII_SIBT_FONT
+            >> le_u16
+            >> id: le_u8
+            >> sz: le_u16
+            >> st: le_u32
+            >> s: ucs2_string
+            >> (HiiSibtFont {
+                FontId: id,
+                FontSize: sz,
+                FontStyle: st,
+                FontName: String::from_utf16_lossy(&s[..s.len() - 1]),
+            })
+    )
+}
+
+// FontId of a string block with a font, it is the first byte of its data
+pub fn sibt_font_id(typ: HiiSibtType, data: &[u8]) -> Option<u8> {
+    match typ {
+        HiiSibtType::StringScsuFont
+        | HiiSibtType::StringsScsuFont
+        | HiiSibtType::StringUcs2Font
+        | HiiSibtType::StringsUcs2Font => data.first().copied(),
+        _ => None,
+    }
+}
+
+//
+// HII font package
+//
+pub fn hii_font_package_candidate(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    do_parse!(
+        input,
+        len: peek!(hii_font_package_candidate_helper) >> dat: take!(len) >> (dat)
+    )
+}
+
+fn hii_font_package_candidate_helper(input: &[u8]) -> IResult<&[u8], usize> {
+    do_parse!(
+        input,
+        len_raw: le_u32 >>
+        len: verify!(value!(len_raw & 0x00FFFFFF), |val: u32| val > 0x1E) >> // Total length of the package is sane
+        verify!(value!(len_raw & 0xFF000000), |val: u32| val == 0x05000000) >> // Package type is 0x05
+        hs: verify!(le_u32, |val: u32| val >= 0x1E && val < len) >> // HdrSize includes at least an empty FontFamily
+        verify!(le_u32, |val: u32| val >= hs && val < len) >> // GlyphBlockOffset is after the header
+        take!(len - 0x0C) >>
+        ( len as usize )
+    )
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct HiiGlyphInfo {
+    pub Width: u16,
+    pub Height: u16,
+    pub OffsetX: i16,
+    pub OffsetY: i16,
+    pub AdvanceX: i16,
+}
+
+impl HiiGlyphInfo {
+    // Size of a glyph bitmap of this cell, rows are padded to whole bytes
+    pub fn bitmap_size(&self) -> usize {
+        (self.Width as usize).div_ceil(8) * self.Height as usize
+    }
+}
+
+pub fn hii_glyph_info(input: &[u8]) -> IResult<&[u8], HiiGlyphInfo> {
+    do_parse!(
+        input,
+        w: le_u16
+            >> h: le_u16
+            >> x: le_i16
+            >> y: le_i16
+            >> a: le_i16
+            >> (HiiGlyphInfo {
+                Width: w,
+                Height: h,
+                OffsetX: x,
+                OffsetY: y,
+                AdvanceX: a,
+            })
+    )
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiFontPackage {
+    pub HdrSize: u32,
+    pub GlyphBlockOffset: u32, // Relative to the start of package header
+    pub Cell: HiiGlyphInfo,
+    pub FontStyle: u32,
+    pub FontFamily: String,
+}
+
+pub fn hii_font_package(input: &[u8]) -> IResult<&[u8], HiiFontPackage> {
+    do_parse!(
+        input,
+        hs: le_u32
+            >> gbo: le_u32
+            >> c: hii_glyph_info
+            >> st: le_u32
+            >> s: ucs2_string
+            >> (HiiFontPackage {
+                HdrSize: hs,
+                GlyphBlockOffset: gbo,
+                Cell: c,
+                FontStyle: st,
+                FontFamily: String::from_utf16_lossy(&s[..s.len() - 1]),
+            })
+    )
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum HiiGibtType {
+    End,
+    Glyph,
+    Glyphs,
+    GlyphDefault,
+    GlyphsDefault,
+    Duplicate,
+    Skip2,
+    Skip1,
+    Defaults,
+    Ext1,
+    Ext2,
+    Ext4,
+    Unknown(u8),
+}
+
+impl From<u8> for HiiGibtType {
+    fn from(n: u8) -> HiiGibtType {
+        match n {
+            0x00 => HiiGibtType::End,
+            0x10 => HiiGibtType::Glyph,
+            0x11 => HiiGibtType::Glyphs,
+            0x12 => HiiGibtType::GlyphDefault,
+            0x13 => HiiGibtType::GlyphsDefault,
+            0x20 => HiiGibtType::Duplicate,
+            0x21 => HiiGibtType::Skip2,
+            0x22 => HiiGibtType::Skip1,
+            0x23 => HiiGibtType::Defaults,
+            0x30 => HiiGibtType::Ext1,
+            0x31 => HiiGibtType::Ext2,
+            0x32 => HiiGibtType::Ext4,
+            _ => HiiGibtType::Unknown(n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiGlyphBlock<'a> {
+    pub Type: HiiGibtType,
+    pub Data: Option<&'a [u8]>,
+}
+
+// Glyph blocks without a cell of their own use the default cell, it is changed by Defaults blocks.
+// Unknown blocks, i.e. GlyphVariability, can't be parsed because their length is unknown
+pub fn hii_glyph_block(input: &[u8], default_cell: HiiGlyphInfo) -> IResult<&[u8], HiiGlyphBlock<'_>> {
+    do_parse!(
+        input,
+        typ: peek!(le_u8)
+            >> len: switch!(le_u8,
+                0x00 => value!(0) | // End block has no data
+                0x10 => peek!(do_parse!(c: hii_glyph_info >>
+                                        ( 10 + c.bitmap_size() ))) | // Cell and one bitmap
+                0x11 => peek!(do_parse!(c: hii_glyph_info >> cnt: le_u16 >>
+                                        ( 12 + cnt as usize * c.bitmap_size() ))) | // Cell, count and a number of bitmaps
+                0x12 => value!(default_cell.bitmap_size()) | // One bitmap of the default cell
+                0x13 => peek!(do_parse!(cnt: le_u16 >>
+                                        ( 2 + cnt as usize * default_cell.bitmap_size() ))) | // Count and a number of bitmaps of the default cell
+                0x20 => value!(2) | // Duplicate block has one u16
+                0x21 => value!(2) | // Skip2 block has one u16
+                0x22 => value!(1) | // Skip1 block has one u8
+                0x23 => value!(10) | // Defaults block has one cell
+                0x30 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u8, |val: u8| val >= 3) >>
+                                  ( l as usize - 1 ))) | // Length of Ext1 block includes the header
+                0x31 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u16, |val: u16| val >= 4) >>
+                                  ( l as usize - 1 ))) | // Length of Ext2 block includes the header
+                0x32 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u32, |val: u32| val >= 6) >>
+                                  ( l as usize - 1 ))) // Length of Ext4 block includes the header
+            )
+            >> dat: cond_with_error!(len > 0, take!(len))
+            >> (HiiGlyphBlock {
+                Type: HiiGibtType::from(typ),
+                Data: dat,
+            })
+    )
+}
+
+//
+// HII simple font package
+//
+pub fn hii_simple_font_package_candidate(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    do_parse!(
+        input,
+        len: peek!(hii_simple_font_package_candidate_helper) >> dat: take!(len) >> (dat)
+    )
+}
+
+fn hii_simple_font_package_candidate_helper(input: &[u8]) -> IResult<&[u8], usize> {
+    do_parse!(
+        input,
+        len_raw: le_u32 >>
+        len: value!(len_raw & 0x00FFFFFF) >>
+        verify!(value!(len_raw & 0xFF000000), |val: u32| val == 0x07000000) >> // Package type is 0x07
+        n: le_u16 >>
+        w: le_u16 >>
+        verify!(value!(n as u32 + w as u32), |val: u32| val > 0) >> // Package has glyphs
+        verify!(value!(len), |val: u32| val == 8 + 22 * n as u32 + 44 * w as u32) >> // Length matches the numbers of glyphs
+        take!(len - 0x08) >>
+        ( len as usize )
+    )
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct HiiSimpleGlyph {
+    pub UnicodeWeight: u16,
+    pub Attributes: u8,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiSimpleFontPackage {
+    pub NarrowGlyphs: Vec<HiiSimpleGlyph>,
+    pub WideGlyphs: Vec<HiiSimpleGlyph>,
+}
+
+pub fn hii_simple_font_package(input: &[u8]) -> IResult<&[u8], HiiSimpleFontPackage> {
+    do_parse!(
+        input,
+        n: le_u16
+            >> w: le_u16
+            >> ng: count!(
+                do_parse!(u: le_u16 >> a: le_u8 >> take!(19) >> (HiiSimpleGlyph { UnicodeWeight: u, Attributes: a })),
+                n as usize
+            ) // EFI_NARROW_GLYPH has one 8x19 bitmap
+            >> wg: count!(
+                do_parse!(u: le_u16 >> a: le_u8 >> take!(19 + 19 + 3) >> (HiiSimpleGlyph { UnicodeWeight: u, Attributes: a })),
+                w as usize
+            ) // EFI_WIDE_GLYPH has two 8x19 bitmaps and padding
+            >> (HiiSimpleFontPackage {
+                NarrowGlyphs: ng,
+                WideGlyphs: wg,
+            })
+    )
+}
+
+//
+// HII device path package
+//
+pub fn hii_device_path_package_candidate(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    do_parse!(
+        input,
+        len: peek!(hii_device_path_package_candidate_helper) >> dat: take!(len) >> (dat)
+    )
+}
+
+fn hii_device_path_package_candidate_helper(input: &[u8]) -> IResult<&[u8], usize> {
+    do_parse!(
+        input,
+        len_raw: le_u32 >>
+        len: verify!(value!(len_raw & 0x00FFFFFF), |val: u32| val >= 0x08) >> // Package has at least an end node
+        verify!(value!(len_raw & 0xFF000000), |val: u32| val == 0x08000000) >> // Package type is 0x08
+        verify!(peek!(le_u8), |val: u8| (0x01..=0x05).contains(&val) || val == 0x7F) >> // First node has a known type
+        take!(len - 0x04) >>
+        ( len as usize )
+    )
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct DevicePathNode<'a> {
+    pub Type: u8,
+    pub SubType: u8,
+    pub Data: &'a [u8], // Without the node header
+}
+
+pub fn device_path_node(input: &[u8]) -> IResult<&[u8], DevicePathNode<'_>> {
+    do_parse!(
+        input,
+        t: le_u8
+            >> st: le_u8
+            >> len: verify!(le_u16, |val: u16| val >= 4) // Length includes the header
+            >> dat: take!(len - 4)
+            >> (DevicePathNode {
+                Type: t,
+                SubType: st,
+                Data: dat,
+            })
+    )
+}
+
+// Nodes of a device path up to and including the End Entire Device Path node
+pub fn device_path_nodes(input: &[u8]) -> IResult<&[u8], Vec<DevicePathNode<'_>>> {
+    do_parse!(
+        input,
+        v: many_till!(
+            complete!(device_path_node),
+            verify!(device_path_node, |node: DevicePathNode| node.Type == 0x7F && node.SubType == 0xFF)
+        ) >> ({
+            let (mut nodes, end) = v;
+            nodes.push(end);
+            nodes
+        })
+    )
+}
+
+//
+// HII image package
+//
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiImagePackage {
+    pub ImageInfoOffset: u32,   // Relative to the start of package header
+    pub PaletteInfoOffset: u32, // Relative to the start of package header
+}
+
+pub fn hii_image_package(input: &[u8]) -> IResult<&[u8], HiiImagePackage> {
+    do_parse!(
+        input,
+        iio: le_u32
+            >> pio: le_u32
+            >> (HiiImagePackage {
+                ImageInfoOffset: iio,
+                PaletteInfoOffset: pio,
+            })
+    )
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum HiiIibtType {
+    End,
+    Image1Bit,
+    Image1BitTrans,
+    Image4Bit,
+    Image4BitTrans,
+    Image8Bit,
+    Image8BitTrans,
+    Image24Bit,
+    Image24BitTrans,
+    ImageJpeg,
+    ImagePng,
+    Duplicate,
+    Skip2,
+    Skip1,
+    Ext1,
+    Ext2,
+    Ext4,
+    Unknown(u8),
+}
+
+impl From<u8> for HiiIibtType {
+    fn from(n: u8) -> HiiIibtType {
+        match n {
+            0x00 => HiiIibtType::End,
+            0x01 => HiiIibtType::Image1Bit,
+            0x02 => HiiIibtType::Image1BitTrans,
+            0x03 => HiiIibtType::Image4Bit,
+            0x04 => HiiIibtType::Image4BitTrans,
+            0x05 => HiiIibtType::Image8Bit,
+            0x06 => HiiIibtType::Image8BitTrans,
+            0x07 => HiiIibtType::Image24Bit,
+            0x08 => HiiIibtType::Image24BitTrans,
+            0x09 => HiiIibtType::ImageJpeg,
+            0x0A => HiiIibtType::ImagePng,
+            0x10 => HiiIibtType::Duplicate,
+            0x11 => HiiIibtType::Skip2,
+            0x12 => HiiIibtType::Skip1,
+            0x20 => HiiIibtType::Ext1,
+            0x21 => HiiIibtType::Ext2,
+            0x22 => HiiIibtType::Ext4,
+            _ => HiiIibtType::Unknown(n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiImageBlock<'a> {
+    pub Type: HiiIibtType,
+    pub Data: Option<&'a [u8]>,
+}
+
+pub fn hii_image_blocks(input: &[u8]) -> IResult<&[u8], Vec<HiiImageBlock<'_>>> {
+    do_parse!(
+        input,
+        v: many_till!(
+            complete!(hii_image_block),
+            verify!(le_u8, |val: u8| val == 0x00) // End block has no data
+        ) >> (v.0)
+    )
+}
+
+pub fn hii_image_block(input: &[u8]) -> IResult<&[u8], HiiImageBlock<'_>> {
+    do_parse!(
+        input,
+        typ: peek!(le_u8)
+            >> len: switch!(le_u8,
+                0x01 => peek!(do_parse!(take!(1) >> w: le_u16 >> h: le_u16 >>
+                                        ( 5 + (w as usize).div_ceil(8) * h as usize ))) | // Palette index, width, height and 1-bit bitmap
+                0x02 => peek!(do_parse!(take!(1) >> w: le_u16 >> h: le_u16 >>
+                                        ( 5 + (w as usize).div_ceil(8) * h as usize ))) |
+                0x03 => peek!(do_parse!(take!(1) >> w: le_u16 >> h: le_u16 >>
+                                        ( 5 + (w as usize).div_ceil(2) * h as usize ))) | // Palette index, width, height and 4-bit bitmap
+                0x04 => peek!(do_parse!(take!(1) >> w: le_u16 >> h: le_u16 >>
+                                        ( 5 + (w as usize).div_ceil(2) * h as usize ))) |
+                0x05 => peek!(do_parse!(take!(1) >> w: le_u16 >> h: le_u16 >>
+                                        ( 5 + w as usize * h as usize ))) | // Palette index, width, height and 8-bit bitmap
+                0x06 => peek!(do_parse!(take!(1) >> w: le_u16 >> h: le_u16 >>
+                                        ( 5 + w as usize * h as usize ))) |
+                0x07 => peek!(do_parse!(w: le_u16 >> h: le_u16 >>
+                                        ( 4 + 3 * w as usize * h as usize ))) | // Width, height and 24-bit bitmap
+                0x08 => peek!(do_parse!(w: le_u16 >> h: le_u16 >>
+                                        ( 4 + 3 * w as usize * h as usize ))) |
+                0x09 => peek!(do_parse!(sz: le_u32 >> ( 4 + sz as usize ))) | // Size and JPEG data
+                0x0A => peek!(do_parse!(sz: le_u32 >> ( 4 + sz as usize ))) | // Size and PNG data
+                0x10 => value!(2) | // Duplicate block has one u16
+                0x11 => value!(2) | // Skip2 block has one u16
+                0x12 => value!(1) | // Skip1 block has one u8
+                0x20 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u8, |val: u8| val >= 3) >>
+                                  ( l as usize - 1 ))) | // Length of Ext1 block includes the header
+                0x21 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u16, |val: u16| val >= 4) >>
+                                  ( l as usize - 1 ))) | // Length of Ext2 block includes the header
+                0x22 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u32, |val: u32| val >= 6) >>
+                                  ( l as usize - 1 ))) // Length of Ext4 block includes the header
+            )
+            >> dat: take!(len)
+            >> (HiiImageBlock {
+                Type: HiiIibtType::from(typ),
+                Data: Some(dat),
+            })
+    )
+}
+
+// Obtain image dimensions from the data of an image block
+pub fn hii_image_dimensions(typ: HiiIibtType, data: &[u8]) -> Option<(u32, u32)> {
+    match typ {
+        HiiIibtType::Image1Bit
+        | HiiIibtType::Image1BitTrans
+        | HiiIibtType::Image4Bit
+        | HiiIibtType::Image4BitTrans
+        | HiiIibtType::Image8Bit
+        | HiiIibtType::Image8BitTrans => {
+            if let Ok((_, (w, h))) = do_parse!(data, take!(1) >> w: le_u16 >> h: le_u16 >> ((w, h))) {
+                return Some((w as u32, h as u32));
+            }
+            None
+        }
+        HiiIibtType::Image24Bit | HiiIibtType::Image24BitTrans => {
+            if let Ok((_, (w, h))) = do_parse!(data, w: le_u16 >> h: le_u16 >> ((w, h))) {
+                return Some((w as u32, h as u32));
+            }
+            None
+        }
+        HiiIibtType::ImagePng => {
+            // Width and height are stored as big endian u32 in IHDR chunk that follows the signature
+            if data.len() >= 4 + 24 && &data[4 + 12..4 + 16] == b"IHDR" {
+                let w = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+                let h = u32::from_be_bytes([data[24], data[25], data[26], data[27]]);
+                return Some((w, h));
+            }
+            None
+        }
+        HiiIibtType::ImageJpeg => {
+            // Walk JPEG segments up to the first start of frame marker
+            let mut i = 4 + 2;
+            while i + 9 <= data.len() && data[i] == 0xFF {
+                let marker = data[i + 1];
+                let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+                if (0xC0..=0xCF).contains(&marker) && marker != 0xC4 && marker != 0xC8 && marker != 0xCC {
+                    let h = u16::from_be_bytes([data[i + 5], data[i + 6]]);
+                    let w = u16::from_be_bytes([data[i + 7], data[i + 8]]);
+                    return Some((w as u32, h as u32));
+                }
+                i += 2 + length;
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+// Palettes of an image package, each one is a list of EFI_HII_RGB_PIXEL values in blue, green, red order
+pub fn hii_image_palettes(input: &[u8]) -> IResult<&[u8], Vec<&[u8]>> {
+    do_parse!(
+        input,
+        count: le_u16
+            >> palettes: count!(
+                do_parse!(size: le_u16 >> dat: take!(size) >> (dat)), // PaletteSize doesn't include itself
+                count as usize
+            )
+            >> (palettes)
+    )
+}
+
+//
+// HII animation package
+//
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiAnimationPackage {
+    pub AnimationInfoOffset: u32, // Relative to the start of package header
+}
+
+pub fn hii_animation_package(input: &[u8]) -> IResult<&[u8], HiiAnimationPackage> {
+    do_parse!(
+        input,
+        aio: le_u32
+            >> (HiiAnimationPackage {
+                AnimationInfoOffset: aio,
+            })
+    )
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum HiiAibtType {
+    End,
+    OverlayImages,
+    ClearImages,
+    RestoreScreen,
+    OverlayImagesLoop,
+    ClearImagesLoop,
+    RestoreScreenLoop,
+    Duplicate,
+    Skip2,
+    Skip1,
+    Ext1,
+    Ext2,
+    Ext4,
+    Unknown(u8),
+}
+
+impl From<u8> for HiiAibtType {
+    fn from(n: u8) -> HiiAibtType {
+        match n {
+            0x00 => HiiAibtType::End,
+            0x10 => HiiAibtType::OverlayImages,
+            0x11 => HiiAibtType::ClearImages,
+            0x12 => HiiAibtType::RestoreScreen,
+            0x18 => HiiAibtType::OverlayImagesLoop,
+            0x19 => HiiAibtType::ClearImagesLoop,
+            0x1A => HiiAibtType::RestoreScreenLoop,
+            0x20 => HiiAibtType::Duplicate,
+            0x21 => HiiAibtType::Skip2,
+            0x22 => HiiAibtType::Skip1,
+            0x30 => HiiAibtType::Ext1,
+            0x31 => HiiAibtType::Ext2,
+            0x32 => HiiAibtType::Ext4,
+            _ => HiiAibtType::Unknown(n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HiiAnimationBlock<'a> {
+    pub Type: HiiAibtType,
+    pub Data: Option<&'a [u8]>,
+}
+
+pub fn hii_animation_blocks(input: &[u8]) -> IResult<&[u8], Vec<HiiAnimationBlock<'_>>> {
+    do_parse!(
+        input,
+        v: many_till!(
+            complete!(hii_animation_block),
+            verify!(le_u8, |val: u8| val == 0x00) // End block has no data
+        ) >> (v.0)
+    )
+}
+
+pub fn hii_animation_block(input: &[u8]) -> IResult<&[u8], HiiAnimationBlock<'_>> {
+    do_parse!(
+        input,
+        typ: peek!(le_u8)
+            >> len: switch!(le_u8,
+                0x10 => peek!(do_parse!(take!(6) >> c: le_u16 >> ( 8 + 8 * c as usize ))) | // DftImageId, width, height, count and cells
+                0x11 => peek!(do_parse!(take!(6) >> c: le_u16 >> ( 11 + 8 * c as usize ))) | // Same as above, plus background color
+                0x12 => peek!(do_parse!(take!(6) >> c: le_u16 >> ( 8 + 8 * c as usize ))) |
+                0x18 => peek!(do_parse!(take!(6) >> c: le_u16 >> ( 8 + 8 * c as usize ))) |
+                0x19 => peek!(do_parse!(take!(6) >> c: le_u16 >> ( 11 + 8 * c as usize ))) |
+                0x1A => peek!(do_parse!(take!(6) >> c: le_u16 >> ( 8 + 8 * c as usize ))) |
+                0x20 => value!(2) | // Duplicate block has one u16
+                0x21 => value!(2) | // Skip2 block has one u16
+                0x22 => value!(1) | // Skip1 block has one u8
+                0x30 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u8, |val: u8| val >= 3) >>
+                                  ( l as usize - 1 ))) | // Length of Ext1 block includes the header
+                0x31 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u16, |val: u16| val >= 4) >>
+                                  ( l as usize - 1 ))) | // Length of Ext2 block includes the header
+                0x32 => peek!(do_parse!(le_u8 >>
+                                  l: verify!(le_u32, |val: u32| val >= 6) >>
+                                  ( l as usize - 1 ))) // Length of Ext4 block includes the header
+            )
+            >> dat: take!(len)
+            >> (HiiAnimationBlock {
+                Type: HiiAibtType::from(typ),
+                Data: Some(dat),
+            })
+    )
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HiiAnimationCell {
+    pub OffsetX: u16,
+    pub OffsetY: u16,
+    pub ImageId: u16,
+    pub Delay: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HiiAnimation {
+    pub DftImageId: u16,
+    pub Width: u16,
+    pub Height: u16,
+    pub Cells: Vec<HiiAnimationCell>,
+}
+
+named!(
+    hii_animation_cell<HiiAnimationCell>,
+    do_parse!(
+        x: le_u16
+            >> y: le_u16
+            >> iid: le_u16
+            >> d: le_u16
+            >> (HiiAnimationCell {
+                OffsetX: x,
+                OffsetY: y,
+                ImageId: iid,
+                Delay: d,
+            })
+    )
+);
+
+// Parse data of an animation block, ClearImages blocks have background color before the cells
+pub fn hii_animation(input: &[u8], with_background: bool) -> IResult<&[u8], HiiAnimation> {
+    do_parse!(
+        input,
+        did: le_u16
+            >> w: le_u16
+            >> h: le_u16
+            >> c: le_u16
+            >> cond!(with_background, take!(3))
+            >> cells: count!(hii_animation_cell, c as usize)
+            >> (HiiAnimation {
+                DftImageId: did,
+                Width: w,
+                Height: h,
+                Cells: cells,
+            })
+    )
+}
+
 //
 // HII form package
 //
@@ -402,7 +1081,7 @@ pub struct IfrOperation<'a> {
     pub Data: Option<&'a [u8]>,
 }
 
-pub fn ifr_operation(input: &[u8]) -> IResult<&[u8], IfrOperation> {
+pub fn ifr_operation(input: &[u8]) -> IResult<&[u8], IfrOperation<'_>> {
     do_parse!(
         input,
         opcode: le_u8
@@ -418,10 +1097,34 @@ pub fn ifr_operation(input: &[u8]) -> IResult<&[u8], IfrOperation> {
     )
 }
 
-pub fn ifr_operations(input: &[u8]) -> IResult<&[u8], Vec<IfrOperation>> {
+pub fn ifr_operations(input: &[u8]) -> IResult<&[u8], Vec<IfrOperation<'_>>> {
     do_parse!(input, v: many1!(complete!(ifr_operation)) >> (v))
 }
 
+// Parse operations skipping pseudo-opcodes shorter than 2 bytes, which some vendors use as padding.
+// Every such pseudo-opcode is skipped as 2 bytes, ranges of skipped bytes relative to the input are returned too
+pub fn ifr_operations_skipping_padding(input: &[u8]) -> (Vec<IfrOperation<'_>>, Vec<Range<usize>>) {
+    let mut operations = Vec::new();
+    let mut padding: Vec<Range<usize>> = Vec::new();
+    let mut offset = 0;
+    while offset < input.len() {
+        if let Ok((_, operation)) = ifr_operation(&input[offset..]) {
+            offset += operation.Length as usize;
+            operations.push(operation);
+        } else if input.len() - offset >= 2 && input[offset + 1] & 0x7F < 2 {
+            match padding.last_mut() {
+                Some(range) if range.end == offset => range.end = offset + 2,
+                _ => padding.push(offset..offset + 2),
+            }
+            offset += 2;
+        } else {
+            // Truncated operation
+            break;
+        }
+    }
+    (operations, padding)
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum IfrOpcode {
     Form,              // Form
@@ -743,6 +1446,116 @@ impl From<IfrOpcode> for u8 {
     }
 }
 
+impl IfrOpcode {
+    // Length of the shortest valid opcode of this type, header included.
+    // Optional trailing fields (i.e. VarStoreEfi Size and Name, FormSet class GUIDs) are not counted
+    pub fn minimum_length(self) -> u8 {
+        match self {
+            IfrOpcode::Rule
+            | IfrOpcode::Refresh
+            | IfrOpcode::RuleRef
+            | IfrOpcode::Uint8
+            | IfrOpcode::ToString
+            | IfrOpcode::Find
+            | IfrOpcode::Span => 3,
+            IfrOpcode::Image
+            | IfrOpcode::NoSubmitIf
+            | IfrOpcode::InconsistentIf
+            | IfrOpcode::Animation
+            | IfrOpcode::VarStoreDevice
+            | IfrOpcode::QuestionRef1
+            | IfrOpcode::Uint16
+            | IfrOpcode::StringRef1 => 4,
+            IfrOpcode::WarningIf => 5,
+            IfrOpcode::Form
+            | IfrOpcode::EqIdVal
+            | IfrOpcode::EqIdId
+            | IfrOpcode::EqIdValList
+            | IfrOpcode::Uint32
+            | IfrOpcode::Default
+            | IfrOpcode::DefaultStore => 6,
+            IfrOpcode::Subtitle | IfrOpcode::OneOfOption | IfrOpcode::Get | IfrOpcode::Set => 7,
+            IfrOpcode::Text | IfrOpcode::ResetButton => 8,
+            IfrOpcode::Uint64 => 10,
+            IfrOpcode::Action | IfrOpcode::Ref => 13,
+            IfrOpcode::CheckBox | IfrOpcode::Date | IfrOpcode::Time => 14,
+            IfrOpcode::OrderedList => 15,
+            IfrOpcode::String => 16,
+            IfrOpcode::OneOf | IfrOpcode::Numeric | IfrOpcode::Password => 17,
+            IfrOpcode::Guid | IfrOpcode::Security | IfrOpcode::RefreshId | IfrOpcode::Match2 => 18,
+            IfrOpcode::VarStoreNameValue => 20,
+            IfrOpcode::FormSet | IfrOpcode::FormMap => 22,
+            IfrOpcode::VarStore => 23,
+            IfrOpcode::VarStoreEfi => 24,
+            _ => 2,
+        }
+    }
+
+    // Opcodes that start with a question header:
+    // PromptStringId, HelpStringId, QuestionId, VarStoreId, VarStoreInfo and Flags
+    pub fn is_question(self) -> bool {
+        matches!(
+            self,
+            IfrOpcode::OneOf
+                | IfrOpcode::CheckBox
+                | IfrOpcode::Numeric
+                | IfrOpcode::Password
+                | IfrOpcode::Action
+                | IfrOpcode::Ref
+                | IfrOpcode::Date
+                | IfrOpcode::Time
+                | IfrOpcode::String
+                | IfrOpcode::OrderedList
+        )
+    }
+
+    // Semantic type of questions, None for other opcodes
+    pub fn question_type(self) -> Option<QuestionType> {
+        match self {
+            IfrOpcode::CheckBox => Some(QuestionType::CheckBox),
+            IfrOpcode::OneOf => Some(QuestionType::Enum),
+            IfrOpcode::Numeric => Some(QuestionType::Integer),
+            IfrOpcode::String | IfrOpcode::Password => Some(QuestionType::String),
+            IfrOpcode::Date => Some(QuestionType::Date),
+            IfrOpcode::Time => Some(QuestionType::Time),
+            IfrOpcode::OrderedList => Some(QuestionType::OrderedList),
+            IfrOpcode::Action => Some(QuestionType::Action),
+            IfrOpcode::Ref => Some(QuestionType::Goto),
+            _ => None,
+        }
+    }
+
+    // Category used to filter opcodes during extraction, End belongs to the opcode that opened its scope
+    pub fn category(self) -> OpcodeCategory {
+        match self {
+            IfrOpcode::FormSet | IfrOpcode::Form | IfrOpcode::FormMap | IfrOpcode::ModalTag => OpcodeCategory::Form,
+            IfrOpcode::ResetButton => OpcodeCategory::Question,
+            opcode if opcode.is_question() => OpcodeCategory::Question,
+            IfrOpcode::OneOfOption => OpcodeCategory::Option,
+            IfrOpcode::Subtitle | IfrOpcode::Text | IfrOpcode::Image | IfrOpcode::Animation => {
+                OpcodeCategory::Statement
+            }
+            IfrOpcode::SuppressIf
+            | IfrOpcode::GrayOutIf
+            | IfrOpcode::DisableIf
+            | IfrOpcode::NoSubmitIf
+            | IfrOpcode::InconsistentIf
+            | IfrOpcode::WarningIf
+            | IfrOpcode::Rule => OpcodeCategory::Condition,
+            IfrOpcode::VarStore
+            | IfrOpcode::VarStoreNameValue
+            | IfrOpcode::VarStoreEfi
+            | IfrOpcode::VarStoreDevice
+            | IfrOpcode::DefaultStore => OpcodeCategory::Storage,
+            IfrOpcode::Default | IfrOpcode::Value | IfrOpcode::Read | IfrOpcode::Write => OpcodeCategory::Default,
+            IfrOpcode::Locked | IfrOpcode::Refresh | IfrOpcode::RefreshId => OpcodeCategory::Modifier,
+            IfrOpcode::Guid => OpcodeCategory::Extension,
+            IfrOpcode::End | IfrOpcode::Unknown(_) => OpcodeCategory::Other,
+            _ => OpcodeCategory::Expression,
+        }
+    }
+}
+
 impl fmt::Display for IfrOperation<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let opcode: u8 = self.OpCode.into();
@@ -804,6 +1617,10 @@ pub struct IfrSubtitle {
     pub Flags: u8,
 }
 
+pub enum IfrSubtitleFlags {
+    Horizontal = 0x01,
+}
+
 pub fn ifr_subtitle(input: &[u8]) -> IResult<&[u8], IfrSubtitle> {
     do_parse!(
         input,
@@ -1008,6 +1825,86 @@ pub fn ifr_numeric(input: &[u8]) -> IResult<&[u8], IfrNumeric> {
     )
 }
 
+// Display format of OneOf and Numeric values, encoded in bits 4 and 5 of their Flags
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum IfrNumericDisplay {
+    IntDec,  // Signed decimal
+    UintDec, // Unsigned decimal
+    UintHex, // Unsigned hexadecimal
+    Unknown,
+}
+
+impl IfrNumericDisplay {
+    pub fn from_flags(flags: u8) -> IfrNumericDisplay {
+        match flags & 0x30 {
+            0x00 => IfrNumericDisplay::IntDec,
+            0x10 => IfrNumericDisplay::UintDec,
+            0x20 => IfrNumericDisplay::UintHex,
+            _ => IfrNumericDisplay::Unknown,
+        }
+    }
+
+    // Questions in bit varstores keep the display format in bits 6 and 7 of their Flags
+    pub fn from_bit_flags(flags: u8) -> IfrNumericDisplay {
+        match flags & 0xC0 {
+            0x00 => IfrNumericDisplay::IntDec,
+            0x40 => IfrNumericDisplay::UintDec,
+            0x80 => IfrNumericDisplay::UintHex,
+            _ => IfrNumericDisplay::Unknown,
+        }
+    }
+}
+
+// OneOf and Numeric in the scope of a Guid opcode with IFR_BIT_VARSTORE_GUID.
+// VarStoreInfo is an offset in bits, Flags keep the width in bits and Min, Max and Step are always 32-bit
+#[derive(Debug, PartialEq, Eq)]
+pub struct IfrBitFieldNumeric {
+    pub PromptStringId: u16,
+    pub HelpStringId: u16,
+    pub QuestionId: u16,
+    pub VarStoreId: u16,
+    pub BitOffset: u16,
+    pub QuestionFlags: u8,
+    pub Flags: u8,
+    pub MinValue: u32,
+    pub MaxValue: u32,
+    pub Step: u32,
+}
+
+impl IfrBitFieldNumeric {
+    pub fn bit_width(&self) -> u8 {
+        self.Flags & 0x3F
+    }
+}
+
+pub fn ifr_bit_field_numeric(input: &[u8]) -> IResult<&[u8], IfrBitFieldNumeric> {
+    do_parse!(
+        input,
+        psid: le_u16
+            >> hsid: le_u16
+            >> qid: le_u16
+            >> vsid: le_u16
+            >> bo: le_u16
+            >> qf: le_u8
+            >> f: le_u8
+            >> min: le_u32
+            >> max: le_u32
+            >> step: le_u32
+            >> (IfrBitFieldNumeric {
+                PromptStringId: psid,
+                HelpStringId: hsid,
+                QuestionId: qid,
+                VarStoreId: vsid,
+                BitOffset: bo,
+                QuestionFlags: qf,
+                Flags: f,
+                MinValue: min,
+                MaxValue: max,
+                Step: step,
+            })
+    )
+}
+
 //
 //0x08 => IfrOpcode::Password
 //
@@ -1518,6 +2415,32 @@ pub fn ifr_date(input: &[u8]) -> IResult<&[u8], IfrDate> {
     )
 }
 
+pub enum IfrDateFlags {
+    YearSuppress = 0x01,
+    MonthSuppress = 0x02,
+    DaySuppress = 0x04,
+}
+
+// Storage type is encoded the same way for Date and Time questions
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum IfrDateTimeStorage {
+    Normal,
+    Time,
+    Wait,
+    Unknown(u8),
+}
+
+impl From<u8> for IfrDateTimeStorage {
+    fn from(flags: u8) -> IfrDateTimeStorage {
+        match flags & 0x30 {
+            0x00 => IfrDateTimeStorage::Normal,
+            0x10 => IfrDateTimeStorage::Time,
+            0x20 => IfrDateTimeStorage::Wait,
+            x => IfrDateTimeStorage::Unknown(x),
+        }
+    }
+}
+
 //
 //0x1B => IfrOpcode::Time
 //
@@ -1554,6 +2477,12 @@ pub fn ifr_time(input: &[u8]) -> IResult<&[u8], IfrTime> {
     )
 }
 
+pub enum IfrTimeFlags {
+    HourSuppress = 0x01,
+    MinuteSuppress = 0x02,
+    SecondSuppress = 0x04,
+}
+
 //
 //0x1C => IfrOpcode::String
 //
@@ -1782,6 +2711,28 @@ pub struct IfrGet {
     pub VarStoreType: u8,
 }
 
+// Storage types used by Get and Set opcodes, and implied by varstore declarations
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum IfrVarStoreType {
+    Buffer,
+    NameValue,
+    EfiVariable,
+    EfiVariableBuffer,
+    Unknown(u8),
+}
+
+impl From<u8> for IfrVarStoreType {
+    fn from(n: u8) -> IfrVarStoreType {
+        match n {
+            0x00 => IfrVarStoreType::Buffer,
+            0x01 => IfrVarStoreType::NameValue,
+            0x02 => IfrVarStoreType::EfiVariable,
+            0x03 => IfrVarStoreType::EfiVariableBuffer,
+            _ => IfrVarStoreType::Unknown(n),
+        }
+    }
+}
+
 pub fn ifr_get(input: &[u8]) -> IResult<&[u8], IfrGet> {
     do_parse!(
         input,
@@ -2028,6 +2979,19 @@ pub fn ifr_default_store(input: &[u8]) -> IResult<&[u8], IfrDefaultStore> {
     )
 }
 
+// Default classes defined by UEFI specification, used when no DefaultStore declares a DefaultId
+pub fn default_class_name(default_id: u16) -> &'static str {
+    match default_id {
+        0x0000 => "Standard",
+        0x0001 => "Manufacturing",
+        0x0002 => "Safe",
+        0x4000..=0x7FFF => "Platform",
+        0x8000..=0xBFFF => "Hardware",
+        0xC000..=0xFFFF => "Firmware",
+        _ => "Reserved",
+    }
+}
+
 //
 //0x5D => IfrOpcode::FormMap
 //
@@ -2078,7 +3042,7 @@ pub struct IfrGuid<'a> {
     pub Data: &'a [u8],
 }
 
-pub fn ifr_guid(input: &[u8]) -> IResult<&[u8], IfrGuid> {
+pub fn ifr_guid(input: &[u8]) -> IResult<&[u8], IfrGuid<'_>> {
     do_parse!(input, g: guid >> d: rest >> (IfrGuid { Guid: g, Data: d }))
 }
 
@@ -2119,7 +3083,7 @@ pub struct IfrGuidEdk2<'a> {
     pub Data: &'a [u8],
 }
 
-pub fn ifr_guid_edk2(input: &[u8]) -> IResult<&[u8], IfrGuidEdk2> {
+pub fn ifr_guid_edk2(input: &[u8]) -> IResult<&[u8], IfrGuidEdk2<'_>> {
     do_parse!(
         input,
         e: le_u8
@@ -2136,7 +3100,26 @@ pub fn ifr_guid_edk2(input: &[u8]) -> IResult<&[u8], IfrGuidEdk2> {
 pub struct IfrGuidEdk2Banner {
     pub TitleId: u16,
     pub LineNumber: u16,
-    pub Alignment: u8,
+    pub Alignment: IfrBannerAlignment,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum IfrBannerAlignment {
+    Left,
+    Center,
+    Right,
+    Unknown(u8),
+}
+
+impl From<u8> for IfrBannerAlignment {
+    fn from(n: u8) -> IfrBannerAlignment {
+        match n {
+            0x00 => IfrBannerAlignment::Left,
+            0x01 => IfrBannerAlignment::Center,
+            0x02 => IfrBannerAlignment::Right,
+            _ => IfrBannerAlignment::Unknown(n),
+        }
+    }
 }
 
 pub fn ifr_guid_edk2_banner(input: &[u8]) -> IResult<&[u8], IfrGuidEdk2Banner> {
@@ -2148,7 +3131,7 @@ pub fn ifr_guid_edk2_banner(input: &[u8]) -> IResult<&[u8], IfrGuidEdk2Banner> {
             >> (IfrGuidEdk2Banner {
                 TitleId: t,
                 LineNumber: l,
-                Alignment: a
+                Alignment: IfrBannerAlignment::from(a)
             })
     )
 }
@@ -2185,7 +3168,7 @@ pub struct IfrGuidEdk<'a> {
     pub Data: &'a [u8],
 }
 
-pub fn ifr_guid_edk(input: &[u8]) -> IResult<&[u8], IfrGuidEdk> {
+pub fn ifr_guid_edk(input: &[u8]) -> IResult<&[u8], IfrGuidEdk<'_>> {
     do_parse!(
         input,
         e: le_u8
@@ -2200,6 +3183,101 @@ pub fn ifr_guid_edk(input: &[u8]) -> IResult<&[u8], IfrGuidEdk> {
 }
 // VarEqName has NameId as Data
 
+// Other well-known GUIDs that can be found in Guid opcodes and FormSet class GUIDs
+pub const IFR_BIT_VARSTORE_GUID: Guid = Guid {
+    data1: 0x82ddd68b,
+    data2: 0x9163,
+    data3: 0x4187,
+    data4: [0x9b, 0x27, 0x20, 0xa8, 0xfd, 0x60, 0xa7, 0x1d],
+};
+
+pub const HII_PLATFORM_SETUP_FORMSET_GUID: Guid = Guid {
+    data1: 0x93039971,
+    data2: 0x8545,
+    data3: 0x4b04,
+    data4: [0xb4, 0x5e, 0x32, 0xeb, 0x83, 0x26, 0x04, 0x0e],
+};
+
+pub const HII_DRIVER_HEALTH_FORMSET_GUID: Guid = Guid {
+    data1: 0xf22fc20c,
+    data2: 0x8cf4,
+    data3: 0x45eb,
+    data4: [0x8e, 0x06, 0xad, 0x4e, 0x50, 0xb9, 0x5d, 0xd3],
+};
+
+pub const HII_USER_CREDENTIAL_FORMSET_GUID: Guid = Guid {
+    data1: 0x337f4407,
+    data2: 0x5aee,
+    data3: 0x4b83,
+    data4: [0xb2, 0xa7, 0x4e, 0xad, 0xca, 0x30, 0x88, 0xcd],
+};
+
+pub const HII_STANDARD_FORM_GUID: Guid = Guid {
+    data1: 0x3bd2f4ec,
+    data2: 0xe524,
+    data3: 0x46e4,
+    data4: [0xa9, 0xd8, 0x51, 0x01, 0x17, 0x42, 0x55, 0x62],
+};
+
+pub fn guid_friendly_name(guid: &Guid) -> Option<&'static str> {
+    match *guid {
+        IFR_TIANO_GUID => Some("EfiIfrTiano"),
+        IFR_FRAMEWORK_GUID => Some("EfiIfrFramework"),
+        IFR_BIT_VARSTORE_GUID => Some("EdkiiIfrBitVarstore"),
+        HII_PLATFORM_SETUP_FORMSET_GUID => Some("HiiPlatformSetupFormset"),
+        HII_DRIVER_HEALTH_FORMSET_GUID => Some("HiiDriverHealthFormset"),
+        HII_USER_CREDENTIAL_FORMSET_GUID => Some("HiiUserCredentialFormset"),
+        HII_STANDARD_FORM_GUID => Some("HiiStandardForm"),
+        _ => None,
+    }
+}
+
+// Name of the GUID in EDK2 sources
+pub fn guid_token_name(guid: &Guid) -> Option<&'static str> {
+    match *guid {
+        IFR_TIANO_GUID => Some("gEfiIfrTianoGuid"),
+        IFR_FRAMEWORK_GUID => Some("gEfiIfrFrameworkGuid"),
+        IFR_BIT_VARSTORE_GUID => Some("gEdkiiIfrBitVarstoreGuid"),
+        HII_PLATFORM_SETUP_FORMSET_GUID => Some("gEfiHiiPlatformSetupFormsetGuid"),
+        HII_DRIVER_HEALTH_FORMSET_GUID => Some("gEfiHiiDriverHealthFormsetGuid"),
+        HII_USER_CREDENTIAL_FORMSET_GUID => Some("gEfiHiiUserCredentialFormsetGuid"),
+        HII_STANDARD_FORM_GUID => Some("gEfiHiiStandardFormGuid"),
+        _ => None,
+    }
+}
+
+// Guid opcode of the EDKII bit varstore extension. OneOf, Numeric and CheckBox questions in its scope
+// give VarStoreInfo in bits, OneOf and Numeric keep their width in bits in Flags
+pub fn ifr_is_bit_var_store(opcode: IfrOpcode, data: &[u8]) -> bool {
+    opcode == IfrOpcode::Guid
+        && ifr_guid(data).is_ok_and(|(_, guid)| guid.Guid == IFR_BIT_VARSTORE_GUID)
+}
+
+// Flags telling which operations are in the scope of a bit varstore Guid opcode, one per operation
+pub fn ifr_bit_var_store_scopes(operations: &[IfrOperation]) -> Vec<bool> {
+    let mut result = Vec::with_capacity(operations.len());
+    let mut depth = 0usize;
+    let mut bit_scope: Option<usize> = None; // Depth of the scope of the bit varstore Guid opcode
+    for operation in operations {
+        if operation.OpCode == IfrOpcode::End {
+            depth = depth.saturating_sub(1);
+            if bit_scope.is_some_and(|scope| depth < scope) {
+                bit_scope = None;
+            }
+        }
+        result.push(bit_scope.is_some());
+        if operation.ScopeStart {
+            depth += 1;
+            if bit_scope.is_none()
+                && ifr_is_bit_var_store(operation.OpCode, operation.Data.unwrap_or(&[]))
+            {
+                bit_scope = Some(depth);
+            }
+        }
+    }
+    result
+}
+
 //
 //0x60 => IfrOpcode::Security
 //
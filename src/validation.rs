@@ -0,0 +1,330 @@
+// Strict checks of UEFI form packages against the UEFI specification.
+// Opcodes are walked as raw bytes, so malformed packages are reported instead of failing to parse
+use super::FormPackage;
+use uefi_parser::IfrOpcode;
+
+// Single deviation from the specification, offset is from the start of the input data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecDeviation {
+    pub offset: usize,
+    pub opcode: Option<IfrOpcode>, // None for package-level deviations
+    pub message: String,
+}
+
+impl SpecDeviation {
+    fn new(offset: usize, opcode: Option<IfrOpcode>, message: String) -> SpecDeviation {
+        SpecDeviation {
+            offset,
+            opcode,
+            message,
+        }
+    }
+}
+
+// Allowed total lengths of an opcode, header included
+enum LengthRule {
+    Exact(Vec<usize>),
+    AtLeast(usize),
+}
+
+// Size of EFI_IFR_TYPE_VALUE for a given value type, None for variable-size and invalid types
+fn type_value_size(value_type: u8) -> Option<usize> {
+    match value_type {
+        0x00 | 0x04 => Some(1),        // NumSize8, Boolean
+        0x01 | 0x07 | 0x0A => Some(2), // NumSize16, String, Action
+        0x02 | 0x06 => Some(4),        // NumSize32, Date
+        0x03 => Some(8),               // NumSize64
+        0x05 => Some(3),               // Time
+        0x08 | 0x09 => Some(0),        // Other, Undefined
+        0x0C => Some(22),              // Ref
+        _ => None,                     // Buffer and invalid types
+    }
+}
+
+// Length rule of an opcode, data is everything after the opcode header.
+// Rules that depend on fields of the opcode are only returned if those fields are present
+fn length_rule(opcode: IfrOpcode, data: &[u8]) -> Option<LengthRule> {
+    let exact = |length: usize| Some(LengthRule::Exact(vec![length]));
+    match opcode {
+        IfrOpcode::Form => exact(6),
+        IfrOpcode::Subtitle => exact(7),
+        IfrOpcode::Text => exact(8),
+        IfrOpcode::Image | IfrOpcode::Animation => exact(4),
+        IfrOpcode::OneOf | IfrOpcode::Numeric => match data.get(11) {
+            // MinValue, MaxValue and Step of the size encoded in Flags
+            Some(flags) => exact(14 + 3 * (1 << (flags & 0x03))),
+            None => exact(14 + 3),
+        },
+        IfrOpcode::CheckBox => exact(14),
+        IfrOpcode::Password => exact(17),
+        IfrOpcode::OneOfOption => match data.get(3) {
+            Some(&value_type) => match type_value_size(value_type) {
+                Some(size) => exact(6 + size),
+                None => Some(LengthRule::AtLeast(6)),
+            },
+            None => exact(6),
+        },
+        IfrOpcode::Action => Some(LengthRule::Exact(vec![13, 15])),
+        IfrOpcode::ResetButton => exact(8),
+        IfrOpcode::FormSet => match data.get(20) {
+            // ClassGuid count is encoded in Flags
+            Some(flags) => exact(23 + 16 * (flags & 0x03) as usize),
+            None => exact(23),
+        },
+        IfrOpcode::Ref => Some(LengthRule::Exact(vec![13, 15, 17, 33, 35])),
+        IfrOpcode::NoSubmitIf | IfrOpcode::InconsistentIf => exact(4),
+        IfrOpcode::EqIdVal | IfrOpcode::EqIdId => exact(6),
+        IfrOpcode::EqIdValList => match data.get(2..4) {
+            Some(list_length) => {
+                exact(6 + 2 * u16::from_le_bytes([list_length[0], list_length[1]]) as usize)
+            }
+            None => exact(6),
+        },
+        IfrOpcode::Rule | IfrOpcode::Refresh | IfrOpcode::RuleRef => exact(3),
+        IfrOpcode::Date | IfrOpcode::Time => exact(14),
+        IfrOpcode::String => exact(16),
+        IfrOpcode::OrderedList => exact(15),
+        IfrOpcode::VarStore => Some(LengthRule::AtLeast(23)),
+        IfrOpcode::VarStoreNameValue => exact(20),
+        IfrOpcode::VarStoreEfi => match data.len() {
+            // UEFI 2.1 VarStoreEfi has no Size and Name fields
+            22 => exact(24),
+            _ => Some(LengthRule::AtLeast(27)),
+        },
+        IfrOpcode::VarStoreDevice => exact(4),
+        IfrOpcode::Get | IfrOpcode::Set => exact(7),
+        IfrOpcode::QuestionRef1 | IfrOpcode::StringRef1 | IfrOpcode::Uint16 => exact(4),
+        IfrOpcode::QuestionRef3 => Some(LengthRule::Exact(vec![2, 4, 20])),
+        IfrOpcode::Uint8 | IfrOpcode::ToString | IfrOpcode::Find | IfrOpcode::Span => exact(3),
+        IfrOpcode::Uint32 => exact(6),
+        IfrOpcode::Uint64 => exact(10),
+        IfrOpcode::Default => match data.get(2) {
+            Some(&value_type) => match type_value_size(value_type) {
+                Some(size) => exact(5 + size),
+                None => Some(LengthRule::AtLeast(5)),
+            },
+            None => exact(5),
+        },
+        IfrOpcode::DefaultStore => exact(6),
+        IfrOpcode::FormMap => Some(LengthRule::AtLeast(4)),
+        IfrOpcode::Guid => Some(LengthRule::AtLeast(18)),
+        IfrOpcode::Security | IfrOpcode::RefreshId | IfrOpcode::Match2 => exact(18),
+        IfrOpcode::WarningIf => exact(5),
+        IfrOpcode::Unknown(_) => None,
+        // Everything else has no fields
+        _ => exact(2),
+    }
+}
+
+// Offset and mask of reserved flag bits of an opcode, relative to the end of its header.
+// Comments list the flags that are defined
+fn reserved_flags(opcode: IfrOpcode) -> Option<(usize, u8)> {
+    match opcode {
+        // Horizontal
+        IfrOpcode::Subtitle => Some((4, 0xFE)),
+        // Default, DefaultMfg
+        IfrOpcode::CheckBox => Some((11, 0xFC)),
+        // Size, Display
+        IfrOpcode::OneOf | IfrOpcode::Numeric => Some((11, 0xCC)),
+        // Default, DefaultMfg
+        IfrOpcode::OneOfOption => Some((2, 0xCF)),
+        // Suppress flags, Storage
+        IfrOpcode::Date | IfrOpcode::Time => Some((11, 0xC8)),
+        // MultiLine
+        IfrOpcode::String => Some((13, 0xFE)),
+        // UniqueSet, NoEmptySet
+        IfrOpcode::OrderedList => Some((12, 0xFC)),
+        // ClassGuid count
+        IfrOpcode::FormSet => Some((20, 0xFC)),
+        _ => None,
+    }
+}
+
+// Check the package header, opcode lengths, reserved flag bits, value types and scope nesting of a UEFI form package
+pub fn validate_uefi_form_package(data: &[u8], form_package: &FormPackage) -> Vec<SpecDeviation> {
+    let mut result = Vec::new();
+
+    let start = form_package.offset;
+    let end = start + form_package.length;
+    if end > data.len() || form_package.length < 4 {
+        result.push(SpecDeviation::new(
+            start,
+            None,
+            format!(
+                "Package length 0x{:X} is out of bounds",
+                form_package.length
+            ),
+        ));
+        return result;
+    }
+    let header = u32::from_le_bytes([
+        data[start],
+        data[start + 1],
+        data[start + 2],
+        data[start + 3],
+    ]);
+    if header >> 24 != 0x02 {
+        result.push(SpecDeviation::new(
+            start,
+            None,
+            format!("Package type 0x{:X} is not Forms", header >> 24),
+        ));
+    }
+    if (header & 0x00FF_FFFF) as usize != form_package.length {
+        result.push(SpecDeviation::new(
+            start,
+            None,
+            format!(
+                "Package header length 0x{:X} doesn't match package length 0x{:X}",
+                header & 0x00FF_FFFF,
+                form_package.length
+            ),
+        ));
+    }
+
+    let mut offset = start + 4;
+    let mut scope_depth: usize = 0;
+    while offset < end {
+        if end - offset < 2 {
+            result.push(SpecDeviation::new(
+                offset,
+                None,
+                format!("{} trailing byte(s) after the last opcode", end - offset),
+            ));
+            break;
+        }
+        let opcode = IfrOpcode::from(data[offset]);
+        let length = (data[offset + 1] & 0x7F) as usize;
+        let scope_start = data[offset + 1] & 0x80 != 0;
+        if length < 2 {
+            result.push(SpecDeviation::new(
+                offset,
+                Some(opcode),
+                format!("Opcode length 0x{length:X} is shorter than its header"),
+            ));
+            break;
+        }
+        if offset + length > end {
+            result.push(SpecDeviation::new(
+                offset,
+                Some(opcode),
+                format!("Opcode length 0x{length:X} exceeds the package"),
+            ));
+            break;
+        }
+        let op_data = &data[offset + 2..offset + length];
+
+        match length_rule(opcode, op_data) {
+            Some(LengthRule::Exact(lengths)) => {
+                if !lengths.contains(&length) {
+                    result.push(SpecDeviation::new(
+                        offset,
+                        Some(opcode),
+                        format!("Opcode length 0x{length:X}, expected {lengths:X?}"),
+                    ));
+                }
+            }
+            Some(LengthRule::AtLeast(minimum)) => {
+                if length < minimum {
+                    result.push(SpecDeviation::new(
+                        offset,
+                        Some(opcode),
+                        format!("Opcode length 0x{length:X}, expected at least 0x{minimum:X}"),
+                    ));
+                }
+            }
+            None => result.push(SpecDeviation::new(
+                offset,
+                Some(opcode),
+                String::from("Opcode is not defined by the specification"),
+            )),
+        }
+
+        // Question header flags, bits 1 and 3 are reserved
+        if opcode.is_question() {
+            if let Some(flags) = op_data.get(10) {
+                if flags & 0x0A != 0 {
+                    result.push(SpecDeviation::new(
+                        offset,
+                        Some(opcode),
+                        format!("Reserved question flags set: 0x{:X}", flags & 0x0A),
+                    ));
+                }
+            }
+        }
+        if let Some((flags_offset, mask)) = reserved_flags(opcode) {
+            if let Some(flags) = op_data.get(flags_offset) {
+                if flags & mask != 0 {
+                    result.push(SpecDeviation::new(
+                        offset,
+                        Some(opcode),
+                        format!("Reserved flags set: 0x{:X}", flags & mask),
+                    ));
+                }
+            }
+        }
+
+        // Value types
+        let value_type = match opcode {
+            IfrOpcode::OneOfOption => op_data.get(3),
+            IfrOpcode::Default => op_data.get(2),
+            _ => None,
+        };
+        if let Some(&value_type) = value_type {
+            if value_type > 0x0C {
+                result.push(SpecDeviation::new(
+                    offset,
+                    Some(opcode),
+                    format!("Invalid value type 0x{value_type:X}"),
+                ));
+            }
+        }
+        if opcode == IfrOpcode::VarStore && op_data.len() > 20 && !op_data[20..].contains(&0) {
+            result.push(SpecDeviation::new(
+                offset,
+                Some(opcode),
+                String::from("Name is not NUL-terminated"),
+            ));
+        }
+        if opcode == IfrOpcode::FormMap && length >= 4 && !(length - 4).is_multiple_of(18) {
+            result.push(SpecDeviation::new(
+                offset,
+                Some(opcode),
+                format!("Opcode length 0x{length:X} doesn't match whole method entries"),
+            ));
+        }
+
+        // Scope nesting
+        if opcode == IfrOpcode::End {
+            if scope_start {
+                result.push(SpecDeviation::new(
+                    offset,
+                    Some(opcode),
+                    String::from("End opens a new scope"),
+                ));
+            }
+            if scope_depth == 0 {
+                result.push(SpecDeviation::new(
+                    offset,
+                    Some(opcode),
+                    String::from("End without an open scope"),
+                ));
+            } else {
+                scope_depth -= 1;
+            }
+        } else if scope_start {
+            scope_depth += 1;
+        }
+
+        offset += length;
+    }
+    if scope_depth > 0 {
+        result.push(SpecDeviation::new(
+            end,
+            None,
+            format!("{scope_depth} scope(s) not closed at the end of the package"),
+        ));
+    }
+
+    result
+}
@@ -0,0 +1,125 @@
+// Inventory of varstores declared in a UEFI form package, so questions can be matched to their storage by VarStoreId
+use std::fmt::Write;
+
+use super::FormPackage;
+use uefi_parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarStoreDeclaration {
+    pub offset: usize, // Offset of the VarStore, VarStoreNameValue or VarStoreEfi opcode in the input data
+    pub opcode: uefi_parser::IfrOpcode,
+    pub var_store_id: u16,
+    pub guid: uefi_parser::Guid,
+    pub name: Option<String>, // None for name/value varstores and VarStoreEfi opcodes without a name
+    pub size: Option<u16>, // None for name/value varstores and VarStoreEfi opcodes without a size
+    pub attributes: Option<u32>, // VarStoreEfi only
+}
+
+impl VarStoreDeclaration {
+    // Storage type questions using this varstore refer to
+    pub fn var_store_type(&self) -> uefi_parser::IfrVarStoreType {
+        match self.opcode {
+            uefi_parser::IfrOpcode::VarStoreNameValue => uefi_parser::IfrVarStoreType::NameValue,
+            uefi_parser::IfrOpcode::VarStoreEfi => uefi_parser::IfrVarStoreType::EfiVariableBuffer,
+            _ => uefi_parser::IfrVarStoreType::Buffer,
+        }
+    }
+}
+
+// Collect varstore declarations from parsed operations, offset is the offset of the first operation in the input data
+pub fn var_store_declarations(
+    operations: &[uefi_parser::IfrOperation],
+    offset: usize,
+) -> Vec<VarStoreDeclaration> {
+    let mut result = Vec::new();
+    let mut offset = offset;
+    for operation in operations {
+        let op_data = operation.Data.unwrap_or(&[]);
+        let declaration = match operation.OpCode {
+            uefi_parser::IfrOpcode::VarStore => {
+                uefi_parser::ifr_var_store(op_data)
+                    .ok()
+                    .map(|(_, var_store)| {
+                        (
+                            var_store.VarStoreId,
+                            var_store.Guid,
+                            Some(var_store.Name),
+                            Some(var_store.Size),
+                            None,
+                        )
+                    })
+            }
+            uefi_parser::IfrOpcode::VarStoreNameValue => {
+                uefi_parser::ifr_var_store_name_value(op_data)
+                    .ok()
+                    .map(|(_, var_store)| (var_store.VarStoreId, var_store.Guid, None, None, None))
+            }
+            uefi_parser::IfrOpcode::VarStoreEfi => uefi_parser::ifr_var_store_efi(op_data)
+                .ok()
+                .map(|(_, var_store)| {
+                    (
+                        var_store.VarStoreId,
+                        var_store.Guid,
+                        var_store.Name,
+                        var_store.Size,
+                        Some(var_store.Attributes),
+                    )
+                }),
+            _ => None,
+        };
+        if let Some((var_store_id, guid, name, size, attributes)) = declaration {
+            result.push(VarStoreDeclaration {
+                offset,
+                opcode: operation.OpCode,
+                var_store_id,
+                guid,
+                name,
+                size,
+                attributes,
+            });
+        }
+        offset += operation.Length as usize;
+    }
+    result
+}
+
+// Varstores of a UEFI form package in the order they are declared
+pub fn uefi_var_stores(data: &[u8], form_package: &FormPackage) -> Vec<VarStoreDeclaration> {
+    match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate).ok())
+        .and_then(|(_, package)| uefi_parser::ifr_operations(package.Data.unwrap_or(&[])).ok())
+    {
+        // Form package header is 4 bytes long
+        Some((_, operations)) => var_store_declarations(&operations, form_package.offset + 4),
+        None => Vec::new(),
+    }
+}
+
+// Table written before the opcodes of an extraction, one line per varstore
+pub fn var_store_table(var_stores: &[VarStoreDeclaration]) -> String {
+    let mut text = String::new();
+    if var_stores.is_empty() {
+        return text;
+    }
+    text.push_str("VarStores:\n");
+    for var_store in var_stores {
+        write!(
+            &mut text,
+            "\tVarStoreId: 0x{:X}, Type: {:?}, Guid: {}",
+            var_store.var_store_id, var_store.opcode, var_store.guid
+        )
+        .unwrap();
+        if let Some(name) = &var_store.name {
+            write!(&mut text, ", Name: \"{name}\"").unwrap();
+        }
+        if let Some(size) = var_store.size {
+            write!(&mut text, ", Size: 0x{size:X}").unwrap();
+        }
+        if let Some(attributes) = var_store.attributes {
+            write!(&mut text, ", Attributes: 0x{attributes:X}").unwrap();
+        }
+        text.push('\n');
+    }
+    text
+}
@@ -0,0 +1,137 @@
+// EFI variables and external questions that influence form behavior through expressions
+use std::collections::HashMap;
+
+use super::{FormPackage, StringMap, StringPackage};
+use uefi_parser;
+
+// Something outside of the form package an expression depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariableReference {
+    // Variable or name/value pair read by Get
+    Variable {
+        name: String,
+        guid: Option<uefi_parser::Guid>, // None if the varstore is not declared in this form package
+        var_store_id: u16,
+        var_store_type: uefi_parser::IfrVarStoreType,
+        var_store_info: u16,
+    },
+    // Question of another formset referenced by QuestionRef3
+    ExternalQuestion {
+        device_path: Option<String>,
+        formset_guid: Option<uefi_parser::Guid>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableDependency {
+    pub offset: usize,                          // Offset of the referencing opcode in the input data
+    pub owner: Option<uefi_parser::IfrOpcode>, // Opcode whose scope contains the expression, i.e. SuppressIf
+    pub reference: VariableReference,
+}
+
+// Find all Get and QuestionRef3 opcodes of a form package that reach outside of it,
+// names are resolved using declared varstores and a given string package
+pub fn uefi_variable_dependencies(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: Option<&StringPackage>,
+) -> Vec<VariableDependency> {
+    let mut result = Vec::new();
+    let no_strings = HashMap::new();
+    let strings_map = &StringMap::plain(
+        string_package.map_or(&no_strings, |strings| &strings.string_id_map),
+        "StringId {id}",
+    );
+
+    let operations = match uefi_parser::hii_form_package_candidate(&data[form_package.offset..])
+        .ok()
+        .and_then(|(_, candidate)| uefi_parser::hii_package(candidate).ok())
+        .and_then(|(_, package)| uefi_parser::ifr_operations(package.Data.unwrap_or(&[])).ok())
+    {
+        Some((_, operations)) => operations,
+        None => return result,
+    };
+
+    // Declared varstores with their GUIDs and names, name/value varstores have names per question
+    let mut var_stores: HashMap<u16, (uefi_parser::Guid, Option<String>)> = HashMap::new();
+    for operation in &operations {
+        let op_data = operation.Data.unwrap_or(&[]);
+        match operation.OpCode {
+            uefi_parser::IfrOpcode::VarStore => {
+                if let Ok((_, var_store)) = uefi_parser::ifr_var_store(op_data) {
+                    var_stores.insert(var_store.VarStoreId, (var_store.Guid, Some(var_store.Name)));
+                }
+            }
+            uefi_parser::IfrOpcode::VarStoreNameValue => {
+                if let Ok((_, var_store)) = uefi_parser::ifr_var_store_name_value(op_data) {
+                    var_stores.insert(var_store.VarStoreId, (var_store.Guid, None));
+                }
+            }
+            uefi_parser::IfrOpcode::VarStoreEfi => {
+                if let Ok((_, var_store)) = uefi_parser::ifr_var_store_efi(op_data) {
+                    var_stores.insert(var_store.VarStoreId, (var_store.Guid, var_store.Name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Form package header is 4 bytes long
+    let mut offset = form_package.offset + 4;
+    let mut scopes: Vec<uefi_parser::IfrOpcode> = Vec::new();
+    for operation in &operations {
+        let op_data = operation.Data.unwrap_or(&[]);
+        let reference = match operation.OpCode {
+            uefi_parser::IfrOpcode::Get => uefi_parser::ifr_get(op_data).ok().map(|(_, get)| {
+                let var_store_type = uefi_parser::IfrVarStoreType::from(get.VarStoreType);
+                let declared = var_stores.get(&get.VarStoreId);
+                // Name/value pairs and plain EFI variables have their names in VarStoreInfo
+                let name = match (var_store_type, declared) {
+                    (uefi_parser::IfrVarStoreType::NameValue, _)
+                    | (uefi_parser::IfrVarStoreType::EfiVariable, _) => {
+                        strings_map.resolve(get.VarStoreInfo).into_owned()
+                    }
+                    (_, Some((_, Some(name)))) => name.clone(),
+                    _ => String::new(),
+                };
+                VariableReference::Variable {
+                    name,
+                    guid: declared.map(|(guid, _)| *guid),
+                    var_store_id: get.VarStoreId,
+                    var_store_type,
+                    var_store_info: get.VarStoreInfo,
+                }
+            }),
+            uefi_parser::IfrOpcode::QuestionRef3 => match uefi_parser::ifr_question_ref_3(op_data) {
+                // QuestionRef3 without a device path refers to a question of the same formset
+                Ok((_, question_ref)) if question_ref.DevicePathId.is_some() => {
+                    Some(VariableReference::ExternalQuestion {
+                        device_path: question_ref
+                            .DevicePathId
+                            .filter(|&string_id| string_id != 0)
+                            .map(|string_id| strings_map.resolve(string_id).into_owned()),
+                        formset_guid: question_ref.QuestionGuid,
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(reference) = reference {
+            result.push(VariableDependency {
+                offset,
+                owner: scopes.last().copied(),
+                reference,
+            });
+        }
+
+        if operation.OpCode == uefi_parser::IfrOpcode::End {
+            scopes.pop();
+        } else if operation.ScopeStart {
+            scopes.push(operation.OpCode);
+        }
+        offset += operation.Length as usize;
+    }
+
+    result
+}
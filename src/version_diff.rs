@@ -0,0 +1,190 @@
+// Matching of UEFI form packages across several versions of the same firmware
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::{
+    find_uefi_packages, uefi_preferred_string_package, FormPackage, StringMap, StringPackage,
+};
+use uefi_parser;
+
+// Questions and formsets of a single form package, used to compare it with other versions
+#[derive(Debug, Clone)]
+pub struct FormPackageSummary {
+    pub offset: usize,
+    pub formset_guids: Vec<uefi_parser::Guid>,
+    pub questions: Vec<String>, // Question opcode and its prompt, i.e. OneOf "Boot Mode"
+}
+
+// Form package of one version matched to a form package of the previous version
+#[derive(Debug, Clone)]
+pub struct FormPackageMatch {
+    pub version: usize,              // Index of the image in the input slice
+    pub form_package: Option<usize>, // Index of the form package in that image, None if it was dropped
+    pub previous: Option<usize>,     // Index of the matched form package in the previous image
+    pub similarity: f64,             // Share of questions both packages have in common
+    pub introduced: Vec<String>,     // Questions not present in the previous version
+    pub removed: Vec<String>,        // Questions of the previous version not present anymore
+}
+
+// Form packages with no formset GUIDs in common are only matched if they are similar enough
+pub const VERSION_MATCH_THRESHOLD: f64 = 0.5;
+
+// Collect formset GUIDs and questions of a form package, prompts are resolved using a given string package
+pub fn summarize_uefi_form_package(
+    data: &[u8],
+    form_package: &FormPackage,
+    string_package: Option<&StringPackage>,
+) -> FormPackageSummary {
+    let mut summary = FormPackageSummary {
+        offset: form_package.offset,
+        formset_guids: Vec::new(),
+        questions: Vec::new(),
+    };
+    let no_strings = HashMap::new();
+    let strings_map = &StringMap::plain(
+        string_package.map_or(&no_strings, |strings| &strings.string_id_map),
+        "StringId {id}",
+    );
+
+    if let Ok((_, candidate)) = uefi_parser::hii_form_package_candidate(&data[form_package.offset..]) {
+        if let Ok((_, package)) = uefi_parser::hii_package(candidate) {
+            if let Ok((_, operations)) = uefi_parser::ifr_operations(package.Data.unwrap_or(&[])) {
+                for operation in &operations {
+                    let data = operation.Data.unwrap_or(&[]);
+                    if operation.OpCode == uefi_parser::IfrOpcode::FormSet {
+                        if let Ok((_, form_set)) = uefi_parser::ifr_form_set(data) {
+                            summary.formset_guids.push(form_set.Guid);
+                        }
+                    } else if operation.OpCode.is_question() && data.len() >= 2 {
+                        // All questions start with a statement header, PromptStringId goes first
+                        let prompt_id = u16::from_le_bytes([data[0], data[1]]);
+                        let prompt = strings_map.resolve(prompt_id);
+                        summary.questions.push(format!("{:?} \"{}\"", operation.OpCode, prompt));
+                    }
+                }
+            }
+        }
+    }
+
+    summary
+}
+
+// Summarize all form packages of an image
+pub fn summarize_uefi_image(data: &[u8]) -> Vec<FormPackageSummary> {
+    let (strings, forms) = find_uefi_packages(data);
+    forms
+        .iter()
+        .map(|form| summarize_uefi_form_package(data, form, uefi_preferred_string_package(data, &strings, form)))
+        .collect()
+}
+
+// Count occurrences of every question, the same prompt can be used more than once
+fn question_counts(questions: &[String]) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for question in questions {
+        *counts.entry(question.as_str()).or_insert(0) += 1;
+    }
+    counts
+}
+
+// Questions of the first summary that are missing in the second one
+fn question_difference(first: &[String], second: &[String]) -> Vec<String> {
+    let mut remaining = question_counts(second);
+    let mut result = Vec::new();
+    for question in first {
+        match remaining.get_mut(question.as_str()) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => result.push(question.clone()),
+        }
+    }
+    result
+}
+
+fn question_similarity(first: &[String], second: &[String]) -> f64 {
+    if first.is_empty() && second.is_empty() {
+        return 1.0;
+    }
+    let only_first = question_difference(first, second).len();
+    let common = first.len() - only_first;
+    let total = first.len() + second.len() - common;
+    common as f64 / total as f64
+}
+
+// Match form packages of every version to the previous one by formset GUID and question similarity,
+// then report questions that were introduced or removed. Images are expected in chronological order
+pub fn match_uefi_versions(images: &[&[u8]]) -> Vec<FormPackageMatch> {
+    let summaries: Vec<Vec<FormPackageSummary>> = images.iter().map(|data| summarize_uefi_image(data)).collect();
+    let mut result = Vec::new();
+
+    for (version, current) in summaries.iter().enumerate() {
+        let previous_summaries = if version > 0 { &summaries[version - 1][..] } else { &[] };
+        // Candidate pairs sharing a formset GUID or similar enough, best ones first.
+        // Every form package is matched at most once, so pairs are taken greedily
+        let mut pairs: Vec<(usize, usize, f64, bool)> = Vec::new();
+        for (form_package, summary) in current.iter().enumerate() {
+            for (index, candidate) in previous_summaries.iter().enumerate() {
+                let same_guid = summary
+                    .formset_guids
+                    .iter()
+                    .any(|guid| candidate.formset_guids.contains(guid));
+                let similarity = question_similarity(&summary.questions, &candidate.questions);
+                if same_guid || similarity >= VERSION_MATCH_THRESHOLD {
+                    pairs.push((form_package, index, similarity, same_guid));
+                }
+            }
+        }
+        pairs.sort_by(|first, second| {
+            (second.3, second.2)
+                .partial_cmp(&(first.3, first.2))
+                .unwrap_or(Ordering::Equal)
+                .then((first.0, first.1).cmp(&(second.0, second.1)))
+        });
+        let mut best: Vec<Option<(usize, f64)>> = vec![None; current.len()];
+        let mut matched = vec![false; previous_summaries.len()];
+        for (form_package, index, similarity, _) in pairs {
+            if best[form_package].is_none() && !matched[index] {
+                best[form_package] = Some((index, similarity));
+                matched[index] = true;
+            }
+        }
+
+        for (form_package, summary) in current.iter().enumerate() {
+            let (previous, similarity, introduced, removed) = match best[form_package] {
+                Some((index, similarity)) => {
+                    let previous_questions = &previous_summaries[index].questions;
+                    (
+                        Some(index),
+                        similarity,
+                        question_difference(&summary.questions, previous_questions),
+                        question_difference(previous_questions, &summary.questions),
+                    )
+                }
+                None => (None, 0.0, summary.questions.clone(), Vec::new()),
+            };
+            result.push(FormPackageMatch {
+                version,
+                form_package: Some(form_package),
+                previous,
+                similarity,
+                introduced,
+                removed,
+            });
+        }
+
+        // Form packages of the previous version nothing was matched to are reported as dropped
+        for (index, summary) in previous_summaries.iter().enumerate() {
+            if !matched[index] {
+                result.push(FormPackageMatch {
+                    version,
+                    form_package: None,
+                    previous: Some(index),
+                    similarity: 0.0,
+                    introduced: Vec::new(),
+                    removed: summary.questions.clone(),
+                });
+            }
+        }
+    }
+
+    result
+}
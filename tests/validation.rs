@@ -0,0 +1,34 @@
+extern crate ifrextractor;
+
+use ifrextractor::uefi_parser::IfrOpcode;
+use ifrextractor::validation::validate_uefi_form_package;
+use ifrextractor::FormPackage;
+
+fn form_package(length: usize) -> FormPackage {
+    FormPackage {
+        offset: 0,
+        length,
+        used_strings: 0,
+        min_string_id: 0,
+        max_string_id: 0,
+        question_count: 0,
+        opcode_count: 0,
+        estimated_text_size: 0,
+        sha256: [0; 32],
+        framework_var_stores: Vec::new(),
+    }
+}
+
+// FormMap opcodes shorter than their FormId are reported, not subtracted from
+#[test]
+fn short_form_map() {
+    for data in [
+        &[8, 0, 0, 2, 0x5D, 0x02, 0x29, 0x02][..],
+        &[9, 0, 0, 2, 0x5D, 0x03, 0x00, 0x29, 0x02][..],
+    ] {
+        let deviations = validate_uefi_form_package(data, &form_package(data.len()));
+        assert!(deviations
+            .iter()
+            .any(|deviation| deviation.offset == 4 && deviation.opcode == Some(IfrOpcode::FormMap)));
+    }
+}